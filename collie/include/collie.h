@@ -0,0 +1,129 @@
+/*
+ * collie — C ABI for embedding the engine (src/ffi.rs).
+ *
+ * Build: `cargo build --release` produces target/release/libcollie.so
+ * (.dylib on macOS); link with -lcollie. tests/c/roundtrip.c is a worked
+ * example, built and run by scripts/c_abi_test.sh.
+ *
+ * Handles are opaque. Ownership:
+ *   - collie_value*       returned by a constructor is OWNED by the caller
+ *                         until passed to a function documented as taking
+ *                         it (collie_prod / collie_sum / collie_list /
+ *                         collie_compile / collie_bind), or freed with
+ *                         collie_value_free.
+ *   - const collie_value* returned by a reader is BORROWED from the result
+ *                         (or value) it came from; never free it.
+ *   - Functions that take handles take them even when they fail.
+ *
+ * Input buffers are copied; the caller keeps ownership of them. Result
+ * buffers are borrowed zero-copy: every pointer read out of a
+ * collie_result stays valid until collie_result_free.
+ *
+ * Errors: functions with a `char **err` out-parameter return NULL on
+ * failure (collie_bind: -1) and, if `err` is non-NULL, set *err to a NUL-terminated message
+ * (free it with collie_string_free); on success *err is set to NULL.
+ * Compile errors carry a source excerpt, as `collie check` prints them.
+ *
+ * Widths are in bytes: 1, 2, 4 or 8. A Prim holds raw words; how they are
+ * read (signed, unsigned, float) is up to the program and the host.
+ */
+#ifndef COLLIE_H
+#define COLLIE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct collie_value collie_value;
+typedef struct collie_graph collie_graph;
+typedef struct collie_result collie_result;
+
+/* The four content shapes (collie_value_kind). */
+enum {
+    COLLIE_PRIM = 0,
+    COLLIE_PROD = 1,
+    COLLIE_SUM  = 2,
+    COLLIE_LIST = 3,
+};
+
+/* ── Building inputs ──────────────────────────────────────────────────── */
+
+/* A flat column of `len` elements of `width` bytes, copied from `data`
+ * (which needn't be aligned; may be NULL when len == 0). */
+collie_value *collie_prim(uint32_t width, const void *data, size_t len, char **err);
+
+/* A tuple of `n` >= 1 columns sharing a row count. Takes the fields. */
+collie_value *collie_prod(collie_value *const *fields, size_t n, char **err);
+
+/* A tagged union over `len` rows: row i lives in lane disc[i]. Lane k
+ * holds exactly the rows whose disc is k, in order. Takes the lanes. */
+collie_value *collie_sum(const uint8_t *disc, size_t len,
+                         collie_value *const *lanes, size_t n_lanes, char **err);
+
+/* `rows` variable-length rows over `values`: row i is
+ * values[bounds[i] .. bounds[i+1]]. `bounds` has rows + 1 entries,
+ * starting at 0, nondecreasing, ending at the row count of `values`.
+ * Takes `values`. */
+collie_value *collie_list(const uint64_t *bounds, size_t rows,
+                          collie_value *values, char **err);
+
+void collie_value_free(collie_value *v);
+
+/* ── Compile and execute ──────────────────────────────────────────────── */
+
+/* Compile a .col program that expects `inputs` on its stack (inputs[0]
+ * at the bottom). Each input becomes slot i of the graph: the program is
+ * typechecked against the inputs' shapes, and the inputs are the slots'
+ * first bindings. Takes the inputs. */
+collie_graph *collie_compile(const char *src, collie_value *const *inputs,
+                             size_t n_inputs, char **err);
+
+/* Bind a new value to input `slot`, replacing the previous one. `v` must
+ * have the shape slot `slot` was compiled against (row counts may differ).
+ * Takes `v`. Returns 0 on success, -1 on failure (the old binding stays). */
+int collie_bind(collie_graph *g, size_t slot, collie_value *v, char **err);
+
+/* Run a compiled graph over its current bindings. Returns the final
+ * stack; may be called repeatedly. */
+collie_result *collie_execute(const collie_graph *g, char **err);
+
+void collie_graph_free(collie_graph *g);
+
+/* Number of values left on the stack, and the i-th (0 = bottom). */
+size_t collie_result_count(const collie_result *r);
+const collie_value *collie_result_get(const collie_result *r, size_t i);
+
+void collie_result_free(collie_result *r);
+
+void collie_string_free(char *s);
+
+/* ── Reading values ───────────────────────────────────────────────────── */
+/* All borrowed. A reader applied to the wrong kind returns NULL / 0. */
+
+uint32_t collie_value_kind(const collie_value *v);
+/* Row count (for a List, the number of rows, not of inner elements). */
+size_t collie_value_len(const collie_value *v);
+
+/* Prim: element width in bytes, and collie_value_len elements of data. */
+uint32_t collie_prim_width(const collie_value *v);
+const void *collie_prim_data(const collie_value *v);
+
+/* Prod fields / Sum lanes: how many, and the i-th. */
+size_t collie_arity(const collie_value *v);
+const collie_value *collie_child(const collie_value *v, size_t i);
+
+/* Sum: collie_value_len discriminants, one byte each. */
+const uint8_t *collie_sum_disc(const collie_value *v);
+
+/* List: collie_value_len + 1 bounds (as for collie_list), and the values. */
+const uint64_t *collie_list_bounds(const collie_value *v);
+const collie_value *collie_list_values(const collie_value *v);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* COLLIE_H */
@@ -0,0 +1,12 @@
+#!/usr/bin/env bash
+# Build the cdylib, compile tests/c/roundtrip.c against include/collie.h,
+# and run it.
+#
+# Usage: ./scripts/c_abi_test.sh        (from the crate root; CC overrides cc)
+
+set -eu
+cargo build --release --lib
+out=target/release/c_abi_roundtrip
+"${CC:-cc}" -std=c99 -Wall -Wextra -Werror -Iinclude tests/c/roundtrip.c \
+    -Ltarget/release -lcollie -Wl,-rpath,"$PWD/target/release" -o "$out"
+"$out"
@@ -0,0 +1,458 @@
+//! C ABI for embedding the engine in a non-Rust host. The header is
+//! `include/collie.h`; `tests/c/roundtrip.c` drives it end to end
+//! (`scripts/c_abi_test.sh` builds and runs it).
+//!
+//! Three opaque handles:
+//!
+//! - `collie_value` — a column. Built from caller-owned buffers with
+//!   `collie_prim` / `collie_prod` / `collie_sum` / `collie_list`, which
+//!   *copy* the buffers (engine storage is `Arc<Vec<_>>`, which can't adopt
+//!   foreign memory), or borrowed out of a result.
+//! - `collie_graph` — a `.col` program compiled against its inputs' shapes:
+//!   parsed, lowered with one `SystemOp::Input` slot per input (so
+//!   typechecked against their shapes), optimized. It holds one bound value
+//!   per slot — the compile-time inputs until `collie_bind` replaces one —
+//!   and executes over them any number of times.
+//! - `collie_result` — the roots of one execution, bottom-to-top, each
+//!   `helpers::normalize`d so every column is plain storage. Data, bounds
+//!   and disc pointers read out of it borrow straight into its buffers and
+//!   stay valid until `collie_result_free`.
+//!
+//! A `collie_value` is `#[repr(transparent)]` over `Value`, so a borrowed
+//! field of a result is a pointer into the result itself — no handle is
+//! allocated to read it, and none may be freed.
+//!
+//! Errors come back through a `char **err` out-parameter (may be NULL):
+//! set to a NUL-terminated message on failure (free it with
+//! `collie_string_free`), NULL on success; the function returns NULL. A
+//! panic inside the engine is caught here and reported the same way —
+//! unwinding never crosses the boundary.
+//!
+//! The pointer contracts (validity, ownership transfer) are stated per
+//! function in the header, rather than repeated as `# Safety` sections.
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use crate::ir::shape::{shape_of, Shape};
+use crate::ir::value::{bounds_var, list, prim_p8, prod, Prim, BoundsRepr, Value};
+use crate::ops::helpers::normalize;
+use crate::pipeline::graph::Graph;
+use crate::pipeline::{build_parsed_inputs, eval_graph_inputs, optimize};
+use crate::syntax::{parse::parse_program, registry::OpRegistry};
+
+#[repr(transparent)]
+pub struct CollieValue(Value);
+
+/// `shapes[slot]` is what the graph was compiled against; `bound[slot]`
+/// is what the next `collie_execute` reads.
+pub struct CollieGraph { graph: Graph, shapes: Vec<Shape>, bound: Vec<Value> }
+
+pub struct CollieResult { roots: Vec<Value> }
+
+/// `collie_kind` in the header.
+pub const COLLIE_PRIM: u32 = 0;
+pub const COLLIE_PROD: u32 = 1;
+pub const COLLIE_SUM: u32 = 2;
+pub const COLLIE_LIST: u32 = 3;
+
+// ── Errors ──────────────────────────────────────────────────────────────
+
+unsafe fn set_err(err: *mut *mut c_char, msg: Option<String>) {
+    if err.is_null() { return; }
+    *err = match msg {
+        // An interior NUL can't cross as a C string; cut the message there.
+        Some(m) => CString::new(m.split('\0').next().unwrap_or("")).expect("no NUL").into_raw(),
+        None => ptr::null_mut(),
+    };
+}
+
+/// Run `f`, turning an `Err` or a panic into `*err` and `None`.
+unsafe fn guard<T>(err: *mut *mut c_char, f: impl FnOnce() -> Result<T, String>) -> Option<T> {
+    let r = catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|p| {
+        let what = p.downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| p.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(format!("internal error: {}", what))
+    });
+    match r {
+        Ok(v) => { set_err(err, None); Some(v) }
+        Err(e) => { set_err(err, Some(e)); None }
+    }
+}
+
+fn boxed(v: Value) -> *mut CollieValue { Box::into_raw(Box::new(CollieValue(v))) }
+
+/// Take ownership of `n` handles (NULL entries are an error, after the
+/// rest have been taken so nothing leaks).
+unsafe fn take_all(ptrs: *const *mut CollieValue, n: usize, what: &str) -> Result<Vec<Value>, String> {
+    if n == 0 { return Ok(Vec::new()); }
+    if ptrs.is_null() { return Err(format!("{}: NULL array", what)); }
+    let taken: Vec<Option<Value>> = std::slice::from_raw_parts(ptrs, n).iter()
+        .map(|&p| if p.is_null() { None } else { Some(Box::from_raw(p).0) })
+        .collect();
+    taken.into_iter().enumerate()
+        .map(|(i, v)| v.ok_or_else(|| format!("{}: entry {} is NULL", what, i)))
+        .collect()
+}
+
+/// `len` elements of `T` from a possibly unaligned caller buffer.
+unsafe fn copy_in<T: bytemuck::Pod>(data: *const c_void, len: usize) -> Vec<T> {
+    if len == 0 { return Vec::new(); }
+    let bytes = std::slice::from_raw_parts(data as *const u8, len * std::mem::size_of::<T>());
+    bytemuck::pod_collect_to_vec(bytes)
+}
+
+// ── Building inputs ─────────────────────────────────────────────────────
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_prim(width: u32, data: *const c_void, len: usize, err: *mut *mut c_char) -> *mut CollieValue {
+    guard(err, || {
+        if data.is_null() && len > 0 { return Err("collie_prim: NULL data".into()); }
+        let p = match width {
+            1 => Prim::P8(copy_in::<u8>(data, len).into()),
+            2 => Prim::P16(copy_in::<u16>(data, len).into()),
+            4 => Prim::P32(copy_in::<u32>(data, len).into()),
+            8 => Prim::P64(copy_in::<u64>(data, len).into()),
+            w => return Err(format!("collie_prim: width must be 1, 2, 4 or 8 bytes, got {}", w)),
+        };
+        Ok(boxed(Value::Prim(p)))
+    }).unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_prod(fields: *const *mut CollieValue, n: usize, err: *mut *mut c_char) -> *mut CollieValue {
+    guard(err, || {
+        let fs = take_all(fields, n, "collie_prod")?;
+        let Some(rows) = fs.first().map(Value::len) else { return Err("collie_prod: no fields".into()) };
+        if let Some((i, f)) = fs.iter().enumerate().find(|(_, f)| f.len() != rows) {
+            return Err(format!("collie_prod: field {} has {} rows, field 0 has {}", i, f.len(), rows));
+        }
+        Ok(boxed(prod(fs)))
+    }).unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_sum(
+    disc: *const u8, len: usize, lanes: *const *mut CollieValue, n_lanes: usize, err: *mut *mut c_char,
+) -> *mut CollieValue {
+    guard(err, || {
+        let lanes = take_all(lanes, n_lanes, "collie_sum")?;
+        if disc.is_null() && len > 0 { return Err("collie_sum: NULL disc".into()); }
+        let d: Vec<u8> = copy_in(disc as *const c_void, len);
+        if let Some(&k) = d.iter().find(|&&k| k as usize >= n_lanes) {
+            return Err(format!("collie_sum: disc {} out of range for {} lanes", k, n_lanes));
+        }
+        // `inject`'s kernel checks each lane holds its rows.
+        let mut st = vec![Value::Prim(prim_p8(d))];
+        st.extend(lanes);
+        crate::ops::combinators::inject_run(n_lanes, &mut st)
+            .map_err(|e| format!("collie_sum: {}", e))?;
+        Ok(boxed(st.pop().expect("inject pushes one")))
+    }).unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_list(
+    bounds: *const u64, rows: usize, values: *mut CollieValue, err: *mut *mut c_char,
+) -> *mut CollieValue {
+    guard(err, || {
+        let values = take_all(&values, 1, "collie_list")?.pop().expect("one");
+        if bounds.is_null() { return Err("collie_list: NULL bounds".into()); }
+        let b: Vec<u64> = copy_in(bounds as *const c_void, rows + 1);
+        if b[0] != 0 { return Err(format!("collie_list: bounds must start at 0, got {}", b[0])); }
+        if let Some(r) = b.windows(2).position(|w| w[0] > w[1]) {
+            return Err(format!("collie_list: bounds decrease at row {}", r));
+        }
+        if b[rows] != values.len() as u64 {
+            return Err(format!("collie_list: bounds end at {}, values have {} rows", b[rows], values.len()));
+        }
+        Ok(boxed(list(bounds_var(b), values)))
+    }).unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_value_free(v: *mut CollieValue) {
+    if !v.is_null() { drop(Box::from_raw(v)); }
+}
+
+// ── Compile and execute ─────────────────────────────────────────────────
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_compile(
+    src: *const c_char, inputs: *const *mut CollieValue, n_inputs: usize, err: *mut *mut c_char,
+) -> *mut CollieGraph {
+    guard(err, || {
+        let bound = take_all(inputs, n_inputs, "collie_compile")?;
+        if src.is_null() { return Err("collie_compile: NULL source".into()); }
+        let src = CStr::from_ptr(src).to_str().map_err(|e| format!("collie_compile: source is not UTF-8: {}", e))?;
+        let reg = OpRegistry::standard();
+        let parsed = parse_program(src, &reg).map_err(|d| d.render(src, "<source>"))?;
+        let shapes: Vec<Shape> = bound.iter().map(shape_of).collect();
+        let built = build_parsed_inputs(parsed, &shapes).map_err(|d| d.render(src, "<source>"))?;
+        // The inputs are `Input` slots, not `Const`s, so folding only
+        // touches the program's own literals.
+        Ok(Box::into_raw(Box::new(CollieGraph { graph: optimize(built.graph), shapes, bound })))
+    }).unwrap_or(ptr::null_mut())
+}
+
+/// Replace input `slot`'s value with `v`, which must have the shape the
+/// graph was compiled against. Returns 0 on success, -1 on failure.
+#[no_mangle]
+pub unsafe extern "C" fn collie_bind(g: *mut CollieGraph, slot: usize, v: *mut CollieValue, err: *mut *mut c_char) -> i32 {
+    guard(err, || {
+        let v = take_all(&v, 1, "collie_bind")?.pop().expect("one");
+        let g = g.as_mut().ok_or("collie_bind: NULL graph")?;
+        let Some(want) = g.shapes.get(slot) else {
+            return Err(format!("collie_bind: slot {} out of range for {} inputs", slot, g.shapes.len()));
+        };
+        let got = shape_of(&v);
+        if &got != want {
+            return Err(format!("collie_bind: slot {} compiled for {}, got {}", slot, want, got));
+        }
+        g.bound[slot] = v;
+        Ok(0)
+    }).unwrap_or(-1)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_execute(g: *const CollieGraph, err: *mut *mut c_char) -> *mut CollieResult {
+    guard(err, || {
+        let g = g.as_ref().ok_or("collie_execute: NULL graph")?;
+        let roots = eval_graph_inputs(&g.graph, g.bound.clone())?
+            .iter().map(normalize).collect::<Result<Vec<_>, _>>()?;
+        Ok(Box::into_raw(Box::new(CollieResult { roots })))
+    }).unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_graph_free(g: *mut CollieGraph) {
+    if !g.is_null() { drop(Box::from_raw(g)); }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_result_count(r: *const CollieResult) -> usize {
+    r.as_ref().map_or(0, |r| r.roots.len())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_result_get(r: *const CollieResult, i: usize) -> *const CollieValue {
+    match r.as_ref().and_then(|r| r.roots.get(i)) {
+        Some(v) => borrowed(v),
+        None => ptr::null(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_result_free(r: *mut CollieResult) {
+    if !r.is_null() { drop(Box::from_raw(r)); }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_string_free(s: *mut c_char) {
+    if !s.is_null() { drop(CString::from_raw(s)); }
+}
+
+// ── Reading values (all borrowed; NULL / 0 on a kind mismatch) ──────────
+
+fn borrowed(v: &Value) -> *const CollieValue { v as *const Value as *const CollieValue }
+
+/// Every value reachable through the API is plain storage: inputs are
+/// built that way and results are normalized. A `View` / `Encoded` reads
+/// as a kind mismatch rather than being materialized behind a borrow.
+unsafe fn peek<'a>(v: *const CollieValue) -> Option<&'a Value> { v.as_ref().map(|v| &v.0) }
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_value_kind(v: *const CollieValue) -> u32 {
+    match peek(v) {
+        Some(Value::Prod(_)) => COLLIE_PROD,
+        Some(Value::Sum { .. }) => COLLIE_SUM,
+        Some(Value::List { .. }) => COLLIE_LIST,
+        _ => COLLIE_PRIM,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_value_len(v: *const CollieValue) -> usize {
+    peek(v).map_or(0, Value::len)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_prim_width(v: *const CollieValue) -> u32 {
+    match peek(v) {
+        Some(Value::Prim(Prim::P8(_))) => 1,
+        Some(Value::Prim(Prim::P16(_))) => 2,
+        Some(Value::Prim(Prim::P32(_))) => 4,
+        Some(Value::Prim(Prim::P64(_))) => 8,
+        _ => 0,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_prim_data(v: *const CollieValue) -> *const c_void {
+    match peek(v) {
+        Some(Value::Prim(Prim::P8(xs))) => xs.as_ptr() as *const c_void,
+        Some(Value::Prim(Prim::P16(xs))) => xs.as_ptr() as *const c_void,
+        Some(Value::Prim(Prim::P32(xs))) => xs.as_ptr() as *const c_void,
+        Some(Value::Prim(Prim::P64(xs))) => xs.as_ptr() as *const c_void,
+        _ => ptr::null(),
+    }
+}
+
+/// Fields of a Prod, lanes of a Sum.
+#[no_mangle]
+pub unsafe extern "C" fn collie_arity(v: *const CollieValue) -> usize {
+    match peek(v) {
+        Some(Value::Prod(fs)) => fs.len(),
+        Some(Value::Sum { lanes, .. }) => lanes.len(),
+        _ => 0,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_child(v: *const CollieValue, i: usize) -> *const CollieValue {
+    let child = match peek(v) {
+        Some(Value::Prod(fs)) => fs.get(i),
+        Some(Value::Sum { lanes, .. }) => lanes.get(i),
+        _ => None,
+    };
+    child.map_or(ptr::null(), borrowed)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_sum_disc(v: *const CollieValue) -> *const u8 {
+    match peek(v) {
+        Some(Value::Sum { disc: Prim::P8(d), .. }) => d.as_ptr(),
+        _ => ptr::null(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_list_bounds(v: *const CollieValue) -> *const u64 {
+    match peek(v) {
+        Some(Value::List { bounds: BoundsRepr::Var(Prim::P64(b)), .. }) => b.as_ptr(),
+        _ => ptr::null(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn collie_list_values(v: *const CollieValue) -> *const CollieValue {
+    match peek(v) {
+        Some(Value::List { values, .. }) => borrowed(values),
+        _ => ptr::null(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn u64s(xs: &[u64]) -> *mut CollieValue {
+        collie_prim(8, xs.as_ptr() as *const c_void, xs.len(), ptr::null_mut())
+    }
+
+    unsafe fn read_u64s(v: *const CollieValue) -> Vec<u64> {
+        assert_eq!(collie_prim_width(v), 8);
+        std::slice::from_raw_parts(collie_prim_data(v) as *const u64, collie_value_len(v)).to_vec()
+    }
+
+    unsafe fn run(src: &str, inputs: Vec<*mut CollieValue>) -> Result<*mut CollieResult, String> {
+        let src = CString::new(src).unwrap();
+        let mut err: *mut c_char = ptr::null_mut();
+        let g = collie_compile(src.as_ptr(), inputs.as_ptr(), inputs.len(), &mut err);
+        if g.is_null() {
+            let msg = CStr::from_ptr(err).to_string_lossy().into_owned();
+            collie_string_free(err);
+            return Err(msg);
+        }
+        let r = collie_execute(g, &mut err);
+        collie_graph_free(g);
+        if r.is_null() {
+            let msg = CStr::from_ptr(err).to_string_lossy().into_owned();
+            collie_string_free(err);
+            return Err(msg);
+        }
+        Ok(r)
+    }
+
+    #[test]
+    fn c_abi_round_trips() {
+        unsafe {
+            // Prim in, Prim out.
+            let r = run("2u64 +.u64", vec![u64s(&[1, 2, 3])]).unwrap();
+            assert_eq!(collie_result_count(r), 1);
+            assert_eq!(read_u64s(collie_result_get(r, 0)), vec![3, 4, 5]);
+            collie_result_free(r);
+
+            // Prod in, fields out (swapped).
+            let p = collie_prod([u64s(&[1, 2]), u64s(&[10, 20])].as_ptr(), 2, ptr::null_mut());
+            let r = run("dup .1 swap .0 entuple", vec![p]).unwrap();
+            let v = collie_result_get(r, 0);
+            assert_eq!(collie_value_kind(v), COLLIE_PROD);
+            assert_eq!(read_u64s(collie_child(v, 0)), vec![10, 20]);
+            assert_eq!(read_u64s(collie_child(v, 1)), vec![1, 2]);
+            collie_result_free(r);
+
+            // List in; a filtered (View-backed) List comes back flat.
+            let l = collie_list([0, 2, 2, 5].as_ptr(), 3, u64s(&[1, 2, 3, 4, 5]), ptr::null_mut());
+            let r = run("dup reduce.+.u64 swap bool[1 0 1] filter", vec![l]).unwrap();
+            assert_eq!(read_u64s(collie_result_get(r, 0)), vec![3, 0, 12]);
+            let v = collie_result_get(r, 1);
+            assert_eq!(collie_value_kind(v), COLLIE_LIST);
+            let b = std::slice::from_raw_parts(collie_list_bounds(v), collie_value_len(v) + 1);
+            assert_eq!(b, &[0, 2, 5]);
+            assert_eq!(read_u64s(collie_list_values(v)), vec![1, 2, 3, 4, 5]);
+            collie_result_free(r);
+        }
+    }
+
+    #[test]
+    fn c_abi_rebinds_inputs() {
+        unsafe {
+            let src = CString::new("dup *.u64 reduce.+.u64").unwrap();
+            let inputs = [u64s(&[1, 2, 3])];
+            let g = collie_compile(src.as_ptr(), inputs.as_ptr(), 1, ptr::null_mut());
+            let r = collie_execute(g, ptr::null_mut());
+            assert_eq!(read_u64s(collie_result_get(r, 0)), vec![14]);
+            collie_result_free(r);
+
+            // Same graph, a new buffer of a different length.
+            assert_eq!(collie_bind(g, 0, u64s(&[4, 5]), ptr::null_mut()), 0);
+            let r = collie_execute(g, ptr::null_mut());
+            assert_eq!(read_u64s(collie_result_get(r, 0)), vec![41]);
+            collie_result_free(r);
+
+            // A mismatched shape or slot is refused; the old binding stays.
+            let mut err: *mut c_char = ptr::null_mut();
+            let narrow = collie_prim(4, [1u32].as_ptr() as *const c_void, 1, ptr::null_mut());
+            assert_eq!(collie_bind(g, 0, narrow, &mut err), -1);
+            assert!(CStr::from_ptr(err).to_str().unwrap().contains("compiled for"));
+            collie_string_free(err);
+            assert_eq!(collie_bind(g, 1, u64s(&[1]), &mut err), -1);
+            assert!(CStr::from_ptr(err).to_str().unwrap().contains("out of range"));
+            collie_string_free(err);
+            let r = collie_execute(g, ptr::null_mut());
+            assert_eq!(read_u64s(collie_result_get(r, 0)), vec![41]);
+            collie_result_free(r);
+            collie_graph_free(g);
+        }
+    }
+
+    #[test]
+    fn c_abi_reports_errors() {
+        unsafe {
+            let mut err: *mut c_char = ptr::null_mut();
+            let l = collie_list([0, 4].as_ptr(), 1, u64s(&[1, 2]), &mut err);
+            assert!(l.is_null());
+            assert!(CStr::from_ptr(err).to_str().unwrap().contains("values have 2 rows"));
+            collie_string_free(err);
+
+            let e = run("reduce.+.u64", vec![]).unwrap_err();
+            assert!(e.contains("error:") && e.contains("reduce.+"), "{}", e);
+            let e = run("entuple", vec![u64s(&[1]), u64s(&[1, 2])]).unwrap_err();
+            assert!(e.contains("zip"), "{}", e);
+        }
+    }
+}
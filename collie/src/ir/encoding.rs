@@ -0,0 +1,220 @@
+//! Compressed storage encodings of a flat column — the storage-axis
+//! modifiers that sit alongside `View` (FOLLOWUPS §10).
+//!
+//! An `Encoded` value is shape-transparent: it always stands for a `Prim`
+//! of its `width()`, and `decode()` produces that Prim. Two encodings:
+//!
+//! - `Rle` — run-length. One value per run plus the run-end offsets.
+//!   Sorted group keys, flags and low-cardinality columns collapse to a
+//!   handful of runs.
+//! - `For` — frame-of-reference bit-packing. A base word plus per-element
+//!   deltas packed at the narrowest bit width that holds `max - min`.
+//!   Low-range ids (dense keys, timestamps within a window).
+//!
+//! Like `View`, an encoding is only seen by the ops that ask for it:
+//! `stack::pop` decodes, and the encoding-aware kernels (`reduce.*`,
+//! `group`, `sort`, `Cmp`, `filter`) pop with `pop_flat` / `pop_raw` and
+//! dispatch on it. Encodings live only at the top of the stack — `view()`
+//! decodes an encoded source, and every constructor of a compound value
+//! pops its parts through `pop` — so no kernel finds one nested inside a
+//! `Prod` / `Sum` / `List`.
+//!
+//! Both encodings are interp-free. FOR takes `min` / `max` over unsigned
+//! words and stores each word's difference from the minimum, so it
+//! round-trips any bit pattern however ops later read it.
+
+use std::sync::Arc;
+use crate::ir::value::{Prim, PrimWidth};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Encoded {
+    /// Run `r` is `values[r]` repeated over positions `[ends[r-1],
+    /// ends[r])` (with `ends[-1] = 0`). `ends` is strictly increasing —
+    /// no empty runs — but adjacent runs may hold equal values; kernels
+    /// don't rely on runs being maximal.
+    Rle { values: Prim, ends: Arc<Vec<u64>> },
+    /// Element `i` is `base + delta_i`, each delta
+    /// stored in `bits` bits, LSB-first across consecutive `words`.
+    /// `bits == 0` means every element equals `base`.
+    For { width: PrimWidth, base: u64, bits: u32, len: usize, words: Arc<Vec<u64>> },
+}
+
+impl Encoded {
+    /// Logical element count (the decoded Prim's length).
+    pub fn len(&self) -> usize {
+        match self {
+            Encoded::Rle { ends, .. } => ends.last().copied().unwrap_or(0) as usize,
+            Encoded::For { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    pub fn width(&self) -> PrimWidth {
+        match self {
+            Encoded::Rle { values, .. } => values.width(),
+            Encoded::For { width, .. } => *width,
+        }
+    }
+
+    /// Short name of the encoding, for display and errors.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Encoded::Rle { .. } => "rle",
+            Encoded::For { .. } => "for",
+        }
+    }
+
+    /// Bytes of payload held — what the encoding costs, to set against
+    /// `len() * width` for the decoded column.
+    pub fn payload_bytes(&self) -> usize {
+        match self {
+            Encoded::Rle { values, ends } => values.len() * width_bytes(values.width()) + ends.len() * 8,
+            Encoded::For { words, .. } => words.len() * 8 + 8,
+        }
+    }
+
+    /// Run `r`'s `(lo, hi)` positions, for `Rle`. Empty for `For`.
+    pub fn runs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let ends: &[u64] = match self { Encoded::Rle { ends, .. } => ends, Encoded::For { .. } => &[] };
+        ends.iter().scan(0usize, |lo, &e| {
+            let run = (*lo, e as usize);
+            *lo = e as usize;
+            Some(run)
+        })
+    }
+
+    /// The `i`-th delta of a `For` (0 for `Rle`).
+    fn delta(&self, i: usize) -> u64 {
+        match self {
+            Encoded::For { bits, words, .. } if *bits > 0 => {
+                let bits = *bits as usize;
+                let at = i * bits;
+                let (w, off) = (at / 64, at % 64);
+                let mut d = words[w] >> off;
+                if off + bits > 64 { d |= words[w + 1] << (64 - off); }
+                d & mask(bits as u32)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Decode to the plain column this stands for — the fallback every
+    /// kernel without an encoding-aware path goes through.
+    pub fn decode(&self) -> Prim {
+        match self {
+            Encoded::Rle { values, .. } => {
+                let idxs: Vec<usize> = self.runs()
+                    .enumerate()
+                    .flat_map(|(r, (lo, hi))| std::iter::repeat_n(r, hi - lo))
+                    .collect();
+                crate::ops::helpers::gather_prim(values, &idxs)
+            }
+            Encoded::For { width, base, len, .. } => {
+                let m = mask(bits_of(*width));
+                let ws: Vec<u64> = (0..*len).map(|i| base.wrapping_add(self.delta(i)) & m).collect();
+                from_words(*width, ws)
+            }
+        }
+    }
+
+    /// Σ of the elements as unsigned words, wrapping at `width`. Integer
+    /// addition mod 2^w is the same ring for every signedness, so this is
+    /// `reduce.+` for any integer interp without decoding: Σ vᵣ·lenᵣ over
+    /// runs, or `n·base + Σ deltas`.
+    pub fn wrapping_sum(&self) -> u64 {
+        let s = match self {
+            Encoded::Rle { values, .. } => self.runs()
+                .enumerate()
+                .fold(0u64, |acc, (r, (lo, hi))| acc.wrapping_add(word(values, r).wrapping_mul((hi - lo) as u64))),
+            Encoded::For { base, len, bits, .. } => {
+                let n = *len as u64;
+                let deltas = if *bits == 0 { 0 } else { (0..*len).fold(0u64, |acc, i| acc.wrapping_add(self.delta(i))) };
+                n.wrapping_mul(*base).wrapping_add(deltas)
+            }
+        };
+        s & mask(bits_of(self.width()))
+    }
+}
+
+/// Filter an RLE column keeping `kept[r]` of run `r`'s elements — all a
+/// filter needs to know, since a run's elements are equal. Emptied runs
+/// drop out and neighbours left adjacent with equal values merge, so the
+/// result's runs are maximal.
+pub fn rle_keep(values: &Prim, kept: &[u64]) -> Encoded {
+    let mut firsts: Vec<usize> = Vec::new();
+    let mut ends: Vec<u64> = Vec::new();
+    let mut n = 0u64;
+    for (r, &k) in kept.iter().enumerate() {
+        if k == 0 { continue; }
+        n += k;
+        match (firsts.last(), ends.last_mut()) {
+            (Some(&f), Some(end)) if word(values, f) == word(values, r) => *end = n,
+            _ => { firsts.push(r); ends.push(n); }
+        }
+    }
+    Encoded::Rle { values: crate::ops::helpers::gather_prim(values, &firsts), ends: Arc::new(ends) }
+}
+
+/// Run-length encode a column. Maximal runs: adjacent equal words merge.
+pub fn rle(p: &Prim) -> Encoded {
+    let n = p.len();
+    let mut firsts: Vec<usize> = Vec::new();
+    let mut ends: Vec<u64> = Vec::new();
+    for i in 0..n {
+        if i == 0 || word(p, i) != word(p, i - 1) {
+            if i > 0 { ends.push(i as u64); }
+            firsts.push(i);
+        }
+    }
+    if n > 0 { ends.push(n as u64); }
+    Encoded::Rle { values: crate::ops::helpers::gather_prim(p, &firsts), ends: Arc::new(ends) }
+}
+
+/// Frame-of-reference encode a column: base = min word, deltas packed at
+/// the bit width of `max - min`.
+pub fn pack(p: &Prim) -> Encoded {
+    let n = p.len();
+    let base = (0..n).map(|i| word(p, i)).min().unwrap_or(0);
+    let top = (0..n).map(|i| word(p, i)).max().unwrap_or(0);
+    let bits = 64 - (top - base).leading_zeros();
+    let mut words = vec![0u64; (n * bits as usize).div_ceil(64)];
+    if bits > 0 {
+        for i in 0..n {
+            let d = word(p, i) - base;
+            let at = i * bits as usize;
+            let (w, off) = (at / 64, at % 64);
+            words[w] |= d << off;
+            if off + bits as usize > 64 { words[w + 1] |= d >> (64 - off); }
+        }
+    }
+    Encoded::For { width: p.width(), base, bits, len: n, words: Arc::new(words) }
+}
+
+/// Element `i` of a Prim as an unsigned word.
+pub fn word(p: &Prim, i: usize) -> u64 {
+    match p {
+        Prim::P8(v) => v[i] as u64,
+        Prim::P16(v) => v[i] as u64,
+        Prim::P32(v) => v[i] as u64,
+        Prim::P64(v) => v[i],
+    }
+}
+
+/// A Prim of `width` from unsigned words (each already within the width).
+pub fn from_words(width: PrimWidth, ws: Vec<u64>) -> Prim {
+    match width {
+        PrimWidth::W8 => Prim::P8(Arc::new(ws.into_iter().map(|w| w as u8).collect())),
+        PrimWidth::W16 => Prim::P16(Arc::new(ws.into_iter().map(|w| w as u16).collect())),
+        PrimWidth::W32 => Prim::P32(Arc::new(ws.into_iter().map(|w| w as u32).collect())),
+        PrimWidth::W64 => Prim::P64(Arc::new(ws)),
+    }
+}
+
+fn width_bytes(w: PrimWidth) -> usize {
+    match w { PrimWidth::W8 => 1, PrimWidth::W16 => 2, PrimWidth::W32 => 4, PrimWidth::W64 => 8 }
+}
+
+fn bits_of(w: PrimWidth) -> u32 { width_bytes(w) as u32 * 8 }
+
+fn mask(bits: u32) -> u64 { if bits >= 64 { u64::MAX } else { (1u64 << bits) - 1 } }
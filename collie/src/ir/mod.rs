@@ -1,15 +1,21 @@
 //! Layer 1 + Layer 3: the language definition.
 //!
-//! - `value` — the dynamic value model (Prim, Prod, Sum, List, View).
+//! - `value` — the dynamic value model (Prim, Prod, Sum, List, View, Encoded).
+//! - `encoding` — the compressed storage encodings (RLE, frame-of-reference).
 //! - `stack` — value stack with View-materializing pop.
 //! - `op` — the PrimOp trait and the eval loop.
 //! - `shape` — structural shape (no interpretation tags).
 //! - `typecheck` — Typed trait and the typecheck runner.
-//! - `profile` — per-op self-time profiling instrumentation for eval.
+//! - `profile` — per-op self-time profiling instrumentation for eval, and
+//!   the allocation counter the per-term graph profile reads.
+//! - `span` — source locations and diagnostics, threaded from the parser
+//!   through lowering.
 
 pub mod value;
+pub mod encoding;
 pub mod stack;
 pub mod op;
 pub mod shape;
 pub mod typecheck;
 pub mod profile;
+pub mod span;
@@ -42,4 +42,11 @@ pub trait PrimOp: std::fmt::Debug + std::any::Any {
     /// Inputs and outputs are both stack order (index 0 = deepest /
     /// pushed first), matching `arity`.
     fn routing_map(&self) -> Option<Vec<usize>> { None }
+
+    /// The token the standard registry re-creates this op from, for ops
+    /// that survive lowering as `SystemOp::Foreign` and whose `name` drops
+    /// parameters (`trunc` for `trunc.day.date`). Compiled plans
+    /// (`tools::plan`) store it. `None` (default): the op has no surface
+    /// form the registry knows, so a graph holding it can't be saved.
+    fn token(&self) -> Option<String> { None }
 }
@@ -7,11 +7,67 @@
 //! ops, the parent's accumulated time excludes the children's time. This
 //! makes the share % honest — children appear under their own names, not
 //! double-counted under their parents.
+//!
+//! Also home to [`CountingAlloc`], the byte counter behind the per-term
+//! profile (`pipeline::profile`). It is a `GlobalAlloc` wrapper the
+//! *binary* installs; the library never installs an allocator itself.
 
+use std::alloc::{GlobalAlloc, Layout, System};
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
+static ALLOCATED: AtomicU64 = AtomicU64::new(0);
+static COUNTING: AtomicBool = AtomicBool::new(false);
+
+/// The system allocator, plus a running total of bytes allocated (frees
+/// are not subtracted; a `realloc` counts its growth). Install with
+/// `#[global_allocator] static A: CountingAlloc = CountingAlloc;` and call
+/// [`CountingAlloc::start`] when a profile is wanted; until then each
+/// allocation only reads the (never-written) flag.
+pub struct CountingAlloc;
+
+impl CountingAlloc {
+    /// Begin counting. Call once, before the work to be measured.
+    pub fn start() { COUNTING.store(true, Ordering::Relaxed); }
+
+    #[inline]
+    fn count(bytes: usize) {
+        if COUNTING.load(Ordering::Relaxed) {
+            ALLOCATED.fetch_add(bytes as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        CountingAlloc::count(layout.size());
+        System.alloc(layout)
+    }
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        CountingAlloc::count(layout.size());
+        System.alloc_zeroed(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        CountingAlloc::count(new_size.saturating_sub(layout.size()));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Bytes allocated since [`CountingAlloc::start`], or `None` if counting
+/// was never started (or [`CountingAlloc`] isn't the global allocator).
+pub fn allocated_bytes() -> Option<u64> {
+    if COUNTING.load(Ordering::Relaxed) {
+        Some(ALLOCATED.load(Ordering::Relaxed))
+    } else {
+        None
+    }
+}
+
 thread_local! {
     static ENABLED: Cell<bool> = const { Cell::new(false) };
     static STATS: RefCell<HashMap<String, (u64, Duration)>> = RefCell::new(HashMap::new());
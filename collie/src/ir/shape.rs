@@ -66,6 +66,7 @@ pub fn shape_of(v: &Value) -> Shape {
             },
             _ => shape_of(source),
         },
+        Value::Encoded(e) => Shape::Prim(e.width()),
     }
 }
 
@@ -94,6 +95,21 @@ impl Interp {
             Interp::I64 | Interp::U64 | Interp::F64                   => PrimWidth::W64,
         }
     }
+
+    /// The unsigned interp of a width — how width-only ops (bare `sort`,
+    /// `<`, …) read a word.
+    pub fn unsigned(w: PrimWidth) -> Interp {
+        match w {
+            PrimWidth::W8 => Interp::U8,
+            PrimWidth::W16 => Interp::U16,
+            PrimWidth::W32 => Interp::U32,
+            PrimWidth::W64 => Interp::U64,
+        }
+    }
+
+    pub fn is_unsigned(&self) -> bool { matches!(self, Interp::U8 | Interp::U16 | Interp::U32 | Interp::U64) }
+
+    pub fn is_float(&self) -> bool { matches!(self, Interp::F32 | Interp::F64) }
 }
 
 impl fmt::Display for Interp {
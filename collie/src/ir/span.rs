@@ -0,0 +1,179 @@
+//! Source locations, carried from the tokenizer through lowering so that
+//! parse and typecheck errors can point at the text that caused them.
+//!
+//! - `Span` — a token's line, column and width (1-based, in chars).
+//! - `Site` — where an op came from: its token's span, plus the stack of
+//!   `def` expansions it was produced inside (ids into
+//!   `SourceMap::expansions`, outermost first).
+//! - `SourceMap` — the `def`s a parse saw and every expansion of them.
+//! - `Diagnostic` — an error message with an optional span and notes;
+//!   `render` draws the offending line with a caret under the span.
+
+use std::fmt;
+
+use crate::ir::shape::Shape;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub col: usize,
+    /// Width in chars (at least 1 when rendered).
+    pub len: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Site {
+    pub span: Span,
+    /// Enclosing `def` expansions, outermost first.
+    pub within: Vec<usize>,
+}
+
+/// One `def name { … }` the parser saw.
+#[derive(Clone, Debug)]
+pub struct DefSite {
+    pub name: String,
+    /// Span of the name token.
+    pub span: Span,
+}
+
+/// One inline expansion of a def: which def (index into
+/// `SourceMap::defs`) and the span of the token that invoked it.
+#[derive(Clone, Debug)]
+pub struct Expansion {
+    pub def: usize,
+    pub call: Span,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    pub defs: Vec<DefSite>,
+    pub expansions: Vec<Expansion>,
+    /// Names of the bound inputs, bottom first (`parse_program_bound`);
+    /// empty for an open program.
+    pub inputs: Vec<String>,
+}
+
+impl SourceMap {
+    /// "in expansion of `f` at 3:5 (defined at 1:5)".
+    pub fn expansion_note(&self, e: usize) -> Option<String> {
+        let x = self.expansions.get(e)?;
+        let d = self.defs.get(x.def)?;
+        Some(format!("in expansion of `{}` at {} (defined at {})", d.name, x.call, d.span))
+    }
+
+    /// Expansion notes for an op at `site`, innermost first.
+    pub fn notes(&self, site: &Site) -> Vec<String> {
+        site.within.iter().rev().filter_map(|&e| self.expansion_note(e)).collect()
+    }
+}
+
+/// Net stack effect of a program or a `def` expansion: the shapes it
+/// consumes from below its entry height and the shapes it leaves there.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StackEffect {
+    pub inputs: Vec<Shape>,
+    pub outputs: Vec<Shape>,
+}
+
+/// Quotations show as `[…]` (lowering stands them in with a zero-field
+/// Prod, which no value has).
+impl fmt::Display for StackEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let one = |f: &mut fmt::Formatter<'_>, s: &Shape| match s {
+            Shape::Prod(fs) if fs.is_empty() => write!(f, " […]"),
+            s => write!(f, " {}", s),
+        };
+        write!(f, "(")?;
+        for s in &self.inputs { one(f, s)?; }
+        write!(f, " --")?;
+        for s in &self.outputs { one(f, s)?; }
+        write!(f, " )")
+    }
+}
+
+/// An error with (optionally) the span it is about.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Option<Span>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Self {
+        Diagnostic { message: message.into(), span: None, notes: Vec::new() }
+    }
+
+    pub fn at(span: Span, message: impl Into<String>) -> Self {
+        Diagnostic { message: message.into(), span: Some(span), notes: Vec::new() }
+    }
+
+    /// Attach `span` unless a more specific one is already set.
+    pub fn or_at(mut self, span: Span) -> Self {
+        if self.span.is_none() { self.span = Some(span); }
+        self
+    }
+
+    pub fn note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// rustc-style rendering against the source the spans refer to:
+    ///
+    /// ```text
+    /// error: graph build: +.u64: …
+    ///  --> foo.col:12:7
+    ///    |
+    /// 12 |   u64[1 2] +.u32
+    ///    |            ^^^^^
+    ///    = note: in expansion of `f` at 20:3 (defined at 11:5)
+    /// ```
+    pub fn render(&self, src: &str, origin: &str) -> String {
+        let mut out = format!("error: {}\n", self.message);
+        if let Some(sp) = self.span {
+            let text = src.lines().nth(sp.line.saturating_sub(1)).unwrap_or("");
+            let gutter = sp.line.to_string().len();
+            let pad = " ".repeat(gutter);
+            out.push_str(&format!("{} --> {}:{}:{}\n", pad, origin, sp.line, sp.col));
+            out.push_str(&format!("{} |\n", pad));
+            out.push_str(&format!("{} | {}\n", sp.line, text));
+            // Keep tabs so the caret lines up under the token.
+            let lead: String = text.chars().take(sp.col.saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+            out.push_str(&format!("{} | {}{}\n", pad, lead, "^".repeat(sp.len.max(1))));
+            for n in &self.notes { out.push_str(&format!("{} = note: {}\n", pad, n)); }
+        } else {
+            out.push_str(&format!(" --> {}\n", origin));
+            for n in &self.notes { out.push_str(&format!("  = note: {}\n", n)); }
+        }
+        out.pop();
+        out
+    }
+}
+
+/// Without the source at hand: `line:col: message`.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(sp) => write!(f, "{}: {}", sp, self.message)?,
+            None => write!(f, "{}", self.message)?,
+        }
+        for n in &self.notes { write!(f, " ({})", n)?; }
+        Ok(())
+    }
+}
+
+impl From<String> for Diagnostic {
+    fn from(message: String) -> Self { Diagnostic::new(message) }
+}
+
+impl From<&str> for Diagnostic {
+    fn from(message: &str) -> Self { Diagnostic::new(message) }
+}
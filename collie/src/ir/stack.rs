@@ -8,6 +8,8 @@
 //!   and don't need a `View` arm. This is the path that gives the
 //!   `view` op universal correctness for free.
 //!
+//!   An `Encoded` column on top is decoded the same way.
+//!
 //! - `pop_raw()`: bypasses materialization. View-aware fast paths (e.g.,
 //!   `search.<interp>` over a viewed query column) use this to inspect the
 //!   `View` directly and avoid the gather.
@@ -28,21 +30,38 @@ pub fn pop_raw(st: &mut Stack) -> Result<Value, String> {
     st.pop().ok_or_else(|| "stack underflow".to_string())
 }
 
+/// Pop, materializing a top-level View unless it is a flat selector over a
+/// `Prim` source — the form `helpers::for_each_prim` streams through. An
+/// `Encoded` column passes through too.
+/// Row-shaped views (`SequenceRange`) and views over structured sources
+/// come back as the real `List` / `Prod` / ... they stand for. For ops
+/// whose only View fast path is the flat one.
+pub fn pop_flat(st: &mut Stack) -> Result<Value, String> {
+    let v = pop_raw(st)?;
+    match &v {
+        Value::View { source, selector } if matches!(source.as_ref(), Value::Prim(_))
+            && !matches!(selector, crate::ir::value::Selector::SequenceRange { .. }) => Ok(v),
+        Value::Encoded(_) => Ok(v),
+        _ => materialize_top(v),
+    }
+}
+
 /// If `v` is a `View`, materialize it. Two paths:
 ///
 ///   - Flat selectors (`Indices`, `Range`): result is `gather(source, idxs)` —
-///     keeps source's shape.
+///     keeps source's shape. `Mask` compresses (`ops::compress`) instead.
 ///   - `SequenceRange`: result is a real `Value::List` whose bounds are the
 ///     cumulative `his - los` and whose inner values are the catenation of
 ///     the per-row sub-slices of source. Source must be a `Value::Prim`.
 ///
-/// Non-View values pass through unchanged.
+/// An `Encoded` column decodes to its `Prim`. Other values pass through
+/// unchanged.
 pub fn materialize_top(v: Value) -> Result<Value, String> {
     match v {
         Value::View { source, selector } => match selector {
+            crate::ir::value::Selector::Mask(m) => crate::ops::compress::compress_value(&source, &m),
             crate::ir::value::Selector::Indices(_)
-            | crate::ir::value::Selector::Runs(_)
-            | crate::ir::value::Selector::Mask(_) => {
+            | crate::ir::value::Selector::Runs(_) => {
                 let idxs = selector.to_usize_vec();
                 crate::ops::helpers::gather(&source, &idxs)
             }
@@ -50,6 +69,7 @@ pub fn materialize_top(v: Value) -> Result<Value, String> {
                 materialize_sequence_range(&source, &los, &his)
             }
         },
+        Value::Encoded(e) => Ok(Value::Prim(e.decode())),
         other => Ok(other),
     }
 }
@@ -14,6 +14,12 @@ pub trait Typed {
     fn tc(&self, _st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         Ok(())
     }
+
+    /// A boxed copy of this op, or `None` (the default) if it can't be
+    /// copied. Lowering copies a quotation's body once per use, so an op
+    /// without a copy may sit only in a quotation applied once. Opt in
+    /// with [`cloneable!`](crate::cloneable) inside the impl.
+    fn clone_op(&self) -> Option<Box<dyn Op>> { None }
 }
 
 /// The combined trait that parser+eval+typecheck all share.
@@ -21,6 +27,20 @@ pub trait Typed {
 pub trait Op: PrimOp + Typed {}
 impl<T: PrimOp + Typed + ?Sized> Op for T {}
 
+/// `Typed::clone_op` via the op's `Clone`: write `cloneable!();` in an
+/// `impl Typed` of a `Clone` op.
+#[macro_export]
+macro_rules! cloneable {
+    () => {
+        fn clone_op(&self) -> Option<Box<dyn $crate::ir::typecheck::Op>> { Some(Box::new(self.clone())) }
+    };
+}
+
+/// Copies of `ops`, or `None` if any op can't be copied.
+pub fn clone_ops(ops: &[Box<dyn Op>]) -> Option<Vec<Box<dyn Op>>> {
+    ops.iter().map(|op| op.clone_op()).collect()
+}
+
 /// Walk a program type-checking each op.
 pub fn typecheck<O: Typed + ?Sized>(
     prog: &[Box<O>],
@@ -11,20 +11,22 @@
 //!
 //!   Value = (PositionStorage, Content)
 //!     Content := Prim | Prod | Sum | List          (closed, shape-bearing)
-//!     PositionStorage := Identity | View {selector} (shape-transparent)
+//!     PositionStorage := Identity | View {selector} | Encoded  (shape-transparent)
 //!
 //! Today both axes live in the same flat enum: the first four variants are
-//! Content; `View` is the sole non-Identity PositionStorage. Per FOLLOWUPS
-//! §11, Content stays closed — additions go on the storage axis. View
-//! composes with itself via canonical-form collapsing in the smart
-//! constructor (`view()`); operators that don't fast-path View call
-//! `materialize()` and proceed against the unrestricted form.
+//! Content; `View` and `Encoded` are the non-Identity PositionStorage. Per
+//! FOLLOWUPS §11, Content stays closed — additions go on the storage axis.
+//! View composes with itself via canonical-form collapsing in the smart
+//! constructor (`view()`); `Encoded` (RLE / frame-of-reference, see
+//! `ir::encoding`) wraps a flat column only. Operators that don't fast-path
+//! either call `materialize()` and proceed against the unrestricted form.
 
 use std::fmt;
 use std::sync::Arc;
+pub use crate::ir::encoding::Encoded;
 
 /// Colang's value universe. A closed inductive set of four content cases,
-/// plus one storage-axis modifier (`View`):
+/// plus two storage-axis modifiers (`View`, `Encoded`):
 ///
 /// - `Prim` — a flat column of width-tagged bytes (the leaf).
 /// - `Prod` — a heterogeneous tuple; all fields share the same row count.
@@ -33,6 +35,9 @@ use std::sync::Arc;
 ///            into rows.
 /// - `View` — lazy-gather wrapper. Shape-transparent: `shape_of(View(s, _))
 ///            == shape_of(s)`. Outer access goes through `selector`.
+/// - `Encoded` — a compressed flat column (run-length or frame-of-reference
+///   bit-packed). Shape-transparent: stands for the `Prim` that `decode()`
+///   produces. Only ever on top of the stack.
 ///
 /// All structural payloads (Prod's fields, Sum's lanes, List's inner, View's
 /// source) are `Arc`-wrapped, so `Value::clone()` is O(1) regardless of
@@ -50,6 +55,7 @@ pub enum Value {
     Sum  { disc: Prim, lanes: Arc<Vec<Value>> },
     List { bounds: BoundsRepr, values: Arc<Value> },
     View { source: Arc<Value>, selector: Selector },
+    Encoded(Encoded),
 }
 
 /// Default for a `Value` is an empty `Prim::P64`. Used as the sentinel
@@ -141,11 +147,7 @@ impl Selector {
                 out
             }
             Selector::Mask(m) => {
-                let mut out = Vec::with_capacity(self.len());
-                for (i, &b) in m.iter().enumerate() {
-                    if b != 0 { out.push(i); }
-                }
-                out
+                crate::ops::compress::mask_positions(m).into_iter().map(|i| i as usize).collect()
             }
             Selector::SequenceRange { .. } => panic!(
                 "Selector::to_usize_vec on SequenceRange — use materialize_to_list instead"
@@ -170,7 +172,8 @@ impl Selector {
 /// Smart constructor for `View`. Collapses `View(View(s, inner), outer)` to a
 /// single layer `View(s, compose(inner, outer))`, so the canonical form is
 /// always "at most one View wrapper." A view of a non-View just becomes a
-/// fresh View.
+/// fresh View; an `Encoded` source is decoded first (encodings stay at the
+/// top of the stack, never inside another value).
 ///
 /// Composition (for `View(View(s, inner), outer)`): the outer selector
 /// indexes *into* the inner view, so `composed[i] = inner[outer[i]]` at
@@ -181,6 +184,7 @@ pub fn view(source: Value, selector: Selector) -> Value {
             let composed = compose_selectors(&inner_sel, &selector);
             Value::View { source: inner_source, selector: composed }
         }
+        Value::Encoded(e) => Value::View { source: Arc::new(Value::Prim(e.decode())), selector },
         other => Value::View { source: Arc::new(other), selector },
     }
 }
@@ -191,10 +195,11 @@ pub fn view(source: Value, selector: Selector) -> Value {
 ///
 /// `composed[i] = inner[outer[i]]`.
 ///
-/// SequenceRange compositions error: composing per-row sub-list-views with
-/// flat selectors doesn't have a single natural answer (does the flat
-/// selector restrict rows? Or restrict positions within each row?). For
-/// now, those compositions force eager materialization through `gather`.
+/// A flat selector over a `SequenceRange` view picks *rows* (the view is
+/// List-shaped, and `gather` on it selects rows too), so it composes to a
+/// `SequenceRange` over the selected rows. A `SequenceRange` *outer*
+/// doesn't collapse (it would be rank-lifting twice); callers materialize
+/// the inner view first.
 pub fn compose_selectors(inner: &Selector, outer: &Selector) -> Selector {
     // Helper: extract (start, end) from a single-interval Runs. The
     // common case (formerly Range) gets specialized arms below.
@@ -225,6 +230,14 @@ pub fn compose_selectors(inner: &Selector, outer: &Selector) -> Selector {
             let (os, oe) = as_single_run(outer).unwrap();
             Selector::range(is + os, is + oe)
         }
+        // Row selection over a per-row view: pick the selected rows' slices.
+        (Selector::SequenceRange { los, his }, outer) if !matches!(outer, Selector::SequenceRange { .. }) => {
+            let rows = outer.to_usize_vec();
+            Selector::SequenceRange {
+                los: Arc::new(rows.iter().map(|&r| los[r]).collect()),
+                his: Arc::new(rows.iter().map(|&r| his[r]).collect()),
+            }
+        }
         // Mask ∩ Mask: the chained-filter hot path. Inner is a Mask over
         // source; outer is a Mask over the inner view (length =
         // popcount(inner)). The composed mask is: for each source
@@ -294,7 +307,7 @@ impl Value {
     /// Row count. For `Prod`, the shared field length (0 if no fields).
     /// For `Sum`, the discriminant length. For `List`, the outer row count.
     /// For `View`, the selector's length (= row count of the materialized
-    /// form, by construction).
+    /// form, by construction). For `Encoded`, the decoded length.
     pub fn len(&self) -> usize {
         match self {
             Value::Prim(p) => p.len(),
@@ -302,6 +315,7 @@ impl Value {
             Value::Sum { disc, .. } => disc.len(),
             Value::List { bounds, .. } => bounds.len(),
             Value::View { selector, .. } => selector.len(),
+            Value::Encoded(e) => e.len(),
         }
     }
 }
@@ -345,6 +359,10 @@ impl fmt::Display for Value {
                 }
                 write!(f, "List{{bounds={:?}, values={}}}", bounds, values)
             }
+            Value::Encoded(e) => {
+                // Like View: show what it means, tagged with how it's stored.
+                write!(f, "{}<n={}>{{{}}}", e.kind().to_uppercase(), e.len(), Value::Prim(e.decode()))
+            }
             Value::View { source, selector } => {
                 // Display materializes — show what the view *means*, not how
                 // it's stored. Three selector shapes, three rendering paths.
@@ -410,7 +428,7 @@ pub fn from_vec<T: Storage>(v: Vec<T>) -> Value { Value::Prim(T::wrap(v)) }
 // Re-exports — supporting types live in submodules below; external code
 // uses them as `value::Prim` / `value::BoundsRepr` / etc.
 pub use prim::{Prim, PrimWidth, prim_p64, prim_p8};
-pub use bounds::{BoundsRepr, bounds_var, bounds_var_from_ends, bounds_stride, bounds_runs};
+pub use bounds::{BoundsRepr, bounds_var, bounds_var_from_ends, bounds_from_ends, bounds_stride, bounds_runs};
 pub use storage::Storage;
 
 mod prim {
@@ -568,6 +586,69 @@ mod bounds {
             }
         }
 
+        /// The first and last start-offsets: where the rows begin and end
+        /// in `values`. O(1) except for `Runs`.
+        pub fn span(&self) -> (u64, u64) {
+            match self {
+                BoundsRepr::Var(Prim::P64(v)) => (v.first().copied().unwrap_or(0), v.last().copied().unwrap_or(0)),
+                BoundsRepr::Stride { stride, count } => (0, stride * count),
+                BoundsRepr::Runs(runs) => (0, runs.iter().map(|(lo, hi)| hi - lo).sum()),
+                _ => (0, 0),
+            }
+        }
+
+        /// `Some(stride)` when every row is `stride` long by construction
+        /// (the `Stride` repr): row `i` is `values[i·stride .. (i+1)·stride]`,
+        /// so a kernel can read the list as a dense `Prod[T; stride]`.
+        pub fn stride(&self) -> Option<u64> {
+            match self {
+                BoundsRepr::Stride { stride, .. } => Some(*stride),
+                _ => None,
+            }
+        }
+
+        /// Call `f(lo, hi)` for each row in order. A direct loop per repr —
+        /// no allocation and no boxed iterator — for per-row kernels that
+        /// would otherwise materialize the bounds (`bounds_as_u64`).
+        #[inline]
+        pub fn for_each_row(&self, mut f: impl FnMut(usize, usize)) {
+            let _ = self.try_for_each_row(|lo, hi| { f(lo, hi); Ok::<(), std::convert::Infallible>(()) });
+        }
+
+        /// `for_each_row`, stopping at the first error.
+        #[inline]
+        pub fn try_for_each_row<E>(&self, mut f: impl FnMut(usize, usize) -> Result<(), E>) -> Result<(), E> {
+            match self {
+                BoundsRepr::Var(Prim::P64(v)) => {
+                    for w in v.windows(2) { f(w[0] as usize, w[1] as usize)?; }
+                }
+                // Bounds are P64 by construction (`decode` widens); walk a
+                // narrower buffer anyway rather than reporting no rows.
+                BoundsRepr::Var(Prim::P32(v)) => {
+                    for w in v.windows(2) { f(w[0] as usize, w[1] as usize)?; }
+                }
+                BoundsRepr::Var(Prim::P16(v)) => {
+                    for w in v.windows(2) { f(w[0] as usize, w[1] as usize)?; }
+                }
+                BoundsRepr::Var(Prim::P8(v)) => {
+                    for w in v.windows(2) { f(w[0] as usize, w[1] as usize)?; }
+                }
+                BoundsRepr::Stride { stride, count } => {
+                    let s = *stride as usize;
+                    for i in 0..*count as usize { f(i * s, (i + 1) * s)?; }
+                }
+                BoundsRepr::Runs(runs) => {
+                    let mut acc = 0usize;
+                    for (lo, hi) in runs.iter() {
+                        let len = (hi - lo) as usize;
+                        f(acc, acc + len)?;
+                        acc += len;
+                    }
+                }
+            }
+            Ok(())
+        }
+
         /// Materialize as a `Vec<u64>` of N+1 start-offsets when a caller
         /// needs a contiguous slice. Cheap clone for `Var`; O(count)
         /// allocation for `Stride` / `Runs`.
@@ -603,6 +684,16 @@ mod bounds {
         BoundsRepr::Var(prim_p64(starts))
     }
 
+    /// Bounds from N row-end offsets, as `bounds_var_from_ends`, but
+    /// `Stride` when every row has the same length — what list kernels
+    /// emit so a rectangular result stays 16 bytes.
+    pub fn bounds_from_ends(ends: Vec<u64>) -> BoundsRepr {
+        let stride = ends.first().copied().unwrap_or(0);
+        let mut prev = 0;
+        let uniform = !ends.is_empty() && ends.iter().all(|&e| { let ok = e.wrapping_sub(prev) == stride; prev = e; ok });
+        if uniform { bounds_stride(stride, ends.len() as u64) } else { bounds_var_from_ends(ends) }
+    }
+
     /// Build a `BoundsRepr::Runs` from an `Arc<Vec<(lo, hi)>>`. Bounds
     /// are computed lazily as cumulative `hi - lo`. Used by the
     /// row-shaped view-of-list path to share the runs Arc with the
@@ -33,12 +33,22 @@
 //! let stack = eval_graph(&graph)?;        // the only evaluator
 //! // stack now holds the result.
 //! ```
+//!
+//! A host running the same program over changing data uses a
+//! `session::Session` instead: register named tables, `compile` once into
+//! a `Plan`, `execute` it per refresh and read the roots back by name.
 
 pub mod ir;
 pub mod ops;
 pub mod syntax;
 pub mod pipeline;
 
+// Host embedding: named tables, compile-once plans (`Session`).
+pub mod session;
+
+// C ABI over the pipeline for non-Rust hosts (`include/collie.h`).
+pub mod ffi;
+
 // Binary-only helpers. Lives in the library crate so it can use `crate::ir`,
 // but not part of the public language API — library consumers should ignore
 // it. The `main.rs` binary uses these to provide REPL, bench, examples, etc.
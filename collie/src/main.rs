@@ -1,31 +1,103 @@
 //! collie binary: thin runner over the `collie` library. The language
 //! itself lives in `lib.rs` (and `ir/`, `ops/`, `syntax/`). This file
 //! only handles argv dispatch and the `tools/` modules that provide the
-//! binary's features (bench, pretty-printer, examples runner).
+//! binary's features (bench, fuzzer, pretty-printer, examples runner,
+//! compiled plans).
 
-use collie::pipeline::{build, eval_graph, optimize};
-use collie::ir::value::Value;
-use collie::syntax::{parse, registry};
+use collie::pipeline::{build_parsed, build_parsed_inputs, build_parsed_seeded, elaborate, eval_graph, eval_graph_profiled, infer, optimize, optimize_saturated, optimize_saturated_unfolded, optimize_unfolded, Built, Kind};
+use collie::pipeline::graph::Graph;
+use collie::pipeline::sysop::SystemOp;
+use collie::ir::profile::CountingAlloc;
+use collie::syntax::{header, parse, registry};
 use collie::tools;
 
-fn main() -> Result<(), String> {
-    let args: Vec<String> = std::env::args().collect();
+// Counts bytes allocated, for the per-term `--profile` report. Counting
+// starts only under `--profile`; otherwise an allocation reads one flag.
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+fn main() {
+    // Errors may be multi-line (rendered source excerpts), so print them
+    // as-is rather than through `Result`'s `Debug`.
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut args: Vec<String> = std::env::args().collect();
+    // `--cache DIR` (or `--cache=DIR`): reuse term outputs saved by earlier
+    // runs and save this run's (`tools::cache`), reporting hits after the
+    // results.
+    if let Some(i) = args.iter().position(|a| a == "--cache") {
+        if i + 1 == args.len() { return Err("--cache: expected a directory".into()); }
+        let dir = args.remove(i + 1);
+        args[i] = format!("--cache={}", dir);
+    }
+    let cache = args.iter().find_map(|a| a.strip_prefix("--cache=")).map(str::to_string);
     // `--no-opt` runs the graph engine without the optimizer (the
     // `Graph → Graph` passes are never load-bearing for execution; see
     // dev/LAYERING.md). The graph engine is the only evaluator.
     let no_opt = args.iter().any(|a| a == "--no-opt");
     let elide = args.iter().any(|a| a == "--elide");
+    // `--egraph`: optimize by equality saturation (`pipeline::egraph`)
+    // instead of the greedy rewrite sweep.
+    let opt = match (no_opt, args.iter().any(|a| a == "--egraph")) {
+        (true, _) => Opt::Off,
+        (false, true) => Opt::Saturate,
+        (false, false) => Opt::Rewrite,
+    };
+    // `--infer`: check numeric interpretations across ops and resolve
+    // interp-less `sort`/`<`/`reduce.min` (`pipeline::interp`) before
+    // anything else sees the graph.
+    let infer = args.iter().any(|a| a == "--infer");
+    // `--profile[=PREFIX]`: per-term table on stdout, plus
+    // `PREFIX.trace.json` (Chrome trace events) and `PREFIX.dot`. PREFIX
+    // defaults to the script's file stem. `collie run <path>` is the same
+    // as `collie <path>`.
+    let profile: Option<Option<String>> = args.iter().find_map(|a| {
+        if a == "--profile" { Some(None) } else { a.strip_prefix("--profile=").map(|p| Some(p.to_string())) }
+    });
+    if profile.is_some() { CountingAlloc::start(); }
+    // `--format=pretty|table|csv|jsonl`: how `run` prints its results
+    // (`tools::report`); `pretty` is the default.
+    let format = match args.iter().find_map(|a| a.strip_prefix("--format=")) {
+        Some(f) => tools::report::Format::parse(f)?,
+        None => tools::report::Format::Pretty,
+    };
     let mut args_iter = args.iter().skip(1)
-        .filter(|a| !matches!(a.as_str(), "--no-opt" | "--elide"));
+        .filter(|a| !matches!(a.as_str(), "--no-opt" | "--elide" | "--infer" | "--egraph")
+            && !a.starts_with("--profile") && !a.starts_with("--format=") && !a.starts_with("--cache="));
     match args_iter.next().map(|s| s.as_str()) {
         Some("bench") => tools::bench::run_bench(),
+        Some("check") => match args_iter.next() {
+            Some(path) => check_script(path, infer),
+            None => Err("check: expected a .col path".into()),
+        },
+        // `compile <path> -o <out>`: lower and optimize once, save the
+        // graph as a plan (`tools::plan`) for `run` to load directly.
+        Some("compile") => {
+            let rest: Vec<&String> = args_iter.collect();
+            match rest.as_slice() {
+                [path, o, out] if o.as_str() == "-o" => compile_script(path, out, opt, infer),
+                _ => Err("compile: expected <path>.col -o <out>.plan".into()),
+            }
+        }
         Some("examples") => tools::examples_runner::run_all(),
+        Some("fuzz") => tools::fuzz::run_fuzz(&args_iter.cloned().collect::<Vec<_>>()),
         Some("graph") => match args_iter.next() {
-            Some(path) => dump_graph(path, elide),
+            Some(path) => dump_graph(path, elide, infer),
             None => Err("graph: expected a .col path".into()),
         },
+        // Any further `name=VALUE` arguments bind the script's declared
+        // inputs (see `tools::bind`). The path may be a compiled plan.
+        Some("run") => match args_iter.next() {
+            Some(path) => run_script(path, &args_iter.cloned().collect::<Vec<_>>(), opt, infer, profile, format, cache),
+            None => Err("run: expected a .col or .plan path".into()),
+        },
         Some(path) if path.ends_with(".col") || std::path::Path::new(path).exists() => {
-            run_script(path, no_opt)
+            run_script(path, &args_iter.cloned().collect::<Vec<_>>(), opt, infer, profile, format, cache)
         }
         _ => {
             tools::examples_runner::run_all()?;
@@ -38,10 +110,8 @@ fn main() -> Result<(), String> {
 /// term as `tN: op(child, …) -> outputs`, then roots. With `--elide`, runs
 /// the full default `optimize` pipeline first, so the dump matches the
 /// graph the engine actually executes.
-fn dump_graph(path: &str, elide: bool) -> Result<(), String> {
-    let reg = registry::OpRegistry::standard();
-    let prog = parse::parse_file(std::path::Path::new(path), &reg)?;
-    let (g, _shapes) = build(prog)?;
+fn dump_graph(path: &str, elide: bool, infer: bool) -> Result<(), String> {
+    let (Built { graph: g, .. }, ..) = load(path, None, infer)?;
     let raw_terms = g.terms.len();
     let (g, optimized) = if elide { (optimize(g), true) } else { (g, false) };
     for (i, term) in g.terms.iter().enumerate() {
@@ -62,21 +132,193 @@ fn dump_graph(path: &str, elide: bool) -> Result<(), String> {
     Ok(())
 }
 
-fn run_script(path: &str, no_opt: bool) -> Result<(), String> {
+type Loaded = (Built, collie::ir::span::SourceMap, Vec<header::Decl>, Option<Vec<Kind>>);
+
+/// Parse and lower a script, rendering any error against its source. A
+/// script with an `input` / `param` header (`syntax::header`) is lowered
+/// over its declared inputs: bound from `binds` (`name=VALUE` arguments,
+/// checked against the declared shapes first) when given, else as
+/// `Input` sources of the declared shapes — typechecked, not runnable.
+/// With `infer_interps`, the graph comes back elaborated (`pipeline::interp`),
+/// the declared inputs seeding the analysis. Also returns the header's
+/// `output` declarations, and then the kinds of the results.
+fn load(path: &str, binds: Option<&[String]>, infer_interps: bool) -> Result<Loaded, String> {
     let reg = registry::OpRegistry::standard();
-    let prog = parse::parse_file(std::path::Path::new(path), &reg)?;
-    let (graph, _shapes) = build(prog)?;
-    let graph = if no_opt { graph } else { optimize(graph) };
-    let stack: Vec<Value> = eval_graph(&graph)?;
+    let src = std::fs::read_to_string(path).map_err(|e| format!("read {}: {}", path, e))?;
+    let render = |d: collie::ir::span::Diagnostic| d.render(&src, path);
+    let (decls, body) = header::split_header(&src).map_err(render)?;
+    let (decls, outputs): (Vec<_>, Vec<_>) = decls.into_iter().partition(|d| d.is_bound());
+    let names: Vec<String> = decls.iter().map(|d| d.name.clone()).collect();
+    let parsed = if decls.is_empty() {
+        parse::parse_program(&body, &reg)
+    } else {
+        parse::parse_program_bound(&body, &reg, &names)
+    }.map_err(render)?;
+    let map = parsed.map.clone();
+    let built = match binds {
+        Some(args) => {
+            let seeds = tools::bind::bind(&decls, args).map_err(render)?;
+            build_parsed_seeded(parsed, seeds)
+        }
+        None if decls.is_empty() => build_parsed(parsed),
+        None => build_parsed_inputs(parsed, &decls.iter().map(|d| d.ty.shape()).collect::<Vec<_>>()),
+    }.map_err(render)?;
+    if !outputs.is_empty() && outputs.len() != built.graph.roots.len() {
+        let d = &outputs[0];
+        return Err(render(collie::ir::span::Diagnostic::at(d.span, format!(
+            "{} output(s) declared, but the program leaves {} values", outputs.len(), built.graph.roots.len()))));
+    }
+    if !infer_interps { return Ok((built, map, outputs, None)); }
+    let sources: Vec<Kind> = decls.iter().map(|d| d.ty.kind()).collect();
+    let inf = infer(&built, &sources).map_err(render)?;
+    let results = built.graph.roots.iter().map(|r| inf.of(*r).clone()).collect();
+    Ok((elaborate(built, &inf), map, outputs, Some(results)))
+}
+
+/// `collie check <path>`: parse and typecheck without running, then print
+/// the stack effect of every `def` (one line per distinct effect across
+/// its expansions) and of the whole program — with `--infer`, also the
+/// inferred kind of each result.
+fn check_script(path: &str, infer: bool) -> Result<(), String> {
+    let (built, map, _, results) = load(path, None, infer)?;
     println!("{}", path);
-    if stack.is_empty() {
-        println!("  (stack empty)");
+    for (d, effects) in map.defs.iter().zip(&built.def_effects) {
+        println!("  def {} ({})", d.name, d.span);
+        if effects.is_empty() { println!("    (never expanded)"); }
+        for e in effects { println!("    {}", e); }
+    }
+    println!("  program");
+    println!("    {}", built.effect);
+    if let Some(ks) = results {
+        println!("    interps: {}", ks.iter().map(|k| k.to_string()).collect::<Vec<_>>().join(" "));
+    }
+    Ok(())
+}
+
+/// Which optimizer a script goes through: none (`--no-opt`), the greedy
+/// rewrite pipeline (the default), or equality saturation (`--egraph`).
+#[derive(Clone, Copy)]
+enum Opt { Off, Rewrite, Saturate }
+
+/// `collie compile <path> -o <out>`: write the optimized graph (unless
+/// `--no-opt`) and the script's declarations as a plan. Declared inputs
+/// stay `Input` sources, bound when the plan is run.
+fn compile_script(path: &str, out: &str, opt: Opt, infer: bool) -> Result<(), String> {
+    let (Built { graph, .. }, ..) = load(path, None, infer)?;
+    let graph = match opt {
+        Opt::Off => graph,
+        Opt::Rewrite => optimize(graph),
+        Opt::Saturate => optimize_saturated(graph),
+    };
+    let src = std::fs::read_to_string(path).map_err(|e| format!("read {}: {}", path, e))?;
+    let (decls, _) = header::split_header(&src).map_err(|d| d.render(&src, path))?;
+    // Bound declarations first, in slot order, then the outputs.
+    let (bound, outputs): (Vec<_>, Vec<_>) = decls.iter().partition(|d| d.is_bound());
+    let header: Vec<String> = bound.iter().chain(&outputs).map(|d| d.to_string()).collect();
+    let bytes = tools::plan::encode(&graph, &header.join("\n"))?;
+    std::fs::write(out, &bytes).map_err(|e| format!("write {}: {}", out, e))?;
+    println!("wrote {} ({} terms, {} bytes)", out, graph.terms.len(), bytes.len());
+    Ok(())
+}
+
+/// Load a compiled plan and bind its declared inputs from `binds`: each
+/// `Input` source becomes the bound value. Errors about a binding render
+/// against the plan's stored header.
+fn load_plan(path: &str, bytes: &[u8], binds: &[String]) -> Result<(Graph, Vec<header::Decl>), String> {
+    let (mut graph, text) = tools::plan::decode(bytes).map_err(|e| format!("{}: {}", path, e))?;
+    let render = |d: collie::ir::span::Diagnostic| d.render(&text, path);
+    let (decls, _) = header::split_header(&text).map_err(render)?;
+    let mut values = tools::bind::bind(&decls, binds).map_err(render)?;
+    for term in &mut graph.terms {
+        if let SystemOp::Input { slot, .. } = term.op {
+            let v = values.get_mut(slot).ok_or_else(|| format!("{}: input {} is not declared", path, slot))?;
+            term.op = SystemOp::Const(std::mem::take(v));
+        }
+    }
+    Ok((graph, decls.into_iter().filter(|d| !d.is_bound()).collect()))
+}
+
+/// Run a script, or a compiled plan (recognized by its magic, not its
+/// extension). A plan was optimized when it was compiled, so `--no-opt`,
+/// `--egraph` and `--infer` don't apply to it.
+fn run_script(
+    path: &str,
+    binds: &[String],
+    opt: Opt,
+    infer: bool,
+    profile: Option<Option<String>>,
+    format: tools::report::Format,
+    cache: Option<String>,
+) -> Result<(), String> {
+    if cache.is_some() && profile.is_some() {
+        return Err("--cache and --profile don't combine: a profile of cache hits measures nothing".into());
+    }
+    let bytes = std::fs::read(path).map_err(|e| format!("read {}: {}", path, e))?;
+    let (graph, outputs) = if tools::plan::is_plan(&bytes) {
+        load_plan(path, &bytes, binds)?
     } else {
-        let total = stack.len();
-        for (i, v) in stack.iter().enumerate() {
-            let depth = total - 1 - i;
-            println!("  [{}] {}", depth, tools::pretty::pretty(v));
+        let (Built { graph, .. }, _, outputs, _) = load(path, Some(binds), infer)?;
+        // Profiling and caching skip constant folding: the examples are
+        // literal-fed and bound inputs are constants, and folding would
+        // move all their work to optimize time.
+        let graph = match (opt, profile.is_some() || cache.is_some()) {
+            (Opt::Off, _) => graph,
+            (Opt::Rewrite, true) => optimize_unfolded(graph),
+            (Opt::Rewrite, false) => optimize(graph),
+            (Opt::Saturate, true) => optimize_saturated_unfolded(graph),
+            (Opt::Saturate, false) => optimize_saturated(graph),
+        };
+        (graph, outputs)
+    };
+    let mut stats = None;
+    let (stack, prof) = match (profile.is_some(), &cache) {
+        (true, _) => {
+            let (stack, prof) = eval_graph_profiled(&graph)?;
+            (stack, Some(prof))
+        }
+        (false, Some(dir)) => {
+            let (stack, s) = tools::cache::Cache::open(std::path::Path::new(dir))?.run(&graph)?;
+            stats = Some(s);
+            (stack, None)
+        }
+        (false, None) => (eval_graph(&graph)?, None),
+    };
+    if format == tools::report::Format::Pretty {
+        println!("{}", path);
+        if stack.is_empty() {
+            println!("  (stack empty)");
+        } else {
+            let total = stack.len();
+            for (i, v) in stack.iter().enumerate() {
+                let depth = total - 1 - i;
+                println!("  [{}] {}", depth, tools::pretty::pretty(v));
+            }
         }
+    } else {
+        // Results without an `output` line are named by depth, as in the
+        // pretty dump, and read with the default schema.
+        let total = stack.len();
+        let results: Vec<_> = stack.into_iter().enumerate().map(|(i, v)| match outputs.get(i) {
+            Some(d) => (d.name.clone(), d.ty.clone(), v),
+            None => (format!("[{}]", total - 1 - i), header::Ty::of_shape(&collie::ir::shape::shape_of(&v)), v),
+        }).collect();
+        print!("{}", tools::report::write(format, &results)?);
+    }
+    // Keep machine-readable formats clean on stdout.
+    if let Some(stats) = stats {
+        if format == tools::report::Format::Pretty { println!("{}", stats) } else { eprintln!("{}", stats) }
+    }
+    if let (Some(prof), Some(prefix)) = (prof, profile) {
+        let prefix = prefix.unwrap_or_else(|| {
+            std::path::Path::new(path).file_stem()
+                .map_or("collie".to_string(), |s| s.to_string_lossy().into_owned())
+        });
+        print!("{}", prof.table());
+        let trace = format!("{}.trace.json", prefix);
+        let dot = format!("{}.dot", prefix);
+        std::fs::write(&trace, prof.chrome_trace()).map_err(|e| format!("{}: {}", trace, e))?;
+        std::fs::write(&dot, prof.dot(&graph)).map_err(|e| format!("{}: {}", dot, e))?;
+        println!("wrote {} and {}", trace, dot);
     }
     Ok(())
 }
@@ -0,0 +1,886 @@
+//! Approximate aggregates — distinct counts and quantiles in bounded space.
+//!
+//! - `approx.distinct` — HyperLogLog over a *structural* row hash, so any
+//!   shape (Prim, Prod, Sum, List, and Views of them) can be counted. The
+//!   sketch is a plain `P8` column of `HLL_M` registers; merging two
+//!   sketches is an element-wise max, which makes batches combine exactly
+//!   (the merged sketch equals the sketch of the concatenated input).
+//! - `approx.quantile.<i>` — a KLL-style compactor sketch. The sketch is a
+//!   `Prod(items, levels)`: each retained item carries a level `h` and
+//!   stands for `2^h` input elements. Merging concatenates levels and
+//!   re-compacts. Ordering follows the interpretation suffix. An empty
+//!   row of per-row input yields NaN (float interps) or 0 per `q`; an
+//!   empty flat column is an error.
+//!
+//! Both come in flat form (one estimate for the whole column) and per-row
+//! form (`List<T>` → one estimate per row), mirroring `reduce.*`. The
+//! `.sketch` / `.merge` / `.estimate` ops expose the intermediate values so
+//! a caller can sketch batches independently and combine them later.
+
+use std::cmp::Ordering;
+use std::sync::Arc;
+use crate::ir::op::PrimOp;
+use crate::ir::stack::{Stack, pop};
+use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
+use crate::ir::value::{Value, Prim, PrimWidth, Storage, from_vec, prod, bounds_stride, bounds_var_from_ends};
+use crate::ir::shape::{Interp, Shape, disc_as_u8, prim_width};
+use crate::ops::helpers::{materialize_ref, splitmix64};
+
+/// HyperLogLog precision: `2^HLL_P` registers per sketch. Standard error is
+/// about `1.04 / sqrt(HLL_M)` ≈ 1.6%.
+pub const HLL_P: u32 = 12;
+pub const HLL_M: usize = 1 << HLL_P;
+
+/// Per-level capacity of the quantile compactor. Worst-case rank error is
+/// bounded by roughly `2 / KLL_K` of the input size; the randomized
+/// compaction offset keeps the typical error well below that.
+pub const KLL_K: usize = 512;
+
+// ── Structural hashing ─────────────────────────────────────────────────────
+
+/// SplitMix64 finalizer: a cheap, well-distributed 64-bit mixer.
+fn mix(mut z: u64) -> u64 { splitmix64(&mut z) }
+
+fn combine(h: u64, x: u64) -> u64 { mix(h.rotate_left(23) ^ x) }
+
+// Distinct seeds per constructor, so e.g. `(a)` and `[a]` hash differently.
+const SEED_PRIM: u64 = 0x5052_494d;
+const SEED_PROD: u64 = 0x5052_4f44;
+const SEED_SUM: u64 = 0x0053_554d;
+const SEED_LIST: u64 = 0x4c49_5354;
+
+/// One 64-bit hash per row of `v`, structural over the whole value: equal
+/// rows hash equal regardless of storage (Views are materialized, List
+/// bounds compared by content). Prim leaves hash their bits plus width.
+pub fn row_hashes(v: &Value) -> Result<Vec<u64>, String> {
+    let v = materialize_ref(v)?;
+    match v.as_ref() {
+        Value::Prim(p) => {
+            let seed = mix(SEED_PRIM ^ (p.width() as u64) << 8);
+            Ok(match p {
+                Prim::P8(x)  => x.iter().map(|&e| combine(seed, e as u64)).collect(),
+                Prim::P16(x) => x.iter().map(|&e| combine(seed, e as u64)).collect(),
+                Prim::P32(x) => x.iter().map(|&e| combine(seed, e as u64)).collect(),
+                Prim::P64(x) => x.iter().map(|&e| combine(seed, e)).collect(),
+            })
+        }
+        Value::Prod(fs) => {
+            let mut hs = vec![mix(SEED_PROD ^ fs.len() as u64); v.len()];
+            for f in fs.iter() {
+                let fh = row_hashes(f)?;
+                if fh.len() != hs.len() {
+                    return Err(format!("approx: Prod field length {} != {}", fh.len(), hs.len()));
+                }
+                for (h, x) in hs.iter_mut().zip(fh) { *h = combine(*h, x); }
+            }
+            Ok(hs)
+        }
+        Value::Sum { disc, lanes } => {
+            let d = disc_as_u8(disc)?;
+            let lane_hs: Vec<Vec<u64>> = lanes.iter().map(row_hashes).collect::<Result<_, _>>()?;
+            let mut cursors = vec![0usize; lanes.len()];
+            let mut hs = Vec::with_capacity(d.len());
+            for &k in d {
+                let k = k as usize;
+                let x = lane_hs.get(k).and_then(|l| l.get(cursors[k]))
+                    .ok_or_else(|| format!("approx: Sum lane {} shorter than its disc count", k))?;
+                cursors[k] += 1;
+                hs.push(combine(mix(SEED_SUM ^ k as u64), *x));
+            }
+            Ok(hs)
+        }
+        Value::List { bounds, values } => {
+            let inner = row_hashes(values)?;
+            Ok(bounds.iter_pairs().map(|(lo, hi)| {
+                inner[lo as usize..hi as usize].iter()
+                    .fold(mix(SEED_LIST ^ (hi - lo)), |h, &x| combine(h, x))
+            }).collect())
+        }
+        Value::View { .. } => Err("approx: unmaterialized View".into()),
+        Value::Encoded(_) => Err("approx: undecoded Encoded".into()),
+    }
+}
+
+// ── HyperLogLog ────────────────────────────────────────────────────────────
+
+fn hll_insert(regs: &mut [u8], h: u64) {
+    let idx = (h >> (64 - HLL_P)) as usize;
+    let rest = h << HLL_P;
+    // Rank = position of the first set bit in the remaining 64-P bits.
+    let rank = (rest.leading_zeros().min(64 - HLL_P) + 1) as u8;
+    if rank > regs[idx] { regs[idx] = rank; }
+}
+
+/// Cardinality estimate from one sketch's registers (HLL with the
+/// linear-counting small-range correction; 64-bit hashes need no
+/// large-range correction).
+pub fn hll_estimate(regs: &[u8]) -> u64 {
+    let m = regs.len() as f64;
+    let alpha = 0.7213 / (1.0 + 1.079 / m);
+    let mut inv = 0.0f64;
+    let mut zeros = 0usize;
+    for &r in regs {
+        inv += 2f64.powi(-(r as i32));
+        if r == 0 { zeros += 1; }
+    }
+    let raw = alpha * m * m / inv;
+    let est = if raw <= 2.5 * m && zeros > 0 { m * (m / zeros as f64).ln() } else { raw };
+    est.round() as u64
+}
+
+/// Row hashes plus the per-sketch `(lo, hi)` ranges over them.
+type HashGroups = (Vec<u64>, Vec<(u64, u64)>);
+
+/// Split a flat or per-row (`List`) input into the row hashes to sketch
+/// and the per-sketch ranges over them.
+fn hash_groups(v: &Value) -> Result<HashGroups, String> {
+    match v {
+        Value::List { bounds, values } => Ok((row_hashes(values)?, bounds.iter_pairs().collect())),
+        other => {
+            let hs = row_hashes(other)?;
+            let n = hs.len() as u64;
+            Ok((hs, vec![(0, n)]))
+        }
+    }
+}
+
+fn sketch_registers(v: &Value) -> Result<(Vec<u8>, usize), String> {
+    let (hs, groups) = hash_groups(v)?;
+    let mut regs = vec![0u8; groups.len() * HLL_M];
+    for (g, &(lo, hi)) in groups.iter().enumerate() {
+        let r = &mut regs[g * HLL_M..(g + 1) * HLL_M];
+        for &h in &hs[lo as usize..hi as usize] { hll_insert(r, h); }
+    }
+    Ok((regs, groups.len()))
+}
+
+/// Registers of a flat (`P8`) or per-row (`List<P8>`) distinct sketch,
+/// checked to hold a whole number of `HLL_M`-register sketches.
+fn sketch_regs_of(v: &Value, ctx: &str) -> Result<Arc<Vec<u8>>, String> {
+    let p = match v {
+        Value::Prim(Prim::P8(r)) => r.clone(),
+        Value::List { values, .. } => match values.as_ref() {
+            Value::Prim(Prim::P8(r)) => r.clone(),
+            other => return Err(format!("{}: sketch rows must be P8, got {:?}", ctx, other)),
+        },
+        other => return Err(format!("{}: expected a P8 sketch, got {:?}", ctx, other)),
+    };
+    if p.len() % HLL_M != 0 {
+        return Err(format!("{}: sketch length {} is not a multiple of {}", ctx, p.len(), HLL_M));
+    }
+    Ok(p)
+}
+
+/// Shape arm shared by the distinct ops: flat input → `flat`, `List` input
+/// → `per_row`.
+fn distinct_out_tc(v: &Shape, flat: Shape, per_row: Shape) -> Shape {
+    if matches!(v, Shape::List { .. }) { per_row } else { flat }
+}
+
+fn sketch_shape_tc(tag: &str, v: &Shape) -> Result<(), String> {
+    match v {
+        Shape::Prim(PrimWidth::W8) => Ok(()),
+        Shape::List { inner, .. } if prim_width(inner) == Some(PrimWidth::W8) => Ok(()),
+        other => Err(format!("{}: expected a P8 or List<P8> sketch, got {}", tag, other)),
+    }
+}
+
+/// `approx.distinct` — estimated number of distinct rows. Any shape flat
+/// (one P64), or per-row over a `List<T>` (one P64 per row).
+#[derive(Debug, Clone)] pub struct ApproxDistinct;
+impl PrimOp for ApproxDistinct {
+    fn name(&self) -> &str { "approx.distinct" }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { distinct_run(st) }
+}
+impl Typed for ApproxDistinct {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { distinct_tc(st) }
+}
+pub fn distinct_run(st: &mut Stack) -> Result<(), String> {
+    let v = pop(st)?;
+    let (hs, groups) = hash_groups(&v)?;
+    // One scratch register file reused per row — no per-row sketch kept.
+    let mut regs = vec![0u8; HLL_M];
+    let mut out = Vec::with_capacity(groups.len());
+    for (lo, hi) in groups {
+        regs.iter_mut().for_each(|r| *r = 0);
+        for &h in &hs[lo as usize..hi as usize] { hll_insert(&mut regs, h); }
+        out.push(hll_estimate(&regs));
+    }
+    st.push(from_vec::<u64>(out));
+    Ok(())
+}
+pub fn distinct_tc(st: &mut TypeStack) -> Result<(), String> {
+    let _ = tc_pop(st, "approx.distinct")?;
+    st.push(Shape::Prim(PrimWidth::W64));
+    Ok(())
+}
+
+/// `approx.distinct.sketch` — HLL registers: flat input → `P8` of `HLL_M`
+/// registers; `List<T>` → `List<P8>` with one stride-`HLL_M` row per row.
+#[derive(Debug, Clone)] pub struct DistinctSketch;
+impl PrimOp for DistinctSketch {
+    fn name(&self) -> &str { "approx.distinct.sketch" }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { distinct_sketch_run(st) }
+}
+impl Typed for DistinctSketch {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { distinct_sketch_tc(st) }
+}
+pub fn distinct_sketch_run(st: &mut Stack) -> Result<(), String> {
+    let v = pop(st)?;
+    let per_row = matches!(v, Value::List { .. });
+    let (regs, n) = sketch_registers(&v)?;
+    let regs = from_vec::<u8>(regs);
+    st.push(if per_row {
+        Value::List { bounds: bounds_stride(HLL_M as u64, n as u64), values: Arc::new(regs) }
+    } else { regs });
+    Ok(())
+}
+pub fn distinct_sketch_tc(st: &mut TypeStack) -> Result<(), String> {
+    let v = tc_pop(st, "approx.distinct.sketch")?;
+    let p8 = Shape::Prim(PrimWidth::W8);
+    st.push(distinct_out_tc(&v, p8.clone(), Shape::List { bounds: PrimWidth::W64, inner: Box::new(p8) }));
+    Ok(())
+}
+
+/// `approx.distinct.merge` — combine two sketches (register-wise max).
+/// Flat or per-row; per-row sketches must have the same row count.
+#[derive(Debug, Clone)] pub struct DistinctMerge;
+impl PrimOp for DistinctMerge {
+    fn name(&self) -> &str { "approx.distinct.merge" }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { distinct_merge_run(st) }
+}
+impl Typed for DistinctMerge {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { distinct_merge_tc(st) }
+}
+pub fn distinct_merge_run(st: &mut Stack) -> Result<(), String> {
+    let b = pop(st)?;
+    let a = pop(st)?;
+    let ra = sketch_regs_of(&a, "approx.distinct.merge")?;
+    let rb = sketch_regs_of(&b, "approx.distinct.merge")?;
+    if ra.len() != rb.len() {
+        return Err(format!("approx.distinct.merge: sketch sizes differ ({} vs {})", ra.len(), rb.len()));
+    }
+    let merged = from_vec::<u8>(ra.iter().zip(rb.iter()).map(|(&x, &y)| x.max(y)).collect());
+    st.push(match a {
+        Value::List { bounds, .. } => Value::List { bounds, values: Arc::new(merged) },
+        _ => merged,
+    });
+    Ok(())
+}
+pub fn distinct_merge_tc(st: &mut TypeStack) -> Result<(), String> {
+    let b = tc_pop(st, "approx.distinct.merge")?;
+    let a = tc_pop(st, "approx.distinct.merge")?;
+    sketch_shape_tc("approx.distinct.merge", &a)?;
+    if a != b { return Err(format!("approx.distinct.merge: sketch shapes differ: {} vs {}", a, b)); }
+    st.push(a);
+    Ok(())
+}
+
+/// `approx.distinct.estimate` — cardinality of a sketch: `P8` → one P64,
+/// `List<P8>` → one P64 per row.
+#[derive(Debug, Clone)] pub struct DistinctEstimate;
+impl PrimOp for DistinctEstimate {
+    fn name(&self) -> &str { "approx.distinct.estimate" }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { distinct_estimate_run(st) }
+}
+impl Typed for DistinctEstimate {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { distinct_estimate_tc(st) }
+}
+pub fn distinct_estimate_run(st: &mut Stack) -> Result<(), String> {
+    let v = pop(st)?;
+    let regs = sketch_regs_of(&v, "approx.distinct.estimate")?;
+    st.push(from_vec::<u64>(regs.chunks(HLL_M).map(hll_estimate).collect()));
+    Ok(())
+}
+pub fn distinct_estimate_tc(st: &mut TypeStack) -> Result<(), String> {
+    let v = tc_pop(st, "approx.distinct.estimate")?;
+    sketch_shape_tc("approx.distinct.estimate", &v)?;
+    st.push(Shape::Prim(PrimWidth::W64));
+    Ok(())
+}
+
+// ── Quantile sketch (KLL-style compactors) ─────────────────────────────────
+
+/// Ordering used by the compactor: numeric under the interpretation, with
+/// `total_cmp` for floats so NaNs have a fixed place.
+pub trait SketchKey: Storage {
+    fn key_cmp(a: &Self, b: &Self) -> Ordering;
+    /// Quantile of an empty row in per-row output: NaN for floats (as
+    /// `stats.*` gives for too few rows), zero for integers.
+    const EMPTY: Self;
+}
+macro_rules! sketch_key_ord { ($($t:ty),*) => { $(
+    impl SketchKey for $t { fn key_cmp(a: &Self, b: &Self) -> Ordering { a.cmp(b) } const EMPTY: Self = 0; }
+)* } }
+sketch_key_ord!(u8, i8, u16, i16, u32, i32, u64, i64);
+impl SketchKey for f32 { fn key_cmp(a: &Self, b: &Self) -> Ordering { a.total_cmp(b) } const EMPTY: Self = f32::NAN; }
+impl SketchKey for f64 { fn key_cmp(a: &Self, b: &Self) -> Ordering { a.total_cmp(b) } const EMPTY: Self = f64::NAN; }
+
+/// In-memory compactor stack: `levels[h]` holds items of weight `2^h`.
+struct Kll<T> { levels: Vec<Vec<T>> }
+
+impl<T: SketchKey> Kll<T> {
+    fn new() -> Self { Kll { levels: vec![Vec::new()] } }
+
+    fn from_parts(items: &[T], levels: &[u8]) -> Self {
+        let mut k = Kll::new();
+        for (&x, &h) in items.iter().zip(levels) {
+            let h = h as usize;
+            if k.levels.len() <= h { k.levels.resize_with(h + 1, Vec::new); }
+            k.levels[h].push(x);
+        }
+        k
+    }
+
+    /// Stream `xs` in: feed level 0 a `KLL_K`-sized chunk at a time and
+    /// compact as levels fill, so the sketch never holds more than
+    /// O(k log(n/k)) items however long the group is.
+    fn extend(&mut self, xs: &[T]) {
+        for chunk in xs.chunks(KLL_K) {
+            self.levels[0].extend_from_slice(chunk);
+            self.compress();
+        }
+    }
+
+    fn absorb(&mut self, other: Kll<T>) {
+        for (h, items) in other.levels.into_iter().enumerate() {
+            if self.levels.len() <= h { self.levels.resize_with(h + 1, Vec::new); }
+            self.levels[h].extend(items);
+        }
+        self.compress();
+    }
+
+    /// Compact every over-full level into the next: sort, keep one parity
+    /// of the even prefix, promote it with doubled weight. The parity comes
+    /// from a hash of the level's contents — deterministic, but unbiased
+    /// across compactions, which is what keeps rank errors cancelling.
+    fn compress(&mut self) {
+        let mut h = 0;
+        while h < self.levels.len() {
+            if self.levels[h].len() > KLL_K {
+                let mut lvl = std::mem::take(&mut self.levels[h]);
+                lvl.sort_by(T::key_cmp);
+                let seed = lvl.iter().fold(mix(h as u64 ^ lvl.len() as u64), |acc, x| {
+                    bytemuck::bytes_of(x).iter().fold(acc, |a, &b| a.rotate_left(8) ^ b as u64)
+                });
+                let offset = (mix(seed) & 1) as usize;
+                let even = lvl.len() & !1;
+                let promoted: Vec<T> = lvl[offset..even].iter().step_by(2).copied().collect();
+                // An odd item stays behind at this level.
+                let keep: Vec<T> = lvl[even..].to_vec();
+                self.levels[h] = keep;
+                if self.levels.len() <= h + 1 { self.levels.push(Vec::new()); }
+                self.levels[h + 1].extend(promoted);
+            }
+            h += 1;
+        }
+    }
+
+    fn to_parts(&self) -> (Vec<T>, Vec<u8>) {
+        let mut items = Vec::new();
+        let mut levels = Vec::new();
+        for (h, lvl) in self.levels.iter().enumerate() {
+            items.extend_from_slice(lvl);
+            levels.extend(std::iter::repeat_n(h as u8, lvl.len()));
+        }
+        (items, levels)
+    }
+
+    fn is_empty(&self) -> bool { self.levels.iter().all(Vec::is_empty) }
+
+    /// Weighted rank query: the smallest retained item whose cumulative
+    /// weight reaches `ceil(q · total)`. The sketch must be non-empty and
+    /// every `q` in `[0, 1]` (`quantile_output` checks both).
+    fn quantiles(&self, qs: &[f64]) -> Vec<T> {
+        let mut weighted: Vec<(T, u64)> = Vec::new();
+        for (h, lvl) in self.levels.iter().enumerate() {
+            weighted.extend(lvl.iter().map(|&x| (x, 1u64 << h)));
+        }
+        weighted.sort_by(|a, b| T::key_cmp(&a.0, &b.0));
+        let total: u64 = weighted.iter().map(|w| w.1).sum();
+        let mut out = Vec::with_capacity(qs.len());
+        for &q in qs {
+            let target = ((q * total as f64).ceil() as u64).max(1);
+            let mut cum = 0u64;
+            let mut pick = weighted[weighted.len() - 1].0;
+            for &(x, w) in &weighted {
+                cum += w;
+                if cum >= target { pick = x; break; }
+            }
+            out.push(pick);
+        }
+        out
+    }
+}
+
+/// Flat or per-row sketch input, split into `(items, levels)` per sketch.
+/// A flat sketch is `Prod(items, levels)`; a per-row one is
+/// `List<Prod(items, levels)>`.
+fn sketch_rows<T: SketchKey>(v: &Value, ctx: &str) -> Result<(Vec<Kll<T>>, bool), String> {
+    let (fields, ranges, per_row) = match v {
+        Value::Prod(fs) => (fs.clone(), vec![(0u64, v.len() as u64)], false),
+        Value::List { bounds, values } => match materialize_ref(values)?.as_ref() {
+            Value::Prod(fs) => (fs.clone(), bounds.iter_pairs().collect(), true),
+            other => return Err(format!("{}: expected List<Prod(items, levels)>, got {:?}", ctx, other)),
+        },
+        other => return Err(format!("{}: expected a quantile sketch, got {:?}", ctx, other)),
+    };
+    if fields.len() != 2 { return Err(format!("{}: sketch must be a 2-field Prod", ctx)); }
+    let items = match materialize_ref(&fields[0])?.as_ref() {
+        Value::Prim(p) => T::extract(p)?.to_vec(),
+        other => return Err(format!("{}: sketch items must be Prim, got {:?}", ctx, other)),
+    };
+    let levels = match materialize_ref(&fields[1])?.as_ref() {
+        Value::Prim(Prim::P8(l)) => l.to_vec(),
+        other => return Err(format!("{}: sketch levels must be P8, got {:?}", ctx, other)),
+    };
+    let sketches = ranges.iter()
+        .map(|&(lo, hi)| Kll::from_parts(&items[lo as usize..hi as usize], &levels[lo as usize..hi as usize]))
+        .collect();
+    Ok((sketches, per_row))
+}
+
+fn sketch_value<T: SketchKey>(sketches: &[Kll<T>], per_row: bool) -> Value {
+    let mut items = Vec::new();
+    let mut levels = Vec::new();
+    let mut ends = Vec::with_capacity(sketches.len());
+    for s in sketches {
+        let (i, l) = s.to_parts();
+        items.extend(i);
+        levels.extend(l);
+        ends.push(items.len() as u64);
+    }
+    let p = prod(vec![from_vec::<T>(items), from_vec::<u8>(levels)]);
+    if per_row { Value::List { bounds: bounds_var_from_ends(ends), values: Arc::new(p) } } else { p }
+}
+
+/// Build one sketch per group of a flat (`Prim`) or per-row (`List<Prim>`)
+/// column.
+fn build_sketches<T: SketchKey>(v: &Value) -> Result<(Vec<Kll<T>>, bool), String> {
+    let (xs, ranges, per_row): (&[T], Vec<(u64, u64)>, bool) = match v {
+        Value::Prim(p) => (T::extract(p)?, vec![(0, p.len() as u64)], false),
+        Value::List { bounds, values } => match values.as_ref() {
+            Value::Prim(p) => (T::extract(p)?, bounds.iter_pairs().collect(), true),
+            other => return Err(format!("approx.quantile: list inner must be Prim, got {:?}", other)),
+        },
+        other => return Err(format!("approx.quantile: expected Prim or List<Prim>, got {:?}", other)),
+    };
+    let sketches = ranges.iter().map(|&(lo, hi)| {
+        let mut k = Kll::new();
+        k.extend(&xs[lo as usize..hi as usize]);
+        k
+    }).collect();
+    Ok((sketches, per_row))
+}
+
+/// Quantiles per sketch. An empty flat input is an error; an empty row of
+/// a per-row input gets `T::EMPTY` for each `q`, keeping rows
+/// `qs.len()` wide.
+fn quantile_output<T: SketchKey>(sketches: &[Kll<T>], per_row: bool, qs: &[f64]) -> Result<Value, String> {
+    if let Some(q) = qs.iter().find(|q| !(0.0..=1.0).contains(*q)) {
+        return Err(format!("approx.quantile: q must be in [0, 1], got {}", q));
+    }
+    if !per_row && sketches.iter().any(Kll::is_empty) {
+        return Err("approx.quantile: empty input".into());
+    }
+    let mut out: Vec<T> = Vec::with_capacity(sketches.len() * qs.len());
+    for s in sketches {
+        if s.is_empty() { out.extend(std::iter::repeat_n(T::EMPTY, qs.len())); } else { out.extend(s.quantiles(qs)); }
+    }
+    let col = from_vec::<T>(out);
+    Ok(if per_row {
+        Value::List { bounds: bounds_stride(qs.len() as u64, sketches.len() as u64), values: Arc::new(col) }
+    } else { col })
+}
+
+fn pop_qs(st: &mut Stack, ctx: &str) -> Result<Vec<f64>, String> {
+    match pop(st)? {
+        Value::Prim(p) => Ok(<f64 as Storage>::extract(&p)?.to_vec()),
+        other => Err(format!("{}: quantiles must be an f64 column, got {:?}", ctx, other)),
+    }
+}
+
+macro_rules! with_interp {
+    ($interp:expr, $f:ident ( $($arg:expr),* )) => {
+        match $interp {
+            Interp::U8  => $f::<u8>($($arg),*),  Interp::I8  => $f::<i8>($($arg),*),
+            Interp::U16 => $f::<u16>($($arg),*), Interp::I16 => $f::<i16>($($arg),*),
+            Interp::U32 => $f::<u32>($($arg),*), Interp::I32 => $f::<i32>($($arg),*),
+            Interp::F32 => $f::<f32>($($arg),*),
+            Interp::U64 => $f::<u64>($($arg),*), Interp::I64 => $f::<i64>($($arg),*),
+            Interp::F64 => $f::<f64>($($arg),*),
+        }
+    };
+}
+
+fn quantile_direct<T: SketchKey>(v: &Value, qs: &[f64]) -> Result<Value, String> {
+    let (sk, per_row) = build_sketches::<T>(v)?;
+    quantile_output(&sk, per_row, qs)
+}
+fn quantile_sketch<T: SketchKey>(v: &Value) -> Result<Value, String> {
+    let (sk, per_row) = build_sketches::<T>(v)?;
+    Ok(sketch_value(&sk, per_row))
+}
+fn quantile_merge<T: SketchKey>(a: &Value, b: &Value) -> Result<Value, String> {
+    let (mut sa, per_row) = sketch_rows::<T>(a, "approx.quantile.merge")?;
+    let (sb, _) = sketch_rows::<T>(b, "approx.quantile.merge")?;
+    if sa.len() != sb.len() {
+        return Err(format!("approx.quantile.merge: row counts differ ({} vs {})", sa.len(), sb.len()));
+    }
+    for (x, y) in sa.iter_mut().zip(sb) { x.absorb(y); }
+    Ok(sketch_value(&sa, per_row))
+}
+fn quantile_estimate<T: SketchKey>(s: &Value, qs: &[f64]) -> Result<Value, String> {
+    let (sk, per_row) = sketch_rows::<T>(s, "approx.quantile.estimate")?;
+    quantile_output(&sk, per_row, qs)
+}
+
+/// Value shape under an interp: `Prim(w)` → `flat(w)`, `List<Prim(w)>` →
+/// `List<flat(w)>`.
+fn quantile_tc_shape(tag: &str, interp: Interp, v: &Shape) -> Result<(PrimWidth, bool), String> {
+    let (inner, per_row) = match v { Shape::List { inner, .. } => (inner.as_ref(), true), other => (other, false) };
+    match inner {
+        Shape::Prim(w) if *w == interp.width() => Ok((*w, per_row)),
+        other => Err(format!("{}.{}: expected Prim({}), got {}", tag, interp, interp.width(), other)),
+    }
+}
+
+fn sketch_shape(w: PrimWidth) -> Shape { Shape::Prod(vec![Shape::Prim(w), Shape::Prim(PrimWidth::W8)]) }
+
+fn wrap_per_row(s: Shape, per_row: bool) -> Shape {
+    if per_row { Shape::List { bounds: PrimWidth::W64, inner: Box::new(s) } } else { s }
+}
+
+fn tc_qs(st: &mut TypeStack, tag: &str) -> Result<(), String> {
+    match tc_pop(st, tag)? {
+        Shape::Prim(PrimWidth::W64) => Ok(()),
+        other => Err(format!("{}: quantiles must be Prim(P64) f64, got {}", tag, other)),
+    }
+}
+
+/// Sketch shape check: `Prod(Prim(w), P8)` flat or `List<…>` per-row.
+fn tc_sketch(tag: &str, interp: Interp, s: &Shape) -> Result<bool, String> {
+    let (inner, per_row) = match s { Shape::List { inner, .. } => (inner.as_ref(), true), other => (other, false) };
+    if *inner != sketch_shape(interp.width()) {
+        return Err(format!("{}.{}: expected sketch {}, got {}", tag, interp, sketch_shape(interp.width()), s));
+    }
+    Ok(per_row)
+}
+
+/// `approx.quantile.<i>` — `vals qs → quantiles`. `qs` is an f64 column of
+/// fractions in `[0, 1]`. Flat: one value per q. Per-row (`List<T>`): a
+/// `List<T>` with `len(qs)` entries per row (stride bounds).
+#[derive(Debug, Clone)] pub struct ApproxQuantile { pub interp: Interp }
+impl PrimOp for ApproxQuantile {
+    fn name(&self) -> &str { "approx.quantile" }
+    fn token(&self) -> Option<String> { Some(format!("{}.{}", self.name(), self.interp)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { quantile_run(self.interp, st) }
+}
+impl Typed for ApproxQuantile {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { quantile_tc(self.interp, st) }
+}
+pub fn quantile_run(interp: Interp, st: &mut Stack) -> Result<(), String> {
+    let qs = pop_qs(st, "approx.quantile")?;
+    let v = pop(st)?;
+    st.push(with_interp!(interp, quantile_direct(&v, &qs))?);
+    Ok(())
+}
+pub fn quantile_tc(interp: Interp, st: &mut TypeStack) -> Result<(), String> {
+    tc_qs(st, "approx.quantile")?;
+    let v = tc_pop(st, "approx.quantile")?;
+    let (w, per_row) = quantile_tc_shape("approx.quantile", interp, &v)?;
+    st.push(wrap_per_row(Shape::Prim(w), per_row));
+    Ok(())
+}
+
+/// `approx.quantile.sketch.<i>` — `vals → sketch` (`Prod(items, levels)`,
+/// or `List<Prod(items, levels)>` per row).
+#[derive(Debug, Clone)] pub struct QuantileSketch { pub interp: Interp }
+impl PrimOp for QuantileSketch {
+    fn name(&self) -> &str { "approx.quantile.sketch" }
+    fn token(&self) -> Option<String> { Some(format!("{}.{}", self.name(), self.interp)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let v = pop(st)?;
+        st.push(with_interp!(self.interp, quantile_sketch(&v))?);
+        Ok(())
+    }
+}
+impl Typed for QuantileSketch {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let v = tc_pop(st, "approx.quantile.sketch")?;
+        let (w, per_row) = quantile_tc_shape("approx.quantile.sketch", self.interp, &v)?;
+        st.push(wrap_per_row(sketch_shape(w), per_row));
+        Ok(())
+    }
+}
+
+/// `approx.quantile.merge.<i>` — `sketch sketch → sketch`; per-row sketches
+/// merge row by row.
+#[derive(Debug, Clone)] pub struct QuantileMerge { pub interp: Interp }
+impl PrimOp for QuantileMerge {
+    fn name(&self) -> &str { "approx.quantile.merge" }
+    fn token(&self) -> Option<String> { Some(format!("{}.{}", self.name(), self.interp)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let b = pop(st)?;
+        let a = pop(st)?;
+        st.push(with_interp!(self.interp, quantile_merge(&a, &b))?);
+        Ok(())
+    }
+}
+impl Typed for QuantileMerge {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let b = tc_pop(st, "approx.quantile.merge")?;
+        let a = tc_pop(st, "approx.quantile.merge")?;
+        let per_row = tc_sketch("approx.quantile.merge", self.interp, &a)?;
+        if tc_sketch("approx.quantile.merge", self.interp, &b)? != per_row {
+            return Err(format!("approx.quantile.merge: cannot merge {} with {}", a, b));
+        }
+        st.push(a);
+        Ok(())
+    }
+}
+
+/// `approx.quantile.estimate.<i>` — `sketch qs → quantiles`, same output
+/// shape as `approx.quantile.<i>`.
+#[derive(Debug, Clone)] pub struct QuantileEstimate { pub interp: Interp }
+impl PrimOp for QuantileEstimate {
+    fn name(&self) -> &str { "approx.quantile.estimate" }
+    fn token(&self) -> Option<String> { Some(format!("{}.{}", self.name(), self.interp)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let qs = pop_qs(st, "approx.quantile.estimate")?;
+        let s = pop(st)?;
+        st.push(with_interp!(self.interp, quantile_estimate(&s, &qs))?);
+        Ok(())
+    }
+}
+impl Typed for QuantileEstimate {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        tc_qs(st, "approx.quantile.estimate")?;
+        let s = tc_pop(st, "approx.quantile.estimate")?;
+        let per_row = tc_sketch("approx.quantile.estimate", self.interp, &s)?;
+        st.push(wrap_per_row(Shape::Prim(self.interp.width()), per_row));
+        Ok(())
+    }
+}
+
+pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
+    use crate::ir::typecheck::Op;
+    use crate::syntax::registry::{parse_interp, split_suffix};
+    r.add(|t: &str| -> Option<Box<dyn Op>> {
+        match t {
+            "approx.distinct" => Some(Box::new(ApproxDistinct)),
+            "approx.distinct.sketch" => Some(Box::new(DistinctSketch)),
+            "approx.distinct.merge" => Some(Box::new(DistinctMerge)),
+            "approx.distinct.estimate" => Some(Box::new(DistinctEstimate)),
+            _ => None,
+        }
+    });
+    // approx.quantile[.sketch|.merge|.estimate].<interp>
+    r.add(|t: &str| -> Option<Box<dyn Op>> {
+        let (head, sfx) = split_suffix(t)?;
+        let interp = parse_interp(sfx)?;
+        match head {
+            "approx.quantile" => Some(Box::new(ApproxQuantile { interp })),
+            "approx.quantile.sketch" => Some(Box::new(QuantileSketch { interp })),
+            "approx.quantile.merge" => Some(Box::new(QuantileMerge { interp })),
+            "approx.quantile.estimate" => Some(Box::new(QuantileEstimate { interp })),
+            _ => None,
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::value::{list, bounds_var_from_ends};
+
+    fn run1(op: &dyn PrimOp, stack: Vec<Value>) -> Vec<Value> {
+        let mut st = stack;
+        let mut env = Vec::new();
+        op.run(&mut st, &mut env).unwrap();
+        st
+    }
+
+    /// Deterministic test data (xorshift).
+    fn pseudo(n: usize, seed: u64, modulo: u64) -> Vec<u64> {
+        let mut x = seed;
+        (0..n).map(|_| { x ^= x << 13; x ^= x >> 7; x ^= x << 17; x % modulo }).collect()
+    }
+
+    fn exact_distinct(xs: &[u64]) -> usize {
+        let mut v = xs.to_vec();
+        v.sort_unstable();
+        v.dedup();
+        v.len()
+    }
+
+    fn rel_err(est: u64, exact: usize) -> f64 { (est as f64 - exact as f64).abs() / exact as f64 }
+
+    #[test]
+    fn distinct_flat_within_bounds() {
+        let xs = pseudo(200_000, 7, 80_000);
+        let exact = exact_distinct(&xs);
+        let st = run1(&ApproxDistinct, vec![from_vec::<u64>(xs)]);
+        let est = <u64 as Storage>::extract(match &st[0] { Value::Prim(p) => p, _ => panic!() }).unwrap()[0];
+        // ~1.6% standard error; 5% is > 3 sigma.
+        assert!(rel_err(est, exact) < 0.05, "est {} exact {}", est, exact);
+    }
+
+    #[test]
+    fn distinct_structural_prod_key() {
+        // (a, b) pairs: distinct pairs, not distinct fields.
+        let a = pseudo(50_000, 11, 300);
+        let b = pseudo(50_000, 13, 300);
+        let mut pairs: Vec<(u64, u64)> = a.iter().copied().zip(b.iter().copied()).collect();
+        pairs.sort_unstable();
+        pairs.dedup();
+        let exact = pairs.len();
+        let st = run1(&ApproxDistinct, vec![prod(vec![from_vec::<u64>(a), from_vec::<u64>(b)])]);
+        let est = match &st[0] { Value::Prim(Prim::P64(v)) => v[0], _ => panic!() };
+        assert!(rel_err(est, exact) < 0.05, "est {} exact {}", est, exact);
+    }
+
+    #[test]
+    fn distinct_per_row_within_bounds() {
+        let r0 = pseudo(1_000, 3, 100);
+        let r1 = pseudo(30_000, 5, 20_000);
+        let r2 = vec![42u64; 500];
+        let exact = [exact_distinct(&r0), exact_distinct(&r1), exact_distinct(&r2)];
+        let ends = vec![r0.len() as u64, (r0.len() + r1.len()) as u64, (r0.len() + r1.len() + r2.len()) as u64];
+        let flat: Vec<u64> = r0.into_iter().chain(r1).chain(r2).collect();
+        let st = run1(&ApproxDistinct, vec![list(bounds_var_from_ends(ends), from_vec::<u64>(flat))]);
+        let ests = match &st[0] { Value::Prim(Prim::P64(v)) => v.clone(), _ => panic!() };
+        for (e, x) in ests.iter().zip(exact) { assert!(rel_err(*e, x) < 0.05, "est {} exact {}", e, x); }
+    }
+
+    #[test]
+    fn distinct_merge_equals_whole() {
+        // HLL merge is lossless: sketch(a) ⊔ sketch(b) == sketch(a ++ b).
+        let xs = pseudo(20_000, 17, 1 << 40);
+        let (lo, hi) = xs.split_at(7_000);
+        let sa = run1(&DistinctSketch, vec![from_vec::<u64>(lo.to_vec())]).pop().unwrap();
+        let sb = run1(&DistinctSketch, vec![from_vec::<u64>(hi.to_vec())]).pop().unwrap();
+        let whole = run1(&DistinctSketch, vec![from_vec::<u64>(xs.clone())]).pop().unwrap();
+        let merged = run1(&DistinctMerge, vec![sa, sb]).pop().unwrap();
+        assert_eq!(merged, whole);
+        let est = run1(&DistinctEstimate, vec![merged]).pop().unwrap();
+        let est = match est { Value::Prim(Prim::P64(v)) => v[0], _ => panic!() };
+        assert!(rel_err(est, exact_distinct(&xs)) < 0.05);
+    }
+
+    fn rank_err(sorted: &[u64], v: u64, q: f64) -> f64 {
+        let lo = sorted.partition_point(|&x| x < v) as f64;
+        let hi = sorted.partition_point(|&x| x <= v) as f64;
+        let target = q * sorted.len() as f64;
+        // Distance from the target rank to v's rank interval.
+        let d = if target < lo { lo - target } else if target > hi { target - hi } else { 0.0 };
+        d / sorted.len() as f64
+    }
+
+    #[test]
+    fn quantile_flat_within_bounds() {
+        let xs = pseudo(300_000, 23, 1 << 32);
+        let mut sorted = xs.clone();
+        sorted.sort_unstable();
+        let qs = vec![0.0, 0.01, 0.25, 0.5, 0.9, 0.99, 1.0];
+        let st = run1(&ApproxQuantile { interp: Interp::U64 }, vec![from_vec::<u64>(xs), from_vec::<f64>(qs.clone())]);
+        let got = match &st[0] { Value::Prim(Prim::P64(v)) => v.clone(), _ => panic!() };
+        for (&g, &q) in got.iter().zip(&qs) {
+            assert!(rank_err(&sorted, g, q) < 0.01, "q {} got {}", q, g);
+        }
+    }
+
+    #[test]
+    fn quantile_sketch_stays_small_while_streaming() {
+        // Building never holds a whole group: every level ends at most
+        // `KLL_K` items, so the sketch is O(k log(n/k)) for any n.
+        let xs = pseudo(300_000, 31, 1 << 32);
+        let mut k = Kll::<u64>::new();
+        k.extend(&xs);
+        assert!(k.levels.iter().all(|l| l.len() <= KLL_K));
+        let retained: usize = k.levels.iter().map(Vec::len).sum();
+        assert!(retained <= KLL_K * k.levels.len());
+        assert!(k.levels.len() <= (xs.len() / KLL_K).ilog2() as usize + 2, "{} levels", k.levels.len());
+        let weight: u64 = k.levels.iter().enumerate().map(|(h, l)| (l.len() as u64) << h).sum();
+        assert_eq!(weight, xs.len() as u64);
+    }
+
+    #[test]
+    fn quantile_merge_within_bounds() {
+        let xs = pseudo(100_000, 29, 1_000_000);
+        let mut sorted = xs.clone();
+        sorted.sort_unstable();
+        let (a, b) = xs.split_at(40_000);
+        let op = QuantileSketch { interp: Interp::U64 };
+        let sa = run1(&op, vec![from_vec::<u64>(a.to_vec())]).pop().unwrap();
+        let sb = run1(&op, vec![from_vec::<u64>(b.to_vec())]).pop().unwrap();
+        let merged = run1(&QuantileMerge { interp: Interp::U64 }, vec![sa, sb]).pop().unwrap();
+        let qs = vec![0.1, 0.5, 0.9];
+        let st = run1(&QuantileEstimate { interp: Interp::U64 }, vec![merged, from_vec::<f64>(qs.clone())]);
+        let got = match &st[0] { Value::Prim(Prim::P64(v)) => v.clone(), _ => panic!() };
+        for (&g, &q) in got.iter().zip(&qs) {
+            assert!(rank_err(&sorted, g, q) < 0.01, "q {} got {}", q, g);
+        }
+    }
+
+    #[test]
+    fn quantile_per_row_signed_and_small_rows_exact() {
+        // Rows under KLL_K are never compacted, so quantiles are exact.
+        let rows = list(bounds_var_from_ends(vec![5, 8]), from_vec::<i64>(vec![3, -1, 4, -5, 9, 7, 7, -2]));
+        let st = run1(&ApproxQuantile { interp: Interp::I64 }, vec![rows, from_vec::<f64>(vec![0.0, 0.5, 1.0])]);
+        assert_eq!(st[0], list(bounds_stride(3, 2), from_vec::<i64>(vec![-5, 3, 9, -2, 7, 7])));
+    }
+
+    #[test]
+    fn quantile_per_row_fills_empty_rows() {
+        use crate::syntax::registry::OpRegistry;
+        use crate::syntax::parse::parse;
+        let reg = OpRegistry::standard();
+        let run = |src: &str| {
+            let (g, _) = crate::pipeline::build(parse(src, &reg).unwrap()).unwrap();
+            crate::pipeline::eval_graph(&g)
+        };
+        let st = run("u64[1 2 3 4] u64[0 2 2 4] nest f64[0.5] approx.quantile.u64").unwrap();
+        assert_eq!(st[0], list(bounds_stride(1, 3), from_vec::<u64>(vec![1, 0, 3])));
+        // Through a sketch, and as NaN for a float interp.
+        let st = run("f64[1.0 2.0] u64[0 0 2] nest approx.quantile.sketch.f64 f64[0.0 1.0] approx.quantile.estimate.f64").unwrap();
+        let Value::List { values, .. } = &st[0] else { panic!("{:?}", st[0]) };
+        let Value::Prim(Prim::P64(bits)) = values.as_ref() else { panic!("{:?}", values) };
+        let got: Vec<f64> = bits.iter().map(|&b| f64::from_bits(b)).collect();
+        assert!(got[0].is_nan() && got[1].is_nan() && got[2..] == [1.0, 2.0], "{:?}", got);
+        // A flat column still needs a row.
+        let e = run("u64[] f64[0.5] approx.quantile.u64").unwrap_err();
+        assert!(e.contains("empty input"), "{}", e);
+    }
+
+    #[test]
+    fn quantile_f64_end_to_end() {
+        use crate::syntax::registry::OpRegistry;
+        use crate::syntax::parse::parse;
+        let reg = OpRegistry::standard();
+        let prog = parse("f64[2.5 -1.0 8.0 0.5] f64[0.5] approx.quantile.f64", &reg).unwrap();
+        let (g, _) = crate::pipeline::build(prog).unwrap();
+        let st = crate::pipeline::eval_graph(&g).unwrap();
+        assert_eq!(st[0], from_vec::<f64>(vec![0.5]));
+    }
+}
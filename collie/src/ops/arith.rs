@@ -8,7 +8,7 @@
 //! match arms in the file and can be skipped on a first read.
 
 use crate::ir::op::PrimOp;
-use crate::ir::stack::{Stack, pop, pop_raw, materialize_top};
+use crate::ir::stack::{Stack, pop, pop_flat, materialize_top};
 use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
 use crate::ir::value::{Value, Prim, Selector, Storage};
 use crate::ir::shape::{Interp, Shape, prim_width};
@@ -17,7 +17,7 @@ use crate::ops::helpers::{extract_prim, list_elementwise1, list_elementwise2};
 #[derive(Copy, Clone, Debug)]
 pub enum ArithOp { Add, Sub, Mul, Div, Mod }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Arith { pub op: ArithOp, pub interp: Interp }
 
 impl Arith {
@@ -40,14 +40,18 @@ impl PrimOp for Arith {
 }
 
 impl Typed for Arith {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { tc(self.interp, st) }
 }
 
 /// The binary-arith kernel (back-end `SystemOp::Arith` calls this directly;
 /// the struct's `run` is a thin shim).
 pub fn run(op: ArithOp, interp: Interp, st: &mut Stack) -> Result<(), String> {
-        let b = pop_raw(st)?;
-        let a = pop_raw(st)?;
+        // pop_flat: the fast paths below only want flat Views over a Prim
+        // (and encoded columns); a row-shaped View comes back as its List
+        // so the segmented path sees it.
+        let b = pop_flat(st)?;
+        let a = pop_flat(st)?;
 
         // Segmented (List-preserving) path: equal-bounds Lists, or a List
         // against a length-1 scalar. Arithmetic never crosses a row boundary,
@@ -523,7 +527,7 @@ pub fn do_arith(a: &Prim, b: &Prim, op: ArithOp, interp: Interp) -> Result<Prim,
 #[derive(Copy, Clone, Debug)]
 pub enum UnaryArithOp { Neg, Abs }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UnaryArith { pub op: UnaryArithOp, pub interp: Interp }
 
 /// Operator name — free fn so `SystemOp::UnaryArith` names it without a struct.
@@ -537,6 +541,7 @@ impl PrimOp for UnaryArith {
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { unary_run(self.op, self.interp, st) }
 }
 impl Typed for UnaryArith {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { unary_tc(self.op, self.interp, st) }
 }
 
@@ -0,0 +1,732 @@
+//! Calendar ops over `date` and `timestamp` columns.
+//!
+//! Two interpretation tags, both proleptic Gregorian in UTC:
+//!
+//! - `date` — days since 1970-01-01, an `i32` column (`P32`).
+//! - `timestamp` — microseconds since 1970-01-01T00:00:00Z, an `i64`
+//!   column (`P64`).
+//!
+//! The tag is an op suffix (like an interp, it isn't recorded in the
+//! value), and the header types `date` / `timestamp` (`syntax::header`)
+//! read and write ISO-8601 text in the runner. Ops:
+//!
+//! - `trunc.<unit>.<t>` — round down to `second`, `minute`, `hour`, `day`,
+//!   `week` (ISO: Monday), `month`, `quarter` or `year`; same tag out.
+//! - `extract.<field>.<t>` — `year`, `quarter`, `month`, `day`, `weekday`
+//!   (ISO: Monday = 1 … Sunday = 7), `yearday` (1-based), and for
+//!   timestamps `hour`, `minute`, `second`, `micros`; an `i32` column.
+//! - `add.months.<t>` — `vals months → vals`, `months` an `i32` column
+//!   (or one-element scalar); the day clamps to the target month's length
+//!   (Jan 31 + 1 month = Feb 28/29) and time of day is kept.
+//! - `parse.<t>` — `List<P8>` ISO-8601 text → `Sum{err | ok}`: lane 0
+//!   holds the rows that didn't parse (their text), lane 1 the values.
+//! - `format.<t>` — values → `List<P8>` ISO-8601 text.
+//!
+//! All but `parse`/`format` are element-wise over flat columns and over a
+//! `List`'s values (bounds unchanged). The civil-date conversions are
+//! Howard Hinnant's `days_from_civil` / `civil_from_days`: branch-light
+//! integer arithmetic, one pass per column.
+
+use std::fmt;
+use std::sync::Arc;
+use crate::ir::op::PrimOp;
+use crate::ir::stack::{Stack, pop};
+use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
+use crate::ir::value::{Value, Prim, PrimWidth, Storage, from_vec, list, sum, bounds_var_from_ends};
+use crate::ir::shape::{Shape, prim_width};
+use crate::ops::helpers::{extract_prim, list_elementwise1, list_elementwise2, materialize_ref};
+
+pub const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// Which calendar interpretation a column has.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Temporal { Date, Timestamp }
+
+impl Temporal {
+    pub fn width(self) -> PrimWidth {
+        match self { Temporal::Date => PrimWidth::W32, Temporal::Timestamp => PrimWidth::W64 }
+    }
+    pub fn parse(s: &str) -> Option<Temporal> {
+        match s { "date" => Some(Temporal::Date), "timestamp" => Some(Temporal::Timestamp), _ => None }
+    }
+}
+
+impl fmt::Display for Temporal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self { Temporal::Date => "date", Temporal::Timestamp => "timestamp" })
+    }
+}
+
+/// `trunc.<unit>` granularity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit { Second, Minute, Hour, Day, Week, Month, Quarter, Year }
+
+impl Unit {
+    fn parse(s: &str) -> Option<Unit> {
+        Some(match s {
+            "second" => Unit::Second, "minute" => Unit::Minute, "hour" => Unit::Hour,
+            "day" => Unit::Day, "week" => Unit::Week, "month" => Unit::Month,
+            "quarter" => Unit::Quarter, "year" => Unit::Year,
+            _ => return None,
+        })
+    }
+    fn name(self) -> &'static str {
+        match self {
+            Unit::Second => "second", Unit::Minute => "minute", Unit::Hour => "hour",
+            Unit::Day => "day", Unit::Week => "week", Unit::Month => "month",
+            Unit::Quarter => "quarter", Unit::Year => "year",
+        }
+    }
+    fn sub_day(self) -> bool { matches!(self, Unit::Second | Unit::Minute | Unit::Hour) }
+}
+
+/// `extract.<field>` component.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field { Year, Quarter, Month, Day, Weekday, Yearday, Hour, Minute, Second, Micros }
+
+impl Field {
+    fn parse(s: &str) -> Option<Field> {
+        Some(match s {
+            "year" => Field::Year, "quarter" => Field::Quarter, "month" => Field::Month,
+            "day" => Field::Day, "weekday" => Field::Weekday, "yearday" => Field::Yearday,
+            "hour" => Field::Hour, "minute" => Field::Minute, "second" => Field::Second,
+            "micros" => Field::Micros,
+            _ => return None,
+        })
+    }
+    fn name(self) -> &'static str {
+        match self {
+            Field::Year => "year", Field::Quarter => "quarter", Field::Month => "month",
+            Field::Day => "day", Field::Weekday => "weekday", Field::Yearday => "yearday",
+            Field::Hour => "hour", Field::Minute => "minute", Field::Second => "second",
+            Field::Micros => "micros",
+        }
+    }
+    fn sub_day(self) -> bool { matches!(self, Field::Hour | Field::Minute | Field::Second | Field::Micros) }
+}
+
+// ── Civil calendar ─────────────────────────────────────────────────────────
+
+/// Days since 1970-01-01 of `y-m-d` (`m` in 1..=12, `d` in 1..=31), or
+/// `None` when the count doesn't fit an `i64`.
+pub fn days_from_civil(y: i64, m: u32, d: u32) -> Option<i64> {
+    let y = if m <= 2 { y.checked_sub(1)? } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (m as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era.checked_mul(146_097)?.checked_add(doe - 719_468)
+}
+
+/// `(year, month, day)` of a day count since 1970-01-01. `z` must be a
+/// date or timestamp day count (|z| < 2^47), far inside the `i64` range.
+pub fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (yoe + era * 400 + (m <= 2) as i64, m, d)
+}
+
+fn is_leap(y: i64) -> bool { y % 4 == 0 && (y % 100 != 0 || y % 400 == 0) }
+
+fn days_in_month(y: i64, m: u32) -> u32 {
+    match m {
+        2 => if is_leap(y) { 29 } else { 28 },
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// ISO weekday, Monday = 1 … Sunday = 7 (1970-01-01 was a Thursday).
+fn weekday(days: i64) -> i64 { (days + 3).rem_euclid(7) + 1 }
+
+fn trunc_days(unit: Unit, days: i64) -> Option<i64> {
+    match unit {
+        Unit::Second | Unit::Minute | Unit::Hour | Unit::Day => Some(days),
+        Unit::Week => days.checked_sub(weekday(days) - 1),
+        Unit::Month | Unit::Quarter | Unit::Year => {
+            let (y, m, _) = civil_from_days(days);
+            let m = match unit { Unit::Month => m, Unit::Quarter => (m - 1) / 3 * 3 + 1, _ => 1 };
+            days_from_civil(y, m, 1)
+        }
+    }
+}
+
+fn trunc_micros(unit: Unit, t: i64) -> Option<i64> {
+    let step = match unit {
+        Unit::Second => 1_000_000,
+        Unit::Minute => 60_000_000,
+        Unit::Hour => 3_600_000_000,
+        _ => return trunc_days(unit, t.div_euclid(MICROS_PER_DAY))?.checked_mul(MICROS_PER_DAY),
+    };
+    t.checked_sub(t.rem_euclid(step))
+}
+
+fn extract_days(field: Field, days: i64) -> Option<i64> {
+    let (y, m, d) = civil_from_days(days);
+    Some(match field {
+        Field::Year => y,
+        Field::Quarter => (m as i64 - 1) / 3 + 1,
+        Field::Month => m as i64,
+        Field::Day => d as i64,
+        Field::Weekday => weekday(days),
+        Field::Yearday => days - days_from_civil(y, 1, 1)? + 1,
+        Field::Hour | Field::Minute | Field::Second | Field::Micros => 0,
+    })
+}
+
+fn extract_micros(field: Field, t: i64) -> Option<i64> {
+    let tod = t.rem_euclid(MICROS_PER_DAY);
+    Some(match field {
+        Field::Hour => tod / 3_600_000_000,
+        Field::Minute => tod / 60_000_000 % 60,
+        Field::Second => tod / 1_000_000 % 60,
+        Field::Micros => tod % 1_000_000,
+        _ => return extract_days(field, t.div_euclid(MICROS_PER_DAY)),
+    })
+}
+
+fn add_months_days(days: i64, k: i64) -> Option<i64> {
+    let (y, m, d) = civil_from_days(days);
+    let total = y.checked_mul(12)?.checked_add(m as i64 - 1)?.checked_add(k)?;
+    let (y, m) = (total.div_euclid(12), (total.rem_euclid(12) + 1) as u32);
+    days_from_civil(y, m, d.min(days_in_month(y, m)))
+}
+
+fn add_months_micros(t: i64, k: i64) -> Option<i64> {
+    let (days, tod) = (t.div_euclid(MICROS_PER_DAY), t.rem_euclid(MICROS_PER_DAY));
+    add_months_days(days, k)?.checked_mul(MICROS_PER_DAY)?.checked_add(tod)
+}
+
+// ── ISO-8601 text ──────────────────────────────────────────────────────────
+
+/// `YYYY-MM-DD` (years outside 0..=9999 carry a sign, as ISO-8601 expands them).
+pub fn format_date(days: i64) -> String {
+    let (y, m, d) = civil_from_days(days);
+    if (0..=9999).contains(&y) { format!("{:04}-{:02}-{:02}", y, m, d) } else { format!("{:+05}-{:02}-{:02}", y, m, d) }
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ`, with `.ffffff` when the microseconds aren't 0.
+pub fn format_timestamp(t: i64) -> String {
+    let (days, tod) = (t.div_euclid(MICROS_PER_DAY), t.rem_euclid(MICROS_PER_DAY));
+    let (s, us) = (tod / 1_000_000, tod % 1_000_000);
+    let hms = format!("{:02}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60);
+    if us == 0 {
+        format!("{}T{}Z", format_date(days), hms)
+    } else {
+        format!("{}T{}.{:06}Z", format_date(days), hms, us)
+    }
+}
+
+/// A cursor over ASCII text.
+struct Cursor<'a> { s: &'a [u8], i: usize }
+
+impl Cursor<'_> {
+    fn peek(&self) -> Option<u8> { self.s.get(self.i).copied() }
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) { self.i += 1; true } else { false }
+    }
+    /// Exactly `n` digits (at least `n` when `more`).
+    fn digits(&mut self, n: usize, more: bool) -> Option<i64> {
+        let start = self.i;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) && (more || self.i - start < n) { self.i += 1; }
+        if self.i - start < n || self.i - start > 18 { return None; }
+        std::str::from_utf8(&self.s[start..self.i]).ok()?.parse().ok()
+    }
+    fn done(&self) -> bool { self.i == self.s.len() }
+}
+
+fn date_at(c: &mut Cursor) -> Option<i64> {
+    let neg = if c.eat(b'-') { true } else { c.eat(b'+'); false };
+    let y = c.digits(4, true)?;
+    let y = if neg { -y } else { y };
+    if !c.eat(b'-') { return None; }
+    let m = c.digits(2, false)? as u32;
+    if !c.eat(b'-') { return None; }
+    let d = c.digits(2, false)? as u32;
+    if !(1..=12).contains(&m) || d == 0 || d > days_in_month(y, m) { return None; }
+    days_from_civil(y, m, d)
+}
+
+/// `YYYY-MM-DD`.
+pub fn parse_date(s: &[u8]) -> Option<i64> {
+    let mut c = Cursor { s, i: 0 };
+    let d = date_at(&mut c)?;
+    c.done().then_some(d)
+}
+
+/// `YYYY-MM-DD`, optionally followed by `T` (or a space) and
+/// `HH:MM[:SS[.fraction]]` and a zone (`Z` or `±HH[:MM]`; none means UTC).
+/// Fractions beyond microseconds are truncated.
+pub fn parse_timestamp(s: &[u8]) -> Option<i64> {
+    let mut c = Cursor { s, i: 0 };
+    let days = date_at(&mut c)?;
+    if c.done() { return days.checked_mul(MICROS_PER_DAY); }
+    if !(c.eat(b'T') || c.eat(b't') || c.eat(b' ')) { return None; }
+    let h = c.digits(2, false)?;
+    if !c.eat(b':') { return None; }
+    let mi = c.digits(2, false)?;
+    let (mut sec, mut us) = (0, 0);
+    if c.eat(b':') {
+        sec = c.digits(2, false)?;
+        if c.eat(b'.') || c.eat(b',') {
+            let start = c.i;
+            while c.peek().is_some_and(|d| d.is_ascii_digit()) { c.i += 1; }
+            let frac = &s[start..c.i];
+            if frac.is_empty() { return None; }
+            us = frac.iter().chain(std::iter::repeat(&b'0')).take(6).fold(0i64, |a, &d| a * 10 + (d - b'0') as i64);
+        }
+    }
+    if h > 23 || mi > 59 || sec > 59 { return None; }
+    let offset = match c.peek() {
+        None => 0,
+        Some(b'Z') | Some(b'z') => { c.i += 1; 0 }
+        Some(sign @ (b'+' | b'-')) => {
+            c.i += 1;
+            let oh = c.digits(2, false)?;
+            let om = if c.done() { 0 } else { c.eat(b':'); c.digits(2, false)? };
+            if oh > 23 || om > 59 { return None; }
+            let o = (oh * 60 + om) * 60_000_000;
+            if sign == b'+' { o } else { -o }
+        }
+        Some(_) => return None,
+    };
+    if !c.done() { return None; }
+    let tod = ((h * 60 + mi) * 60 + sec) * 1_000_000 + us;
+    days.checked_mul(MICROS_PER_DAY)?.checked_add(tod)?.checked_sub(offset)
+}
+
+// ── Column kernels ─────────────────────────────────────────────────────────
+
+/// A `t`-tagged Prim column as i64s.
+fn values(p: &Prim, t: Temporal, ctx: &str) -> Result<Vec<i64>, String> {
+    match t {
+        Temporal::Date => Ok(<i32 as Storage>::extract(p).map_err(|_| format!("{}: expected a date (P32) column, got {:?}", ctx, p.width()))?
+            .iter().map(|&d| d as i64).collect()),
+        Temporal::Timestamp => Ok(<i64 as Storage>::extract(p).map_err(|_| format!("{}: expected a timestamp (P64) column, got {:?}", ctx, p.width()))?
+            .to_vec()),
+    }
+}
+
+/// i64s back to a `t`-tagged column; dates must fit `i32`.
+fn column(xs: Vec<i64>, t: Temporal, ctx: &str) -> Result<Value, String> {
+    match t {
+        Temporal::Date => xs.into_iter()
+            .map(|d| i32::try_from(d).map_err(|_| format!("{}: date {} days out of range", ctx, d)))
+            .collect::<Result<Vec<i32>, String>>()
+            .map(from_vec::<i32>),
+        Temporal::Timestamp => Ok(from_vec::<i64>(xs)),
+    }
+}
+
+/// Map `f` over a flat column or a List's values; `f` returns `None` when
+/// the result overflows.
+fn map_column(v: &Value, t: Temporal, ctx: &str, f: impl Fn(i64) -> Option<i64>, out: Temporal) -> Result<Value, String> {
+    let go = |v: &Value| -> Result<Value, String> {
+        let xs = values(&extract_prim(v, ctx)?, t, ctx)?;
+        let ys = xs.into_iter()
+            .map(|x| f(x).ok_or_else(|| format!("{}.{}: {} out of range", ctx, t, x)))
+            .collect::<Result<Vec<i64>, String>>()?;
+        column(ys, out, ctx)
+    };
+    list_elementwise1(v, go).unwrap_or_else(|| go(v))
+}
+
+/// `Prim(w)` or `List<Prim(w)>` with `w` the tag's width; returns the
+/// same shape over `out`.
+fn tc_column(tag: &str, t: Temporal, v: &Shape, out: PrimWidth) -> Result<Shape, String> {
+    let w = t.width();
+    match v {
+        Shape::Prim(vw) if *vw == w => Ok(Shape::Prim(out)),
+        Shape::List { bounds, inner } if prim_width(inner) == Some(w) => {
+            Ok(Shape::List { bounds: *bounds, inner: Box::new(Shape::Prim(out)) })
+        }
+        _ => Err(format!("{}.{}: needs Prim({}) or List<Prim({})>, got {}", tag, t, w, w, v)),
+    }
+}
+
+fn text_shape() -> Shape {
+    Shape::List { bounds: PrimWidth::W64, inner: Box::new(Shape::Prim(PrimWidth::W8)) }
+}
+
+/// `trunc.<unit>.<t>` — round each value down to `unit`.
+#[derive(Debug, Clone)] pub struct Trunc { pub unit: Unit, pub t: Temporal }
+impl Trunc {
+    fn check(&self) -> Result<(), String> {
+        if self.t == Temporal::Date && self.unit.sub_day() {
+            return Err(format!("trunc.{:?}.date: dates have no time of day", self.unit).to_lowercase());
+        }
+        Ok(())
+    }
+}
+impl PrimOp for Trunc {
+    fn name(&self) -> &str { "trunc" }
+    fn token(&self) -> Option<String> { Some(format!("trunc.{}.{}", self.unit.name(), self.t)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        self.check()?;
+        let v = pop(st)?;
+        let unit = self.unit;
+        let out = match self.t {
+            Temporal::Date => map_column(&v, self.t, "trunc", |d| trunc_days(unit, d), self.t)?,
+            Temporal::Timestamp => map_column(&v, self.t, "trunc", |x| trunc_micros(unit, x), self.t)?,
+        };
+        st.push(out);
+        Ok(())
+    }
+}
+impl Typed for Trunc {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        self.check()?;
+        let v = tc_pop(st, "trunc")?;
+        st.push(tc_column("trunc", self.t, &v, self.t.width())?);
+        Ok(())
+    }
+}
+
+/// `extract.<field>.<t>` — one calendar component per value, as `i32`.
+#[derive(Debug, Clone)] pub struct Extract { pub field: Field, pub t: Temporal }
+impl Extract {
+    fn check(&self) -> Result<(), String> {
+        if self.t == Temporal::Date && self.field.sub_day() {
+            return Err(format!("extract.{:?}.date: dates have no time of day", self.field).to_lowercase());
+        }
+        Ok(())
+    }
+}
+impl PrimOp for Extract {
+    fn name(&self) -> &str { "extract" }
+    fn token(&self) -> Option<String> { Some(format!("extract.{}.{}", self.field.name(), self.t)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        self.check()?;
+        let v = pop(st)?;
+        let field = self.field;
+        // Every field fits an i32 (years within the date range do).
+        let out = match self.t {
+            Temporal::Date => map_column(&v, self.t, "extract", |d| extract_days(field, d), Temporal::Date)?,
+            Temporal::Timestamp => map_column(&v, self.t, "extract", |x| extract_micros(field, x), Temporal::Date)?,
+        };
+        st.push(out);
+        Ok(())
+    }
+}
+impl Typed for Extract {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        self.check()?;
+        let v = tc_pop(st, "extract")?;
+        st.push(tc_column("extract", self.t, &v, PrimWidth::W32)?);
+        Ok(())
+    }
+}
+
+/// `add.months.<t>` — `vals months → vals`; `months` is an `i32` column of
+/// the same length, or a one-element scalar.
+#[derive(Debug, Clone)] pub struct AddMonths { pub t: Temporal }
+impl PrimOp for AddMonths {
+    fn name(&self) -> &str { "add.months" }
+    fn token(&self) -> Option<String> { Some(format!("add.months.{}", self.t)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let ks = pop(st)?;
+        let v = pop(st)?;
+        let t = self.t;
+        let go = |v: &Value, ks: &Value| -> Result<Value, String> {
+            let xs = values(&extract_prim(v, "add.months")?, t, "add.months")?;
+            let ks = <i32 as Storage>::extract(&extract_prim(ks, "add.months")?)
+                .map_err(|_| "add.months: months must be an i32 column".to_string())?
+                .to_vec();
+            if ks.len() != 1 && ks.len() != xs.len() {
+                return Err(format!("add.months: {} values but {} month counts", xs.len(), ks.len()));
+            }
+            let k = |i: usize| ks[if ks.len() == 1 { 0 } else { i }] as i64;
+            let f = match t { Temporal::Date => add_months_days, Temporal::Timestamp => add_months_micros };
+            let ys = xs.iter().enumerate()
+                .map(|(i, &x)| f(x, k(i)).ok_or_else(|| format!("add.months.{}: {} + {} months out of range", t, x, k(i))))
+                .collect::<Result<Vec<i64>, String>>()?;
+            column(ys, t, "add.months")
+        };
+        st.push(list_elementwise2(&v, &ks, go).unwrap_or_else(|| go(&v, &ks))?);
+        Ok(())
+    }
+}
+impl Typed for AddMonths {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let ks = tc_pop(st, "add.months")?;
+        let v = tc_pop(st, "add.months")?;
+        let out = tc_column("add.months", self.t, &v, self.t.width())?;
+        let ks_ok = match (&v, &ks) {
+            (_, Shape::Prim(PrimWidth::W32)) => true,
+            (Shape::List { .. }, Shape::List { inner, .. }) => **inner == Shape::Prim(PrimWidth::W32),
+            _ => false,
+        };
+        if !ks_ok { return Err(format!("add.months.{}: months must be Prim(P32) i32, got {}", self.t, ks)); }
+        st.push(out);
+        Ok(())
+    }
+}
+
+/// `parse.<t>` — `List<P8>` text → `Sum{err: List<P8> | ok: t}`.
+#[derive(Debug, Clone)] pub struct ParseIso { pub t: Temporal }
+impl PrimOp for ParseIso {
+    fn name(&self) -> &str { "parse" }
+    fn token(&self) -> Option<String> { Some(format!("parse.{}", self.t)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let ctx = format!("parse.{}", self.t);
+        let (bounds, bytes) = match pop(st)? {
+            Value::List { bounds, values } => match materialize_ref(&values)?.as_ref() {
+                Value::Prim(Prim::P8(b)) => (bounds, b.clone()),
+                other => return Err(format!("{}: expected List<P8> text, got List of {}", ctx, other)),
+            },
+            other => return Err(format!("{}: expected List<P8> text, got {}", ctx, other)),
+        };
+        let mut disc = Vec::with_capacity(bounds.len());
+        let (mut ok, mut bad, mut bad_ends) = (Vec::new(), Vec::new(), Vec::new());
+        for (lo, hi) in bounds.iter_pairs() {
+            let s = &bytes[lo as usize..hi as usize];
+            let parsed = match self.t {
+                Temporal::Date => parse_date(s).filter(|d| i32::try_from(*d).is_ok()),
+                Temporal::Timestamp => parse_timestamp(s),
+            };
+            match parsed {
+                Some(x) => { disc.push(1u8); ok.push(x); }
+                None => { disc.push(0u8); bad.extend_from_slice(s); bad_ends.push(bad.len() as u64); }
+            }
+        }
+        let bad = list(bounds_var_from_ends(bad_ends), from_vec::<u8>(bad));
+        st.push(sum(Prim::P8(Arc::new(disc)), vec![bad, column(ok, self.t, &ctx)?]));
+        Ok(())
+    }
+}
+impl Typed for ParseIso {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let v = tc_pop(st, "parse")?;
+        if !matches!(&v, Shape::List { inner, .. } if **inner == Shape::Prim(PrimWidth::W8)) {
+            return Err(format!("parse.{}: expected List<P8> text, got {}", self.t, v));
+        }
+        st.push(Shape::Sum { disc: PrimWidth::W8, lanes: vec![text_shape(), Shape::Prim(self.t.width())] });
+        Ok(())
+    }
+}
+
+/// `format.<t>` — values → `List<P8>` ISO-8601 text.
+#[derive(Debug, Clone)] pub struct FormatIso { pub t: Temporal }
+impl PrimOp for FormatIso {
+    fn name(&self) -> &str { "format" }
+    fn token(&self) -> Option<String> { Some(format!("format.{}", self.t)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let ctx = format!("format.{}", self.t);
+        let xs = values(&extract_prim(&pop(st)?, &ctx)?, self.t, &ctx)?;
+        let mut bytes = Vec::with_capacity(xs.len() * 20);
+        let mut ends = Vec::with_capacity(xs.len());
+        for x in xs {
+            let s = match self.t { Temporal::Date => format_date(x), Temporal::Timestamp => format_timestamp(x) };
+            bytes.extend_from_slice(s.as_bytes());
+            ends.push(bytes.len() as u64);
+        }
+        st.push(list(bounds_var_from_ends(ends), from_vec::<u8>(bytes)));
+        Ok(())
+    }
+}
+impl Typed for FormatIso {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let v = tc_pop(st, "format")?;
+        if v != Shape::Prim(self.t.width()) {
+            return Err(format!("format.{}: needs Prim({}), got {}", self.t, self.t.width(), v));
+        }
+        st.push(text_shape());
+        Ok(())
+    }
+}
+
+pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
+    use crate::ir::typecheck::Op;
+    use crate::syntax::registry::split_suffix;
+    // trunc.<unit>.<t>, extract.<field>.<t>, add.months.<t>, parse.<t>, format.<t>
+    r.add(|tok: &str| -> Option<Box<dyn Op>> {
+        let (head, sfx) = split_suffix(tok)?;
+        let t = Temporal::parse(sfx)?;
+        match head {
+            "add.months" => Some(Box::new(AddMonths { t })),
+            "parse" => Some(Box::new(ParseIso { t })),
+            "format" => Some(Box::new(FormatIso { t })),
+            _ => {
+                if let Some(u) = head.strip_prefix("trunc.") {
+                    return Some(Box::new(Trunc { unit: Unit::parse(u)?, t }));
+                }
+                let f = head.strip_prefix("extract.")?;
+                Some(Box::new(Extract { field: Field::parse(f)?, t }))
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::value::bounds_var;
+
+    fn run(op: &dyn PrimOp, mut st: Vec<Value>) -> Result<Value, String> {
+        op.run(&mut st, &mut Vec::new())?;
+        Ok(st.pop().unwrap())
+    }
+
+    fn text(rows: &[&str]) -> Value {
+        let mut ends = Vec::new();
+        let mut bytes = Vec::new();
+        for r in rows { bytes.extend_from_slice(r.as_bytes()); ends.push(bytes.len() as u64); }
+        list(bounds_var_from_ends(ends), from_vec::<u8>(bytes))
+    }
+
+    fn i64s(v: &Value) -> Vec<i64> {
+        match v {
+            Value::Prim(p @ Prim::P32(_)) => <i32 as Storage>::extract(p).unwrap().iter().map(|&x| x as i64).collect(),
+            Value::Prim(p) => <i64 as Storage>::extract(p).unwrap().to_vec(),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn civil_round_trip_and_known_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1).unwrap(), 0);
+        assert_eq!(days_from_civil(2000, 3, 1).unwrap(), 11_017);
+        assert_eq!(days_from_civil(1969, 12, 31).unwrap(), -1);
+        assert_eq!(civil_from_days(-719_468), (0, 3, 1));
+        // Every day across several 400-year eras, including negative years.
+        let mut prev = days_from_civil(-800, 1, 1).unwrap() - 1;
+        for y in -800..=2400 {
+            for m in 1..=12 {
+                for d in 1..=days_in_month(y, m) {
+                    let z = days_from_civil(y, m, d).unwrap();
+                    assert_eq!(z, prev + 1);
+                    assert_eq!(civil_from_days(z), (y, m, d));
+                    prev = z;
+                }
+            }
+        }
+        assert_eq!(weekday(0), 4); // Thursday
+        assert_eq!(weekday(days_from_civil(2024, 2, 29).unwrap()), 4);
+        assert_eq!(weekday(days_from_civil(2023, 1, 1).unwrap()), 7);
+    }
+
+    #[test]
+    fn iso_text_parses_and_formats() {
+        assert_eq!(parse_date(b"2024-02-29"), Some(days_from_civil(2024, 2, 29).unwrap()));
+        for bad in ["2023-02-29", "2024-13-01", "2024-1-01", "24-01-01", "2024-01-01x", ""] {
+            assert_eq!(parse_date(bad.as_bytes()), None, "{}", bad);
+        }
+        let t = parse_timestamp(b"2024-03-10T12:34:56.789123456+02:00").unwrap();
+        assert_eq!(format_timestamp(t), "2024-03-10T10:34:56.789123Z");
+        assert_eq!(parse_timestamp(b"2024-03-10 00:00-0130"), parse_timestamp(b"2024-03-10T01:30:00Z"));
+        assert_eq!(parse_timestamp(b"1969-12-31T23:59:59.5Z"), Some(-500_000));
+        assert_eq!(format_timestamp(-500_000), "1969-12-31T23:59:59.500000Z");
+        assert_eq!(parse_timestamp(b"2024-03-10"), Some(days_from_civil(2024, 3, 10).unwrap() * MICROS_PER_DAY));
+        for bad in ["2024-03-10T24:00", "2024-03-10T12", "2024-03-10T12:00:00.", "2024-03-10T12:00+5:00"] {
+            assert_eq!(parse_timestamp(bad.as_bytes()), None, "{}", bad);
+        }
+        assert_eq!(format_date(days_from_civil(-44, 3, 15).unwrap()), "-0044-03-15");
+        assert_eq!(format_date(days_from_civil(12345, 1, 1).unwrap()), "+12345-01-01");
+        assert_eq!(parse_date(b"-0044-03-15"), Some(days_from_civil(-44, 3, 15).unwrap()));
+
+        // `parse` splits rows into the err and ok lanes; `format` inverts ok.
+        let out = run(&ParseIso { t: Temporal::Date }, vec![text(&["2024-01-31", "nope", "1970-01-02"])]).unwrap();
+        let Value::Sum { disc, lanes } = &out else { panic!("{:?}", out) };
+        assert_eq!(<u8 as Storage>::extract(disc).unwrap(), &[1, 0, 1]);
+        assert_eq!(lanes[0], text(&["nope"]));
+        assert_eq!(i64s(&lanes[1]), vec![days_from_civil(2024, 1, 31).unwrap(), 1]);
+        let back = run(&FormatIso { t: Temporal::Date }, vec![lanes[1].clone()]).unwrap();
+        assert_eq!(back, text(&["2024-01-31", "1970-01-02"]));
+    }
+
+    #[test]
+    fn trunc_extract_and_add_months() {
+        let ts = parse_timestamp(b"2024-05-15T13:45:30.25Z").unwrap();
+        let col = from_vec::<i64>(vec![ts, -1]);
+        let trunc = |unit| i64s(&run(&Trunc { unit, t: Temporal::Timestamp }, vec![col.clone()]).unwrap())
+            .into_iter().map(format_timestamp).collect::<Vec<_>>();
+        assert_eq!(trunc(Unit::Second), ["2024-05-15T13:45:30Z", "1969-12-31T23:59:59Z"]);
+        assert_eq!(trunc(Unit::Hour), ["2024-05-15T13:00:00Z", "1969-12-31T23:00:00Z"]);
+        assert_eq!(trunc(Unit::Week), ["2024-05-13T00:00:00Z", "1969-12-29T00:00:00Z"]);
+        assert_eq!(trunc(Unit::Quarter), ["2024-04-01T00:00:00Z", "1969-10-01T00:00:00Z"]);
+        assert_eq!(trunc(Unit::Year), ["2024-01-01T00:00:00Z", "1969-01-01T00:00:00Z"]);
+        let extract = |field| i64s(&run(&Extract { field, t: Temporal::Timestamp }, vec![col.clone()]).unwrap());
+        assert_eq!(extract(Field::Year), [2024, 1969]);
+        assert_eq!(extract(Field::Weekday), [3, 3]);
+        assert_eq!(extract(Field::Yearday), [136, 365]);
+        assert_eq!(extract(Field::Minute), [45, 59]);
+        assert_eq!(extract(Field::Micros), [250_000, 999_999]);
+        assert!(run(&Extract { field: Field::Hour, t: Temporal::Date }, vec![from_vec::<i32>(vec![0])]).is_err());
+
+        // Per-row over a List, clamping to month end, one scalar count.
+        let days = |s: &str| parse_date(s.as_bytes()).unwrap() as i32;
+        let l = list(bounds_var(vec![0, 2, 3]), from_vec::<i32>(vec![days("2024-01-31"), days("2023-03-31"), days("2024-02-29")]));
+        let out = run(&AddMonths { t: Temporal::Date }, vec![l, from_vec::<i32>(vec![1])]).unwrap();
+        let Value::List { bounds, values } = &out else { panic!("{:?}", out) };
+        assert_eq!(bounds.iter_pairs().collect::<Vec<_>>(), vec![(0, 2), (2, 3)]);
+        let got: Vec<String> = i64s(values).into_iter().map(format_date).collect();
+        assert_eq!(got, ["2024-02-29", "2023-04-30", "2024-03-29"]);
+        let out = run(&AddMonths { t: Temporal::Date }, vec![from_vec::<i32>(vec![days("2024-02-29"); 2]), from_vec::<i32>(vec![12, -25])]).unwrap();
+        assert_eq!(i64s(&out).into_iter().map(format_date).collect::<Vec<_>>(), ["2025-02-28", "2022-01-29"]);
+    }
+
+    #[test]
+    fn extremes_error_instead_of_overflowing() {
+        let ts = |xs: Vec<i64>| from_vec::<i64>(xs);
+        let months = |k: i32| from_vec::<i32>(vec![k]);
+        let e = run(&AddMonths { t: Temporal::Timestamp }, vec![ts(vec![0]), months(i32::MAX)]).unwrap_err();
+        assert!(e.contains("add.months.timestamp: 0 + 2147483647 months out of range"), "{}", e);
+        assert!(run(&AddMonths { t: Temporal::Timestamp }, vec![ts(vec![i64::MAX]), months(1)]).is_err());
+        assert!(run(&AddMonths { t: Temporal::Date }, vec![from_vec::<i32>(vec![i32::MIN]), months(i32::MIN)]).is_err());
+        for unit in [Unit::Second, Unit::Day, Unit::Week, Unit::Month, Unit::Year] {
+            let e = run(&Trunc { unit, t: Temporal::Timestamp }, vec![ts(vec![i64::MIN])]).unwrap_err();
+            assert!(e.contains("trunc.timestamp: -9223372036854775808 out of range"), "{}", e);
+        }
+        assert!(run(&Trunc { unit: Unit::Week, t: Temporal::Date }, vec![from_vec::<i32>(vec![i32::MIN])]).is_err());
+        // The largest values that still fit come back unchanged.
+        let top = run(&Trunc { unit: Unit::Second, t: Temporal::Timestamp }, vec![ts(vec![i64::MAX])]).unwrap();
+        assert_eq!(i64s(&top), [i64::MAX - i64::MAX % 1_000_000]);
+        let e = run(&Extract { field: Field::Year, t: Temporal::Timestamp }, vec![ts(vec![i64::MIN, i64::MAX])]).unwrap();
+        assert_eq!(i64s(&e), [-290_308, 294_247]);
+
+        // Text with a huge year lands in the err lane instead of panicking.
+        assert_eq!(days_from_civil(i64::MIN, 1, 1), None);
+        assert_eq!(days_from_civil(i64::MAX, 12, 31), None);
+        for s in ["999999999999999999-01-01", "-999999999999999999-01-01", "300000-01-01"] {
+            assert_eq!(parse_timestamp(s.as_bytes()), None, "{}", s);
+        }
+        assert_eq!(parse_date(b"999999999999999999-01-01"), None);
+        let out = run(&ParseIso { t: Temporal::Date }, vec![text(&["999999999999999999-01-01", "2024-01-01"])]).unwrap();
+        let Value::Sum { disc, .. } = &out else { panic!("{:?}", out) };
+        assert_eq!(<u8 as Storage>::extract(disc).unwrap(), &[0, 1]);
+    }
+
+    #[test]
+    fn calendar_ops_parse_and_typecheck() {
+        use crate::syntax::{parse::parse, registry::OpRegistry};
+        let reg = OpRegistry::standard();
+        let run = |src: &str| crate::pipeline::build(parse(src, &reg).unwrap())
+            .and_then(|(g, _)| crate::pipeline::eval_graph(&g));
+        // 19_782 is 2024-02-29.
+        let out = run("i32[19782] dup i32[1] add.months.date swap trunc.month.date extract.day.date").unwrap();
+        assert_eq!(out, vec![from_vec::<i32>(vec![19_811]), from_vec::<i32>(vec![1])]);
+        let e = run("i32[0] trunc.hour.date").unwrap_err();
+        assert!(e.contains("trunc.hour.date: dates have no time of day"), "{}", e);
+        let e = run("i64[0] extract.year.date").unwrap_err();
+        assert!(e.contains("extract.date: needs Prim(P32)"), "{}", e);
+        assert!(parse("i32[0] trunc.fortnight.date", &reg).is_err());
+        assert!(parse("i32[0] extract.year.instant", &reg).is_err());
+    }
+}
@@ -4,6 +4,9 @@
 //! signed/float order needs order-form/swizzled inputs), and `=`/`!=`
 //! are bit-equality (float `=` is bitwise, not IEEE).
 //!
+//! An RLE operand against a scalar compares once per run and yields an
+//! RLE mask (see `ir::encoding`).
+//!
 //! Width-monomorphic kernels. The
 //! mask-aware fast paths (View<Mask> vs scalar, View<Mask> vs full
 //! Prim, View<Mask> × View<Mask>) take ~75% of the file and can be
@@ -12,9 +15,9 @@
 
 use std::sync::Arc;
 use crate::ir::op::PrimOp;
-use crate::ir::stack::{Stack, pop_raw, materialize_top};
+use crate::ir::stack::{Stack, pop_flat, materialize_top};
 use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
-use crate::ir::value::{Value, Prim, PrimWidth, Selector, Storage};
+use crate::ir::value::{Value, Encoded, Prim, PrimWidth, Selector, Storage};
 use crate::ir::shape::Shape;
 use crate::ir::shape::prim_width;
 use crate::ops::helpers::{extract_prim, list_elementwise2};
@@ -22,7 +25,7 @@ use crate::ops::helpers::{extract_prim, list_elementwise2};
 #[derive(Copy, Clone, Debug)]
 pub enum CmpOp { Lt, Le, Eq, Ne, Ge, Gt }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Cmp { pub op: CmpOp }
 
 impl Cmp {
@@ -46,6 +49,7 @@ impl PrimOp for Cmp {
 }
 
 impl Typed for Cmp {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { tc(st) }
 }
 
@@ -53,8 +57,11 @@ impl Typed for Cmp {
 /// the `Cmp` struct's `run` is a thin shim. Pops two operands; pushes a `P8`
 /// mask (or a `List<P8>` for the segmented equal-bounds-List path).
 pub fn run(op: CmpOp, st: &mut Stack) -> Result<(), String> {
-        let b = pop_raw(st)?;
-        let a = pop_raw(st)?;
+        // pop_flat: the fast paths below only want flat Views over a Prim
+        // (and encoded columns); a row-shaped View comes back as its List
+        // so the segmented path sees it.
+        let b = pop_flat(st)?;
+        let a = pop_flat(st)?;
 
         // Segmented (List-preserving) path: equal-bounds Lists, or a List
         // against a length-1 scalar. Comparing never crosses a row boundary,
@@ -68,6 +75,13 @@ pub fn run(op: CmpOp, st: &mut Stack) -> Result<(), String> {
             return Ok(());
         }
 
+        // RLE: compare once per run, yield an RLE mask (which `filter`
+        // turns into a `Runs` selection).
+        if let Some(out) = cmp_rle(&a, &b, op)? {
+            st.push(out);
+            return Ok(());
+        }
+
         // Mask-aware fast paths — chained-filter inner loop. `a` is
         // `View<Prim, Mask>`; depending on `b` we have three streaming
         // shapes that avoid materializing the masked view:
@@ -188,6 +202,20 @@ where T: Copy, F: Fn(T, T) -> bool
     })
 }
 
+/// An RLE column against a length-1 scalar (either side), or against
+/// another RLE column with the same run ends: the mask has the operand's
+/// runs, one comparison per run. `None` for any other pairing.
+fn cmp_rle(a: &Value, b: &Value, op: CmpOp) -> Result<Option<Value>, String> {
+    let rle = |values: Prim, ends: &Arc<Vec<u64>>| Value::Encoded(Encoded::Rle { values, ends: ends.clone() });
+    Ok(Some(match (a, b) {
+        (Value::Encoded(Encoded::Rle { values: va, ends: ea }), Value::Encoded(Encoded::Rle { values: vb, ends: eb }))
+            if ea == eb => rle(do_cmp(va, vb, op)?, ea),
+        (Value::Encoded(Encoded::Rle { values, ends }), Value::Prim(s)) if s.len() == 1 => rle(do_cmp(values, s, op)?, ends),
+        (Value::Prim(s), Value::Encoded(Encoded::Rle { values, ends })) if s.len() == 1 => rle(do_cmp(s, values, op)?, ends),
+        _ => return Ok(None),
+    }))
+}
+
 fn cmp_from_uninterp<T, F>(a: &Prim, b: &Prim, f: F) -> Result<Prim, String>
 where T: Storage + Copy, F: Fn(T, T) -> bool
 {
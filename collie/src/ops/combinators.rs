@@ -19,13 +19,14 @@ use crate::ir::value::{Value, PrimWidth, prod, sum, list};
 use crate::ir::shape::{Shape, disc_as_u8};
 use crate::ops::helpers::{gather, merge_by_disc};
 
-#[derive(Debug)] pub struct ZipN { pub n: usize }
+#[derive(Debug, Clone)] pub struct ZipN { pub n: usize }
 impl PrimOp for ZipN {
     fn name(&self) -> &str { "zip" }
     fn arity(&self) -> Option<(usize, usize)> { Some((self.n, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { zip_run(self.n, st) }
 }
 impl Typed for ZipN {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { zip_tc(self.n, st) }
 }
 /// `zipN` kernel (back-end `SystemOp::Zip` calls this directly).
@@ -53,13 +54,14 @@ pub fn zip_tc(n: usize, st: &mut TypeStack) -> Result<(), String> {
 /// `detuple.K` (or `detuple` for K=2) — opposite of `zipK`/`entupleK`. Pops a
 /// `Prod[A, B, …, K-th]` and pushes the fields onto the stack, last field on
 /// top. K is parse-time; the prod must have exactly K fields.
-#[derive(Debug)] pub struct DetupleN { pub n: usize }
+#[derive(Debug, Clone)] pub struct DetupleN { pub n: usize }
 impl PrimOp for DetupleN {
     fn name(&self) -> &str { "detuple" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, self.n)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { detuple_run(self.n, st) }
 }
 impl Typed for DetupleN {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { detuple_tc(self.n, st) }
 }
 /// `detupleN` kernel (back-end `SystemOp::Detuple` calls this directly).
@@ -90,13 +92,14 @@ pub fn detuple_tc(n: usize, st: &mut TypeStack) -> Result<(), String> {
         }
 }
 
-#[derive(Debug)] pub struct Proj { pub i: usize }
+#[derive(Debug, Clone)] pub struct Proj { pub i: usize }
 impl PrimOp for Proj {
     fn name(&self) -> &str { "." }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }  // Prod (or List<Prod>) → field
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { proj_run(self.i, st) }
 }
 impl Typed for Proj {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { proj_tc(self.i, st) }
 }
 /// `.i` projection kernel (back-end `SystemOp::Proj` calls this directly).
@@ -151,13 +154,14 @@ pub fn proj_tc(i: usize, st: &mut TypeStack) -> Result<(), String> {
         }
 }
 
-#[derive(Debug)] pub struct InjectN { pub n: usize }
+#[derive(Debug, Clone)] pub struct InjectN { pub n: usize }
 impl PrimOp for InjectN {
     fn name(&self) -> &str { "inject" }
     fn arity(&self) -> Option<(usize, usize)> { Some((self.n + 1, 1)) }  // disc + N lanes → Sum
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { inject_run(self.n, st) }
 }
 impl Typed for InjectN {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { inject_tc(self.n, st) }
 }
 /// `injectN` kernel (back-end `SystemOp::Inject` calls this directly).
@@ -195,12 +199,13 @@ pub fn inject_tc(n: usize, st: &mut TypeStack) -> Result<(), String> {
         Ok(())
 }
 
-#[derive(Debug)] pub struct Split;
+#[derive(Debug, Clone)] pub struct Split;
 impl PrimOp for Split {
     fn name(&self) -> &str { "split" }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { split_run(st) }
 }
 impl Typed for Split {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { split_tc(st) }
 }
 /// `split` kernel (back-end `SystemOp::Split` calls this directly).
@@ -237,13 +242,14 @@ pub fn split_tc(st: &mut TypeStack) -> Result<(), String> {
 /// This is the merge half of `match` — `match { -> a0 -> a1 }` desugars to
 /// `split :[disc l0 l1]  disc  l0 a0  l1 a1  merge2`. (Dotless + arity-suffixed
 /// like `injectN`/`partitionN`; bare `merge` is reserved for sorted-set union.)
-#[derive(Debug)] pub struct MergeN { pub n: usize }
+#[derive(Debug, Clone)] pub struct MergeN { pub n: usize }
 impl PrimOp for MergeN {
     fn name(&self) -> &str { "merge" }
     fn arity(&self) -> Option<(usize, usize)> { Some((self.n + 1, 1)) }  // disc + N lanes → merged
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { merge_run(self.n, st) }
 }
 impl Typed for MergeN {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { merge_tc(self.n, st) }
 }
 /// `mergeN` kernel (back-end `SystemOp::Merge` calls this directly).
@@ -278,13 +284,14 @@ pub fn merge_tc(n: usize, st: &mut TypeStack) -> Result<(), String> {
         Ok(())
 }
 
-#[derive(Debug)] pub struct PartitionN { pub n: usize }
+#[derive(Debug, Clone)] pub struct PartitionN { pub n: usize }
 impl PrimOp for PartitionN {
     fn name(&self) -> &str { "partition" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, self.n)) }  // (col, disc) → N lanes
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { partition_run(self.n, st) }
 }
 impl Typed for PartitionN {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { partition_tc(self.n, st) }
 }
 /// `partitionN` kernel (back-end `SystemOp::Partition` calls this directly).
@@ -326,13 +333,14 @@ pub fn partition_tc(n: usize, st: &mut TypeStack) -> Result<(), String> {
 ///
 /// `K` is parse-time (default 2). `branch.4` (K=4), bare `branch` (K=2), and
 /// `branch.K` all parse.
-#[derive(Debug)] pub struct Branch { pub k: usize }
+#[derive(Debug, Clone)] pub struct Branch { pub k: usize }
 impl PrimOp for Branch {
     fn name(&self) -> &str { "branch" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }  // (col, disc) → sum
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { branch_run(self.k, st) }
 }
 impl Typed for Branch {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { branch_tc(self.k, st) }
 }
 /// `branch.K` kernel (back-end `SystemOp::Branch` calls this directly).
@@ -381,7 +389,7 @@ pub fn branch_tc(k_arity: usize, st: &mut TypeStack) -> Result<(), String> {
         Ok(())
 }
 
-#[derive(Debug)] pub struct Nest;
+#[derive(Debug, Clone)] pub struct Nest;
 impl PrimOp for Nest {
     fn name(&self) -> &str { "nest" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }  // (values, bounds) → list
@@ -422,6 +430,7 @@ pub fn nest_run(st: &mut Stack) -> Result<(), String> {
         Ok(())
 }
 impl Typed for Nest {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { nest_tc(st) }
 }
 pub fn nest_tc(st: &mut TypeStack) -> Result<(), String> {
@@ -444,7 +453,7 @@ pub fn nest_tc(st: &mut TypeStack) -> Result<(), String> {
 /// Errors if `values.len() % count != 0`. The List materializes only when
 /// bounds are queried; consumers that just iterate (each/reduce/count/length)
 /// use the Stride directly.
-#[derive(Debug)] pub struct NestStride;
+#[derive(Debug, Clone)] pub struct NestStride;
 impl PrimOp for NestStride {
     fn name(&self) -> &str { "nest.stride" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }  // (values, count) → list
@@ -470,6 +479,7 @@ pub fn nest_stride_run(st: &mut Stack) -> Result<(), String> {
         Ok(())
 }
 impl Typed for NestStride {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { nest_stride_tc(st) }
 }
 pub fn nest_stride_tc(st: &mut TypeStack) -> Result<(), String> {
@@ -495,13 +505,14 @@ pub fn nest_stride_tc(st: &mut TypeStack) -> Result<(), String> {
 /// Recovers the "atom semantics" for sequence-shaped ops — e.g.
 /// `a enlist b enlist intersect unlist` runs intersect on two flat
 /// columns as a single-row intersection problem.
-#[derive(Debug)] pub struct Enlist;
+#[derive(Debug, Clone)] pub struct Enlist;
 impl PrimOp for Enlist {
     fn name(&self) -> &str { "enlist" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { enlist_run(st) }
 }
 impl Typed for Enlist {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { enlist_tc(st) }
 }
 /// `enlist` kernel (back-end `SystemOp::Enlist` calls this directly).
@@ -528,13 +539,14 @@ pub fn enlist_tc(st: &mut TypeStack) -> Result<(), String> {
 /// Dual of `enlist`. Together they let ops that take sequence-of-lists
 /// (`intersect`, `search`, etc.) be invoked on a single pair of flat
 /// sequences with no syntactic ceremony.
-#[derive(Debug)] pub struct Unlist;
+#[derive(Debug, Clone)] pub struct Unlist;
 impl PrimOp for Unlist {
     fn name(&self) -> &str { "unlist" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { unlist_run(st) }
 }
 impl Typed for Unlist {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { unlist_tc(st) }
 }
 /// `unlist` kernel (back-end `SystemOp::Unlist` calls this directly).
@@ -564,13 +576,14 @@ pub fn unlist_tc(st: &mut TypeStack) -> Result<(), String> {
         }
 }
 
-#[derive(Debug)] pub struct Flatten;
+#[derive(Debug, Clone)] pub struct Flatten;
 impl PrimOp for Flatten {
     fn name(&self) -> &str { "flatten" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 2)) }  // list → (values, bounds)
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { flatten_run(st) }
 }
 impl Typed for Flatten {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { flatten_tc(st) }
 }
 /// `flatten` kernel (back-end `SystemOp::Flatten` calls this directly).
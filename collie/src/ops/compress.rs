@@ -0,0 +1,352 @@
+//! Compress kernels: the elements of a column where a P8 mask is nonzero,
+//! in order — the inner loop of `filter` (materializing a `View<Mask>`),
+//! and with an implicit iota column, of `where`.
+//!
+//! `if mask[i] != 0 { out.push(xs[i]) }` branches on data, so it neither
+//! vectorizes nor predicts at middling selectivity. These kernels count
+//! the survivors first, then write without branching:
+//!
+//! - x86-64 with AVX2 (4- and 8-byte elements) or SSSE3 (1- and 2-byte):
+//!   each 8-lane chunk (4 for u64) turns its mask bytes into a bit
+//!   pattern, looks up the shuffle that packs the kept lanes to the front,
+//!   stores the whole vector at the output cursor, and advances the cursor
+//!   by the popcount. Lanes past the survivors are scratch, overwritten by
+//!   the next store or past the end.
+//! - Everywhere else, and for the tail: write every element at the cursor
+//!   and advance it by `mask[i] != 0`.
+//!
+//! Features are detected at run time (`is_x86_feature_detected!`, cached
+//! by std), so one binary serves every x86-64.
+
+use crate::ir::value::{Value, Prim, Storage, from_vec, prod};
+
+/// Spare output slots the vector kernels may write past the last survivor.
+const SLACK: usize = 16;
+
+/// Number of nonzero mask bytes.
+pub fn count_mask(mask: &[u8]) -> usize {
+    mask.iter().map(|&b| (b != 0) as usize).sum()
+}
+
+/// The elements of `xs` where `mask` is nonzero, in order.
+pub fn compress<T: bytemuck::Pod>(xs: &[T], mask: &[u8]) -> Vec<T> {
+    assert_eq!(xs.len(), mask.len(), "compress: {} elements, {} mask bytes", xs.len(), mask.len());
+    let count = count_mask(mask);
+    let mut out: Vec<T> = Vec::with_capacity(count + SLACK);
+    let (done, mut k) = simd::compress(xs, mask, &mut out);
+    let spare = out.spare_capacity_mut();
+    for (x, &b) in xs[done..].iter().zip(&mask[done..]) {
+        spare[k].write(*x);
+        k += (b != 0) as usize;
+    }
+    debug_assert_eq!(k, count);
+    // SAFETY: slots `0..count` were each written (every survivor lands at
+    // its rank), and `count + SLACK` is the capacity.
+    unsafe { out.set_len(count) };
+    out
+}
+
+/// The positions where `mask` is nonzero (`where`'s flat kernel).
+pub fn mask_positions(mask: &[u8]) -> Vec<u64> {
+    let count = count_mask(mask);
+    let mut out: Vec<u64> = Vec::with_capacity(count + SLACK);
+    let (done, mut k) = simd::positions(mask, &mut out);
+    let spare = out.spare_capacity_mut();
+    for (i, &b) in mask.iter().enumerate().skip(done) {
+        spare[k].write(i as u64);
+        k += (b != 0) as usize;
+    }
+    debug_assert_eq!(k, count);
+    // SAFETY: as in `compress`.
+    unsafe { out.set_len(count) };
+    out
+}
+
+/// `compress` at the Prim's width.
+pub fn compress_prim(p: &Prim, mask: &[u8]) -> Prim {
+    match p {
+        Prim::P8(x) => <u8 as Storage>::wrap(compress(x, mask)),
+        Prim::P16(x) => <u16 as Storage>::wrap(compress(x, mask)),
+        Prim::P32(x) => <u32 as Storage>::wrap(compress(x, mask)),
+        Prim::P64(x) => <u64 as Storage>::wrap(compress(x, mask)),
+    }
+}
+
+/// The rows of `v` where `mask` is nonzero: compressed field by field for
+/// Prims and Prods of them, gathered by position otherwise.
+pub fn compress_value(v: &Value, mask: &[u8]) -> Result<Value, String> {
+    if v.len() != mask.len() {
+        return Err(format!("compress: {} rows, {} mask bytes", v.len(), mask.len()));
+    }
+    Ok(match v {
+        Value::Prim(p) => Value::Prim(compress_prim(p, mask)),
+        Value::Encoded(e) => Value::Prim(compress_prim(&e.decode(), mask)),
+        Value::Prod(fs) => prod(fs.iter().map(|f| compress_value(f, mask)).collect::<Result<_, _>>()?),
+        _ => {
+            let idxs: Vec<usize> = mask_positions(mask).into_iter().map(|i| i as usize).collect();
+            crate::ops::helpers::gather(v, &idxs)?
+        }
+    })
+}
+
+/// `where` over a flat mask, as a value.
+pub fn where_value(mask: &[u8]) -> Value { from_vec::<u64>(mask_positions(mask)) }
+
+#[cfg(target_arch = "x86_64")]
+mod simd {
+    use std::arch::x86_64::*;
+    use std::mem::size_of;
+
+    /// Per 8-lane bit pattern: the kept lane numbers, packed to the front.
+    const fn lanes8() -> [[u8; 8]; 256] {
+        let mut t = [[0u8; 8]; 256];
+        let mut b = 0;
+        while b < 256 {
+            let (mut j, mut k) = (0, 0);
+            while j < 8 {
+                if (b >> j) & 1 == 1 { t[b][k] = j as u8; k += 1; }
+                j += 1;
+            }
+            b += 1;
+        }
+        t
+    }
+    static LANES8: [[u8; 8]; 256] = lanes8();
+
+    /// `_mm256_permutevar8x32_epi32` indices for 8 × 32-bit lanes.
+    const fn perm32() -> [[u32; 8]; 256] {
+        let mut t = [[0u32; 8]; 256];
+        let mut b = 0;
+        while b < 256 {
+            let mut k = 0;
+            while k < 8 { t[b][k] = LANES8[b][k] as u32; k += 1; }
+            b += 1;
+        }
+        t
+    }
+    static PERM32: [[u32; 8]; 256] = perm32();
+
+    /// The same for 4 × 64-bit lanes (each lane two 32-bit halves).
+    const fn perm64() -> [[u32; 8]; 16] {
+        let mut t = [[0u32; 8]; 16];
+        let mut b = 0;
+        while b < 16 {
+            let (mut j, mut k) = (0, 0);
+            while j < 4 {
+                if (b >> j) & 1 == 1 { t[b][2 * k] = 2 * j; t[b][2 * k + 1] = 2 * j + 1; k += 1; }
+                j += 1;
+            }
+            b += 1;
+        }
+        t
+    }
+    static PERM64: [[u32; 8]; 16] = perm64();
+
+    /// `_mm_shuffle_epi8` indices for 8 × 16-bit lanes.
+    const fn shuf16() -> [[u8; 16]; 256] {
+        let mut t = [[0x80u8; 16]; 256];
+        let mut b = 0;
+        while b < 256 {
+            let (mut j, mut k) = (0, 0);
+            while j < 8 {
+                if (b >> j) & 1 == 1 { t[b][2 * k] = 2 * j as u8; t[b][2 * k + 1] = 2 * j as u8 + 1; k += 1; }
+                j += 1;
+            }
+            b += 1;
+        }
+        t
+    }
+    static SHUF16: [[u8; 16]; 256] = shuf16();
+
+    /// Bit `j` set iff `mask[j] != 0`, for 16 mask bytes at `p`.
+    #[inline(always)]
+    unsafe fn bits16(p: *const u8) -> u32 {
+        let v = _mm_loadu_si128(p as *const __m128i);
+        !(_mm_movemask_epi8(_mm_cmpeq_epi8(v, _mm_setzero_si128())) as u32) & 0xFFFF
+    }
+
+    /// Compress the longest 16-element-chunked prefix of `xs` into `out`'s
+    /// spare capacity (which must hold the survivors plus `SLACK`).
+    /// Returns (elements consumed, survivors written).
+    pub fn compress<T: bytemuck::Pod>(xs: &[T], mask: &[u8], out: &mut Vec<T>) -> (usize, usize) {
+        debug_assert!(out.capacity() >= super::count_mask(mask) + super::SLACK);
+        let (x, m, o) = (xs.as_ptr() as *const u8, mask.as_ptr(), out.as_mut_ptr() as *mut u8);
+        let n = xs.len();
+        let popcnt = is_x86_feature_detected!("popcnt");
+        let ssse3 = popcnt && is_x86_feature_detected!("ssse3");
+        let avx2 = popcnt && is_x86_feature_detected!("avx2");
+        // SAFETY: every feature the kernel enables is present; `T` is plain
+        // bytes of the kernel's width; each kernel reads whole chunks inside
+        // `n` and writes at most 16 lanes past the survivors so far.
+        unsafe {
+            match size_of::<T>() {
+                1 if ssse3 => compress8(x, m, n, o),
+                2 if ssse3 => compress16(x as *const u16, m, n, o as *mut u16),
+                4 if avx2 => compress32(x as *const u32, m, n, o as *mut u32),
+                8 if avx2 => compress64(x as *const u64, m, n, o as *mut u64),
+                _ => (0, 0),
+            }
+        }
+    }
+
+    /// Positions of the nonzero bytes of a chunked prefix of `mask`.
+    pub fn positions(mask: &[u8], out: &mut Vec<u64>) -> (usize, usize) {
+        debug_assert!(out.capacity() >= super::count_mask(mask) + super::SLACK);
+        if !(is_x86_feature_detected!("avx2") && is_x86_feature_detected!("popcnt")) { return (0, 0); }
+        // SAFETY: as in `compress`.
+        unsafe { positions64(mask.as_ptr(), mask.len(), out.as_mut_ptr()) }
+    }
+
+    #[target_feature(enable = "ssse3,popcnt")]
+    unsafe fn compress8(x: *const u8, m: *const u8, n: usize, o: *mut u8) -> (usize, usize) {
+        let (mut i, mut k) = (0, 0);
+        while i + 16 <= n {
+            let bits = bits16(m.add(i));
+            for h in 0..2 {
+                let b = (bits >> (8 * h)) as usize & 0xFF;
+                let v = _mm_loadl_epi64(x.add(i + 8 * h) as *const __m128i);
+                let s = _mm_loadl_epi64(LANES8[b].as_ptr() as *const __m128i);
+                _mm_storel_epi64(o.add(k) as *mut __m128i, _mm_shuffle_epi8(v, s));
+                k += b.count_ones() as usize;
+            }
+            i += 16;
+        }
+        (i, k)
+    }
+
+    #[target_feature(enable = "ssse3,popcnt")]
+    unsafe fn compress16(x: *const u16, m: *const u8, n: usize, o: *mut u16) -> (usize, usize) {
+        let (mut i, mut k) = (0, 0);
+        while i + 16 <= n {
+            let bits = bits16(m.add(i));
+            for h in 0..2 {
+                let b = (bits >> (8 * h)) as usize & 0xFF;
+                let v = _mm_loadu_si128(x.add(i + 8 * h) as *const __m128i);
+                let s = _mm_loadu_si128(SHUF16[b].as_ptr() as *const __m128i);
+                _mm_storeu_si128(o.add(k) as *mut __m128i, _mm_shuffle_epi8(v, s));
+                k += b.count_ones() as usize;
+            }
+            i += 16;
+        }
+        (i, k)
+    }
+
+    #[target_feature(enable = "avx2,popcnt")]
+    unsafe fn compress32(x: *const u32, m: *const u8, n: usize, o: *mut u32) -> (usize, usize) {
+        let (mut i, mut k) = (0, 0);
+        while i + 16 <= n {
+            let bits = bits16(m.add(i));
+            for h in 0..2 {
+                let b = (bits >> (8 * h)) as usize & 0xFF;
+                let v = _mm256_loadu_si256(x.add(i + 8 * h) as *const __m256i);
+                let p = _mm256_loadu_si256(PERM32[b].as_ptr() as *const __m256i);
+                _mm256_storeu_si256(o.add(k) as *mut __m256i, _mm256_permutevar8x32_epi32(v, p));
+                k += b.count_ones() as usize;
+            }
+            i += 16;
+        }
+        (i, k)
+    }
+
+    #[target_feature(enable = "avx2,popcnt")]
+    unsafe fn compress64(x: *const u64, m: *const u8, n: usize, o: *mut u64) -> (usize, usize) {
+        let (mut i, mut k) = (0, 0);
+        while i + 16 <= n {
+            let bits = bits16(m.add(i));
+            for q in 0..4 {
+                let b = (bits >> (4 * q)) as usize & 0xF;
+                let v = _mm256_loadu_si256(x.add(i + 4 * q) as *const __m256i);
+                let p = _mm256_loadu_si256(PERM64[b].as_ptr() as *const __m256i);
+                _mm256_storeu_si256(o.add(k) as *mut __m256i, _mm256_permutevar8x32_epi32(v, p));
+                k += b.count_ones() as usize;
+            }
+            i += 16;
+        }
+        (i, k)
+    }
+
+    /// `compress64` over the column `0, 1, 2, …`, built in registers.
+    #[target_feature(enable = "avx2,popcnt")]
+    unsafe fn positions64(m: *const u8, n: usize, o: *mut u64) -> (usize, usize) {
+        let (mut i, mut k) = (0, 0);
+        let four = _mm256_set1_epi64x(4);
+        let mut v = _mm256_setr_epi64x(0, 1, 2, 3);
+        while i + 16 <= n {
+            let bits = bits16(m.add(i));
+            for q in 0..4 {
+                let b = (bits >> (4 * q)) as usize & 0xF;
+                let p = _mm256_loadu_si256(PERM64[b].as_ptr() as *const __m256i);
+                _mm256_storeu_si256(o.add(k) as *mut __m256i, _mm256_permutevar8x32_epi32(v, p));
+                k += b.count_ones() as usize;
+                v = _mm256_add_epi64(v, four);
+            }
+            i += 16;
+        }
+        (i, k)
+    }
+}
+
+/// No vector kernels off x86-64: the branch-free scalar loop does it all.
+#[cfg(not(target_arch = "x86_64"))]
+mod simd {
+    pub fn compress<T>(_: &[T], _: &[u8], _: &mut Vec<T>) -> (usize, usize) { (0, 0) }
+    pub fn positions(_: &[u8], _: &mut Vec<u64>) -> (usize, usize) { (0, 0) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive<T: Copy>(xs: &[T], mask: &[u8]) -> Vec<T> {
+        xs.iter().zip(mask).filter(|(_, &b)| b != 0).map(|(x, _)| *x).collect()
+    }
+
+    /// Masks of every density, with non-0/1 true bytes, at lengths around
+    /// the 16-element chunk and far past it.
+    fn masks() -> Vec<Vec<u8>> {
+        let mut s = 0x2545_F491_4F6C_DD1Du64;
+        let mut out = Vec::new();
+        for n in [0usize, 1, 15, 16, 17, 31, 33, 1000, 4099] {
+            for pct in [0u64, 10, 50, 90, 100] {
+                out.push((0..n).map(|_| {
+                    s ^= s << 13; s ^= s >> 7; s ^= s << 17;
+                    if s % 100 < pct { (s >> 40) as u8 | 1 } else { 0 }
+                }).collect());
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn compress_matches_naive_at_every_width() {
+        for m in masks() {
+            let n = m.len();
+            let x8: Vec<u8> = (0..n).map(|i| i as u8 ^ 0x5A).collect();
+            let x16: Vec<u16> = (0..n).map(|i| (i as u16).wrapping_mul(40_503)).collect();
+            let x32: Vec<i32> = (0..n).map(|i| -(i as i32) * 7919).collect();
+            let x64: Vec<f64> = (0..n).map(|i| i as f64 * 0.5 - 3.0).collect();
+            assert_eq!(compress(&x8, &m), naive(&x8, &m));
+            assert_eq!(compress(&x16, &m), naive(&x16, &m));
+            assert_eq!(compress(&x32, &m), naive(&x32, &m));
+            assert_eq!(compress(&x64, &m), naive(&x64, &m));
+            let want: Vec<u64> = (0..n as u64).filter(|&i| m[i as usize] != 0).collect();
+            assert_eq!(mask_positions(&m), want);
+        }
+    }
+
+    #[test]
+    fn filter_and_where_use_compress() {
+        use crate::syntax::{parse::parse, registry::OpRegistry};
+        let reg = OpRegistry::standard();
+        let run = |src: &str| crate::pipeline::build(parse(src, &reg).unwrap())
+            .and_then(|(g, _)| crate::pipeline::eval_graph(&g)).unwrap()
+            .into_iter().map(|v| crate::ir::stack::materialize_top(v).unwrap()).collect::<Vec<_>>();
+        let xs: Vec<String> = (0..40).map(|i| i.to_string()).collect();
+        let xs = xs.join(" ");
+        let out = run(&format!("u16[{0}] i32[{0}] entuple u32[{0}] 3u32 %.u32 0u32 = filter", xs));
+        let keep: Vec<u16> = (0..40).filter(|i| i % 3 == 0).collect();
+        assert_eq!(out, vec![prod(vec![from_vec::<u16>(keep.clone()), from_vec::<i32>(keep.iter().map(|&i| i as i32).collect())])]);
+        let out = run(&format!("u32[{}] 3u32 %.u32 0u32 = where", xs));
+        assert_eq!(out, vec![from_vec::<u64>(keep.iter().map(|&i| i as u64).collect())]);
+    }
+}
@@ -15,7 +15,7 @@ use crate::ir::value::{Value, Prim, PrimWidth, Storage, from_vec};
 use crate::ir::shape::{Interp, Shape, is_primitive};
 use crate::ops::helpers::{extract_prim, list_elementwise1};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct As { pub interp: Interp }
 
 impl PrimOp for As {
@@ -77,6 +77,7 @@ fn cast_prim(p: &Prim, interp: Interp) -> Value {
 }
 
 impl Typed for As {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { tc(self.interp, st) }
 }
 
@@ -97,7 +98,7 @@ pub fn tc(interp: Interp, st: &mut TypeStack) -> Result<(), String> {
 /// Scalar literal. Carries both an integer and a float field so float
 /// literals (`3.14f64`) don't get truncated to their integer part.
 /// `n` is used for integer interpretations; `f` is used for `F32`/`F64`.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LitNum { pub n: i128, pub f: f64, pub interp: Interp }
 
 impl LitNum {
@@ -131,6 +132,7 @@ impl PrimOp for LitNum {
 }
 
 impl Typed for LitNum {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         st.push(Shape::Prim(self.interp.width())); Ok(())
     }
@@ -139,7 +141,7 @@ impl Typed for LitNum {
 /// `<type>[ elem … ]` literal. The parser builds the `Prim` once at
 /// parse time; `run` clones the underlying `Arc<Vec<_>>` (cheap).
 /// `tag` is the surface form (`"u64[]"`, `"bool[]"`, …) for diagnostics.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LitArr { pub tag: &'static str, pub prim: Prim }
 
 impl LitArr {
@@ -156,6 +158,7 @@ impl PrimOp for LitArr {
     }
 }
 impl Typed for LitArr {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         st.push(Shape::Prim(self.prim.width())); Ok(())
     }
@@ -198,9 +201,10 @@ pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
 /// `show.<interp>` — print top-of-stack to stderr with the given interpretation,
 /// then push it back. Useful for debugging when the default Display (raw bytes)
 /// loses interpretation (e.g., F64 printed as u64 bits).
-#[derive(Debug)] pub struct Show { pub interp: Interp }
+#[derive(Debug, Clone)] pub struct Show { pub interp: Interp }
 impl PrimOp for Show {
     fn name(&self) -> &str { "show" }
+    fn token(&self) -> Option<String> { Some(format!("show.{}", self.interp)) }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
         let v = crate::ir::stack::pop(st)?;
@@ -210,6 +214,7 @@ impl PrimOp for Show {
     }
 }
 impl Typed for Show {
+    crate::cloneable!();
     fn tc(&self, _st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         // Stack-shape-preserving; layer-3 inspection optional.
         Ok(())
@@ -239,7 +244,7 @@ fn show_value(v: &Value, interp: Interp) -> String {
         Value::List { bounds, values } => {
             format!("List{{bounds={:?}, values={}}}", bounds, show_value(values, interp))
         }
-        Value::View { .. } => {
+        Value::View { .. } | Value::Encoded(_) => {
             // Materialize before reinterpreting; show.<interp> is a debugging
             // path, the gather is fine here.
             match crate::ops::helpers::materialize_ref(v) {
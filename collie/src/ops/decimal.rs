@@ -0,0 +1,585 @@
+//! Fixed-point decimals for exact money arithmetic.
+//!
+//! Two interpretation tags, `S` the scale (digits after the point):
+//!
+//! - `d64.S` — an `i64` count of `10^-S` units (`P64`), `S ≤ 18`.
+//! - `d128.S` — an `i128` count, stored as `Prod[hi: P64, lo: P64]` (the
+//!   high word signed, the low word unsigned), `S ≤ 38`.
+//!
+//! Like the calendar tags (`ops::calendar`) the tag is an op suffix, not
+//! recorded in the value; the header types `d64.S` / `d128.S`
+//! (`syntax::header`) read and write decimal text in the runner. An
+//! integer column is `d64.0` as it stands, and `round.d64.2.d64.0` lifts
+//! it to cents. Ops, over flat columns (either operand of a binary op
+//! may be one element):
+//!
+//! - `+.D`, `-.D`, `*.D`, `/.D` — `a b → Sum{err: P8 | ok: D}`, both
+//!   operands at `D`. Products and quotients are exact before they're
+//!   rounded back to scale `S`, half away from zero (a 256-bit
+//!   intermediate for `d128`). Rows that overflow `D`, or divide by zero,
+//!   land in lane 0 as a code: 0 overflow, 1 division by zero.
+//! - `round.<to>.<from>` — rescale (and re-width) `from → Sum{err | ok:
+//!   to}`: fewer digits round half away from zero, more are exact; rows
+//!   that don't fit `to` are overflow errors.
+//! - `<.D`, `<=.D`, `=.D`, `!=.D`, `>=.D`, `>.D` — a P8 mask, by the
+//!   unsigned comparison of the swizzled words (`cmp`).
+//! - `enswizzle.D` / `deswizzle.D` — the order-form image (`swizzle`):
+//!   `enswizzle.D sort deswizzle.D` sorts numerically. For `d128` it flips
+//!   the high word's sign bit, so the Prod's lexicographic sort orders it.
+//! - `parse.D` — `List<P8>` text → `Sum{err: List<P8> | ok: D}`:
+//!   `[+-]digits[.digits]`, lane 0 the rows that didn't parse, overflow
+//!   or carry more than `S` nonzero fractional digits (their text).
+//! - `format.D` — `D → List<P8>`: `-1234.50`, exactly `S` fractional
+//!   digits.
+//!
+//! Reductions and scans over `d64` words are the `i64` ones
+//! (`reduce.+.i64`, …); they wrap rather than report overflow.
+
+use std::fmt;
+use std::sync::Arc;
+use crate::ir::op::PrimOp;
+use crate::ir::stack::{Stack, pop};
+use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
+use crate::ir::value::{Value, Prim, PrimWidth, Storage, from_vec, list, prod, sum, bounds_var_from_ends};
+use crate::ir::shape::{Interp, Shape};
+use crate::ops::cmp::CmpOp;
+use crate::ops::helpers::{extract_prim, materialize_ref, normalize};
+
+/// A decimal interpretation: `d64.S` or `d128.S`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dec { pub wide: bool, pub scale: u8 }
+
+impl Dec {
+    /// `d64.2`, `d128.10`, ….
+    pub fn parse(s: &str) -> Option<Dec> {
+        let (w, scale) = s.split_once('.')?;
+        let wide = match w { "d64" => false, "d128" => true, _ => return None };
+        if scale.is_empty() || !scale.bytes().all(|b| b.is_ascii_digit()) { return None; }
+        let scale: u8 = scale.parse().ok()?;
+        (scale <= if wide { 38 } else { 18 }).then_some(Dec { wide, scale })
+    }
+
+    pub fn shape(self) -> Shape {
+        if self.wide { Shape::Prod(vec![Shape::Prim(PrimWidth::W64); 2]) } else { Shape::Prim(PrimWidth::W64) }
+    }
+
+    fn unit(self) -> i128 { 10i128.pow(self.scale as u32) }
+
+    fn fits(self, x: i128) -> bool { self.wide || i64::try_from(x).is_ok() }
+}
+
+impl fmt::Display for Dec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "d{}.{}", if self.wide { 128 } else { 64 }, self.scale)
+    }
+}
+
+/// Why a row went to the error lane (its code there).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault { Overflow = 0, DivZero = 1 }
+
+// ── Exact kernels ──────────────────────────────────────────────────────────
+
+/// The full 256-bit product `a · b` as `(hi, lo)`.
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const M: u128 = u64::MAX as u128;
+    let (a1, a0, b1, b0) = (a >> 64, a & M, b >> 64, b & M);
+    let (p00, p01, p10, p11) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+    let mid = (p00 >> 64) + (p01 & M) + (p10 & M);
+    ((p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64)), (p00 & M) | (mid << 64))
+}
+
+/// `(hi, lo) / d` and the remainder, or `None` when the quotient needs
+/// more than 128 bits. `0 < d ≤ 2^127`.
+fn div_wide(hi: u128, lo: u128, d: u128) -> Option<(u128, u128)> {
+    if hi == 0 { return Some((lo / d, lo % d)); }
+    if hi >= d { return None; }
+    // Restoring long division; the remainder stays below `d ≤ 2^127`, so
+    // the shift never carries out.
+    let (mut q, mut r) = (0u128, hi);
+    for i in (0..128).rev() {
+        r = (r << 1) | ((lo >> i) & 1);
+        q <<= 1;
+        if r >= d { r -= d; q |= 1; }
+    }
+    Some((q, r))
+}
+
+/// `a · b / d` rounded half away from zero, or `None` if it overflows
+/// `i128`. `d ≠ 0`.
+pub fn mul_div(a: i128, b: i128, d: i128) -> Option<i128> {
+    let neg = (a < 0) ^ (b < 0) ^ (d < 0);
+    let d_abs = d.unsigned_abs();
+    let (q, r) = match a.unsigned_abs().checked_mul(b.unsigned_abs()) {
+        Some(p) => (p / d_abs, p % d_abs),
+        None => {
+            let (hi, lo) = mul_wide(a.unsigned_abs(), b.unsigned_abs());
+            div_wide(hi, lo, d_abs)?
+        }
+    };
+    let q = if r >= d_abs - r { q.checked_add(1)? } else { q };
+    if neg { 0i128.checked_sub_unsigned(q) } else { i128::try_from(q).ok() }
+}
+
+fn binary(op: DecOp, dec: Dec, a: i128, b: i128) -> Result<i128, Fault> {
+    let x = match op {
+        DecOp::Add => a.checked_add(b),
+        DecOp::Sub => a.checked_sub(b),
+        DecOp::Mul => mul_div(a, b, dec.unit()),
+        DecOp::Div => {
+            if b == 0 { return Err(Fault::DivZero); }
+            mul_div(a, dec.unit(), b)
+        }
+    };
+    x.filter(|&x| dec.fits(x)).ok_or(Fault::Overflow)
+}
+
+/// `x` at scale `from.scale` rescaled to `to`.
+fn rescale(x: i128, from: Dec, to: Dec) -> Result<i128, Fault> {
+    let x = if to.scale >= from.scale {
+        x.checked_mul(10i128.pow((to.scale - from.scale) as u32))
+    } else {
+        mul_div(x, 1, 10i128.pow((from.scale - to.scale) as u32))
+    };
+    x.filter(|&x| to.fits(x)).ok_or(Fault::Overflow)
+}
+
+/// `[+-]digits[.digits]` at `dec`, or `None` if it isn't one, overflows,
+/// or has nonzero digits past the scale.
+pub fn parse_text(s: &[u8], dec: Dec) -> Option<i128> {
+    let (neg, s) = match s.first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let (int, frac) = match s.iter().position(|&b| b == b'.') {
+        Some(p) => (&s[..p], &s[p + 1..]),
+        None => (s, &s[s.len()..]),
+    };
+    if int.is_empty() && frac.is_empty() { return None; }
+    if !int.iter().chain(frac).all(u8::is_ascii_digit) { return None; }
+    let scale = dec.scale as usize;
+    if frac.iter().skip(scale).any(|&b| b != b'0') { return None; }
+    let mut x: i128 = 0;
+    let digits = int.iter().chain(frac.iter().take(scale)).chain(std::iter::repeat_n(&b'0', scale.saturating_sub(frac.len())));
+    for &b in digits {
+        let d = (b - b'0') as i128;
+        x = x.checked_mul(10)?.checked_add(if neg { -d } else { d })?;
+    }
+    dec.fits(x).then_some(x)
+}
+
+/// `x` at `dec` as text: `-1234.50`.
+pub fn format_text(x: i128, dec: Dec) -> String {
+    let digits = x.unsigned_abs().to_string();
+    let scale = dec.scale as usize;
+    let padded = format!("{:0>width$}", digits, width = scale + 1);
+    let (int, frac) = padded.split_at(padded.len() - scale);
+    let sign = if x < 0 { "-" } else { "" };
+    if scale == 0 { format!("{}{}", sign, int) } else { format!("{}{}.{}", sign, int, frac) }
+}
+
+// ── Columns ────────────────────────────────────────────────────────────────
+
+/// A `dec` column's values, widened to `i128`.
+pub fn read(v: &Value, dec: Dec, ctx: &str) -> Result<Vec<i128>, String> {
+    let words = |v: &Value| -> Result<Vec<u64>, String> {
+        let p = extract_prim(v, ctx)?;
+        <u64 as Storage>::extract(&p).map(|ws| ws.to_vec())
+            .map_err(|_| format!("{}: expected a {} column (P64), got {:?}", ctx, dec, p.width()))
+    };
+    if !dec.wide { return Ok(words(v)?.into_iter().map(|w| w as i64 as i128).collect()); }
+    match normalize(v)? {
+        Value::Prod(fs) if fs.len() == 2 => {
+            let (hi, lo) = (words(&fs[0])?, words(&fs[1])?);
+            Ok(hi.iter().zip(&lo).map(|(&h, &l)| ((h as i128) << 64) | l as i128).collect())
+        }
+        other => Err(format!("{}: expected a {} column (Prod[P64, P64]), got {}", ctx, dec, other)),
+    }
+}
+
+/// Values (each fitting `dec`) as a `dec` column.
+pub fn column(xs: Vec<i128>, dec: Dec) -> Value {
+    if !dec.wide { return from_vec::<i64>(xs.into_iter().map(|x| x as i64).collect()); }
+    let hi = xs.iter().map(|&x| (x >> 64) as u64).collect();
+    let lo = xs.iter().map(|&x| x as u64).collect();
+    prod(vec![from_vec::<u64>(hi), from_vec::<u64>(lo)])
+}
+
+/// Per-row results split into `Sum{err: P8 code | ok: dec}`.
+fn checked(rows: impl Iterator<Item = Result<i128, Fault>>, dec: Dec) -> Value {
+    let (mut disc, mut errs, mut oks) = (Vec::new(), Vec::new(), Vec::new());
+    for r in rows {
+        match r {
+            Ok(x) => { disc.push(1u8); oks.push(x); }
+            Err(f) => { disc.push(0u8); errs.push(f as u8); }
+        }
+    }
+    sum(Prim::P8(Arc::new(disc)), vec![from_vec::<u8>(errs), column(oks, dec)])
+}
+
+fn checked_shape(ok: Shape) -> Shape {
+    Shape::Sum { disc: PrimWidth::W8, lanes: vec![Shape::Prim(PrimWidth::W8), ok] }
+}
+
+fn text_shape() -> Shape {
+    Shape::List { bounds: PrimWidth::W64, inner: Box::new(Shape::Prim(PrimWidth::W8)) }
+}
+
+fn tc_dec(st: &mut TypeStack, dec: Dec, ctx: &str) -> Result<(), String> {
+    let s = tc_pop(st, ctx)?;
+    if s != dec.shape() { return Err(format!("{}: needs {} ({}), got {}", ctx, dec, dec.shape(), s)); }
+    Ok(())
+}
+
+/// Pair up two operands' rows, either of which may be one element.
+fn zip_rows(a: &[i128], b: &[i128], ctx: &str) -> Result<Vec<(i128, i128)>, String> {
+    Ok(match (a.len(), b.len()) {
+        (na, nb) if na == nb => a.iter().copied().zip(b.iter().copied()).collect(),
+        (1, _) => b.iter().map(|&y| (a[0], y)).collect(),
+        (_, 1) => a.iter().map(|&x| (x, b[0])).collect(),
+        (na, nb) => return Err(format!("{}: length mismatch {} vs {}", ctx, na, nb)),
+    })
+}
+
+// ── Ops ────────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecOp { Add, Sub, Mul, Div }
+
+impl DecOp {
+    fn symbol(self) -> &'static str {
+        match self { DecOp::Add => "+", DecOp::Sub => "-", DecOp::Mul => "*", DecOp::Div => "/" }
+    }
+}
+
+/// `+.D`, `-.D`, `*.D`, `/.D` — `a b → Sum{err | ok: D}`.
+#[derive(Debug, Clone)] pub struct Arith { pub op: DecOp, pub dec: Dec }
+impl PrimOp for Arith {
+    fn name(&self) -> &str { self.op.symbol() }
+    fn token(&self) -> Option<String> { Some(format!("{}.{}", self.op.symbol(), self.dec)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let ctx = format!("{}.{}", self.op.symbol(), self.dec);
+        let b = read(&pop(st)?, self.dec, &ctx)?;
+        let a = read(&pop(st)?, self.dec, &ctx)?;
+        let rows = zip_rows(&a, &b, &ctx)?;
+        st.push(checked(rows.into_iter().map(|(x, y)| binary(self.op, self.dec, x, y)), self.dec));
+        Ok(())
+    }
+}
+impl Typed for Arith {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let ctx = format!("{}.{}", self.op.symbol(), self.dec);
+        tc_dec(st, self.dec, &ctx)?;
+        tc_dec(st, self.dec, &ctx)?;
+        st.push(checked_shape(self.dec.shape()));
+        Ok(())
+    }
+}
+
+/// `round.<to>.<from>` — rescale `from → Sum{err | ok: to}`.
+#[derive(Debug, Clone)] pub struct Round { pub to: Dec, pub from: Dec }
+impl PrimOp for Round {
+    fn name(&self) -> &str { "round" }
+    fn token(&self) -> Option<String> { Some(format!("round.{}.{}", self.to, self.from)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let xs = read(&pop(st)?, self.from, &format!("round.{}.{}", self.to, self.from))?;
+        st.push(checked(xs.into_iter().map(|x| rescale(x, self.from, self.to)), self.to));
+        Ok(())
+    }
+}
+impl Typed for Round {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        tc_dec(st, self.from, &format!("round.{}.{}", self.to, self.from))?;
+        st.push(checked_shape(self.to.shape()));
+        Ok(())
+    }
+}
+
+/// `<.D` … `>.D` — compare in order form, a P8 mask.
+#[derive(Debug, Clone)] pub struct Cmp { pub op: CmpOp, pub dec: Dec }
+impl PrimOp for Cmp {
+    fn name(&self) -> &str { crate::ops::cmp::op_name(self.op) }
+    fn token(&self) -> Option<String> { Some(format!("{}.{}", crate::ops::cmp::op_name(self.op), self.dec)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        if !self.dec.wide {
+            // The word comparison itself, on swizzled operands.
+            let b = swizzle(pop(st)?, self.dec)?;
+            let a = swizzle(pop(st)?, self.dec)?;
+            st.push(a);
+            st.push(b);
+            return crate::ops::cmp::run(self.op, st);
+        }
+        let ctx = format!("{}.{}", crate::ops::cmp::op_name(self.op), self.dec);
+        // (hi with its sign bit flipped, lo) — unsigned lexicographic order.
+        let key = |x: i128| ((x >> 64) as u64 ^ (1 << 63), x as u64);
+        let b = read(&pop(st)?, self.dec, &ctx)?;
+        let a = read(&pop(st)?, self.dec, &ctx)?;
+        let mask = zip_rows(&a, &b, &ctx)?.into_iter().map(|(x, y)| {
+            let (x, y) = (key(x), key(y));
+            (match self.op {
+                CmpOp::Lt => x < y, CmpOp::Le => x <= y,
+                CmpOp::Eq => x == y, CmpOp::Ne => x != y,
+                CmpOp::Ge => x >= y, CmpOp::Gt => x > y,
+            }) as u8
+        }).collect();
+        st.push(from_vec::<u8>(mask));
+        Ok(())
+    }
+}
+impl Typed for Cmp {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let ctx = format!("{}.{}", crate::ops::cmp::op_name(self.op), self.dec);
+        tc_dec(st, self.dec, &ctx)?;
+        tc_dec(st, self.dec, &ctx)?;
+        st.push(Shape::Prim(PrimWidth::W8));
+        Ok(())
+    }
+}
+
+/// The order-form image of a `dec` column (self-inverse): the `i64`
+/// swizzle, on the high word for `d128`.
+fn swizzle(v: Value, dec: Dec) -> Result<Value, String> {
+    let flip = |v: Value| -> Result<Value, String> {
+        match v {
+            Value::Prim(p) => Ok(Value::Prim(crate::ops::swizzle::swizzle(p, Interp::I64, true)?)),
+            other => Err(format!("swizzle.{}: expected a P64 column, got {}", dec, other)),
+        }
+    };
+    if !dec.wide { return flip(crate::ir::stack::materialize_top(v)?); }
+    match normalize(&v)? {
+        Value::Prod(fs) if fs.len() == 2 => Ok(prod(vec![flip(fs[0].clone())?, fs[1].clone()])),
+        other => Err(format!("swizzle.{}: expected Prod[P64, P64], got {}", dec, other)),
+    }
+}
+
+/// `enswizzle.D` / `deswizzle.D` — one op: the flip is its own inverse.
+#[derive(Debug, Clone)] pub struct Swizzle { pub dec: Dec, pub encode: bool }
+impl PrimOp for Swizzle {
+    fn name(&self) -> &str { if self.encode { "enswizzle" } else { "deswizzle" } }
+    fn token(&self) -> Option<String> { Some(format!("{}.{}", self.name(), self.dec)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let v = swizzle(pop(st)?, self.dec)?;
+        st.push(v);
+        Ok(())
+    }
+}
+impl Typed for Swizzle {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        tc_dec(st, self.dec, &format!("{}.{}", self.name(), self.dec))?;
+        st.push(self.dec.shape());
+        Ok(())
+    }
+}
+
+/// `parse.D` — `List<P8>` text → `Sum{err: List<P8> | ok: D}`.
+#[derive(Debug, Clone)] pub struct Parse { pub dec: Dec }
+impl PrimOp for Parse {
+    fn name(&self) -> &str { "parse" }
+    fn token(&self) -> Option<String> { Some(format!("parse.{}", self.dec)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let ctx = format!("parse.{}", self.dec);
+        let (bounds, bytes) = match pop(st)? {
+            Value::List { bounds, values } => match materialize_ref(&values)?.as_ref() {
+                Value::Prim(Prim::P8(b)) => (bounds, b.clone()),
+                other => return Err(format!("{}: expected List<P8> text, got List of {}", ctx, other)),
+            },
+            other => return Err(format!("{}: expected List<P8> text, got {}", ctx, other)),
+        };
+        let mut disc = Vec::with_capacity(bounds.len());
+        let (mut ok, mut bad, mut bad_ends) = (Vec::new(), Vec::new(), Vec::new());
+        for (lo, hi) in bounds.iter_pairs() {
+            let s = &bytes[lo as usize..hi as usize];
+            match parse_text(s, self.dec) {
+                Some(x) => { disc.push(1u8); ok.push(x); }
+                None => { disc.push(0u8); bad.extend_from_slice(s); bad_ends.push(bad.len() as u64); }
+            }
+        }
+        let bad = list(bounds_var_from_ends(bad_ends), from_vec::<u8>(bad));
+        st.push(sum(Prim::P8(Arc::new(disc)), vec![bad, column(ok, self.dec)]));
+        Ok(())
+    }
+}
+impl Typed for Parse {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let v = tc_pop(st, "parse")?;
+        if v != text_shape() {
+            return Err(format!("parse.{}: expected List<P8> text, got {}", self.dec, v));
+        }
+        st.push(Shape::Sum { disc: PrimWidth::W8, lanes: vec![text_shape(), self.dec.shape()] });
+        Ok(())
+    }
+}
+
+/// `format.D` — `D → List<P8>` text.
+#[derive(Debug, Clone)] pub struct Format { pub dec: Dec }
+impl PrimOp for Format {
+    fn name(&self) -> &str { "format" }
+    fn token(&self) -> Option<String> { Some(format!("format.{}", self.dec)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let xs = read(&pop(st)?, self.dec, &format!("format.{}", self.dec))?;
+        let mut bytes = Vec::with_capacity(xs.len() * 12);
+        let mut ends = Vec::with_capacity(xs.len());
+        for x in xs {
+            bytes.extend_from_slice(format_text(x, self.dec).as_bytes());
+            ends.push(bytes.len() as u64);
+        }
+        st.push(list(bounds_var_from_ends(ends), from_vec::<u8>(bytes)));
+        Ok(())
+    }
+}
+impl Typed for Format {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        tc_dec(st, self.dec, &format!("format.{}", self.dec))?;
+        st.push(text_shape());
+        Ok(())
+    }
+}
+
+pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
+    use crate::ir::typecheck::Op;
+    use crate::syntax::registry::split_suffix;
+    // <op>.D, round.<to>.D, enswizzle.D, deswizzle.D, parse.D, format.D
+    r.add(|tok: &str| -> Option<Box<dyn Op>> {
+        // The tag is the last two dot-separated parts.
+        let (head, _) = split_suffix(split_suffix(tok)?.0)?;
+        let dec = Dec::parse(&tok[head.len() + 1..])?;
+        let arith = |op| Some(Box::new(Arith { op, dec }) as Box<dyn Op>);
+        let cmp = |op| Some(Box::new(Cmp { op, dec }) as Box<dyn Op>);
+        match head {
+            "+" => arith(DecOp::Add),
+            "-" => arith(DecOp::Sub),
+            "*" => arith(DecOp::Mul),
+            "/" => arith(DecOp::Div),
+            "<" => cmp(CmpOp::Lt),
+            "<=" => cmp(CmpOp::Le),
+            "=" => cmp(CmpOp::Eq),
+            "!=" => cmp(CmpOp::Ne),
+            ">=" => cmp(CmpOp::Ge),
+            ">" => cmp(CmpOp::Gt),
+            "enswizzle" => Some(Box::new(Swizzle { dec, encode: true })),
+            "deswizzle" => Some(Box::new(Swizzle { dec, encode: false })),
+            "parse" => Some(Box::new(Parse { dec })),
+            "format" => Some(Box::new(Format { dec })),
+            _ => Some(Box::new(Round { to: Dec::parse(head.strip_prefix("round.")?)?, from: dec })),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::value::bounds_var_from_ends;
+
+    fn run(op: &dyn PrimOp, mut st: Vec<Value>) -> Result<Value, String> {
+        op.run(&mut st, &mut Vec::new())?;
+        Ok(st.pop().unwrap())
+    }
+
+    fn text(rows: &[&str]) -> Value {
+        let mut ends = Vec::new();
+        let mut bytes = Vec::new();
+        for r in rows { bytes.extend_from_slice(r.as_bytes()); ends.push(bytes.len() as u64); }
+        list(bounds_var_from_ends(ends), from_vec::<u8>(bytes))
+    }
+
+    /// A checked result's rows: `Ok(value)` or `Err(code)`, in row order.
+    fn rows(v: &Value, dec: Dec) -> Vec<Result<i128, u8>> {
+        let Value::Sum { disc, lanes } = v else { panic!("{:?}", v) };
+        let mut errs = <u8 as Storage>::extract(&extract_prim(&lanes[0], "t").unwrap()).unwrap().to_vec().into_iter();
+        let mut oks = read(&lanes[1], dec, "t").unwrap().into_iter();
+        <u8 as Storage>::extract(disc).unwrap().iter()
+            .map(|&d| if d == 1 { Ok(oks.next().unwrap()) } else { Err(errs.next().unwrap()) })
+            .collect()
+    }
+
+    const D2: Dec = Dec { wide: false, scale: 2 };
+    const W4: Dec = Dec { wide: true, scale: 4 };
+
+    #[test]
+    fn text_and_tags() {
+        assert_eq!(Dec::parse("d64.2"), Some(D2));
+        assert_eq!(Dec::parse("d128.38").map(|d| d.to_string()), Some("d128.38".into()));
+        for bad in ["d64.19", "d128.39", "d64.", "d64.+2", "d32.2", "i64"] { assert_eq!(Dec::parse(bad), None, "{}", bad); }
+
+        assert_eq!(parse_text(b"-12.5", D2), Some(-1250));
+        assert_eq!(parse_text(b"+.07", D2), Some(7));
+        assert_eq!(parse_text(b"3.", D2), Some(300));
+        assert_eq!(parse_text(b"1.2300", D2), Some(123));
+        for bad in ["1.234", "", ".", "-", "1e3", "1,5", "92233720368547758.08"] {
+            assert_eq!(parse_text(bad.as_bytes(), D2), None, "{}", bad);
+        }
+        assert_eq!(parse_text(b"-92233720368547758.08", D2), Some(i64::MIN as i128));
+        assert_eq!(format_text(-5, D2), "-0.05");
+        assert_eq!(format_text(123_456, D2), "1234.56");
+        assert_eq!(format_text(-7, Dec { wide: false, scale: 0 }), "-7");
+        assert_eq!(format_text(i128::MIN, Dec { wide: true, scale: 38 }), "-1.70141183460469231731687303715884105728");
+
+        let out = run(&Parse { dec: W4 }, vec![text(&["19.99", "x", "-0.0001"])]).unwrap();
+        let Value::Sum { lanes, .. } = &out else { panic!() };
+        assert_eq!(lanes[0], text(&["x"]));
+        let back = run(&Format { dec: W4 }, vec![lanes[1].clone()]).unwrap();
+        assert_eq!(back, text(&["19.9900", "-0.0001"]));
+    }
+
+    #[test]
+    fn arithmetic_rounds_and_reports_faults() {
+        let col = |xs: &[i128], dec| column(xs.to_vec(), dec);
+        let go = |op, dec, a: &[i128], b: &[i128]| rows(&run(&Arith { op, dec }, vec![col(a, dec), col(b, dec)]).unwrap(), dec);
+
+        // 0.10 + 0.20 is exactly 0.30; i64::MAX + 0.01 overflows.
+        assert_eq!(go(DecOp::Add, D2, &[10, i64::MAX as i128], &[20, 1]), vec![Ok(30), Err(0)]);
+        // 1.25 × 0.10 = 0.125 → 0.13; -1.25 × 0.10 → -0.13 (half away from zero).
+        assert_eq!(go(DecOp::Mul, D2, &[125, -125], &[10]), vec![Ok(13), Ok(-13)]);
+        // 10.00 / 3.00 = 3.33; 2.00 / 3.00 = 0.67; x / 0 is a fault.
+        assert_eq!(go(DecOp::Div, D2, &[1000, 200, 100], &[300, 300, 0]), vec![Ok(333), Ok(67), Err(1)]);
+
+        // d128 products past i128 go through the 256-bit path exactly.
+        let big = 10i128.pow(30) + 7;
+        assert_eq!(go(DecOp::Mul, W4, &[big], &[10i128.pow(8)]), vec![Ok(10i128.pow(34) + 7 * 10i128.pow(4))]);
+        assert_eq!(go(DecOp::Mul, W4, &[i128::MAX], &[2 * 10i128.pow(4)]), vec![Err(0)]);
+        assert_eq!(go(DecOp::Div, W4, &[i128::MAX], &[10i128.pow(4)]), vec![Ok(i128::MAX)]);
+        assert_eq!(mul_div(i128::MIN, 1, 1), Some(i128::MIN));
+        assert_eq!(mul_div(i128::MIN, -1, 1), None);
+
+        // Rescale: 1.005 → 1.01, cents → d128 basis points, and a narrowing overflow.
+        let d3 = Dec { wide: false, scale: 3 };
+        let r = |to, from, xs: &[i128]| rows(&run(&Round { to, from }, vec![col(xs, from)]).unwrap(), to);
+        assert_eq!(r(D2, d3, &[1005, -1005, 1004]), vec![Ok(101), Ok(-101), Ok(100)]);
+        assert_eq!(r(W4, D2, &[i64::MIN as i128]), vec![Ok(i64::MIN as i128 * 100)]);
+        assert_eq!(r(D2, W4, &[i64::MIN as i128 * 100, i64::MIN as i128 * 100 - 100]), vec![Ok(i64::MIN as i128), Err(0)]);
+    }
+
+    #[test]
+    fn compare_and_sort_in_order_form() {
+        let xs: Vec<i128> = vec![5, -3, i128::MIN, 0, 1 << 70, -(1 << 70), i128::MAX];
+        let m = run(&Cmp { op: CmpOp::Lt, dec: W4 }, vec![column(xs.clone(), W4), column(vec![0], W4)]).unwrap();
+        assert_eq!(m, from_vec::<u8>(xs.iter().map(|&x| (x < 0) as u8).collect()));
+
+        let narrow: Vec<i128> = vec![5, -3, i64::MIN as i128, 0, i64::MAX as i128];
+        let m = run(&Cmp { op: CmpOp::Ge, dec: D2 }, vec![column(narrow.clone(), D2), column(vec![0], D2)]).unwrap();
+        assert_eq!(m, from_vec::<u8>(vec![1, 0, 0, 1, 1]));
+
+        for (dec, vals) in [(W4, xs), (D2, narrow)] {
+            let mut st = vec![column(vals.clone(), dec)];
+            Swizzle { dec, encode: true }.run(&mut st, &mut Vec::new()).unwrap();
+            crate::ops::sort::sort_poly_run(&mut st).unwrap();
+            Swizzle { dec, encode: false }.run(&mut st, &mut Vec::new()).unwrap();
+            let mut want = vals;
+            want.sort();
+            assert_eq!(read(&st[0], dec, "t").unwrap(), want);
+        }
+    }
+}
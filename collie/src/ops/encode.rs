@@ -0,0 +1,92 @@
+//! `encode.rle` / `encode.for` / `decode`: put a flat column into (or take
+//! it out of) a compressed storage encoding — see `ir::encoding`.
+//!
+//! Stack effect: `Prim<T> -> Prim<T>` for all three; the encoding is on the
+//! storage axis, invisible to typecheck. Encoding is a hint, never a
+//! requirement: any op without an encoding-aware path decodes through
+//! `pop()`, so an encoded column is correct everywhere and cheap where a
+//! kernel knows it (`reduce.*`, `group`, `sort`, `sort.perm`, `Cmp` against
+//! a scalar, `filter` by an RLE mask).
+
+use crate::ir::encoding::{self, Encoded};
+use crate::ir::op::PrimOp;
+use crate::ir::stack::{Stack, pop};
+use crate::ir::typecheck::{Op, Typed, TypeStack, TypeEnv, tc_pop};
+use crate::ir::value::Value;
+use crate::ir::shape::Shape;
+
+/// Which storage encoding `encode.<kind>` produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding { Rle, For }
+
+#[derive(Debug, Clone)] pub struct Encode { pub kind: Encoding }
+
+impl PrimOp for Encode {
+    fn name(&self) -> &str {
+        match self.kind { Encoding::Rle => "encode.rle", Encoding::For => "encode.for" }
+    }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { encode_run(self.kind, st) }
+}
+impl Typed for Encode {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { encode_tc(st, self.name()) }
+}
+
+/// `encode.<kind>` kernel (back-end `SystemOp::Encode` calls this directly).
+/// An already-encoded input is decoded and re-encoded, so `encode.for` of
+/// an RLE column switches encodings.
+pub fn encode_run(kind: Encoding, st: &mut Stack) -> Result<(), String> {
+    let p = match pop(st)? {
+        Value::Prim(p) => p,
+        other => return Err(format!("encode: expected a flat Prim column, got {:?}", other)),
+    };
+    let e: Encoded = match kind {
+        Encoding::Rle => encoding::rle(&p),
+        Encoding::For => encoding::pack(&p),
+    };
+    st.push(Value::Encoded(e));
+    Ok(())
+}
+pub fn encode_tc(st: &mut TypeStack, tag: &str) -> Result<(), String> {
+    match tc_pop(st, tag)? {
+        Shape::Prim(w) => { st.push(Shape::Prim(w)); Ok(()) }
+        other => Err(format!("{}: expected a flat Prim column, got {}", tag, other)),
+    }
+}
+
+#[derive(Debug, Clone)] pub struct Decode;
+
+impl PrimOp for Decode {
+    fn name(&self) -> &str { "decode" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { decode_run(st) }
+}
+impl Typed for Decode {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { decode_tc(st) }
+}
+
+/// `decode` kernel (back-end `SystemOp::Decode` calls this directly). The
+/// materializing `pop()` does the work; a View on top is gathered too.
+pub fn decode_run(st: &mut Stack) -> Result<(), String> {
+    let v = pop(st)?;
+    st.push(v);
+    Ok(())
+}
+pub fn decode_tc(st: &mut TypeStack) -> Result<(), String> {
+    let v = tc_pop(st, "decode")?;
+    st.push(v);
+    Ok(())
+}
+
+pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
+    r.add(|t: &str| -> Option<Box<dyn Op>> {
+        match t {
+            "encode.rle" => Some(Box::new(Encode { kind: Encoding::Rle })),
+            "encode.for" => Some(Box::new(Encode { kind: Encoding::For })),
+            "decode"     => Some(Box::new(Decode)),
+            _ => None,
+        }
+    });
+}
@@ -2,11 +2,11 @@
 //! These don't implement PrimOp themselves; they're called by ops that need them.
 
 use std::sync::Arc;
-use crate::ir::value::{Value, Prim, PrimWidth, Selector, Storage, bounds_var_from_ends, prod, list, compose_selectors};
+use crate::ir::value::{Value, Prim, PrimWidth, Selector, BoundsRepr, Storage, bounds_var_from_ends, bounds_from_ends, bounds_stride, prod, sum, list, compose_selectors};
 use crate::ir::shape::{Interp, bounds_as_u64};
 
-/// Materialize a `Value::View` by gathering source through selector. Returns
-/// non-View values unchanged. Use this when an op needs an unrestricted
+/// Materialize a `Value::View` by gathering source through selector, and
+/// decode a `Value::Encoded`. Returns other values unchanged. Use this when an op needs an unrestricted
 /// Value and doesn't want to opt into View-aware fast paths.
 pub fn materialize(v: Value) -> Result<Value, String> {
     match v {
@@ -14,12 +14,13 @@ pub fn materialize(v: Value) -> Result<Value, String> {
             let idxs = selector.to_usize_vec();
             gather(&source, &idxs)
         }
+        Value::Encoded(e) => Ok(Value::Prim(e.decode())),
         other => Ok(other),
     }
 }
 
-/// Materialize a `&Value::View` without consuming. Returns `Cow::Borrowed`
-/// for non-View, `Cow::Owned` for View. Helpful when an op already has a
+/// Materialize a `&Value::View` (or decode a `&Value::Encoded`) without
+/// consuming. Returns `Cow::Borrowed` for other values, `Cow::Owned` for these. Helpful when an op already has a
 /// `&Value` borrow and doesn't want to clone.
 ///
 /// Delegates to `stack::materialize_top` for the work (handles all selector
@@ -27,13 +28,42 @@ pub fn materialize(v: Value) -> Result<Value, String> {
 pub fn materialize_ref(v: &Value) -> Result<std::borrow::Cow<'_, Value>, String> {
     use std::borrow::Cow;
     match v {
-        Value::View { .. } => Ok(Cow::Owned(crate::ir::stack::materialize_top(v.clone())?)),
+        Value::View { .. } | Value::Encoded(_) => Ok(Cow::Owned(crate::ir::stack::materialize_top(v.clone())?)),
         other => Ok(Cow::Borrowed(other)),
     }
 }
 
+/// Deep canonical form: every View materialized (at any depth) and every
+/// List re-laid out contiguously with `Var` bounds. Two values that mean
+/// the same sequence normalize to equal values. The differential fuzzer
+/// compares through this; the C ABI hands results out in this form.
+pub fn normalize(v: &Value) -> Result<Value, String> {
+    let v = crate::ir::stack::materialize_top(v.clone())?;
+    Ok(match v {
+        Value::Prim(_) => v,
+        Value::Prod(fs) => prod(fs.iter().map(normalize).collect::<Result<_, _>>()?),
+        Value::Sum { disc, lanes } => sum(disc, lanes.iter().map(normalize).collect::<Result<_, _>>()?),
+        // Already contiguous from 0: keep the bounds and values' buffers.
+        Value::List { bounds: BoundsRepr::Var(Prim::P64(b)), values }
+            if b.first() == Some(&0) && b.last() == Some(&(values.len() as u64)) =>
+        {
+            list(BoundsRepr::Var(Prim::P64(b)), normalize(&values)?)
+        }
+        Value::List { bounds, values } => {
+            let mut idxs = Vec::new();
+            let mut ends = Vec::with_capacity(bounds.len());
+            for (lo, hi) in bounds.iter_pairs() {
+                idxs.extend(lo as usize..hi as usize);
+                ends.push(idxs.len() as u64);
+            }
+            list(bounds_var_from_ends(ends), normalize(&gather(&values, &idxs)?)?)
+        }
+        Value::View { .. } | Value::Encoded(_) => unreachable!("materialize_top removed the View / Encoded"),
+    })
+}
+
 /// Visit every logical element of a Prim-shaped value as a `T`, regardless
-/// of whether the storage is direct or viewed. Fast path for ops that just
+/// of whether the storage is direct, viewed or encoded. Fast path for ops that just
 /// want to iterate (`reduce.+`, `min`/`max`, scalar `where`, etc.) without
 /// allocating an intermediate gathered buffer.
 ///
@@ -79,6 +109,19 @@ pub fn for_each_prim<T: Storage, F: FnMut(T)>(v: &Value, mut f: F) -> Result<(),
             }
             Ok(())
         }
+        Value::Encoded(e @ crate::ir::value::Encoded::Rle { values, .. }) => {
+            // One read per run, not per element.
+            let xs = T::extract(values)?;
+            for (r, (lo, hi)) in e.runs().enumerate() {
+                for _ in lo..hi { f(xs[r]); }
+            }
+            Ok(())
+        }
+        Value::Encoded(e) => {
+            let p = e.decode();
+            for &x in T::extract(&p)? { f(x); }
+            Ok(())
+        }
         other => Err(format!("for_each_prim: expected Prim, got {:?}", other)),
     }
 }
@@ -107,6 +150,7 @@ pub fn gather_prim(p: &Prim, idxs: &[usize]) -> Prim {
 pub fn gather(v: &Value, idxs: &[usize]) -> Result<Value, String> {
     Ok(match v {
         Value::Prim(p) => Value::Prim(gather_prim(p, idxs)),
+        Value::Encoded(e) => Value::Prim(gather_prim(&e.decode(), idxs)),
         Value::Prod(fs) => {
             let mut out = Vec::with_capacity(fs.len());
             for f in fs.iter() { out.push(gather(f, idxs)?); }
@@ -147,6 +191,17 @@ pub fn gather(v: &Value, idxs: &[usize]) -> Result<Value, String> {
             }
             Value::Sum { disc: Prim::P8(Arc::new(new_disc)), lanes: Arc::new(new_lanes) }
         }
+        // Rows of a `Stride` list are dense `stride`-long slots: no bounds
+        // to read, and the gathered rows keep the stride.
+        Value::List { bounds: BoundsRepr::Stride { stride, count }, values } => {
+            let s = *stride as usize;
+            let mut flat_idxs: Vec<usize> = Vec::with_capacity(idxs.len() * s);
+            for &i in idxs {
+                if i as u64 >= *count { return Err(format!("gather: row {} out of range for {} rows", i, count)); }
+                flat_idxs.extend(i * s..(i + 1) * s);
+            }
+            list(bounds_stride(*stride, idxs.len() as u64), gather(values, &flat_idxs)?)
+        }
         Value::List { bounds, values } => {
             let bnds = bounds_as_u64(bounds)?;
             let mut new_bounds = Vec::with_capacity(idxs.len());
@@ -226,6 +281,10 @@ pub fn slice_value(v: &Value, lo: usize, hi: usize) -> Result<Value, String> {
             for f in fs.iter() { out.push(slice_value(f, lo, hi)?); }
             prod(out)
         }
+        Value::List { bounds: BoundsRepr::Stride { stride, .. }, values } => {
+            let s = *stride as usize;
+            list(bounds_stride(*stride, (hi - lo) as u64), slice_value(values, lo * s, hi * s)?)
+        }
         Value::List { bounds, values } => {
             let bnds = bounds_as_u64(bounds)?;
             // Canonical N+1 starts: row i's slice is [bnds[i], bnds[i+1]).
@@ -287,7 +346,7 @@ pub fn concat_values(parts: &[Value]) -> Result<Value, String> {
                 } else { return Err("concat_values: mixed types".into()); }
             }
             let new_inner = concat_values(&inners)?;
-            Ok(list(bounds_var_from_ends(new_bounds), new_inner))
+            Ok(list(bounds_from_ends(new_bounds), new_inner))
         }
         other => Err(format!("concat_values: unsupported {:?}", other)),
     }
@@ -390,21 +449,19 @@ pub fn merge_by_disc(disc: &[u8], lanes: &[Value]) -> Result<Value, String> {
     }
 }
 
-pub fn sum_runs(values: &Value, bounds: &[u64], interp: Interp) -> Result<Value, String> {
+pub fn sum_runs(values: &Value, bounds: &BoundsRepr, interp: Interp) -> Result<Value, String> {
     let p = match values {
         Value::Prim(p) => p,
         _ => return Err("sum_runs: expected Prim".into()),
     };
     macro_rules! run { ($t:ty) => {{
         let xs = <$t as Storage>::extract(p)?;
-        let mut out: Vec<$t> = Vec::with_capacity(bounds.len().saturating_sub(1));
-        for w in bounds.windows(2) {
-            let lo = w[0] as usize;
-            let hi = w[1] as usize;
+        let mut out: Vec<$t> = Vec::with_capacity(bounds.len());
+        bounds.for_each_row(|lo, hi| {
             let mut s: $t = <$t as Default>::default();
             for &x in &xs[lo..hi] { s = s + x; }
             out.push(s);
-        }
+        });
         Ok(crate::ir::value::from_vec::<$t>(out))
     }};}
     match interp {
@@ -421,10 +478,21 @@ pub fn sum_runs(values: &Value, bounds: &[u64], interp: Interp) -> Result<Value,
     }
 }
 
-/// Sum a flat (non-List) value into a single scalar. Accepts either a plain
-/// `Prim` or a `View` over a Prim. The View case dispatches on selector
-/// variant in `for_each_prim` and avoids materializing an intermediate.
+/// Sum a flat (non-List) value into a single scalar. Accepts a plain
+/// `Prim`, a `View` over a Prim, or an `Encoded` column. The View case
+/// dispatches on selector variant in `for_each_prim` and avoids
+/// materializing an intermediate; an encoded integer column sums without
+/// decoding (Σ value·run-length, or `n·base + Σ deltas`). Float sums walk
+/// the elements so rounding matches the decoded column's.
 pub fn sum_whole(v: &Value, interp: Interp) -> Result<Value, String> {
+    if let Value::Encoded(e) = v {
+        if !matches!(interp, Interp::F32 | Interp::F64) {
+            if e.width() != interp.width() {
+                return Err(format!("reduce.+.{}: expected {}, got {}", interp, interp.width(), e.width()));
+            }
+            return Ok(Value::Prim(crate::ir::encoding::from_words(e.width(), vec![e.wrapping_sum()])));
+        }
+    }
     macro_rules! one { ($t:ty) => {{
         let mut acc: $t = <$t as Default>::default();
         for_each_prim::<$t, _>(v, |x| acc = acc + x)?;
@@ -558,3 +626,29 @@ where F: Fn(&Value) -> Result<Value, String>
         _ => None,
     }
 }
+
+/// One SplitMix64 step: advance `state` by the golden-ratio increment and
+/// return its finalized (well-mixed) value. The shared mixer behind the
+/// fuzzer's and `rand.*`'s seeding and the structural hashes in
+/// `approx.distinct` and the run cache; its outputs are part of what
+/// makes seeds and cache identities reproduce, so it must not change.
+pub fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splitmix64_matches_the_reference_sequence() {
+        let mut s = 0u64;
+        assert_eq!(splitmix64(&mut s), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(splitmix64(&mut s), 0x6e78_9e6a_a1b9_65f4);
+        assert_eq!(s, 0x9e37_79b9_7f4a_7c15u64.wrapping_mul(2));
+    }
+}
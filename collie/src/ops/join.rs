@@ -12,6 +12,10 @@
 //! - `gather` — apply a P64 position column to a value column.
 //!   Materializing path for `View` consumers.
 //! - `xprod` — per-list Cartesian product across two parallel lists.
+//! - `join.asof` / `join.interval` — non-equi joins on sorted keys: the
+//!   last build key at or before each probe (optionally per partition),
+//!   and every interval containing each point. Both sort the probes and
+//!   gallop one forward cursor, like `search`.
 //!
 //! The per-list variants (e.g. `intersect` over `List<List<X>>`)
 //! are the long part of each op. The flat-column variant is at the
@@ -22,7 +26,8 @@ use crate::ir::op::PrimOp;
 use crate::ir::stack::{Stack, pop, pop_raw};
 use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
 use crate::ir::value::{Value, Prim, PrimWidth, Selector, Storage, from_vec, prod};
-use crate::ir::shape::{Shape, bounds_as_u64};
+use crate::ir::shape::{Interp, Shape, bounds_as_u64};
+use crate::ir::encoding::word;
 use crate::ops::helpers::{gallop_to, gather, sort_merge_intersect, materialize};
 
 /// `intersect` — sort-merge intersection, polymorphic on input shape,
@@ -40,7 +45,7 @@ use crate::ops::helpers::{gallop_to, gather, sort_merge_intersect, materialize};
 ///
 /// The list-shaped form is the canonical per-list default; flat is the
 /// single-list case. Input types pick the path automatically.
-#[derive(Debug)] pub struct Intersect;
+#[derive(Debug, Clone)] pub struct Intersect;
 impl PrimOp for Intersect {
     fn name(&self) -> &str { "intersect" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 2)) }  // (a, b) → (positions_in_a, positions_in_b)
@@ -187,6 +192,7 @@ fn peel_flat_view(v: Value) -> Result<Value, String> {
             Selector::Indices(_) | Selector::Runs(_) | Selector::Mask(_) => materialize(v),
             Selector::SequenceRange { .. } => Ok(v),
         },
+        Value::Encoded(_) => materialize(v),
         _ => Ok(v),
     }
 }
@@ -299,6 +305,7 @@ impl<'a> ListAccess<'a> {
 }
 
 impl Typed for Intersect {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { intersect_tc(st) }
 }
 pub fn intersect_tc(st: &mut TypeStack) -> Result<(), String> {
@@ -355,7 +362,7 @@ pub fn intersect_tc(st: &mut TypeStack) -> Result<(), String> {
 /// Cost: `O(|queries| * log(|target|))` via per-query gallop. Asymmetric —
 /// good when `|queries| << |target|`. (For balanced sizes, `intersect` is
 /// O(|target| + |queries|) and may be faster.)
-#[derive(Debug)] pub struct Search;
+#[derive(Debug, Clone)] pub struct Search;
 impl PrimOp for Search {
     fn name(&self) -> &str { "search" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }  // (target, queries) → positions
@@ -453,6 +460,7 @@ fn flatten_to_prim(v: Value, op: &str) -> Result<Prim, String> {
     }
 }
 impl Typed for Search {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { search_tc(st) }
 }
 pub fn search_tc(st: &mut TypeStack) -> Result<(), String> {
@@ -487,13 +495,14 @@ pub fn search_tc(st: &mut TypeStack) -> Result<(), String> {
         }
 }
 
-#[derive(Debug)] pub struct Gather;
+#[derive(Debug, Clone)] pub struct Gather;
 impl PrimOp for Gather {
     fn name(&self) -> &str { "gather" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }  // (col, idxs) → gathered
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { gather_run(st) }
 }
 impl Typed for Gather {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { gather_tc(st) }
 }
 /// `gather` kernel (back-end `SystemOp::Gather` calls this directly).
@@ -515,7 +524,7 @@ pub fn gather_tc(st: &mut TypeStack) -> Result<(), String> {
     Ok(())
 }
 
-#[derive(Debug)] pub struct XProd;
+#[derive(Debug, Clone)] pub struct XProd;
 impl PrimOp for XProd {
     fn name(&self) -> &str { "xprod" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }  // Prod[List, List] → List<Prod>
@@ -619,6 +628,205 @@ fn sort_gallop_slice<T: Ord + Copy>(target: &[T], queries: &[T], out: &mut [u64]
     }
 }
 
+/// `join.asof.<interp>` — for each probe key, the last build position
+/// whose key is `<=` it ("latest quote at or before each trade").
+///
+/// **Flat form (Prim, Prim):** keys compared under `<i>`; the build keys
+/// must be sorted ascending under `<i>`.
+///
+/// **Partitioned form (Prod[part, key], Prod[part, key]):** a probe only
+/// matches build rows with an equal `part` (compared as words, any
+/// width); the build must be sorted by `part` (unsigned) then `key`
+/// (under `<i>`) — e.g. `(symbol, time)` sorted by symbol, then time.
+///
+/// Stack effect: `(build, probe) -> (build_positions, probe_positions)`.
+/// Inner-join: a probe with nothing at or before it (in its partition)
+/// contributes no row. Rows come out in probe order. Build sortedness is
+/// checked (one linear pass); probes may be in any order.
+///
+/// Cost: sort the probes, then one forward gallop over the build —
+/// `search`'s kernel, with an upper bound in place of the lower bound.
+#[derive(Debug, Clone)] pub struct AsofJoin { pub interp: Interp }
+impl PrimOp for AsofJoin {
+    fn name(&self) -> &str { "join.asof" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 2)) }  // (build, probe) → (build_pos, probe_pos)
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { asof_run(self.interp, st) }
+}
+impl Typed for AsofJoin {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { asof_tc(self.interp, st) }
+}
+/// `join.asof` kernel (back-end `SystemOp::AsofJoin` calls this directly).
+pub fn asof_run(interp: Interp, st: &mut Stack) -> Result<(), String> {
+    let probe = asof_keys(pop(st)?, interp)?;
+    let build = asof_keys(pop(st)?, interp)?;
+    if build.0 != probe.0 {
+        return Err("join.asof: build and probe must both be partitioned (Prod[part, key]) or neither".into());
+    }
+    let (build, probe) = (build.1, probe.1);
+    if let Some(i) = build.windows(2).position(|w| w[0] > w[1]) {
+        return Err(format!("join.asof.{}: build keys not sorted at position {}", interp, i + 1));
+    }
+    let (bs, ps) = asof_positions(&build, &probe);
+    st.push(from_vec::<u64>(bs));
+    st.push(from_vec::<u64>(ps));
+    Ok(())
+}
+
+/// A side's `(part, key)` pairs in order form (flat form: part 0), and
+/// whether it was partitioned.
+fn asof_keys(v: Value, interp: Interp) -> Result<(bool, Vec<(u64, u64)>), String> {
+    match materialize(v)? {
+        Value::Prod(fs) if fs.len() == 2 => {
+            let part = flatten_to_prim(fs[0].clone(), "join.asof")?;
+            let keys = order_words(fs[1].clone(), interp, "join.asof")?;
+            if part.len() != keys.len() {
+                return Err(format!("join.asof: part has {} rows, key has {}", part.len(), keys.len()));
+            }
+            Ok((true, keys.into_iter().enumerate().map(|(i, k)| (word(&part, i), k)).collect()))
+        }
+        v => Ok((false, order_words(v, interp, "join.asof")?.into_iter().map(|k| (0, k)).collect())),
+    }
+}
+
+/// A Prim column as order-form words: unsigned word order equals `<i>`
+/// order (the `enswizzle` transform).
+fn order_words(v: Value, interp: Interp, op: &str) -> Result<Vec<u64>, String> {
+    let p = flatten_to_prim(v, op)?;
+    if p.width() != interp.width() {
+        return Err(format!("{}.{}: expected {} keys, got {:?}", op, interp, interp.width(), p.width()));
+    }
+    let p = crate::ops::swizzle::swizzle(p, interp, true)?;
+    Ok((0..p.len()).map(|i| word(&p, i)).collect())
+}
+
+/// Sort the probes, gallop a forward cursor to each one's upper bound in
+/// `build`; the row before it is the match if it shares the partition.
+fn asof_positions(build: &[(u64, u64)], probe: &[(u64, u64)]) -> (Vec<u64>, Vec<u64>) {
+    let mut order: Vec<u32> = (0..probe.len() as u32).collect();
+    order.sort_unstable_by_key(|&k| probe[k as usize]);
+    let mut hit = vec![u64::MAX; probe.len()];
+    let mut bi = 0usize;
+    for &qi in &order {
+        let q = probe[qi as usize];
+        bi = gallop_to(build, bi, |x| *x <= q);
+        if bi > 0 && build[bi - 1].0 == q.0 { hit[qi as usize] = (bi - 1) as u64; }
+    }
+    hit.iter().enumerate().filter(|(_, &b)| b != u64::MAX).map(|(p, &b)| (b, p as u64)).unzip()
+}
+
+pub fn asof_tc(interp: Interp, st: &mut TypeStack) -> Result<(), String> {
+    let probe = tc_pop(st, "join.asof")?;
+    let build = tc_pop(st, "join.asof")?;
+    let w = interp.width();
+    let ok = match (&build, &probe) {
+        (Shape::Prim(a), Shape::Prim(b)) => *a == w && *b == w,
+        (Shape::Prod(a), Shape::Prod(b)) if a.len() == 2 && b.len() == 2 => matches!(
+            (&a[0], &a[1], &b[0], &b[1]),
+            (Shape::Prim(pa), Shape::Prim(ka), Shape::Prim(pb), Shape::Prim(kb)) if pa == pb && *ka == w && *kb == w
+        ),
+        _ => false,
+    };
+    if !ok {
+        return Err(format!(
+            "join.asof.{}: expected two Prim({}) key columns or two Prod[part, Prim({})], got {} and {}",
+            interp, w, w, build, probe
+        ));
+    }
+    st.push(Shape::Prim(PrimWidth::W64));
+    st.push(Shape::Prim(PrimWidth::W64));
+    Ok(())
+}
+
+/// `join.interval.<interp>` — every (interval, point) pair with the point
+/// in the half-open interval `[start, end)` ("events within a session").
+///
+/// Stack effect: `(Prod[starts, ends], points) -> (interval_positions,
+/// point_positions)`. All three columns are Prims of the interp's width,
+/// compared under `<i>`; the intervals must be sorted by start (checked).
+/// Rows come out in point order, then interval order. Empty intervals
+/// (`end <= start`) match nothing; overlapping intervals are fine.
+///
+/// Cost: sort the points, then sweep them ascending — gallop the start
+/// cursor to admit intervals that have begun, drop those that have ended
+/// — so `O(n log n + m + output)` over the sorted inputs.
+#[derive(Debug, Clone)] pub struct IntervalJoin { pub interp: Interp }
+impl PrimOp for IntervalJoin {
+    fn name(&self) -> &str { "join.interval" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 2)) }  // (intervals, points) → (interval_pos, point_pos)
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { interval_run(self.interp, st) }
+}
+impl Typed for IntervalJoin {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { interval_tc(self.interp, st) }
+}
+/// `join.interval` kernel (back-end `SystemOp::IntervalJoin` calls this directly).
+pub fn interval_run(interp: Interp, st: &mut Stack) -> Result<(), String> {
+    let points = order_words(pop(st)?, interp, "join.interval")?;
+    let (starts, ends) = match materialize(pop(st)?)? {
+        Value::Prod(fs) if fs.len() == 2 => (
+            order_words(fs[0].clone(), interp, "join.interval")?,
+            order_words(fs[1].clone(), interp, "join.interval")?,
+        ),
+        other => return Err(format!("join.interval: intervals must be Prod[starts, ends], got {}", other)),
+    };
+    if starts.len() != ends.len() {
+        return Err(format!("join.interval: {} starts but {} ends", starts.len(), ends.len()));
+    }
+    if let Some(i) = starts.windows(2).position(|w| w[0] > w[1]) {
+        return Err(format!("join.interval.{}: intervals not sorted by start at position {}", interp, i + 1));
+    }
+    let (is, ps) = interval_positions(&starts, &ends, &points);
+    st.push(from_vec::<u64>(is));
+    st.push(from_vec::<u64>(ps));
+    Ok(())
+}
+
+/// The sweep. `active` holds the begun, unended intervals in position
+/// order (admitted in start order, pruned with `retain`); since points
+/// ascend, an interval that has ended never returns.
+fn interval_positions(starts: &[u64], ends: &[u64], points: &[u64]) -> (Vec<u64>, Vec<u64>) {
+    let mut order: Vec<u32> = (0..points.len() as u32).collect();
+    order.sort_unstable_by_key(|&k| points[k as usize]);
+    let mut active: Vec<u32> = Vec::new();
+    let mut matched: Vec<u32> = Vec::new();
+    let mut ranges = vec![(0usize, 0usize); points.len()];
+    let mut next = 0usize;
+    for &qi in &order {
+        let q = points[qi as usize];
+        let upto = gallop_to(starts, next, |s| *s <= q);
+        active.extend(next as u32..upto as u32);
+        next = upto;
+        active.retain(|&k| ends[k as usize] > q);
+        ranges[qi as usize] = (matched.len(), matched.len() + active.len());
+        matched.extend_from_slice(&active);
+    }
+    let mut is = Vec::with_capacity(matched.len());
+    let mut ps = Vec::with_capacity(matched.len());
+    for (p, &(lo, hi)) in ranges.iter().enumerate() {
+        is.extend(matched[lo..hi].iter().map(|&k| k as u64));
+        ps.extend(std::iter::repeat_n(p as u64, hi - lo));
+    }
+    (is, ps)
+}
+
+pub fn interval_tc(interp: Interp, st: &mut TypeStack) -> Result<(), String> {
+    let points = tc_pop(st, "join.interval")?;
+    let intervals = tc_pop(st, "join.interval")?;
+    let w = Shape::Prim(interp.width());
+    match &intervals {
+        Shape::Prod(fs) if fs.len() == 2 && fs[0] == w && fs[1] == w && points == w => {
+            st.push(Shape::Prim(PrimWidth::W64));
+            st.push(Shape::Prim(PrimWidth::W64));
+            Ok(())
+        }
+        _ => Err(format!(
+            "join.interval.{}: expected Prod[{}, {}] intervals and {} points, got {} and {}",
+            interp, w, w, w, intervals, points
+        )),
+    }
+}
+
 pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
     use crate::ir::typecheck::Op;
     r.add(|t: &str| -> Option<Box<dyn Op>> {
@@ -634,9 +842,20 @@ pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
             _ => None,
         }
     });
+    r.add(|t: &str| -> Option<Box<dyn Op>> {
+        use crate::syntax::registry::{parse_interp, split_suffix};
+        let (head, sfx) = split_suffix(t)?;
+        let interp = parse_interp(sfx)?;
+        match head {
+            "join.asof" => Some(Box::new(AsofJoin { interp })),
+            "join.interval" => Some(Box::new(IntervalJoin { interp })),
+            _ => None,
+        }
+    });
 }
 
 impl Typed for XProd {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { xprod_tc(st) }
 }
 pub fn xprod_tc(st: &mut TypeStack) -> Result<(), String> {
@@ -946,4 +1165,99 @@ mod tests {
         };
         assert_eq!(out, vec![2, 4]);
     }
+
+    /// Deterministic pseudo-random i64s in `[-range, range)`.
+    fn lcg(n: usize, seed: u64, range: i64) -> Vec<i64> {
+        let mut x = seed;
+        (0..n).map(|_| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((x >> 33) as i64).rem_euclid(2 * range) - range
+        }).collect()
+    }
+
+    fn pairs(st: &mut Vec<Value>) -> Vec<(u64, u64)> {
+        let (b, p) = (st.pop().unwrap(), st.pop().unwrap());
+        match (p, b) {
+            (Value::Prim(Prim::P64(l)), Value::Prim(Prim::P64(r))) => l.iter().copied().zip(r.iter().copied()).collect(),
+            other => panic!("expected two P64 columns, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn asof_matches_nested_loops() {
+        // Signed keys with duplicates and negatives; partitioned and not.
+        let mut build_t = lcg(300, 1, 50);
+        let mut build_part: Vec<u64> = lcg(300, 2, 3).iter().map(|&x| (x + 3) as u64).collect();
+        let mut rows: Vec<(u64, i64)> = build_part.iter().copied().zip(build_t.iter().copied()).collect();
+        rows.sort();
+        (build_part, build_t) = rows.into_iter().unzip();
+        let probe_t = lcg(500, 3, 60);
+        let probe_part: Vec<u64> = lcg(500, 4, 4).iter().map(|&x| (x + 4) as u64).collect();
+
+        let mut env = Vec::new();
+        let mut flat_b = build_t.clone();
+        flat_b.sort();
+        let mut st = vec![from_vec::<i64>(flat_b.clone()), from_vec::<i64>(probe_t.clone())];
+        AsofJoin { interp: Interp::I64 }.run(&mut st, &mut env).unwrap();
+        // Oracle: the last build row at or before each probe.
+        let want: Vec<(u64, u64)> = (0..probe_t.len()).filter_map(|p| {
+            (0..flat_b.len()).rev().find(|&b| flat_b[b] <= probe_t[p]).map(|b| (b as u64, p as u64))
+        }).collect();
+        assert_eq!(pairs(&mut st), want);
+
+        let mut st = vec![
+            prod(vec![from_vec::<u64>(build_part.clone()), from_vec::<i64>(build_t.clone())]),
+            prod(vec![from_vec::<u64>(probe_part.clone()), from_vec::<i64>(probe_t.clone())]),
+        ];
+        AsofJoin { interp: Interp::I64 }.run(&mut st, &mut env).unwrap();
+        // Oracle: the last build row (in build order) in the probe's partition.
+        let want: Vec<(u64, u64)> = (0..probe_t.len()).filter_map(|p| {
+            (0..build_t.len()).rev()
+                .find(|&b| build_part[b] == probe_part[p] && build_t[b] <= probe_t[p])
+                .map(|b| (b as u64, p as u64))
+        }).collect();
+        assert!(!want.is_empty() && want.len() < probe_t.len());
+        assert_eq!(pairs(&mut st), want);
+
+        // Unsorted build keys are rejected (here: unsorted under i64).
+        let mut st = vec![from_vec::<i64>(vec![1, -1]), from_vec::<i64>(vec![0])];
+        let e = AsofJoin { interp: Interp::I64 }.run(&mut st, &mut env).unwrap_err();
+        assert!(e.contains("not sorted at position 1"), "{}", e);
+    }
+
+    #[test]
+    fn interval_matches_nested_loops() {
+        let mut starts = lcg(200, 5, 100);
+        starts.sort();
+        let lens = lcg(200, 6, 20);
+        let ends: Vec<i64> = starts.iter().zip(&lens).map(|(s, l)| s + l).collect(); // some empty
+        let points = lcg(400, 7, 130);
+        let mut st = vec![
+            prod(vec![from_vec::<i64>(starts.clone()), from_vec::<i64>(ends.clone())]),
+            from_vec::<i64>(points.clone()),
+        ];
+        IntervalJoin { interp: Interp::I64 }.run(&mut st, &mut Vec::new()).unwrap();
+        let want: Vec<(u64, u64)> = (0..points.len()).flat_map(|p| {
+            let (starts, ends, points) = (&starts, &ends, &points);
+            (0..starts.len()).filter(move |&i| starts[i] <= points[p] && points[p] < ends[i]).map(move |i| (i as u64, p as u64))
+        }).collect();
+        assert!(want.len() > points.len());
+        assert_eq!(pairs(&mut st), want);
+    }
+
+    #[test]
+    fn joins_through_the_pipeline() {
+        use crate::syntax::{registry::OpRegistry, parse::parse};
+        let reg = OpRegistry::standard();
+        // Quotes at t = 1, 4, 9; trades at 0, 4, 5, 10 → quotes 1, 1, 2 for the last three.
+        let prog = parse("u64[1 4 9] u64[0 4 5 10] join.asof.u64", &reg).unwrap();
+        let st = crate::pipeline::eval_graph(&crate::pipeline::build(prog).unwrap().0).unwrap();
+        assert_eq!(st, vec![from_vec::<u64>(vec![1, 1, 2]), from_vec::<u64>(vec![1, 2, 3])]);
+        // Sessions [0, 10) and [5, 8); events at 7, 12, -1.
+        let prog = parse("i32[0 5] i32[10 8] entuple i32[7 12 -1] join.interval.i32", &reg).unwrap();
+        let st = crate::pipeline::eval_graph(&crate::pipeline::build(prog).unwrap().0).unwrap();
+        assert_eq!(st, vec![from_vec::<u64>(vec![0, 1]), from_vec::<u64>(vec![0, 0])]);
+        let e = crate::pipeline::build(parse("u64[1] u32[1] join.asof.u64", &reg).unwrap()).unwrap_err();
+        assert!(e.to_string().contains("join.asof.u64: expected"), "{}", e);
+    }
 }
@@ -9,12 +9,19 @@
 //! anonymous-intermediate chains; see `src/syntax/inference.rs`.
 
 use crate::ir::op::PrimOp;
+use crate::ir::span::Site;
 use crate::ir::stack::Stack;
-use crate::ir::typecheck::{Op, Typed, TypeStack, TypeEnv, typecheck};
+use crate::ir::typecheck::{Op, Typed, TypeStack, TypeEnv, clone_ops, typecheck};
 use crate::ir::value::Value;
 
 #[derive(Debug)]
-pub struct Let { pub names: Vec<String>, pub body: Vec<Box<dyn Op>> }
+pub struct Let {
+    pub names: Vec<String>,
+    pub body: Vec<Box<dyn Op>>,
+    /// Source site of each body op (parallel to `body`; empty when the
+    /// body wasn't parsed from text).
+    pub sites: Vec<Site>,
+}
 
 impl PrimOp for Let {
     fn name(&self) -> &str { "let" }
@@ -30,6 +37,9 @@ impl PrimOp for Let {
     }
 }
 impl Typed for Let {
+    fn clone_op(&self) -> Option<Box<dyn Op>> {
+        Some(Box::new(Let { names: self.names.clone(), body: clone_ops(&self.body)?, sites: self.sites.clone() }))
+    }
     fn tc(&self, st: &mut TypeStack, env: &mut TypeEnv) -> Result<(), String> {
         if st.len() < self.names.len() {
             return Err(format!("let: stack underflow ({} required, {} available)", self.names.len(), st.len()));
@@ -57,7 +67,7 @@ impl Typed for Let {
 /// taken via `name>`, any subsequent reference to `name` errors at
 /// parse time. So a defaulted slot should never be read at runtime; the
 /// Default impl on `Value` is just a safety net.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Ref { pub idx: usize, pub take: bool }
 
 impl PrimOp for Ref {
@@ -77,6 +87,7 @@ impl PrimOp for Ref {
     }
 }
 impl Typed for Ref {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, env: &mut TypeEnv) -> Result<(), String> {
         if self.idx >= env.len() {
             return Err(format!("ref {}: env len {}", self.idx, env.len()));
@@ -12,8 +12,9 @@
 //! - **Shape inspection**: `Bounds`, `BoundsToKeys`, `Count`.
 //!   Cheap, structural.
 //! - **Construction and slicing**: `Like`, `Head`, `Iota`,
-//!   `Spread`, `Where_`, `Filter`. `Filter` is the parser-peephole
-//!   fusion of `where` + `gather`.
+//!   `Spread`, `Where_`, `Filter`, `MaskCompose`. `Filter` is the
+//!   parser-peephole fusion of `where` + `gather`; `MaskCompose` is what
+//!   the optimizer folds a chain of two filters into.
 //!
 //! On a first read, skim the section markers and the typed variants.
 
@@ -21,12 +22,12 @@ use std::sync::Arc;
 use crate::ir::op::PrimOp;
 use crate::ir::stack::{Stack, pop};
 use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
-use crate::ir::value::{Value, Prim, PrimWidth, from_vec, bounds_var_from_ends, prod, view, Selector};
-use crate::ir::shape::{Interp, Shape, bounds_as_u64};
+use crate::ir::value::{Value, Encoded, Prim, PrimWidth, BoundsRepr, from_vec, bounds_from_ends, prod, view, Selector};
+use crate::ir::shape::{Interp, Shape};
 use crate::ops::helpers::{broadcast, gather, sum_runs, sum_whole};
 use crate::ops::sort::{sort_blocks, run_layout};
 
-#[derive(Debug)] pub struct Group;
+#[derive(Debug, Clone)] pub struct Group;
 impl PrimOp for Group {
     fn name(&self) -> &str { "group" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 2)) }  // (vals, keys) → (uniq_keys, list_of_grouped_vals)
@@ -34,11 +35,18 @@ impl PrimOp for Group {
 }
 /// `group` kernel (back-end `SystemOp::Group` calls this directly).
 pub fn group_run(st: &mut Stack) -> Result<(), String> {
-        let keys = pop(st)?;
+        let keys = crate::ir::stack::pop_raw(st)?;
         let vals = pop(st)?;
         if keys.len() != vals.len() {
             return Err(format!("group: vals len {} != keys len {}", vals.len(), keys.len()));
         }
+        if let Value::Encoded(e @ Encoded::Rle { values, .. }) = &keys {
+            let (unique_keys, list) = group_rle(e, values, vals)?;
+            st.push(unique_keys);
+            st.push(list);
+            return Ok(());
+        }
+        let keys = crate::ir::stack::materialize_top(keys)?;
         let n = keys.len();
         // group = sort by key + bundle. Sort the keys through the engine
         // (any shape; unsigned-word order — grouping is by equality, so
@@ -51,12 +59,41 @@ pub fn group_run(st: &mut Stack) -> Result<(), String> {
         let keys_sorted = gather(&keys, &perm_usize)?;
         let (bounds_ends, firsts) = run_layout(&labels);
         let unique_keys = gather(&keys_sorted, &firsts)?;
-        let list = Value::List { bounds: bounds_var_from_ends(bounds_ends), values: Arc::new(vals_sorted) };
+        let list = Value::List { bounds: bounds_from_ends(bounds_ends), values: Arc::new(vals_sorted) };
         st.push(unique_keys);
         st.push(list);
         Ok(())
 }
+/// `group` by RLE keys: sort the *runs* (stably, by unsigned word — the
+/// order the general path's sort gives a Prim key), and each distinct run
+/// value is a group whose members are its runs' positions in order. No
+/// per-element sort; when the runs are already in key order (sorted keys,
+/// the common case) the vals aren't even gathered.
+fn group_rle(e: &Encoded, values: &Prim, vals: Value) -> Result<(Value, Value), String> {
+    use crate::ir::encoding::{word, from_words};
+    let runs: Vec<(usize, usize)> = e.runs().collect();
+    let mut order: Vec<usize> = (0..runs.len()).collect();
+    order.sort_by_key(|&r| word(values, r));
+    let mut keys: Vec<u64> = Vec::new();
+    let mut ends: Vec<u64> = Vec::new();
+    let mut idxs: Vec<usize> = Vec::with_capacity(vals.len());
+    for &r in &order {
+        let k = word(values, r);
+        if keys.last() != Some(&k) {
+            if !keys.is_empty() { ends.push(idxs.len() as u64); }
+            keys.push(k);
+        }
+        idxs.extend(runs[r].0..runs[r].1);
+    }
+    if !keys.is_empty() { ends.push(idxs.len() as u64); }
+    let in_order = order.windows(2).all(|w| w[0] < w[1]);
+    let grouped = if in_order { vals } else { gather(&vals, &idxs)? };
+    let list = Value::List { bounds: bounds_from_ends(ends), values: Arc::new(grouped) };
+    Ok((Value::Prim(from_words(values.width(), keys)), list))
+}
+
 impl Typed for Group {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { group_tc(st) }
 }
 pub fn group_tc(st: &mut TypeStack) -> Result<(), String> {
@@ -78,13 +115,14 @@ pub fn group_tc(st: &mut TypeStack) -> Result<(), String> {
 ///
 /// Arithmetic wraps at the interp's width (same as `+`). For overflow-
 /// safe accumulation widen first via `as.<wider>` then `cumsum.<wider>`.
-#[derive(Debug)] pub struct Cumsum { pub interp: Interp }
+#[derive(Debug, Clone)] pub struct Cumsum { pub interp: Interp }
 impl PrimOp for Cumsum {
     fn name(&self) -> &str { "cumsum" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { cumsum_run(self.interp, st) }
 }
 impl Typed for Cumsum {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { cumsum_tc(self.interp, st) }
 }
 /// `cumsum.<interp>` kernel (back-end `SystemOp::Cumsum` calls this directly).
@@ -92,8 +130,7 @@ pub fn cumsum_run(interp: Interp, st: &mut Stack) -> Result<(), String> {
         let v = pop(st)?;
         match v {
             Value::List { bounds, values } => {
-                let bnds = bounds_as_u64(&bounds)?.to_vec();
-                let new_values = cumsum_runs(&values, &bnds, interp)?;
+                let new_values = cumsum_runs(&values, &bounds, interp)?;
                 st.push(Value::List { bounds, values: Arc::new(new_values) });
             }
             Value::Prim(p) => {
@@ -141,7 +178,7 @@ fn cumsum_flat(p: &Prim, interp: Interp) -> Result<Prim, String> {
     }
 }
 
-fn cumsum_runs(values: &Value, bounds: &[u64], interp: Interp) -> Result<Value, String> {
+fn cumsum_runs(values: &Value, bounds: &BoundsRepr, interp: Interp) -> Result<Value, String> {
     use crate::ir::value::Storage;
     let p = match values {
         Value::Prim(p) => p,
@@ -150,15 +187,13 @@ fn cumsum_runs(values: &Value, bounds: &[u64], interp: Interp) -> Result<Value,
     macro_rules! cs { ($t:ty) => {{
         let xs = <$t as Storage>::extract(p)?;
         let mut out: Vec<$t> = Vec::with_capacity(xs.len());
-        for w in bounds.windows(2) {
-            let lo = w[0] as usize;
-            let hi = w[1] as usize;
+        bounds.for_each_row(|lo, hi| {
             let mut acc: $t = <$t as Default>::default();
             for &x in &xs[lo..hi] {
                 acc += x;
                 out.push(acc);
             }
-        }
+        });
         Ok(Value::Prim(<$t as Storage>::wrap(out)))
     }};}
     match interp {
@@ -179,7 +214,7 @@ fn cumsum_runs(values: &Value, bounds: &[u64], interp: Interp) -> Result<Value,
 /// (so caller doesn't have to) and dedupes. For per-row uniqueness use
 /// `each { sort.<interp> ... }` patterns, or `group.<interp>` if the
 /// satellite list is also needed.
-#[derive(Debug)] pub struct Unique;
+#[derive(Debug, Clone)] pub struct Unique;
 impl PrimOp for Unique {
     fn name(&self) -> &str { "unique" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
@@ -204,6 +239,7 @@ pub fn unique_run(st: &mut Stack) -> Result<(), String> {
         Ok(())
 }
 impl Typed for Unique {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { unique_tc(st) }
 }
 pub fn unique_tc(st: &mut TypeStack) -> Result<(), String> {
@@ -224,13 +260,14 @@ pub fn unique_tc(st: &mut TypeStack) -> Result<(), String> {
 /// List<Prim>: per-row shift; row lengths unchanged.
 ///
 /// Negative shifts (LEAD) and per-row variable shifts are follow-ups.
-#[derive(Debug)] pub struct Shift { pub interp: Interp }
+#[derive(Debug, Clone)] pub struct Shift { pub interp: Interp }
 impl PrimOp for Shift {
     fn name(&self) -> &str { "shift" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { shift_run(self.interp, st) }
 }
 impl Typed for Shift {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { shift_tc(self.interp, st) }
 }
 /// `shift.<interp>` kernel (back-end `SystemOp::Shift` calls this directly).
@@ -243,8 +280,7 @@ pub fn shift_run(interp: Interp, st: &mut Stack) -> Result<(), String> {
         let v = pop(st)?;
         match v {
             Value::List { bounds, values } => {
-                let bnds = bounds_as_u64(&bounds)?.to_vec();
-                let new_values = shift_runs(&values, &bnds, n, interp)?;
+                let new_values = shift_runs(&values, &bounds, n, interp)?;
                 st.push(Value::List { bounds, values: Arc::new(new_values) });
             }
             Value::Prim(p) => {
@@ -299,7 +335,7 @@ fn shift_flat(p: &Prim, n: usize, interp: Interp) -> Result<Prim, String> {
     }
 }
 
-fn shift_runs(values: &Value, bounds: &[u64], n: usize, interp: Interp) -> Result<Value, String> {
+fn shift_runs(values: &Value, bounds: &BoundsRepr, n: usize, interp: Interp) -> Result<Value, String> {
     use crate::ir::value::Storage;
     let p = match values {
         Value::Prim(p) => p,
@@ -309,14 +345,11 @@ fn shift_runs(values: &Value, bounds: &[u64], n: usize, interp: Interp) -> Resul
         let xs = <$t as Storage>::extract(p)?;
         let zero: $t = <$t as Default>::default();
         let mut out: Vec<$t> = Vec::with_capacity(xs.len());
-        for w in bounds.windows(2) {
-            let lo = w[0] as usize;
-            let hi = w[1] as usize;
-            let row_len = hi - lo;
-            let n_capped = n.min(row_len);
-            for _ in 0..n_capped { out.push(zero); }
+        bounds.for_each_row(|lo, hi| {
+            let n_capped = n.min(hi - lo);
+            out.extend(std::iter::repeat_n(zero, n_capped));
             out.extend_from_slice(&xs[lo..hi - n_capped]);
-        }
+        });
         Ok(Value::Prim(<$t as Storage>::wrap(out)))
     }};}
     match interp {
@@ -333,25 +366,25 @@ fn shift_runs(values: &Value, bounds: &[u64], n: usize, interp: Interp) -> Resul
     }
 }
 
-#[derive(Debug)] pub struct ReduceAdd { pub interp: Interp }
+#[derive(Debug, Clone)] pub struct ReduceAdd { pub interp: Interp }
 impl PrimOp for ReduceAdd {
     fn name(&self) -> &str { "reduce.+" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { reduce_add_run(self.interp, st) }
 }
 impl Typed for ReduceAdd {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { reduce_add_tc(self.interp, st) }
 }
 
 /// `reduce.+` kernel (back-end `SystemOp::Reduce{Add}` calls this directly).
 pub fn reduce_add_run(interp: Interp, st: &mut Stack) -> Result<(), String> {
-    // pop_raw so a `View` over a flat Prim can stream through without
+    // pop_flat so a `View` over a flat Prim can stream through without
     // an intermediate gather (`sum_whole` handles both via for_each_prim).
-    let v = crate::ir::stack::pop_raw(st)?;
+    let v = crate::ir::stack::pop_flat(st)?;
     match v {
         Value::List { bounds, values } => {
-            let bnds = bounds_as_u64(&bounds)?.to_vec();
-            let out = sum_runs(&values, &bnds, interp)?;
+            let out = sum_runs(&values, &bounds, interp)?;
             st.push(out);
         }
         other => {
@@ -370,13 +403,14 @@ pub fn reduce_add_tc(interp: Interp, st: &mut TypeStack) -> Result<(), String> {
     Ok(())
 }
 
-#[derive(Debug)] pub struct Bounds;
+#[derive(Debug, Clone)] pub struct Bounds;
 impl PrimOp for Bounds {
     fn name(&self) -> &str { "list>bounds" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { bounds_run(st) }
 }
 impl Typed for Bounds {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { bounds_tc(st) }
 }
 /// `list>bounds` kernel (back-end `SystemOp::Bounds` calls this directly).
@@ -403,13 +437,14 @@ pub fn bounds_tc(st: &mut TypeStack) -> Result<(), String> {
 /// offset by one (`lower = bounds[0..N]`, `upper = bounds[1..N+1]`) — the
 /// logical `(lo, hi)` relation that the N+1 layout stores by sharing
 /// interior endpoints. Degree is `upper - lower`; no separate `count`.
-#[derive(Debug)] pub struct ListRanges;
+#[derive(Debug, Clone)] pub struct ListRanges;
 impl PrimOp for ListRanges {
     fn name(&self) -> &str { "list>ranges" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { list_ranges_run(st) }
 }
 impl Typed for ListRanges {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { list_ranges_tc(st) }
 }
 /// `list>ranges` kernel (back-end `SystemOp::ListRanges` calls this directly).
@@ -437,13 +472,14 @@ pub fn list_ranges_tc(st: &mut TypeStack) -> Result<(), String> {
         Ok(())
 }
 
-#[derive(Debug)] pub struct BoundsToKeys;
+#[derive(Debug, Clone)] pub struct BoundsToKeys;
 impl PrimOp for BoundsToKeys {
     fn name(&self) -> &str { "bounds>keys" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { bounds_to_keys_run(st) }
 }
 impl Typed for BoundsToKeys {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { bounds_to_keys_tc(st) }
 }
 /// `bounds>keys` kernel (back-end `SystemOp::BoundsKeys` calls this directly).
@@ -451,11 +487,13 @@ pub fn bounds_to_keys_run(st: &mut Stack) -> Result<(), String> {
         let v = pop(st)?;
         match v {
             Value::List { bounds, .. } => {
-                let total = bounds.iter_pairs().last().map(|(_, h)| h).unwrap_or(0) as usize;
-                let mut out: Vec<u64> = Vec::with_capacity(total);
-                for (i, (lo, hi)) in bounds.iter_pairs().enumerate() {
-                    for _ in lo..hi { out.push(i as u64); }
-                }
+                let (first, last) = bounds.span();
+                let mut out: Vec<u64> = Vec::with_capacity((last - first) as usize);
+                let mut i = 0u64;
+                bounds.for_each_row(|lo, hi| {
+                    out.extend(std::iter::repeat_n(i, hi - lo));
+                    i += 1;
+                });
                 st.push(from_vec::<u64>(out));
                 Ok(())
             }
@@ -471,13 +509,14 @@ pub fn bounds_to_keys_tc(st: &mut TypeStack) -> Result<(), String> {
         Ok(())
 }
 
-#[derive(Debug)] pub struct Count;
+#[derive(Debug, Clone)] pub struct Count;
 impl PrimOp for Count {
     fn name(&self) -> &str { "count" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { count_run(st) }
 }
 impl Typed for Count {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { count_tc(st) }
 }
 /// `count` kernel (back-end `SystemOp::Count` calls this directly).
@@ -485,8 +524,14 @@ pub fn count_run(st: &mut Stack) -> Result<(), String> {
         let v = pop(st)?;
         match v {
             Value::List { bounds, .. } => {
-                let mut out = Vec::with_capacity(bounds.len());
-                for (lo, hi) in bounds.iter_pairs() { out.push(hi - lo); }
+                let out = match bounds.stride() {
+                    Some(stride) => vec![stride; bounds.len()],
+                    None => {
+                        let mut out = Vec::with_capacity(bounds.len());
+                        bounds.for_each_row(|lo, hi| out.push((hi - lo) as u64));
+                        out
+                    }
+                };
                 st.push(from_vec::<u64>(out));
                 Ok(())
             }
@@ -502,13 +547,14 @@ pub fn count_tc(st: &mut TypeStack) -> Result<(), String> {
         Ok(())
 }
 
-#[derive(Debug)] pub struct Like;
+#[derive(Debug, Clone)] pub struct Like;
 impl PrimOp for Like {
     fn name(&self) -> &str { "like" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }  // (template, scalar) → broadcast
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { like_run(st) }
 }
 impl Typed for Like {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { like_tc(st) }
 }
 /// `like` kernel (back-end `SystemOp::Like` calls this directly).
@@ -528,13 +574,14 @@ pub fn like_tc(st: &mut TypeStack) -> Result<(), String> {
         Ok(())
 }
 
-#[derive(Debug)] pub struct Head;
+#[derive(Debug, Clone)] pub struct Head;
 impl PrimOp for Head {
     fn name(&self) -> &str { "head" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { head_run(st) }
 }
 impl Typed for Head {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { head_tc(st) }
 }
 /// `head` kernel (back-end `SystemOp::Head` calls this directly).
@@ -543,10 +590,11 @@ pub fn head_run(st: &mut Stack) -> Result<(), String> {
         match v {
             Value::List { bounds, values } => {
                 let mut idxs = Vec::with_capacity(bounds.len());
-                for (lo, hi) in bounds.iter_pairs() {
-                    if hi == lo { return Err("head: empty inner row".into()); }
-                    idxs.push(lo as usize);
-                }
+                bounds.try_for_each_row(|lo, hi| {
+                    if hi == lo { return Err("head: empty inner row".to_string()); }
+                    idxs.push(lo);
+                    Ok(())
+                })?;
                 st.push(gather(&values, &idxs)?);
                 Ok(())
             }
@@ -562,13 +610,14 @@ pub fn head_tc(st: &mut TypeStack) -> Result<(), String> {
         Ok(())
 }
 
-#[derive(Debug)] pub struct Iota;
+#[derive(Debug, Clone)] pub struct Iota;
 impl PrimOp for Iota {
     fn name(&self) -> &str { "iota" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { iota_run(st) }
 }
 impl Typed for Iota {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { iota_tc(st) }
 }
 /// `iota` kernel (back-end `SystemOp::Iota` calls this directly).
@@ -588,13 +637,14 @@ pub fn iota_tc(st: &mut TypeStack) -> Result<(), String> {
         Ok(())
 }
 
-#[derive(Debug)] pub struct Spread;
+#[derive(Debug, Clone)] pub struct Spread;
 impl PrimOp for Spread {
     fn name(&self) -> &str { "spread" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }  // (col, counts) → spread col
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { spread_run(st) }
 }
 impl Typed for Spread {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { spread_tc(st) }
 }
 /// `spread` kernel (back-end `SystemOp::Spread` calls this directly).
@@ -635,7 +685,7 @@ pub fn spread_tc(st: &mut TypeStack) -> Result<(), String> {
 ///
 /// Positions are strictly more informative than filtered values — they can
 /// be used to gather *any* column, not just the one originally filtered.
-#[derive(Debug)] pub struct Where_;
+#[derive(Debug, Clone)] pub struct Where_;
 impl PrimOp for Where_ {
     fn name(&self) -> &str { "where" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
@@ -643,15 +693,10 @@ impl PrimOp for Where_ {
 }
 /// `where` kernel (back-end `SystemOp::Where` calls this directly).
 pub fn where_run(st: &mut Stack) -> Result<(), String> {
-        let mask = crate::ir::stack::pop_raw(st)?;
+        let mask = crate::ir::stack::pop(st)?;
         match mask {
             Value::Prim(Prim::P8(m)) => {
-                let ms: &[u8] = &m;
-                let mut idxs: Vec<u64> = Vec::with_capacity(ms.len() / 2);
-                for i in 0..ms.len() {
-                    if ms[i] != 0 { idxs.push(i as u64); }
-                }
-                st.push(from_vec::<u64>(idxs));
+                st.push(crate::ops::compress::where_value(&m));
                 Ok(())
             }
             Value::List { bounds, values } => {
@@ -662,13 +707,10 @@ pub fn where_run(st: &mut Stack) -> Result<(), String> {
                         "where: row-shaped input's inner must be Prim(P8), got {:?}", other
                     )),
                 };
-                let bnds = crate::ir::shape::bounds_as_u64(&bounds)?;
                 let ms: &[u8] = &inner_mask;
                 let mut flat: Vec<u64> = Vec::new();
-                let mut out_bounds: Vec<u64> = Vec::with_capacity(bnds.len() - 1);
-                for w in bnds.windows(2) {
-                    let lo = w[0] as usize;
-                    let hi = w[1] as usize;
+                let mut out_bounds: Vec<u64> = Vec::with_capacity(bounds.len());
+                bounds.for_each_row(|lo, hi| {
                     for j in lo..hi {
                         if ms[j] != 0 {
                             // Row-relative position. (For source-coord
@@ -679,9 +721,9 @@ pub fn where_run(st: &mut Stack) -> Result<(), String> {
                         }
                     }
                     out_bounds.push(flat.len() as u64);
-                }
+                });
                 st.push(Value::List {
-                    bounds: bounds_var_from_ends(out_bounds),
+                    bounds: bounds_from_ends(out_bounds),
                     values: Arc::new(from_vec::<u64>(flat)),
                 });
                 Ok(())
@@ -690,6 +732,7 @@ pub fn where_run(st: &mut Stack) -> Result<(), String> {
         }
 }
 impl Typed for Where_ {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { where_tc(st) }
 }
 pub fn where_tc(st: &mut TypeStack) -> Result<(), String> {
@@ -735,7 +778,7 @@ pub fn where_tc(st: &mut TypeStack) -> Result<(), String> {
 ///
 /// The parser emits this automatically when it sees `where gather` in
 /// sequence; users can also write `filter` directly.
-#[derive(Debug)] pub struct Filter;
+#[derive(Debug, Clone)] pub struct Filter;
 impl PrimOp for Filter {
     fn name(&self) -> &str { "filter" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
@@ -767,16 +810,67 @@ pub fn filter_run(st: &mut Stack) -> Result<(), String> {
             if sb != mb {
                 return Err("segmented filter: bounds differ".into());
             }
-            let bnds = bounds_as_u64(sb)?;
+            // Rows covering the whole mask (the usual case): compress the
+            // values in one pass; the bounds are the per-row survivor counts.
+            if sb.span() == (0, m.len() as u64) && sv.len() == m.len() {
+                let mut kept = 0u64;
+                let mut ends: Vec<u64> = Vec::with_capacity(sb.len());
+                sb.for_each_row(|lo, hi| {
+                    kept += crate::ops::compress::count_mask(&m[lo..hi]) as u64;
+                    ends.push(kept);
+                });
+                let new_vals = crate::ops::compress::compress_value(sv, m)?;
+                st.push(Value::List { bounds: bounds_from_ends(ends), values: Arc::new(new_vals) });
+                return Ok(());
+            }
             let mut keep: Vec<usize> = Vec::new();
             let mut ends: Vec<u64> = Vec::with_capacity(sb.len());
-            for w in bnds.windows(2) {
-                let (lo, hi) = (w[0] as usize, w[1] as usize);
-                for j in lo..hi { if m[j] != 0 { keep.push(j); } }
+            sb.for_each_row(|lo, hi| {
+                keep.extend((lo..hi).filter(|&j| m[j] != 0));
                 ends.push(keep.len() as u64);
-            }
+            });
             let new_vals = gather(sv, &keep)?;
-            st.push(Value::List { bounds: bounds_var_from_ends(ends), values: Arc::new(new_vals) });
+            st.push(Value::List { bounds: bounds_from_ends(ends), values: Arc::new(new_vals) });
+            return Ok(());
+        }
+
+        // Encoded paths. An RLE mask keeps whole runs: a `Runs` selection
+        // (over an RLE src, the src's runs clipped to it). An RLE src
+        // under a plain mask keeps its encoding: each run survives with the
+        // mask's popcount over it.
+        if let Value::Encoded(m @ Encoded::Rle { values: mv, .. }) = &mask {
+            let Prim::P8(mv) = mv else {
+                return Err(format!("filter: mask must be Prim(P8), got {}", mv.width()));
+            };
+            if src.len() != mask.len() {
+                return Err(format!("filter: src len {} != mask len {}", src.len(), mask.len()));
+            }
+            let mut kept: Vec<(u64, u64)> = Vec::new();
+            for (r, (lo, hi)) in m.runs().enumerate() {
+                if mv[r] == 0 { continue; }
+                match kept.last_mut() {
+                    Some(last) if last.1 == lo as u64 => last.1 = hi as u64,
+                    _ => kept.push((lo as u64, hi as u64)),
+                }
+            }
+            if let Value::Encoded(s @ Encoded::Rle { values, .. }) = &src {
+                let counts = overlap_counts(s, &kept);
+                st.push(Value::Encoded(crate::ir::encoding::rle_keep(values, &counts)));
+            } else {
+                st.push(crate::ir::value::view(src, Selector::Runs(Arc::new(kept))));
+            }
+            return Ok(());
+        }
+        if let Value::Encoded(s @ Encoded::Rle { values, .. }) = &src {
+            let m = match crate::ops::helpers::materialize(mask)? {
+                Value::Prim(Prim::P8(m)) => m,
+                other => return Err(format!("filter: mask must be Prim(P8), got {:?}", other)),
+            };
+            if src.len() != m.len() {
+                return Err(format!("filter: src len {} != mask len {}", src.len(), m.len()));
+            }
+            let counts: Vec<u64> = s.runs().map(|(lo, hi)| m[lo..hi].iter().filter(|&&b| b != 0).count() as u64).collect();
+            st.push(Value::Encoded(crate::ir::encoding::rle_keep(values, &counts)));
             return Ok(());
         }
 
@@ -793,7 +887,25 @@ pub fn filter_run(st: &mut Stack) -> Result<(), String> {
         st.push(crate::ir::value::view(src, crate::ir::value::Selector::Mask(ms)));
         Ok(())
 }
+/// Per run of `s`, how many of its positions fall inside the sorted,
+/// disjoint intervals `kept`.
+fn overlap_counts(s: &Encoded, kept: &[(u64, u64)]) -> Vec<u64> {
+    let mut j = 0;
+    s.runs().map(|(lo, hi)| {
+        let (lo, hi) = (lo as u64, hi as u64);
+        while j < kept.len() && kept[j].1 <= lo { j += 1; }
+        let mut n = 0;
+        let mut k = j;
+        while k < kept.len() && kept[k].0 < hi {
+            n += kept[k].1.min(hi) - kept[k].0.max(lo);
+            k += 1;
+        }
+        n
+    }).collect()
+}
+
 impl Typed for Filter {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { filter_tc(st) }
 }
 pub fn filter_tc(st: &mut TypeStack) -> Result<(), String> {
@@ -811,10 +923,59 @@ pub fn filter_tc(st: &mut TypeStack) -> Result<(), String> {
         }
 }
 
+/// `mask.compose` — fold a filter-of-a-filter's two masks into one. `m1`
+/// is a P8 mask over a source, `m2` a P8 mask over `m1`'s survivors
+/// (length = popcount(m1)); the result is the P8 mask over the source that
+/// keeps exactly what both filters keep, so `src m1 filter m2 filter` ≡
+/// `src m1 m2 mask.compose filter`. The optimizer emits this when it
+/// merges chained filters; it is the Mask ∩ Mask arm of
+/// `compose_selectors` as a standalone op.
+#[derive(Debug, Clone)] pub struct MaskCompose;
+impl PrimOp for MaskCompose {
+    fn name(&self) -> &str { "mask.compose" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { mask_compose_run(st) }
+}
+impl Typed for MaskCompose {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { mask_compose_tc(st) }
+}
+/// `mask.compose` kernel (back-end `SystemOp::MaskCompose` calls this directly).
+pub fn mask_compose_run(st: &mut Stack) -> Result<(), String> {
+        use crate::ir::value::compose_selectors;
+        let outer = match pop(st)? {
+            Value::Prim(Prim::P8(m)) => m,
+            other => return Err(format!("mask.compose: outer mask must be Prim(P8), got {:?}", other)),
+        };
+        let inner = match pop(st)? {
+            Value::Prim(Prim::P8(m)) => m,
+            other => return Err(format!("mask.compose: inner mask must be Prim(P8), got {:?}", other)),
+        };
+        let survivors = inner.iter().filter(|&&b| b != 0).count();
+        if outer.len() != survivors {
+            return Err(format!("mask.compose: inner mask keeps {} rows but outer mask has {}", survivors, outer.len()));
+        }
+        match compose_selectors(&Selector::Mask(inner), &Selector::Mask(outer)) {
+            Selector::Mask(m) => { st.push(Value::Prim(Prim::P8(m))); Ok(()) }
+            other => Err(format!("mask.compose: expected a Mask composition, got {:?}", other)),
+        }
+}
+pub fn mask_compose_tc(st: &mut TypeStack) -> Result<(), String> {
+        let outer = tc_pop(st, "mask.compose")?;
+        let inner = tc_pop(st, "mask.compose")?;
+        let p8 = Shape::Prim(PrimWidth::W8);
+        if inner != p8 || outer != p8 {
+            return Err(format!("mask.compose: needs two Prim(P8) masks, got {} and {}", inner, outer));
+        }
+        st.push(p8);
+        Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::ir::value::{from_vec, bounds_var_from_ends, prod, list};
+    use crate::ir::shape::bounds_as_u64;
 
     #[test]
     fn group_by_structured_prod_key() {
@@ -954,6 +1115,40 @@ mod tests {
         assert_eq!(st[0], from_vec::<u64>(vec![8]));
     }
 
+    #[test]
+    fn stride_bounds_survive_a_pipeline() {
+        // A `nest.stride` list keeps its `Stride` repr through the
+        // partition-preserving kernels, and a uniform `group` emits one.
+        use crate::ir::value::BoundsRepr;
+        use crate::syntax::registry::OpRegistry;
+        use crate::syntax::parse::parse;
+        let reg = OpRegistry::standard();
+        let run = |src: &str| {
+            let prog = parse(src, &reg).unwrap();
+            let (g, _) = crate::pipeline::build(prog).unwrap();
+            crate::pipeline::eval_graph(&g).unwrap().pop().unwrap()
+        };
+        let is_stride = |v: &Value, s: u64, n: u64| matches!(v,
+            Value::List { bounds: BoundsRepr::Stride { stride, count }, .. } if *stride == s && *count == n);
+        // [3 1 2] ; [6 5 4] ; [9 7 8]
+        let base = "u64[3 1 2 6 5 4 9 7 8] u64[3] nest.stride";
+        let out = run(&format!("{base} sort.segmented u64[1] shift.u64 cumsum.u64 reverse.segmented"));
+        assert!(is_stride(&out, 3, 3), "{out:?}");
+        assert_eq!(out, list(bounds_var_from_ends(vec![3, 6, 9]), from_vec::<u64>(vec![3, 1, 0, 9, 4, 0, 15, 7, 0])));
+        let out = run(&format!("{base} sort.segmented u64[2] take.segmented u64[2 0] gather reverse"));
+        assert!(is_stride(&out, 2, 2), "{out:?}");
+        assert_eq!(out, list(bounds_var_from_ends(vec![2, 4]), from_vec::<u64>(vec![1, 2, 7, 8])));
+        let out = run(&format!("{base} u64[1] skip {base} cat.2"));
+        assert!(is_stride(&out, 3, 5), "{out:?}");
+        assert_eq!(run(&format!("{base} count")), from_vec::<u64>(vec![3, 3, 3]));
+        assert_eq!(run(&format!("{base} reduce.+.u64")), from_vec::<u64>(vec![6, 15, 24]));
+        // Uniform groups come out strided; ragged ones stay `Var`.
+        let out = run("u64[10 20 30 40] u8[1 0 1 0] group swap drop");
+        assert!(is_stride(&out, 2, 2), "{out:?}");
+        let out = run("u64[10 20 30] u8[1 0 1] group swap drop");
+        assert!(matches!(&out, Value::List { bounds: BoundsRepr::Var(_), .. }), "{out:?}");
+    }
+
     #[test]
     fn list_ranges_are_offset_views() {
         // `list>ranges` exposes a List's per-row (lower, upper) offsets as two
@@ -1082,6 +1277,7 @@ pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
             "spread" => Some(Box::new(Spread)),
             "where" => Some(Box::new(Where_)),
             "filter" => Some(Box::new(Filter)),
+            "mask.compose" => Some(Box::new(MaskCompose)),
             "bounds>keys" => Some(Box::new(BoundsToKeys)),
             "list>bounds" => Some(Box::new(Bounds)),
             "list>ranges" => Some(Box::new(ListRanges)),
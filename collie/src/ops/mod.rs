@@ -2,16 +2,26 @@
 //! Operators are structs that impl `PrimOp` (for `run`) and `Typed` (for `tc`).
 
 pub mod helpers;
+pub mod compress;
 pub mod stack;
 pub mod arith;
 pub mod cmp;
 pub mod convert;
+pub mod decimal;
 pub mod combinators;
 pub mod list;
 pub mod join;
 pub mod letbind;
 pub mod reduce_ops;
+pub mod approx;
+pub mod stats;
+pub mod calendar;
+pub mod pivot;
+pub mod rand;
+pub mod opt;
 pub mod sort_concat;
 pub mod sort;
 pub mod swizzle;
 pub mod view;
+pub mod encode;
+pub mod quote;
@@ -0,0 +1,532 @@
+//! Missing values: the `opt` convention and the ops that honor it.
+//!
+//! An `opt<T>` column is a two-lane `Sum` whose lane 0 is the unit (a
+//! field-less `Prod`) and whose lane 1 holds `T`: `disc[r]` is 0 where row
+//! `r` is missing ("none", SQL's NULL) and 1 where it has a value. Lanes
+//! are compact, so lane 1 *is* the present values, in row order, and
+//! lane 0 carries no data. Header types spell it `T?`, and the CSV / JSONL
+//! loaders (`tools::bind`) read an empty field, `null` or an absent key
+//! as none.
+//!
+//! - `opt.some` — `T → opt<T>`, every row present.
+//! - `opt.none.like` — `T → opt<T>`, every row missing (`T`'s shape, `T`'s
+//!   length; like `like`, the input is only a template).
+//! - `is_some` — `opt<T> → u8` mask; per row over `List<opt<T>>`.
+//! - `coalesce` — `opt<T> T → T`: the value where present, else the
+//!   fallback's row (a one-row fallback is broadcast).
+//! - `opt.<agg>` — `<agg>` over the present values only, for the
+//!   one-input aggregates `reduce.*`, `count` and `stats.*`: flat
+//!   `opt<T>` or per row over `List<opt<T>>` (after `group`). A row
+//!   whose values are all missing aggregates like an empty row. The
+//!   two-input `stats.cov/corr/comoments` keep the rows present on both
+//!   sides (pairwise deletion); either side may also be a plain column.
+//! - `intersect.nulls_distinct` — `intersect` over flat columns either of
+//!   which may be `opt`; a missing key matches nothing (SQL's NULL ≠
+//!   NULL). Positions are rows of the inputs, as for `intersect`.
+//! - `group.nulls_distinct` — `group` where every row whose key is
+//!   missing (or, for a `Prod` key, has a missing field) is a group of
+//!   its own. Plain `group` orders `opt` keys like any `Sum` — missing
+//!   first — and gathers all missing keys into one group.
+//!
+//! `match` over an `opt` sees an empty lane 0, so the ops above, not a
+//! `match`, are the way to read one. Like `stats.*`, these are front-end
+//! ops (`SystemOp::Foreign`).
+
+use std::sync::{Arc, OnceLock};
+use crate::ir::op::PrimOp;
+use crate::ir::stack::{Stack, materialize_top, pop, pop_raw};
+use crate::ir::typecheck::{Op, Typed, TypeStack, TypeEnv, tc_pop};
+use crate::ir::value::{Value, Prim, PrimWidth, bounds_var_from_ends, from_vec, list, prod, sum};
+use crate::ir::shape::Shape;
+use crate::ops::helpers::{concat_values, gather, sort_merge_intersect};
+use crate::ops::sort::{sort_blocks, run_layout};
+use crate::syntax::registry::OpRegistry;
+
+/// The shape of `opt<t>`.
+pub fn opt_shape(t: Shape) -> Shape {
+    Shape::Sum { disc: PrimWidth::W8, lanes: vec![Shape::Prod(vec![]), t] }
+}
+
+/// `T` if `s` is `opt<T>`.
+pub fn opt_inner(s: &Shape) -> Option<&Shape> {
+    match s {
+        Shape::Sum { disc: PrimWidth::W8, lanes } if lanes.len() == 2 && lanes[0] == Shape::Prod(vec![]) => Some(&lanes[1]),
+        _ => None,
+    }
+}
+
+/// An `opt` column from its per-row presence and its present values.
+pub fn opt_value(present: Vec<u8>, values: Value) -> Value {
+    sum(Prim::P8(Arc::new(present)), vec![prod(vec![]), values])
+}
+
+/// An `opt` value's presence bytes and present values.
+fn parts<'a>(v: &'a Value, who: &str) -> Result<(&'a [u8], &'a Value), String> {
+    match v {
+        Value::Sum { disc: Prim::P8(d), lanes } if lanes.len() == 2 && matches!(&lanes[0], Value::Prod(fs) if fs.is_empty()) => {
+            Ok((d.as_slice(), &lanes[1]))
+        }
+        other => Err(format!("{}: expected an opt column, got {}", who, other)),
+    }
+}
+
+/// The present values: `opt<T> → T`, or `List<opt<T>> → List<T>` with
+/// each row keeping its present values.
+fn present(v: Value, who: &str) -> Result<Value, String> {
+    match &v {
+        Value::List { bounds, values } => {
+            let values = materialize_top((**values).clone())?;
+            let (d, somes) = parts(&values, who)?;
+            let mut ends = Vec::with_capacity(bounds.len());
+            let mut kept = 0u64;
+            for (lo, hi) in bounds.iter_pairs() {
+                kept += d[lo as usize..hi as usize].iter().map(|&b| b as u64).sum::<u64>();
+                ends.push(kept);
+            }
+            Ok(list(bounds_var_from_ends(ends), somes.clone()))
+        }
+        _ => parts(&v, who).map(|(_, somes)| somes.clone()),
+    }
+}
+
+fn present_tc(s: Shape, who: &str) -> Result<Shape, String> {
+    match s {
+        Shape::List { bounds, inner } => match opt_inner(&inner) {
+            Some(t) => Ok(Shape::List { bounds, inner: Box::new(t.clone()) }),
+            None => Err(format!("{}: expected a list of opt, got [{}]", who, inner)),
+        },
+        s => opt_inner(&s).cloned().ok_or_else(|| format!("{}: expected an opt column, got {}", who, s)),
+    }
+}
+
+#[derive(Debug, Clone)] pub struct Some_;
+#[derive(Debug, Clone)] pub struct NoneLike;
+#[derive(Debug, Clone)] pub struct IsSome;
+#[derive(Debug, Clone)] pub struct Coalesce;
+/// `opt.<agg>`: `base` over the present values of its `inputs` (1 or 2)
+/// columns.
+#[derive(Debug)] pub struct SkipNone { token: String, inputs: usize, base: Box<dyn Op> }
+#[derive(Debug, Clone)] pub struct IntersectNullsDistinct;
+#[derive(Debug, Clone)] pub struct GroupNullsDistinct;
+
+impl PrimOp for Some_ {
+    fn name(&self) -> &str { "opt.some" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let v = pop(st)?;
+        st.push(opt_value(vec![1; v.len()], v));
+        Ok(())
+    }
+}
+impl Typed for Some_ {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let t = tc_pop(st, "opt.some")?;
+        st.push(opt_shape(t));
+        Ok(())
+    }
+}
+
+impl PrimOp for NoneLike {
+    fn name(&self) -> &str { "opt.none.like" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let v = pop(st)?;
+        st.push(opt_value(vec![0; v.len()], gather(&v, &[])?));
+        Ok(())
+    }
+}
+impl Typed for NoneLike {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let t = tc_pop(st, "opt.none.like")?;
+        st.push(opt_shape(t));
+        Ok(())
+    }
+}
+
+fn is_some(v: &Value) -> Result<Value, String> {
+    match v {
+        Value::List { bounds, values } => Ok(list(bounds.clone(), is_some(&materialize_top((**values).clone())?)?)),
+        _ => parts(v, "is_some").map(|(d, _)| from_vec::<u8>(d.to_vec())),
+    }
+}
+
+impl PrimOp for IsSome {
+    fn name(&self) -> &str { "is_some" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let v = pop(st)?;
+        st.push(is_some(&v)?);
+        Ok(())
+    }
+}
+impl Typed for IsSome {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let mask = |s: Shape| match s {
+            Shape::List { bounds, .. } => Shape::List { bounds, inner: Box::new(Shape::Prim(PrimWidth::W8)) },
+            _ => Shape::Prim(PrimWidth::W8),
+        };
+        let s = tc_pop(st, "is_some")?;
+        present_tc(s.clone(), "is_some")?;
+        st.push(mask(s));
+        Ok(())
+    }
+}
+
+impl PrimOp for Coalesce {
+    fn name(&self) -> &str { "coalesce" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }  // (opt, fallback) → value
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let fallback = pop(st)?;
+        let v = pop(st)?;
+        let (d, somes) = parts(&v, "coalesce")?;
+        let broadcast = fallback.len() == 1;
+        if !broadcast && fallback.len() != d.len() {
+            return Err(format!("coalesce: fallback has {} rows, expected {} or 1", fallback.len(), d.len()));
+        }
+        // Row r reads its present value, or the fallback's row after them.
+        let n_somes = d.iter().filter(|&&b| b == 1).count();
+        let mut next = 0usize;
+        let idxs: Vec<usize> = d.iter().enumerate().map(|(r, &b)| {
+            if b == 1 { next += 1; next - 1 } else { n_somes + if broadcast { 0 } else { r } }
+        }).collect();
+        st.push(gather(&concat_values(&[somes.clone(), fallback])?, &idxs)?);
+        Ok(())
+    }
+}
+impl Typed for Coalesce {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let fallback = tc_pop(st, "coalesce")?;
+        let v = tc_pop(st, "coalesce")?;
+        let t = opt_inner(&v).ok_or_else(|| format!("coalesce: expected an opt column, got {}", v))?;
+        if *t != fallback {
+            return Err(format!("coalesce: fallback {} doesn't match the opt's {}", fallback, t));
+        }
+        st.push(fallback);
+        Ok(())
+    }
+}
+
+/// A flat column's presence bytes (`None` when it isn't `opt`: every row
+/// present) and its present values.
+fn presence<'a>(v: &'a Value, who: &str) -> (Option<&'a [u8]>, &'a Value) {
+    match parts(v, who) {
+        Ok((d, somes)) => (Some(d), somes),
+        Err(_) => (None, v),
+    }
+}
+
+/// Two flat columns' values at the rows present in both.
+fn present_both_flat(a: &Value, b: &Value, who: &str) -> Result<(Value, Value, Vec<bool>), String> {
+    let (da, sa) = presence(a, who);
+    let (db, sb) = presence(b, who);
+    if a.len() != b.len() {
+        return Err(format!("{}: inputs have {} and {} rows", who, a.len(), b.len()));
+    }
+    let here = |d: Option<&[u8]>, r: usize| d.is_none_or(|d| d[r] == 1);
+    let keep: Vec<bool> = (0..a.len()).map(|r| here(da, r) && here(db, r)).collect();
+    // Row r's position among one side's present values.
+    let picks = |d: Option<&[u8]>| {
+        let mut next = 0usize;
+        let mut idxs = Vec::new();
+        for (r, &k) in keep.iter().enumerate() {
+            if k { idxs.push(next); }
+            if here(d, r) { next += 1; }
+        }
+        idxs
+    };
+    Ok((gather(sa, &picks(da))?, gather(sb, &picks(db))?, keep))
+}
+
+/// `present` for two columns at once: the rows present in both, flat or
+/// per row over two `List`s with the same bounds.
+fn present_both(a: Value, b: Value, who: &str) -> Result<(Value, Value), String> {
+    match (&a, &b) {
+        (Value::List { bounds, values: va }, Value::List { bounds: bb, values: vb }) => {
+            if !bounds.iter_pairs().eq(bb.iter_pairs()) {
+                return Err(format!("{}: per-row inputs must have the same row bounds", who));
+            }
+            let va = materialize_top((**va).clone())?;
+            let vb = materialize_top((**vb).clone())?;
+            let (ka, kb, keep) = present_both_flat(&va, &vb, who)?;
+            let mut ends = Vec::with_capacity(bounds.len());
+            let mut kept = 0u64;
+            for (lo, hi) in bounds.iter_pairs() {
+                kept += keep[lo as usize..hi as usize].iter().filter(|&&k| k).count() as u64;
+                ends.push(kept);
+            }
+            Ok((list(bounds_var_from_ends(ends.clone()), ka), list(bounds_var_from_ends(ends), kb)))
+        }
+        (Value::List { .. }, _) | (_, Value::List { .. }) => Err(format!("{}: expected two flat or two per-row inputs", who)),
+        _ => present_both_flat(&a, &b, who).map(|(ka, kb, _)| (ka, kb)),
+    }
+}
+
+impl PrimOp for SkipNone {
+    fn name(&self) -> &str { &self.token }
+    fn arity(&self) -> Option<(usize, usize)> { Some((self.inputs, 1)) }
+    fn token(&self) -> Option<String> { Some(self.token.clone()) }
+    fn run(&self, st: &mut Stack, env: &mut Vec<Value>) -> Result<(), String> {
+        if self.inputs == 2 {
+            let b = pop(st)?;
+            let a = pop(st)?;
+            let (a, b) = present_both(a, b, &self.token)?;
+            st.push(a);
+            st.push(b);
+        } else {
+            let v = pop(st)?;
+            st.push(present(v, &self.token)?);
+        }
+        self.base.run(st, env)
+    }
+}
+impl Typed for SkipNone {
+    fn clone_op(&self) -> Option<Box<dyn Op>> {
+        Some(Box::new(SkipNone { token: self.token.clone(), inputs: self.inputs, base: self.base.clone_op()? }))
+    }
+    fn tc(&self, st: &mut TypeStack, env: &mut TypeEnv) -> Result<(), String> {
+        if self.inputs == 2 {
+            // Either side may be plain; the base op checks the rest.
+            let b = tc_pop(st, &self.token)?;
+            let a = tc_pop(st, &self.token)?;
+            let strip = |s: Shape| present_tc(s.clone(), &self.token).unwrap_or(s);
+            st.push(strip(a));
+            st.push(strip(b));
+        } else {
+            let s = tc_pop(st, &self.token)?;
+            st.push(present_tc(s, &self.token)?);
+        }
+        self.base.tc(st, env)
+    }
+}
+
+/// A flat key column's present values and the row each came from (every
+/// row, when it isn't `opt`).
+fn present_rows(v: Value) -> Result<(Value, Option<Vec<usize>>), String> {
+    match parts(&v, "intersect.nulls_distinct") {
+        Ok((d, somes)) => {
+            let rows = d.iter().enumerate().filter(|(_, &b)| b == 1).map(|(r, _)| r).collect();
+            Ok((somes.clone(), Some(rows)))
+        }
+        Err(_) => Ok((v, None)),
+    }
+}
+
+impl PrimOp for IntersectNullsDistinct {
+    fn name(&self) -> &str { "intersect.nulls_distinct" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 2)) }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let (b, b_rows) = present_rows(pop(st)?)?;
+        let (a, a_rows) = present_rows(pop(st)?)?;
+        let (ai, bi) = sort_merge_intersect(&a, &b)?;
+        let at = |rows: &Option<Vec<usize>>, i: usize| rows.as_ref().map_or(i, |rs| rs[i]) as u64;
+        st.push(from_vec::<u64>(ai.into_iter().map(|i| at(&a_rows, i)).collect()));
+        st.push(from_vec::<u64>(bi.into_iter().map(|i| at(&b_rows, i)).collect()));
+        Ok(())
+    }
+}
+impl Typed for IntersectNullsDistinct {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let b = tc_pop(st, "intersect.nulls_distinct")?;
+        let a = tc_pop(st, "intersect.nulls_distinct")?;
+        let key = |s: &Shape| opt_inner(s).cloned().unwrap_or_else(|| s.clone());
+        match (key(&a), key(&b)) {
+            (Shape::Prim(x), Shape::Prim(y)) if x == y => {}
+            (x, y) => return Err(format!("intersect.nulls_distinct: expected two flat keys of one width, got {} and {}", x, y)),
+        }
+        st.push(Shape::Prim(PrimWidth::W64));
+        st.push(Shape::Prim(PrimWidth::W64));
+        Ok(())
+    }
+}
+
+/// Per row, whether the key is missing: an `opt` key's none, or a `Prod`
+/// key with a missing field.
+fn missing_keys(keys: &Value) -> Result<Vec<bool>, String> {
+    let mut out = vec![false; keys.len()];
+    let mut mark = |v: &Value| {
+        if let Ok((d, _)) = parts(v, "group.nulls_distinct") {
+            for (o, &b) in out.iter_mut().zip(d) { *o |= b == 0; }
+        }
+    };
+    match keys {
+        Value::Prod(fs) => for f in fs.iter() { mark(&materialize_top(f.clone())?); },
+        k => mark(k),
+    }
+    Ok(out)
+}
+
+impl PrimOp for GroupNullsDistinct {
+    fn name(&self) -> &str { "group.nulls_distinct" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 2)) }  // (vals, keys) → (uniq_keys, list_of_grouped_vals)
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let keys = materialize_top(pop_raw(st)?)?;
+        let vals = pop(st)?;
+        if keys.len() != vals.len() {
+            return Err(format!("group.nulls_distinct: vals len {} != keys len {}", vals.len(), keys.len()));
+        }
+        let missing = missing_keys(&keys)?;
+        // `group`'s sort, then each group of missing keys (its rows' keys
+        // are equal, so all missing) split into one group per row.
+        let (perm, labels) = sort_blocks(&vec![0u64; keys.len()], &keys)?;
+        let perm: Vec<usize> = perm.iter().map(|&i| i as usize).collect();
+        let (ends, firsts) = run_layout(&labels);
+        let mut new_ends: Vec<u64> = Vec::with_capacity(ends.len());
+        let mut new_firsts: Vec<usize> = Vec::with_capacity(firsts.len());
+        for (&lo, &hi) in firsts.iter().zip(&ends) {
+            if missing[perm[lo]] {
+                new_firsts.extend(lo..hi as usize);
+                new_ends.extend(lo as u64 + 1..=hi);
+            } else {
+                new_firsts.push(lo);
+                new_ends.push(hi);
+            }
+        }
+        let keys_sorted = gather(&keys, &perm)?;
+        st.push(gather(&keys_sorted, &new_firsts)?);
+        st.push(list(bounds_var_from_ends(new_ends), gather(&vals, &perm)?));
+        Ok(())
+    }
+}
+impl Typed for GroupNullsDistinct {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        crate::ops::list::group_tc(st)
+    }
+}
+
+/// The registry `opt.<agg>` builds its aggregate from.
+fn aggregates() -> &'static OpRegistry {
+    static REG: OnceLock<OpRegistry> = OnceLock::new();
+    REG.get_or_init(OpRegistry::standard)
+}
+
+pub fn register(r: &mut OpRegistry) {
+    r.add(|t: &str| -> Option<Box<dyn Op>> {
+        match t {
+            "opt.some" => Some(Box::new(Some_)),
+            "opt.none.like" => Some(Box::new(NoneLike)),
+            "is_some" => Some(Box::new(IsSome)),
+            "coalesce" => Some(Box::new(Coalesce)),
+            "intersect.nulls_distinct" => Some(Box::new(IntersectNullsDistinct)),
+            "group.nulls_distinct" => Some(Box::new(GroupNullsDistinct)),
+            _ => None,
+        }
+    });
+    // opt.reduce.<…>, opt.count, opt.stats.<…>; of the two-input
+    // aggregates, the bivariate stats (not `stats.merge`, whose inputs
+    // are states rather than data)
+    r.add(|t: &str| -> Option<Box<dyn Op>> {
+        let agg = t.strip_prefix("opt.")?;
+        if !(agg.starts_with("reduce.") || agg == "count" || agg.starts_with("stats.")) { return None; }
+        let base = aggregates().make(agg)?;
+        let inputs = match base.arity()? {
+            (1, 1) => 1,
+            (2, 1) if agg.starts_with("stats.") && agg != "stats.merge" => 2,
+            _ => return None,
+        };
+        Some(Box::new(SkipNone { token: t.to_string(), inputs, base }))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{build, eval_graph};
+    use crate::syntax::parse::parse;
+
+    fn run(op: &dyn PrimOp, mut st: Vec<Value>) -> Result<Vec<Value>, String> {
+        op.run(&mut st, &mut Vec::new())?;
+        Ok(st)
+    }
+
+    /// `1, none, 3, none, 5` as `opt<i64>`.
+    fn sparse() -> Value {
+        opt_value(vec![1, 0, 1, 0, 1], from_vec::<i64>(vec![1, 3, 5]))
+    }
+
+    #[test]
+    fn construct_test_and_coalesce() {
+        let reg = OpRegistry::standard();
+        let (g, _) = build(parse("i64[4 5] opt.some is_some  i64[4 5] opt.none.like is_some", &reg).unwrap()).unwrap();
+        assert_eq!(eval_graph(&g).unwrap(), vec![from_vec::<u8>(vec![1, 1]), from_vec::<u8>(vec![0, 0])]);
+        let out = run(&Coalesce, vec![sparse(), from_vec::<i64>(vec![-1])]).unwrap();
+        assert_eq!(out, vec![from_vec::<i64>(vec![1, -1, 3, -1, 5])]);
+        let out = run(&Coalesce, vec![sparse(), from_vec::<i64>(vec![10, 20, 30, 40, 50])]).unwrap();
+        assert_eq!(out, vec![from_vec::<i64>(vec![1, 20, 3, 40, 5])]);
+        assert!(run(&Coalesce, vec![sparse(), from_vec::<i64>(vec![1, 2])]).unwrap_err().contains("2 rows"));
+    }
+
+    #[test]
+    fn aggregates_skip_missing_values() {
+        let reg = OpRegistry::standard();
+        let agg = |t: &str, v: Value| run(reg.make(t).unwrap().as_ref(), vec![v]).unwrap();
+        assert_eq!(agg("opt.reduce.+.i64", sparse()), vec![from_vec::<i64>(vec![9])]);
+        // Per row after `group`: rows [1, none], [3, none, 5], [].
+        let rows = list(bounds_var_from_ends(vec![2, 5, 5]), sparse());
+        assert_eq!(agg("opt.count", rows.clone()), vec![from_vec::<u64>(vec![1, 2, 0])]);
+        assert_eq!(agg("opt.reduce.+.i64", rows.clone()), vec![from_vec::<i64>(vec![1, 8, 0])]);
+        let rows = list(bounds_var_from_ends(vec![2, 5]), sparse());
+        assert_eq!(agg("opt.reduce.max.i64", rows), vec![from_vec::<i64>(vec![1, 5])]);
+        let mean = agg("opt.stats.mean.f64", opt_value(vec![0, 1, 1], from_vec::<f64>(vec![1.0, 2.0])));
+        assert_eq!(mean, vec![from_vec::<f64>(vec![1.5])]);
+        assert!(reg.make("opt.stats.merge").is_none(), "states have no missing rows");
+        assert!(reg.make("opt.filter").is_none());
+    }
+
+    #[test]
+    fn bivariate_stats_drop_rows_missing_either_side() {
+        let reg = OpRegistry::standard();
+        let agg = |t: &str, a: Value, b: Value| run(reg.make(t).unwrap().as_ref(), vec![a, b]);
+        // xs: 1, none, 3, 4, 5; ys: 2, 4, none, 8, 10. Rows 0, 3, 4 survive.
+        let xs = opt_value(vec![1, 0, 1, 1, 1], from_vec::<f64>(vec![1.0, 3.0, 4.0, 5.0]));
+        let ys = opt_value(vec![1, 1, 0, 1, 1], from_vec::<f64>(vec![2.0, 4.0, 8.0, 10.0]));
+        let want = agg("stats.cov.f64", from_vec::<f64>(vec![1.0, 4.0, 5.0]), from_vec::<f64>(vec![2.0, 8.0, 10.0])).unwrap();
+        assert_eq!(agg("opt.stats.cov.f64", xs.clone(), ys.clone()).unwrap(), want);
+        // A plain side counts as all present.
+        let plain = from_vec::<f64>(vec![2.0, 4.0, 6.0, 8.0, 10.0]);
+        let want = agg("stats.corr.f64", from_vec::<f64>(vec![1.0, 3.0, 4.0, 5.0]), from_vec::<f64>(vec![2.0, 6.0, 8.0, 10.0])).unwrap();
+        assert_eq!(agg("opt.stats.corr.f64", xs.clone(), plain).unwrap(), want);
+        // Per row: [1, none] / [2, 4] and [3, 4, 5] / [none, 8, 10].
+        let rows = |v: Value| list(bounds_var_from_ends(vec![2, 5]), v);
+        let got = agg("opt.stats.comoments.f64", rows(xs.clone()), rows(ys.clone())).unwrap();
+        let want = agg("stats.comoments.f64",
+            list(bounds_var_from_ends(vec![1, 3]), from_vec::<f64>(vec![1.0, 4.0, 5.0])),
+            list(bounds_var_from_ends(vec![1, 3]), from_vec::<f64>(vec![2.0, 8.0, 10.0]))).unwrap();
+        assert_eq!(got, want);
+        let e = agg("opt.stats.cov.f64", xs, from_vec::<f64>(vec![1.0])).unwrap_err();
+        assert!(e.contains("5 and 1 rows"), "{}", e);
+        // And through the typechecker.
+        let (g, _) = build(parse("f64[1 2 3] opt.some f64[2 4 7] opt.stats.cov.f64", &reg).unwrap()).unwrap();
+        assert_eq!(eval_graph(&g).unwrap(), vec![from_vec::<f64>(vec![2.5])]);
+    }
+
+    #[test]
+    fn nulls_distinct_never_match_or_merge() {
+        // Sorted keys, missing first: none, none, 2, 3 against 1, 2, 3.
+        let a = opt_value(vec![0, 0, 1, 1], from_vec::<u64>(vec![2, 3]));
+        let b = from_vec::<u64>(vec![1, 2, 3]);
+        let out = run(&IntersectNullsDistinct, vec![a.clone(), b]).unwrap();
+        assert_eq!(out, vec![from_vec::<u64>(vec![2, 3]), from_vec::<u64>(vec![1, 2])]);
+
+        let vals = from_vec::<u64>(vec![10, 20, 30, 40]);
+        let keys = opt_value(vec![0, 1, 0, 1], from_vec::<u64>(vec![7, 7]));
+        let plain = run(&crate::ops::list::Group, vec![vals.clone(), keys.clone()]).unwrap();
+        let distinct = run(&GroupNullsDistinct, vec![vals, keys]).unwrap();
+        let counts = |v: &Value| match v { Value::List { bounds, .. } => bounds.iter_pairs().map(|(lo, hi)| hi - lo).collect::<Vec<_>>(), _ => panic!() };
+        assert_eq!(counts(&plain[1]), vec![2, 2]);
+        assert_eq!(counts(&distinct[1]), vec![1, 1, 2]);
+        assert_eq!(distinct[0], opt_value(vec![0, 0, 1], from_vec::<u64>(vec![7])));
+        assert_eq!(distinct[1], list(bounds_var_from_ends(vec![1, 2, 4]), from_vec::<u64>(vec![10, 30, 20, 40])));
+    }
+}
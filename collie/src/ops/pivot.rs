@@ -0,0 +1,222 @@
+//! Long ↔ wide reshaping of a `(key, category, value)` table.
+//!
+//! - `pivot.N` — `long fill → wide`. `long` is `Prod[keys, cats, vals]`:
+//!   keys of any shape the sort engine orders, `cats` a Prim column of
+//!   category numbers `0..N`, `vals` a Prim column. `fill` is a
+//!   one-element Prim of `vals`' width. The result is
+//!   `Prod[uniq_keys, v_0, …, v_{N-1}]`: one row per distinct key (in
+//!   `group`'s order), `v_c` holding that key's category-`c` value, or
+//!   `fill` where it has none. A category outside `0..N`, or a
+//!   `(key, category)` pair given twice, is an error — aggregate first.
+//! - `unpivot` — `wide → long`, the inverse: `Prod[keys, v_0, …, v_{N-1}]`
+//!   (all `v_c` the same Prim width) → `Prod[keys, cats, vals]`, `N` rows
+//!   per input row in category order, `cats` a P64 column. Fill cells come
+//!   back as ordinary values; `filter` them out if they mean "missing".
+//!
+//! `pivot` is `group` (the same sort-engine call) plus one scatter per
+//! row; the typecheck derives the output width from `N` alone.
+
+use crate::ir::op::PrimOp;
+use crate::ir::stack::{Stack, materialize_top, pop};
+use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
+use crate::ir::value::{Value, Prim, PrimWidth, from_vec, prod};
+use crate::ir::shape::Shape;
+use crate::ir::encoding::{from_words, word};
+use crate::ops::helpers::gather;
+use crate::ops::sort::{sort_blocks, run_layout};
+
+#[derive(Debug, Clone)] pub struct Pivot { pub n: usize }
+#[derive(Debug, Clone)] pub struct Unpivot;
+
+impl PrimOp for Pivot {
+    fn name(&self) -> &str { "pivot" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }  // (long, fill) → wide
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { pivot_run(self.n, st) }
+}
+impl Typed for Pivot {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { pivot_tc(self.n, st) }
+}
+
+impl PrimOp for Unpivot {
+    fn name(&self) -> &str { "unpivot" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { unpivot_run(st) }
+}
+impl Typed for Unpivot {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { unpivot_tc(st) }
+}
+
+/// A Prod's fields, each materialized.
+fn fields(v: Value, who: &str) -> Result<Vec<Value>, String> {
+    match v {
+        Value::Prod(fs) => fs.iter().map(|f| materialize_top(f.clone())).collect(),
+        other => Err(format!("{}: expected a Prod, got {}", who, other)),
+    }
+}
+
+fn prim(v: Value, who: &str, what: &str) -> Result<Prim, String> {
+    match v {
+        Value::Prim(p) => Ok(p),
+        other => Err(format!("{}: {} must be a Prim column, got {}", who, what, other)),
+    }
+}
+
+/// `pivot.N` kernel (back-end `SystemOp::Pivot` calls this directly).
+pub fn pivot_run(n: usize, st: &mut Stack) -> Result<(), String> {
+    let who = format!("pivot.{}", n);
+    let fill = prim(pop(st)?, &who, "fill")?;
+    let mut fs = fields(pop(st)?, &who)?;
+    if fs.len() != 3 {
+        return Err(format!("{}: expected Prod[keys, cats, vals], got {} fields", who, fs.len()));
+    }
+    let vals = prim(fs.pop().unwrap(), &who, "vals")?;
+    let cats = prim(fs.pop().unwrap(), &who, "cats")?;
+    let keys = fs.pop().unwrap();
+    if fill.len() != 1 || fill.width() != vals.width() {
+        return Err(format!("{}: fill must be one {:?} value, got {} of {:?}", who, vals.width(), fill.len(), fill.width()));
+    }
+    let rows = keys.len();
+    if cats.len() != rows || vals.len() != rows {
+        return Err(format!("{}: field lengths differ ({} keys, {} cats, {} vals)", who, rows, cats.len(), vals.len()));
+    }
+    // Distinct keys as `group` finds them; `g` counts runs of equal keys
+    // along the sorted order.
+    let (perm, labels) = sort_blocks(&vec![0u64; rows], &keys)?;
+    let (_, firsts) = run_layout(&labels);
+    let mut cols = vec![vec![word(&fill, 0); firsts.len()]; n];
+    let mut seen = vec![false; firsts.len() * n];
+    let mut g = 0usize;
+    for (p, &r) in perm.iter().enumerate() {
+        if p > 0 && labels[p] != labels[p - 1] { g += 1; }
+        let r = r as usize;
+        let c = word(&cats, r);
+        if c >= n as u64 {
+            return Err(format!("{}: category {} at row {} is out of range", who, c, r));
+        }
+        let c = c as usize;
+        if std::mem::replace(&mut seen[g * n + c], true) {
+            return Err(format!("{}: row {} repeats category {} for its key", who, r, c));
+        }
+        cols[c][g] = word(&vals, r);
+    }
+    let first_rows: Vec<usize> = firsts.iter().map(|&p| perm[p] as usize).collect();
+    let mut out = vec![gather(&keys, &first_rows)?];
+    out.extend(cols.into_iter().map(|ws| Value::Prim(from_words(vals.width(), ws))));
+    st.push(prod(out));
+    Ok(())
+}
+
+pub fn pivot_tc(n: usize, st: &mut TypeStack) -> Result<(), String> {
+    let fill = tc_pop(st, "pivot")?;
+    let long = tc_pop(st, "pivot")?;
+    let w = match fill {
+        Shape::Prim(w) => w,
+        other => return Err(format!("pivot.{}: fill must be a Prim, got {}", n, other)),
+    };
+    match long {
+        Shape::Prod(mut fs) if fs.len() == 3 && matches!(fs[1], Shape::Prim(_)) && fs[2] == Shape::Prim(w) => {
+            fs.truncate(1);
+            fs.extend(std::iter::repeat_n(Shape::Prim(w), n));
+            st.push(Shape::Prod(fs));
+            Ok(())
+        }
+        other => Err(format!("pivot.{}: expected Prod[keys, Prim, Prim({})] (vals the fill's width), got {}", n, w, other)),
+    }
+}
+
+/// `unpivot` kernel (back-end `SystemOp::Unpivot` calls this directly).
+pub fn unpivot_run(st: &mut Stack) -> Result<(), String> {
+    let mut fs = fields(pop(st)?, "unpivot")?;
+    if fs.len() < 2 {
+        return Err(format!("unpivot: expected Prod[keys, v_0, …], got {} fields", fs.len()));
+    }
+    let cols: Vec<Prim> = fs.drain(1..).map(|f| prim(f, "unpivot", "value fields")).collect::<Result<_, _>>()?;
+    let keys = fs.pop().unwrap();
+    let (n, rows, w) = (cols.len(), keys.len(), cols[0].width());
+    if let Some(c) = cols.iter().find(|c| c.width() != w || c.len() != rows) {
+        return Err(format!("unpivot: value fields must be {} rows of {:?}, got {} of {:?}", rows, w, c.len(), c.width()));
+    }
+    let per_row: Vec<usize> = (0..rows).flat_map(|r| std::iter::repeat_n(r, n)).collect();
+    let cats: Vec<u64> = (0..rows * n).map(|i| (i % n) as u64).collect();
+    let vals: Vec<u64> = (0..rows).flat_map(|r| cols.iter().map(move |c| word(c, r))).collect();
+    st.push(prod(vec![gather(&keys, &per_row)?, from_vec::<u64>(cats), Value::Prim(from_words(w, vals))]));
+    Ok(())
+}
+
+pub fn unpivot_tc(st: &mut TypeStack) -> Result<(), String> {
+    match tc_pop(st, "unpivot")? {
+        Shape::Prod(fs) if fs.len() >= 2 && matches!(fs[1], Shape::Prim(_)) && fs[2..].iter().all(|f| *f == fs[1]) => {
+            st.push(Shape::Prod(vec![fs[0].clone(), Shape::Prim(PrimWidth::W64), fs[1].clone()]));
+            Ok(())
+        }
+        other => Err(format!("unpivot: expected Prod[keys, v_0, …] with same-width Prim values, got {}", other)),
+    }
+}
+
+pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
+    use crate::ir::typecheck::Op;
+    // pivot.N (N ≥ 1), unpivot
+    r.add(|t: &str| -> Option<Box<dyn Op>> {
+        if t == "unpivot" { return Some(Box::new(Unpivot)); }
+        let n = t.strip_prefix("pivot.")?.parse::<usize>().ok()?;
+        if n == 0 { return None; }
+        Some(Box::new(Pivot { n }))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::value::PrimWidth::{W32, W64};
+    use crate::pipeline::{build, eval_graph};
+    use crate::syntax::parse::parse;
+    use crate::syntax::registry::OpRegistry;
+
+    fn run(src: &str) -> Result<Vec<Value>, String> {
+        let (g, _) = build(parse(src, &OpRegistry::standard())?)?;
+        eval_graph(&g)
+    }
+
+    #[test]
+    fn pivot_fills_missing_cells_and_unpivot_inverts_it() {
+        // Sales by (region, quarter): region 7 has no quarter-1 row.
+        let long = "u64[9 7 9 7 9] u8[0 0 1 2 2] i32[5 6 -1 8 4] entuple.3";
+        let wide = run(&format!("{} 0i32 pivot.3", long)).unwrap();
+        assert_eq!(wide, vec![prod(vec![
+            from_vec::<u64>(vec![7, 9]),
+            from_vec::<i32>(vec![6, 5]),
+            from_vec::<i32>(vec![0, -1]),
+            from_vec::<i32>(vec![8, 4]),
+        ])]);
+        let back = run(&format!("{} 0i32 pivot.3 unpivot", long)).unwrap();
+        assert_eq!(back, vec![prod(vec![
+            from_vec::<u64>(vec![7, 7, 7, 9, 9, 9]),
+            from_vec::<u64>(vec![0, 1, 2, 0, 1, 2]),
+            from_vec::<i32>(vec![6, 0, 8, 5, -1, 4]),
+        ])]);
+        // Round trip from wide: sorted keys come back unchanged.
+        let w = "u64[1 2] f64[0.5 1.5] f64[2.5 3.5] entuple.3";
+        assert_eq!(run(&format!("{} unpivot 0f64 pivot.2", w)).unwrap(), run(w).unwrap());
+        // Composite keys sort like `group`'s.
+        let k = run("u8[1 0 1] u8[0 0 0] entuple.2 u8[0 0 1] u64[3 4 5] entuple.3 0u64 pivot.2 .0 detuple.2").unwrap();
+        assert_eq!(k, vec![from_vec::<u8>(vec![0, 1]), from_vec::<u8>(vec![0, 0])]);
+    }
+
+    #[test]
+    fn pivot_rejects_bad_categories_and_types_from_n() {
+        let err = run("u64[1 1] u8[0 0] u64[5 6] entuple.3 0u64 pivot.2").unwrap_err();
+        assert!(err.contains("repeats category 0"), "{}", err);
+        let err = run("u64[1 2] u8[0 2] u64[5 6] entuple.3 0u64 pivot.2").unwrap_err();
+        assert!(err.contains("category 2 at row 1 is out of range"), "{}", err);
+        // The wide shape is the keys plus N columns of the fill's width.
+        let mut st = vec![Shape::Prod(vec![Shape::Prim(W64), Shape::Prim(PrimWidth::W8), Shape::Prim(W32)]), Shape::Prim(W32)];
+        pivot_tc(4, &mut st).unwrap();
+        assert_eq!(st, vec![Shape::Prod(vec![Shape::Prim(W64), Shape::Prim(W32), Shape::Prim(W32), Shape::Prim(W32), Shape::Prim(W32)])]);
+        unpivot_tc(&mut st).unwrap();
+        assert_eq!(st, vec![Shape::Prod(vec![Shape::Prim(W64), Shape::Prim(W64), Shape::Prim(W32)])]);
+        // A fill of the wrong width fails to build.
+        assert!(run("u64[1] u8[0] u64[5] entuple.3 0u32 pivot.1").unwrap_err().contains("fill's width"));
+    }
+}
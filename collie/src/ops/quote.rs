@@ -0,0 +1,97 @@
+//! Quotations — `[ … ]` program fragments as compile-time values — and
+//! the higher-order ops that consume them.
+//!
+//! A `Quote` pushes its body (unevaluated) the way a literal pushes a
+//! column; it can be duplicated, swapped, bound with `:name` and passed
+//! through `def`s like any stack value. The consumers splice a copy of
+//! the body in where they stand:
+//!
+//! - `apply` — run the body on the stack as it is (`x [ f ] apply` ≡ `x f`);
+//! - `match.K` — `sum [a0] … [aK-1] match.K`: run arm `j` on lane `j`,
+//!   each arm `lane → lane'` (the quotation form of `match { -> … }`);
+//! - `under.<i>` — `prod [ f ] under.i`: replace field `i` of a Prod by
+//!   `f` of it, the other fields untouched.
+//!
+//! None of these run: `pipeline::lower` specializes each use (the body's
+//! ops are lowered in place, against the quotation's captured bindings),
+//! so no closure reaches the `SystemOp` graph. Bodies of `match.K` and
+//! `under.<i>` must be exactly one value in, one out; lowering checks that
+//! and reports a mismatch at the consuming op.
+
+use crate::ir::op::PrimOp;
+use crate::ir::span::Site;
+use crate::ir::stack::Stack;
+use crate::ir::typecheck::{Op, Typed, TypeStack, TypeEnv, clone_ops};
+use crate::ir::value::Value;
+
+#[derive(Debug)]
+pub struct Quote {
+    pub body: Vec<Box<dyn Op>>,
+    /// Source site of each body op (parallel to `body`, may be empty).
+    pub sites: Vec<Site>,
+}
+
+/// What a quotation consumer does with the quotation(s) on top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Use {
+    Apply,
+    Match(usize),
+    Under(usize),
+}
+
+/// `apply` / `match.K` / `under.<i>`.
+#[derive(Debug, Clone)]
+pub struct Consume { pub how: Use }
+
+impl Consume {
+    /// Quotations on top of the stack this op consumes.
+    pub fn n_quotes(&self) -> usize {
+        match self.how { Use::Match(k) => k, Use::Apply | Use::Under(_) => 1 }
+    }
+}
+
+impl PrimOp for Quote {
+    fn name(&self) -> &str { "quote" }
+    fn arity(&self) -> Option<(usize, usize)> { Some((0, 1)) }
+    fn run(&self, _st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        Err("quote: specialized during lowering, not directly runnable".into())
+    }
+}
+impl Typed for Quote {
+    fn clone_op(&self) -> Option<Box<dyn Op>> {
+        Some(Box::new(Quote { body: clone_ops(&self.body)?, sites: self.sites.clone() }))
+    }
+    fn tc(&self, _st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        Err("quote: only typechecked by lowering".into())
+    }
+}
+
+impl PrimOp for Consume {
+    fn name(&self) -> &str {
+        match self.how { Use::Apply => "apply", Use::Match(_) => "match", Use::Under(_) => "under" }
+    }
+    // Net effect depends on the quotation; lowering special-cases it.
+    fn arity(&self) -> Option<(usize, usize)> { None }
+    fn run(&self, _st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        Err(format!("{}: specialized during lowering, not directly runnable", self.name()))
+    }
+}
+impl Typed for Consume {
+    crate::cloneable!();
+    fn tc(&self, _st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        Err(format!("{}: only typechecked by lowering", self.name()))
+    }
+}
+
+pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
+    r.add(|t: &str| -> Option<Box<dyn Op>> {
+        if t == "apply" { return Some(Box::new(Consume { how: Use::Apply })); }
+        if let Some(k) = t.strip_prefix("match.").and_then(|k| k.parse::<usize>().ok()) {
+            if k > 0 { return Some(Box::new(Consume { how: Use::Match(k) })); }
+        }
+        if let Some(i) = t.strip_prefix("under.").and_then(|i| i.parse::<usize>().ok()) {
+            return Some(Box::new(Consume { how: Use::Under(i) }));
+        }
+        None
+    });
+}
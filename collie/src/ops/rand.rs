@@ -0,0 +1,557 @@
+//! Seeded random data: source ops for benchmarks, examples and tests that
+//! want realistic distributions rather than `iota` towers of `mod.u64`.
+//!
+//! Every op takes `n seed` on top (both one-element `u64`s) under its
+//! distribution's parameters, and makes `n` rows:
+//!
+//! - `rand.uniform.<i>` — `lo hi n seed →` values in `[lo, hi)`; `lo`/`hi`
+//!   are one-element columns of the interp. Integers are exactly uniform
+//!   (Lemire's unbiased bounded draw), floats `lo + (hi - lo) · u`.
+//! - `rand.zipf` — `k s n seed →` `u64` ranks in `[0, k)`, rank `r` drawn
+//!   with weight `(r + 1)^-s` (`k` a `u64`, `s ≥ 0` an `f64`). Hörmann's
+//!   rejection-inversion: O(1) memory whatever `k` is.
+//! - `rand.normal.f64` — `mean sd n seed →` `f64`s (Marsaglia's polar
+//!   method).
+//! - `rand.perm` — `n seed →` a permutation of `0..n` (`u64`, Fisher–Yates).
+//! - `rand.sample` — `col k seed →` `k` of `col`'s rows without
+//!   replacement, in their original order; any shape. (Here `k` takes
+//!   `n`'s place.)
+//! - `rand.like` — `template n seed →` `n` rows of `template`'s shape:
+//!   Prim leaves are uniform words of their width, each Sum row picks a
+//!   lane uniformly, and List rows have uniform lengths in `[0, 2m]`, `m`
+//!   the template's mean row length at that level (4 if it has no rows).
+//!
+//! The generator is xoshiro256** seeded through SplitMix64, and the
+//! transcendental functions the samplers need (`ln`, `exp`) are computed
+//! here from IEEE-exact `+ - * / sqrt` rather than the platform's libm, so
+//! a seed gives the same data on every machine.
+
+use crate::ir::encoding::{from_words, word};
+use crate::ir::op::PrimOp;
+use crate::ir::shape::{shape_of, Interp, Shape};
+use crate::ir::stack::{pop, Stack};
+use crate::ir::typecheck::{tc_pop, TypeEnv, TypeStack, Typed};
+use crate::ir::value::{bounds_var, from_vec, list, prod, sum, Prim, PrimWidth, Value};
+use crate::ops::helpers::{extract_prim, gather, normalize, splitmix64};
+
+// ── Generator ───────────────────────────────────────────────────────────────
+
+/// xoshiro256**, its state expanded from a `u64` seed by SplitMix64.
+pub struct Rng { s: [u64; 4] }
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        let mut z = seed;
+        Rng { s: [splitmix64(&mut z), splitmix64(&mut z), splitmix64(&mut z), splitmix64(&mut z)] }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.s;
+        let out = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        out
+    }
+
+    /// Uniform in `[0, 1)`, 53 bits.
+    pub fn f64(&mut self) -> f64 { (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64) }
+
+    /// Uniform in `[0, n)`, unbiased (Lemire's multiply-and-reject). `n > 0`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        let mut m = self.next_u64() as u128 * n as u128;
+        if (m as u64) < n {
+            let floor = n.wrapping_neg() % n;
+            while (m as u64) < floor { m = self.next_u64() as u128 * n as u128; }
+        }
+        (m >> 64) as u64
+    }
+}
+
+// ── Portable math ───────────────────────────────────────────────────────────
+
+// ln 2 split as in fdlibm: the high part has trailing zero bits, so `k · LN2_HI`
+// is exact for the exponents we scale by.
+const LN2_HI: f64 = f64::from_bits(0x3fe6_2e42_fee0_0000);
+const LN2_LO: f64 = f64::from_bits(0x3dea_39ef_3579_3c76);
+
+/// Natural log for finite `x > 0`: `x = m · 2^e` with `m` in `[√½, √2)`,
+/// then `ln m = 2 atanh((m - 1) / (m + 1))` by its odd series.
+pub fn ln(x: f64) -> f64 {
+    debug_assert!(x > 0.0 && x.is_finite());
+    let (x, bias) = if x < f64::MIN_POSITIVE { (x * (1u64 << 54) as f64, -54) } else { (x, 0) };
+    let bits = x.to_bits();
+    let mut e = ((bits >> 52) & 0x7ff) as i64 - 1023 + bias;
+    let mut m = f64::from_bits((bits & ((1u64 << 52) - 1)) | (1023u64 << 52));
+    if m > std::f64::consts::SQRT_2 { m *= 0.5; e += 1; }
+    let z = (m - 1.0) / (m + 1.0);
+    let z2 = z * z;
+    // |z| ≤ 0.172: 12 terms leave the remainder far below an ulp.
+    let mut p = 1.0 / 25.0;
+    for k in (0..12).rev() { p = p * z2 + 1.0 / (2 * k + 1) as f64; }
+    let e = e as f64;
+    e * LN2_HI + (e * LN2_LO + 2.0 * z * p)
+}
+
+/// `e^x`: `x = k ln 2 + r` with `|r| ≤ ½ ln 2`, Taylor for `e^r`, then
+/// scaled by `2^k`.
+pub fn exp(x: f64) -> f64 {
+    if x.is_nan() { return x; }
+    if x > 709.8 { return f64::INFINITY; }
+    if x < -745.2 { return 0.0; }
+    let k = (x / std::f64::consts::LN_2).round();
+    let r = (x - k * LN2_HI) - k * LN2_LO;
+    let mut p = 1.0;
+    for n in (1..=18).rev() { p = 1.0 + p * r / n as f64; }
+    // Two factors keep each power of two a normal f64.
+    let k = k as i64;
+    let (a, b) = (k / 2, k - k / 2);
+    p * f64::from_bits(((a + 1023) as u64) << 52) * f64::from_bits(((b + 1023) as u64) << 52)
+}
+
+fn powf(x: f64, y: f64) -> f64 { if x == 0.0 { 0.0 } else { exp(y * ln(x)) } }
+
+// ── Samplers ────────────────────────────────────────────────────────────────
+
+/// Rejection-inversion for Zipf over ranks `1..=k`, weight `x^-s` (after
+/// Hörmann & Derflinger): invert the hat `h(x) = min(1, x^-s)`'s integral
+/// `H`, and accept `x = ⌊b⌋ + 1` with probability `x^-s / h(b)`.
+struct Zipf { s: f64, t: f64 }
+
+impl Zipf {
+    fn new(k: u64, s: f64) -> Zipf {
+        let k = k as f64;
+        let t = if s == 1.0 { 1.0 + ln(k) } else { (powf(k, 1.0 - s) - s) / (1.0 - s) };
+        Zipf { s, t }
+    }
+
+    fn inv_h(&self, p: f64) -> f64 {
+        let pt = p * self.t;
+        if pt <= 1.0 { pt }
+        else if self.s == 1.0 { exp(pt - 1.0) }
+        else { powf(pt * (1.0 - self.s) + self.s, 1.0 / (1.0 - self.s)) }
+    }
+
+    /// A rank in `1..=k`.
+    fn sample(&self, k: u64, rng: &mut Rng) -> u64 {
+        loop {
+            let b = self.inv_h(rng.f64());
+            let x = (b + 1.0).floor();
+            let mut ratio = powf(x, -self.s);
+            if x > 1.0 { ratio *= powf(b, self.s); }
+            if rng.f64() < ratio { return (x as u64).min(k); }
+        }
+    }
+}
+
+/// Standard normals, two per polar draw.
+fn normals(n: usize, rng: &mut Rng) -> Vec<f64> {
+    let mut out = Vec::with_capacity(n + 1);
+    while out.len() < n {
+        let (u, v) = (2.0 * rng.f64() - 1.0, 2.0 * rng.f64() - 1.0);
+        let s = u * u + v * v;
+        if s == 0.0 || s >= 1.0 { continue; }
+        let m = (-2.0 * ln(s) / s).sqrt();
+        out.push(u * m);
+        out.push(v * m);
+    }
+    out.truncate(n);
+    out
+}
+
+/// `k` distinct indices of `0..n`, ascending. Floyd's algorithm when `k`
+/// is small next to `n`, else one selection-sampling pass (Knuth's
+/// Algorithm S).
+fn sample_indices(k: usize, n: usize, rng: &mut Rng) -> Vec<usize> {
+    if k.saturating_mul(16) <= n {
+        let mut chosen = std::collections::HashSet::with_capacity(k);
+        for j in n - k..n {
+            let t = rng.below(j as u64 + 1) as usize;
+            if !chosen.insert(t) { chosen.insert(j); }
+        }
+        let mut out: Vec<usize> = chosen.into_iter().collect();
+        out.sort_unstable();
+        out
+    } else {
+        let mut out = Vec::with_capacity(k);
+        for i in 0..n {
+            if out.len() == k { break; }
+            if rng.below((n - i) as u64) < (k - out.len()) as u64 { out.push(i); }
+        }
+        out
+    }
+}
+
+/// `n` rows shaped like `template` (normalized: no Views).
+fn like(template: &Value, n: usize, rng: &mut Rng) -> Result<Value, String> {
+    Ok(match template {
+        Value::Prim(p) => {
+            let w = p.width();
+            let mask = match w { PrimWidth::W8 => 0xff, PrimWidth::W16 => 0xffff, PrimWidth::W32 => 0xffff_ffff, PrimWidth::W64 => u64::MAX };
+            Value::Prim(from_words(w, (0..n).map(|_| rng.next_u64() & mask).collect()))
+        }
+        Value::Prod(fs) => prod(fs.iter().map(|f| like(f, n, rng)).collect::<Result<_, _>>()?),
+        Value::Sum { disc, lanes } => {
+            if lanes.is_empty() { return Err("rand.like: a Sum with no lanes has no rows to make".into()); }
+            let ds: Vec<u64> = (0..n).map(|_| rng.below(lanes.len() as u64)).collect();
+            let mut counts = vec![0usize; lanes.len()];
+            for &d in &ds { counts[d as usize] += 1; }
+            let lanes = lanes.iter().zip(counts).map(|(l, c)| like(l, c, rng)).collect::<Result<_, _>>()?;
+            sum(from_words(disc.width(), ds), lanes)
+        }
+        Value::List { bounds, values } => {
+            let rows = bounds.len();
+            let m = if rows == 0 { 4 } else { (values.len() as f64 / rows as f64).round() as u64 };
+            let mut starts = Vec::with_capacity(n + 1);
+            starts.push(0u64);
+            for _ in 0..n { let last = *starts.last().unwrap(); starts.push(last + rng.below(2 * m + 1)); }
+            let total = *starts.last().unwrap() as usize;
+            list(bounds_var(starts), like(values, total, rng)?)
+        }
+        Value::View { .. } | Value::Encoded(_) => unreachable!("normalized"),
+    })
+}
+
+// ── Operand helpers ─────────────────────────────────────────────────────────
+
+/// A one-element `u64` operand.
+fn scalar_u64(v: &Value, ctx: &str, what: &str) -> Result<u64, String> {
+    match extract_prim(v, ctx) {
+        Ok(Prim::P64(x)) if x.len() == 1 => Ok(x[0]),
+        _ => Err(format!("{}: {} must be a one-element u64, got {}", ctx, what, shape_of(v))),
+    }
+}
+
+/// A one-element operand of `interp`, widened: integers exactly (signed
+/// sign-extended), floats as `f64`.
+enum Num { Int(i128), Float(f64) }
+
+fn scalar_num(v: &Value, interp: Interp, ctx: &str, what: &str) -> Result<Num, String> {
+    let p = extract_prim(v, ctx)?;
+    if p.len() != 1 || p.width() != interp.width() {
+        return Err(format!("{}: {} must be a one-element {}, got {} ({} rows)", ctx, what, interp, shape_of(v), p.len()));
+    }
+    let w = word(&p, 0);
+    let bits = 8 * match interp.width() { PrimWidth::W8 => 1, PrimWidth::W16 => 2, PrimWidth::W32 => 4, PrimWidth::W64 => 8 };
+    Ok(match interp {
+        Interp::F32 => Num::Float(f32::from_bits(w as u32) as f64),
+        Interp::F64 => Num::Float(f64::from_bits(w)),
+        i if i.is_unsigned() => Num::Int(w as i128),
+        _ => Num::Int(((w << (64 - bits)) as i64 >> (64 - bits)) as i128),
+    })
+}
+
+fn tc_scalar(st: &mut TypeStack, w: PrimWidth, ctx: &str, what: &str) -> Result<(), String> {
+    let s = tc_pop(st, ctx)?;
+    if s != Shape::Prim(w) { return Err(format!("{}: {} must be Prim({}), got {}", ctx, what, w, s)); }
+    Ok(())
+}
+
+/// Pops `n seed` (seed on top).
+fn pop_n_seed(st: &mut Stack, ctx: &str) -> Result<(usize, Rng), String> {
+    let seed = scalar_u64(&pop(st)?, ctx, "seed")?;
+    let n = scalar_u64(&pop(st)?, ctx, "n")? as usize;
+    Ok((n, Rng::new(seed)))
+}
+
+fn tc_n_seed(st: &mut TypeStack, ctx: &str) -> Result<(), String> {
+    tc_scalar(st, PrimWidth::W64, ctx, "seed")?;
+    tc_scalar(st, PrimWidth::W64, ctx, "n")
+}
+
+// ── Ops ─────────────────────────────────────────────────────────────────────
+
+/// `rand.uniform.<i>` — `lo hi n seed → [i]`, values in `[lo, hi)`.
+#[derive(Debug, Clone)] pub struct Uniform { pub interp: Interp }
+impl PrimOp for Uniform {
+    fn name(&self) -> &str { "rand.uniform" }
+    fn token(&self) -> Option<String> { Some(format!("rand.uniform.{}", self.interp)) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((4, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let ctx = format!("rand.uniform.{}", self.interp);
+        let (n, mut rng) = pop_n_seed(st, &ctx)?;
+        let hi = scalar_num(&pop(st)?, self.interp, &ctx, "hi")?;
+        let lo = scalar_num(&pop(st)?, self.interp, &ctx, "lo")?;
+        let out = match (lo, hi) {
+            (Num::Int(lo), Num::Int(hi)) => {
+                if lo >= hi { return Err(format!("{}: lo {} must be below hi {}", ctx, lo, hi)); }
+                let span = (hi - lo) as u64;
+                let ws = (0..n).map(|_| (lo + rng.below(span) as i128) as u64).collect();
+                Value::Prim(from_words(self.interp.width(), ws))
+            }
+            (Num::Float(lo), Num::Float(hi)) => {
+                if !(lo < hi && (hi - lo).is_finite()) {
+                    return Err(format!("{}: need finite lo < hi, got {} and {}", ctx, lo, hi));
+                }
+                // Rounding can land on `hi` (more often in f32); redraw.
+                let mut draw = |ok: &dyn Fn(f64) -> bool| loop {
+                    let x = lo + (hi - lo) * rng.f64();
+                    if ok(x) { return x; }
+                };
+                if self.interp == Interp::F32 {
+                    let hi32 = hi as f32;
+                    from_vec::<f32>((0..n).map(|_| draw(&|x| (x as f32) < hi32) as f32).collect())
+                } else {
+                    from_vec::<f64>((0..n).map(|_| draw(&|x| x < hi)).collect())
+                }
+            }
+            _ => unreachable!("lo and hi share the interp"),
+        };
+        st.push(out);
+        Ok(())
+    }
+}
+impl Typed for Uniform {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let ctx = format!("rand.uniform.{}", self.interp);
+        tc_n_seed(st, &ctx)?;
+        let w = self.interp.width();
+        tc_scalar(st, w, &ctx, "hi")?;
+        tc_scalar(st, w, &ctx, "lo")?;
+        st.push(Shape::Prim(w));
+        Ok(())
+    }
+}
+
+/// `rand.zipf` — `k s n seed → [u64]`, ranks in `[0, k)`.
+#[derive(Debug, Clone)] pub struct ZipfOp;
+impl PrimOp for ZipfOp {
+    fn name(&self) -> &str { "rand.zipf" }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((4, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let (n, mut rng) = pop_n_seed(st, "rand.zipf")?;
+        let s = match scalar_num(&pop(st)?, Interp::F64, "rand.zipf", "s")? { Num::Float(s) => s, Num::Int(_) => unreachable!() };
+        let k = scalar_u64(&pop(st)?, "rand.zipf", "k")?;
+        if k == 0 || !(s >= 0.0 && s.is_finite()) {
+            return Err(format!("rand.zipf: needs k ≥ 1 and a finite s ≥ 0, got k = {} and s = {}", k, s));
+        }
+        let z = Zipf::new(k, s);
+        st.push(from_vec::<u64>((0..n).map(|_| z.sample(k, &mut rng) - 1).collect()));
+        Ok(())
+    }
+}
+impl Typed for ZipfOp {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        tc_n_seed(st, "rand.zipf")?;
+        tc_scalar(st, PrimWidth::W64, "rand.zipf", "s")?;
+        tc_scalar(st, PrimWidth::W64, "rand.zipf", "k")?;
+        st.push(Shape::Prim(PrimWidth::W64));
+        Ok(())
+    }
+}
+
+/// `rand.normal.f64` — `mean sd n seed → [f64]`.
+#[derive(Debug, Clone)] pub struct Normal;
+impl PrimOp for Normal {
+    fn name(&self) -> &str { "rand.normal" }
+    fn token(&self) -> Option<String> { Some("rand.normal.f64".to_string()) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((4, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let ctx = "rand.normal.f64";
+        let (n, mut rng) = pop_n_seed(st, ctx)?;
+        let f = |v: Value, what: &str| match scalar_num(&v, Interp::F64, ctx, what)? {
+            Num::Float(x) => Ok::<f64, String>(x),
+            Num::Int(_) => unreachable!(),
+        };
+        let sd = f(pop(st)?, "sd")?;
+        let mean = f(pop(st)?, "mean")?;
+        if !(sd >= 0.0 && sd.is_finite() && mean.is_finite()) {
+            return Err(format!("{}: needs a finite mean and sd ≥ 0, got {} and {}", ctx, mean, sd));
+        }
+        st.push(from_vec::<f64>(normals(n, &mut rng).into_iter().map(|z| mean + sd * z).collect()));
+        Ok(())
+    }
+}
+impl Typed for Normal {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let ctx = "rand.normal.f64";
+        tc_n_seed(st, ctx)?;
+        tc_scalar(st, PrimWidth::W64, ctx, "sd")?;
+        tc_scalar(st, PrimWidth::W64, ctx, "mean")?;
+        st.push(Shape::Prim(PrimWidth::W64));
+        Ok(())
+    }
+}
+
+/// `rand.perm` — `n seed → [u64]`, a permutation of `0..n`.
+#[derive(Debug, Clone)] pub struct Perm;
+impl PrimOp for Perm {
+    fn name(&self) -> &str { "rand.perm" }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let (n, mut rng) = pop_n_seed(st, "rand.perm")?;
+        let mut xs: Vec<u64> = (0..n as u64).collect();
+        for i in (1..n).rev() { xs.swap(i, rng.below(i as u64 + 1) as usize); }
+        st.push(from_vec::<u64>(xs));
+        Ok(())
+    }
+}
+impl Typed for Perm {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        tc_n_seed(st, "rand.perm")?;
+        st.push(Shape::Prim(PrimWidth::W64));
+        Ok(())
+    }
+}
+
+/// `rand.sample` — `col k seed → col'`, `k` rows without replacement, in
+/// order.
+#[derive(Debug, Clone)] pub struct Sample;
+impl PrimOp for Sample {
+    fn name(&self) -> &str { "rand.sample" }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((3, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let seed = scalar_u64(&pop(st)?, "rand.sample", "seed")?;
+        let k = scalar_u64(&pop(st)?, "rand.sample", "k")? as usize;
+        let col = pop(st)?;
+        let n = col.len();
+        if k > n { return Err(format!("rand.sample: can't take {} rows of {} without replacement", k, n)); }
+        st.push(gather(&col, &sample_indices(k, n, &mut Rng::new(seed)))?);
+        Ok(())
+    }
+}
+impl Typed for Sample {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        tc_scalar(st, PrimWidth::W64, "rand.sample", "seed")?;
+        tc_scalar(st, PrimWidth::W64, "rand.sample", "k")?;
+        let col = tc_pop(st, "rand.sample")?;
+        st.push(col);
+        Ok(())
+    }
+}
+
+/// `rand.like` — `template n seed → ` `n` random rows of `template`'s shape.
+#[derive(Debug, Clone)] pub struct Like;
+impl PrimOp for Like {
+    fn name(&self) -> &str { "rand.like" }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((3, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let (n, mut rng) = pop_n_seed(st, "rand.like")?;
+        let template = normalize(&pop(st)?)?;
+        st.push(like(&template, n, &mut rng)?);
+        Ok(())
+    }
+}
+impl Typed for Like {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        tc_n_seed(st, "rand.like")?;
+        let t = tc_pop(st, "rand.like")?;
+        st.push(t);
+        Ok(())
+    }
+}
+
+pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
+    use crate::ir::typecheck::Op;
+    use crate::syntax::registry::parse_interp;
+    r.add(|t: &str| -> Option<Box<dyn Op>> {
+        match t {
+            "rand.zipf" => Some(Box::new(ZipfOp)),
+            "rand.normal.f64" => Some(Box::new(Normal)),
+            "rand.perm" => Some(Box::new(Perm)),
+            "rand.sample" => Some(Box::new(Sample)),
+            "rand.like" => Some(Box::new(Like)),
+            _ => Some(Box::new(Uniform { interp: parse_interp(t.strip_prefix("rand.uniform.")?)? })),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::value::Storage;
+
+    fn run(op: &dyn PrimOp, mut st: Vec<Value>) -> Result<Value, String> {
+        op.run(&mut st, &mut Vec::new())?;
+        Ok(st.pop().unwrap())
+    }
+
+    fn u64s(v: &Value) -> Vec<u64> {
+        match v { Value::Prim(p) => <u64 as Storage>::extract(p).unwrap().to_vec(), other => panic!("{:?}", other) }
+    }
+
+    fn f64s(v: &Value) -> Vec<f64> {
+        match v { Value::Prim(p) => <f64 as Storage>::extract(p).unwrap().to_vec(), other => panic!("{:?}", other) }
+    }
+
+    fn one<T: crate::ir::value::Storage>(x: T) -> Value { from_vec(vec![x]) }
+
+    #[test]
+    fn generators_are_pinned_and_respect_their_parameters() {
+        // Fixed outputs: a change here breaks reproducibility across versions.
+        let mut r = Rng::new(42);
+        assert_eq!([r.next_u64(), r.next_u64()], [0x1578_0b2e_0c2e_c716, 0x6104_d986_6d11_3a7e]);
+        for x in [1e-310, 1e-9, 0.3, 1.0, 2.5, 10.0, 123.456, 1e300] {
+            assert!((ln(x) - x.ln()).abs() <= 2e-16 * x.ln().abs().max(1.0), "ln {}", x);
+        }
+        for x in [-700.0, -3.7, -1e-9, 0.0, 1.0, 9.25, 700.0] {
+            assert!((exp(x) - x.exp()).abs() <= 4e-16 * x.exp(), "exp {}", x);
+        }
+
+        let xs = run(&Uniform { interp: Interp::I32 }, vec![one(-5i32), one(5i32), one(10_000u64), one(7u64)]).unwrap();
+        let xs: Vec<i32> = match &xs { Value::Prim(p) => <i32 as Storage>::extract(p).unwrap().to_vec(), _ => unreachable!() };
+        assert!(xs.iter().all(|x| (-5..5).contains(x)));
+        assert!((-5..5).all(|v| xs.contains(&v)));
+        let again = run(&Uniform { interp: Interp::I32 }, vec![one(-5i32), one(5i32), one(10_000u64), one(7u64)]).unwrap();
+        assert_eq!(again, from_vec(xs));
+
+        let zs = u64s(&run(&ZipfOp, vec![one(100u64), one(1.2f64), one(50_000u64), one(1u64)]).unwrap());
+        let mut counts = [0usize; 100];
+        for &z in &zs { counts[z as usize] += 1; }
+        // Rank 0 dominates, and rank r's share falls off like (r + 1)^-1.2.
+        assert!(counts[0] > 2 * counts[1] && counts[1] > counts[9] && counts[9] > counts[99]);
+        let ratio = counts[0] as f64 / counts[1] as f64;
+        assert!((ratio - 2f64.powf(1.2)).abs() < 0.15, "{}", ratio);
+
+        let ns = f64s(&run(&Normal, vec![one(10.0f64), one(2.0f64), one(100_000u64), one(3u64)]).unwrap());
+        let mean = ns.iter().sum::<f64>() / ns.len() as f64;
+        let var = ns.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / ns.len() as f64;
+        assert!((mean - 10.0).abs() < 0.03 && (var.sqrt() - 2.0).abs() < 0.03, "{} {}", mean, var);
+
+        let mut p = u64s(&run(&Perm, vec![one(1000u64), one(9u64)]).unwrap());
+        assert_ne!(p, (0..1000).collect::<Vec<_>>());
+        p.sort_unstable();
+        assert_eq!(p, (0..1000).collect::<Vec<_>>());
+
+        let e = run(&Uniform { interp: Interp::U8 }, vec![one(3u8), one(3u8), one(1u64), one(0u64)]).unwrap_err();
+        assert!(e.contains("lo 3 must be below hi 3"), "{}", e);
+    }
+
+    #[test]
+    fn sample_and_like() {
+        let col = from_vec::<u64>((100..200).collect());
+        for k in [0, 3, 60, 100] {
+            let s = u64s(&run(&Sample, vec![col.clone(), one(k as u64), one(5u64)]).unwrap());
+            assert_eq!(s.len(), k);
+            assert!(s.windows(2).all(|w| w[0] < w[1]) && s.iter().all(|x| (100..200).contains(x)));
+        }
+        assert!(run(&Sample, vec![col, one(101u64), one(5u64)]).unwrap_err().contains("101 rows of 100"));
+
+        // A Prod of a Sum and a List of u8 rows averaging 3 long.
+        let template = prod(vec![
+            sum(crate::ir::value::prim_p8(vec![0, 1]), vec![from_vec::<u32>(vec![1]), from_vec::<u16>(vec![2])]),
+            list(bounds_var(vec![0, 2, 6]), from_vec::<u8>(vec![1, 2, 3, 4, 5, 6])),
+        ]);
+        let v = run(&Like, vec![template.clone(), one(1000u64), one(11u64)]).unwrap();
+        assert_eq!(shape_of(&v), shape_of(&template));
+        assert_eq!(v.len(), 1000);
+        let Value::Prod(fs) = &v else { unreachable!() };
+        let Value::List { bounds, values } = &fs[1] else { unreachable!() };
+        let avg = values.len() as f64 / bounds.len() as f64;
+        assert!((avg - 3.0).abs() < 0.3, "{}", avg);
+    }
+}
@@ -9,8 +9,8 @@
 use crate::ir::op::PrimOp;
 use crate::ir::stack::{Stack, pop};
 use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
-use crate::ir::value::{Value, Storage, from_vec, PrimWidth};
-use crate::ir::shape::{Interp, Shape, bounds_as_u64, prim_width};
+use crate::ir::value::{Value, Encoded, Storage, BoundsRepr, from_vec, PrimWidth};
+use crate::ir::shape::{Interp, Shape, prim_width};
 use crate::ops::helpers::{list_elementwise1, list_elementwise2};
 
 /// Element-wise boolean binary op over two P8 columns (non-zero = true).
@@ -47,28 +47,34 @@ fn bool_binop_tc(tag: &str, a: &Shape, b: &Shape) -> Result<Shape, String> {
 /// Empty rows are rejected — call sites should pre-filter or use a different
 /// op if zero-row semantics are needed.
 fn fold_runs<T: Storage, F: Fn(T, T) -> T>(
-    values: &Value, bounds: &[u64], f: F,
+    values: &Value, bounds: &BoundsRepr, f: F,
 ) -> Result<Value, String> {
     let p = match values {
         Value::Prim(p) => p,
         _ => return Err("reducer: expected Prim".into()),
     };
     let xs = T::extract(p)?;
-    let mut out: Vec<T> = Vec::with_capacity(bounds.len().saturating_sub(1));
-    for w in bounds.windows(2) {
-        let lo = w[0] as usize;
-        let hi = w[1] as usize;
-        if hi == lo { return Err("reducer: empty inner row".into()); }
+    let mut out: Vec<T> = Vec::with_capacity(bounds.len());
+    bounds.try_for_each_row(|lo, hi| {
+        if hi == lo { return Err("reducer: empty inner row".to_string()); }
         let mut acc = xs[lo];
         for &x in &xs[lo + 1..hi] { acc = f(acc, x); }
         out.push(acc);
-    }
+        Ok(())
+    })?;
     Ok(from_vec::<T>(out))
 }
 
 fn fold_whole<T: Storage, F: Fn(T, T) -> T>(
-    v: &Value, f: F,
+    v: &Value, f: F, idempotent: bool,
 ) -> Result<Value, String> {
+    // An idempotent fold (min/max) over an RLE column only needs each
+    // run's value once.
+    if idempotent {
+        if let Value::Encoded(Encoded::Rle { values, .. }) = v {
+            return fold_whole::<T, F>(&Value::Prim(values.clone()), f, false);
+        }
+    }
     // View-aware via `for_each_prim`: walks Prim, View(Indices), and
     // View(Range) without materializing an intermediate. Cost is one pass
     // through the logical elements.
@@ -86,8 +92,8 @@ fn fold_whole<T: Storage, F: Fn(T, T) -> T>(
 }
 
 macro_rules! reducer_op {
-    ($name:ident, $run:ident, $tc:ident, $tag:literal, $accum:expr) => {
-        #[derive(Debug)]
+    ($name:ident, $run:ident, $tc:ident, $tag:literal, $idem:literal, $accum:expr) => {
+        #[derive(Debug, Clone)]
         pub struct $name { pub interp: Interp }
         impl PrimOp for $name {
             fn name(&self) -> &str { $tag }
@@ -95,40 +101,40 @@ macro_rules! reducer_op {
             fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { $run(self.interp, st) }
         }
         impl Typed for $name {
+            crate::cloneable!();
             fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { $tc(self.interp, st) }
         }
         /// Reduce kernel (back-end `SystemOp::Reduce` calls this directly).
         pub fn $run(interp: Interp, st: &mut Stack) -> Result<(), String> {
-            // pop_raw — `fold_whole` is View-aware and avoids the
-            // intermediate gather that `pop()` would force.
-            let v = crate::ir::stack::pop_raw(st)?;
+            // pop_flat — `fold_whole` is View- and encoding-aware and
+            // avoids the intermediate gather / decode that `pop()` would force.
+            let v = crate::ir::stack::pop_flat(st)?;
             let out = match v {
                 Value::List { bounds, values } => {
-                    let bnds = bounds_as_u64(&bounds)?.to_vec();
                     match interp {
-                        Interp::U8  => fold_runs::<u8, _>(&values, &bnds, $accum)?,
-                        Interp::I8  => fold_runs::<i8, _>(&values, &bnds, $accum)?,
-                        Interp::U16 => fold_runs::<u16, _>(&values, &bnds, $accum)?,
-                        Interp::I16 => fold_runs::<i16, _>(&values, &bnds, $accum)?,
-                        Interp::U32 => fold_runs::<u32, _>(&values, &bnds, $accum)?,
-                        Interp::I32 => fold_runs::<i32, _>(&values, &bnds, $accum)?,
-                        Interp::U64 => fold_runs::<u64, _>(&values, &bnds, $accum)?,
-                        Interp::I64 => fold_runs::<i64, _>(&values, &bnds, $accum)?,
-                        Interp::F32 => fold_runs::<f32, _>(&values, &bnds, $accum)?,
-                        Interp::F64 => fold_runs::<f64, _>(&values, &bnds, $accum)?,
+                        Interp::U8  => fold_runs::<u8, _>(&values, &bounds, $accum)?,
+                        Interp::I8  => fold_runs::<i8, _>(&values, &bounds, $accum)?,
+                        Interp::U16 => fold_runs::<u16, _>(&values, &bounds, $accum)?,
+                        Interp::I16 => fold_runs::<i16, _>(&values, &bounds, $accum)?,
+                        Interp::U32 => fold_runs::<u32, _>(&values, &bounds, $accum)?,
+                        Interp::I32 => fold_runs::<i32, _>(&values, &bounds, $accum)?,
+                        Interp::U64 => fold_runs::<u64, _>(&values, &bounds, $accum)?,
+                        Interp::I64 => fold_runs::<i64, _>(&values, &bounds, $accum)?,
+                        Interp::F32 => fold_runs::<f32, _>(&values, &bounds, $accum)?,
+                        Interp::F64 => fold_runs::<f64, _>(&values, &bounds, $accum)?,
                     }
                 }
                 other => match interp {
-                    Interp::U8  => fold_whole::<u8, _>(&other, $accum)?,
-                    Interp::I8  => fold_whole::<i8, _>(&other, $accum)?,
-                    Interp::U16 => fold_whole::<u16, _>(&other, $accum)?,
-                    Interp::I16 => fold_whole::<i16, _>(&other, $accum)?,
-                    Interp::U32 => fold_whole::<u32, _>(&other, $accum)?,
-                    Interp::I32 => fold_whole::<i32, _>(&other, $accum)?,
-                    Interp::U64 => fold_whole::<u64, _>(&other, $accum)?,
-                    Interp::I64 => fold_whole::<i64, _>(&other, $accum)?,
-                    Interp::F32 => fold_whole::<f32, _>(&other, $accum)?,
-                    Interp::F64 => fold_whole::<f64, _>(&other, $accum)?,
+                    Interp::U8  => fold_whole::<u8, _>(&other, $accum, $idem)?,
+                    Interp::I8  => fold_whole::<i8, _>(&other, $accum, $idem)?,
+                    Interp::U16 => fold_whole::<u16, _>(&other, $accum, $idem)?,
+                    Interp::I16 => fold_whole::<i16, _>(&other, $accum, $idem)?,
+                    Interp::U32 => fold_whole::<u32, _>(&other, $accum, $idem)?,
+                    Interp::I32 => fold_whole::<i32, _>(&other, $accum, $idem)?,
+                    Interp::U64 => fold_whole::<u64, _>(&other, $accum, $idem)?,
+                    Interp::I64 => fold_whole::<i64, _>(&other, $accum, $idem)?,
+                    Interp::F32 => fold_whole::<f32, _>(&other, $accum, $idem)?,
+                    Interp::F64 => fold_whole::<f64, _>(&other, $accum, $idem)?,
                 }
             };
             st.push(out);
@@ -146,35 +152,73 @@ macro_rules! reducer_op {
     };
 }
 
-reducer_op!(ReduceMin, reduce_min_run, reduce_min_tc, "reduce.min", |a, b| if a < b { a } else { b });
-reducer_op!(ReduceMax, reduce_max_run, reduce_max_tc, "reduce.max", |a, b| if a > b { a } else { b });
-reducer_op!(ReduceMul, reduce_mul_run, reduce_mul_tc, "reduce.*",   |a, b| a * b);
+reducer_op!(ReduceMin, reduce_min_run, reduce_min_tc, "reduce.min", true,  |a, b| if a < b { a } else { b });
+reducer_op!(ReduceMax, reduce_max_run, reduce_max_tc, "reduce.max", true,  |a, b| if a > b { a } else { b });
+reducer_op!(ReduceMul, reduce_mul_run, reduce_mul_tc, "reduce.*",   false, |a, b| a * b);
+
+/// Bare `reduce.min` / `reduce.max`: with no interp the word is read
+/// unsigned at the column's width — the order bare `sort` and `<` use.
+/// Interp inference (`pipeline::interp`) resolves it to the typed
+/// reduction when it knows what the column holds.
+#[derive(Debug, Clone)]
+pub struct ReduceOrd { pub max: bool }
+
+impl PrimOp for ReduceOrd {
+    fn name(&self) -> &str { ord_tag(self.max) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { reduce_ord_run(self.max, st) }
+}
+impl Typed for ReduceOrd {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { reduce_ord_tc(self.max, st) }
+}
+
+fn ord_tag(max: bool) -> &'static str { if max { "reduce.max" } else { "reduce.min" } }
+
+/// The unsigned interp of a (List of) Prim's width.
+fn ord_interp(s: &Shape, max: bool) -> Result<Interp, String> {
+    match s {
+        Shape::List { inner, .. } => ord_interp(inner, max),
+        Shape::Prim(w) => Ok(Interp::unsigned(*w)),
+        other => Err(format!("{}: expected Prim or List<Prim>, got {}", ord_tag(max), other)),
+    }
+}
+
+/// Interp-less min/max kernel (back-end `SystemOp::ReduceOrd`).
+pub fn reduce_ord_run(max: bool, st: &mut Stack) -> Result<(), String> {
+    let top = st.last().ok_or_else(|| "stack underflow".to_string())?;
+    let interp = ord_interp(&crate::ir::shape::shape_of(top), max)?;
+    if max { reduce_max_run(interp, st) } else { reduce_min_run(interp, st) }
+}
+
+pub fn reduce_ord_tc(max: bool, st: &mut TypeStack) -> Result<(), String> {
+    let top = st.last().ok_or_else(|| format!("{}: stack underflow", ord_tag(max)))?;
+    let interp = ord_interp(top, max)?;
+    if max { reduce_max_tc(interp, st) } else { reduce_min_tc(interp, st) }
+}
 
 // any / all: input is P8 (boolean column or list of bools), output is P8 with one value per row.
-#[derive(Debug)] pub struct Any;
+#[derive(Debug, Clone)] pub struct Any;
 impl PrimOp for Any {
     fn name(&self) -> &str { "any" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { any_run(st) }
 }
 impl Typed for Any {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { any_tc(st) }
 }
 
 /// `any` kernel (back-end `SystemOp::Any` calls this directly).
 pub fn any_run(st: &mut Stack) -> Result<(), String> {
-        // pop_raw so we can dispatch on View ourselves.
-        let v = crate::ir::stack::pop_raw(st)?;
+        // pop_flat so a flat View streams through `for_each_prim`.
+        let v = crate::ir::stack::pop_flat(st)?;
         let out = match v {
             Value::List { bounds, values } => {
-                let bnds = bounds_as_u64(&bounds)?.to_vec();
-                let xs = <u8 as Storage>::extract(&match (*values).clone() { Value::Prim(p) => p, _ => return Err("any: list inner must be Prim".into()) })?.to_vec();
-                let mut out: Vec<u8> = Vec::with_capacity(bnds.len().saturating_sub(1));
-                for w in bnds.windows(2) {
-                    let lo = w[0] as usize;
-                    let hi = w[1] as usize;
-                    out.push(xs[lo..hi].iter().any(|&x| x != 0) as u8);
-                }
+                let Value::Prim(p) = values.as_ref() else { return Err("any: list inner must be Prim".into()) };
+                let xs = <u8 as Storage>::extract(p)?;
+                let mut out: Vec<u8> = Vec::with_capacity(bounds.len());
+                bounds.for_each_row(|lo, hi| out.push(xs[lo..hi].iter().any(|&x| x != 0) as u8));
                 from_vec::<u8>(out)
             }
             other => {
@@ -198,29 +242,26 @@ pub fn any_tc(st: &mut TypeStack) -> Result<(), String> {
         Ok(())
 }
 
-#[derive(Debug)] pub struct All;
+#[derive(Debug, Clone)] pub struct All;
 impl PrimOp for All {
     fn name(&self) -> &str { "all" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { all_run(st) }
 }
 impl Typed for All {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { all_tc(st) }
 }
 
 /// `all` kernel (back-end `SystemOp::All` calls this directly).
 pub fn all_run(st: &mut Stack) -> Result<(), String> {
-        let v = crate::ir::stack::pop_raw(st)?;
+        let v = crate::ir::stack::pop_flat(st)?;
         let out = match v {
             Value::List { bounds, values } => {
-                let bnds = bounds_as_u64(&bounds)?.to_vec();
-                let xs = <u8 as Storage>::extract(&match (*values).clone() { Value::Prim(p) => p, _ => return Err("all: list inner must be Prim".into()) })?.to_vec();
-                let mut out: Vec<u8> = Vec::with_capacity(bnds.len().saturating_sub(1));
-                for w in bnds.windows(2) {
-                    let lo = w[0] as usize;
-                    let hi = w[1] as usize;
-                    out.push(xs[lo..hi].iter().all(|&x| x != 0) as u8);
-                }
+                let Value::Prim(p) = values.as_ref() else { return Err("all: list inner must be Prim".into()) };
+                let xs = <u8 as Storage>::extract(p)?;
+                let mut out: Vec<u8> = Vec::with_capacity(bounds.len());
+                bounds.for_each_row(|lo, hi| out.push(xs[lo..hi].iter().all(|&x| x != 0) as u8));
                 from_vec::<u8>(out)
             }
             other => {
@@ -244,13 +285,14 @@ pub fn all_tc(st: &mut TypeStack) -> Result<(), String> {
         Ok(())
 }
 
-#[derive(Debug)] pub struct Not;
+#[derive(Debug, Clone)] pub struct Not;
 impl PrimOp for Not {
     fn name(&self) -> &str { "not" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { not_run(st) }
 }
 impl Typed for Not {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { not_tc(st) }
 }
 
@@ -282,13 +324,14 @@ fn not_prim(v: &Value) -> Result<Value, String> {
 
 /// `and` — elementwise AND of two P8 masks. Treats non-zero as true, zero as
 /// false; output is 0/1. Pops two same-length P8s, pushes P8 of same length.
-#[derive(Debug)] pub struct And;
+#[derive(Debug, Clone)] pub struct And;
 impl PrimOp for And {
     fn name(&self) -> &str { "and" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { and_run(st) }
 }
 impl Typed for And {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { and_tc(st) }
 }
 
@@ -310,13 +353,14 @@ pub fn and_tc(st: &mut TypeStack) -> Result<(), String> {
 }
 
 /// `or` — elementwise OR of two P8 masks.
-#[derive(Debug)] pub struct Or;
+#[derive(Debug, Clone)] pub struct Or;
 impl PrimOp for Or {
     fn name(&self) -> &str { "or" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { or_run(st) }
 }
 impl Typed for Or {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { or_tc(st) }
 }
 
@@ -347,6 +391,8 @@ pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
             "not" => Some(Box::new(Not)),
             "and" => Some(Box::new(And)),
             "or"  => Some(Box::new(Or)),
+            "reduce.min" => Some(Box::new(ReduceOrd { max: false })),
+            "reduce.max" => Some(Box::new(ReduceOrd { max: true })),
             _ => None,
         }
     });
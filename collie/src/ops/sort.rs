@@ -44,6 +44,9 @@
 //! signed/float order on a flat Prim, swizzle first:
 //! `enswizzle.<i> sort deswizzle.<i>`.
 //!
+//! An RLE column (`ir::encoding`) sorts run-wise: `sort` orders the runs
+//! and stays encoded, `sort.perm` expands the stable run order.
+//!
 //! ## Two approaches (the file's organizing axis)
 //!
 //! Every function below belongs to one of two families, marked by `----`
@@ -67,7 +70,7 @@ use std::sync::Arc;
 use crate::ir::op::PrimOp;
 use crate::ir::stack::{Stack, pop};
 use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
-use crate::ir::value::{Value, Prim, PrimWidth, BoundsRepr, Storage};
+use crate::ir::value::{Value, Encoded, Prim, PrimWidth, BoundsRepr, Storage};
 use crate::ir::shape::Shape;
 use crate::ops::helpers::{gather, materialize_ref};
 
@@ -85,7 +88,7 @@ pub use runs::*;
 /// (principle 7): a permutation is the fully-resolved special case of
 /// the engine's order/group labels, and consuming one via `gather`
 /// is random access. Prefer `sort` (data-returning) where you can.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SortPerm;
 
 impl PrimOp for SortPerm {
@@ -94,11 +97,22 @@ impl PrimOp for SortPerm {
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { sort_perm_run(st) }
 }
 impl Typed for SortPerm {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { sort_perm_tc(st) }
 }
 /// `sort.perm` kernel (back-end `SystemOp::SortPerm` calls this directly).
 pub fn sort_perm_run(st: &mut Stack) -> Result<(), String> {
-    let v = pop(st)?;
+    let v = crate::ir::stack::pop_raw(st)?;
+    // RLE: order the runs, not the elements. Stable — ties keep position order.
+    if let Value::Encoded(e @ Encoded::Rle { values, .. }) = &v {
+        let runs: Vec<(usize, usize)> = e.runs().collect();
+        let perm: Vec<u64> = rle_run_order(values).into_iter()
+            .flat_map(|r| (runs[r].0 as u64)..(runs[r].1 as u64))
+            .collect();
+        st.push(Value::Prim(Prim::P64(Arc::new(perm))));
+        return Ok(());
+    }
+    let v = crate::ir::stack::materialize_top(v)?;
     // Fast path: top-level Prim.
     if let Value::Prim(p) = &v {
         let perm = sort_prim_perm_top(p);
@@ -111,6 +125,13 @@ pub fn sort_perm_run(st: &mut Stack) -> Result<(), String> {
     st.push(Value::Prim(Prim::P64(Arc::new(perm))));
     Ok(())
 }
+/// Run indices of an RLE column in stable unsigned-word order of their values.
+fn rle_run_order(values: &Prim) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by_key(|&r| crate::ir::encoding::word(values, r));
+    order
+}
+
 pub fn sort_perm_tc(st: &mut TypeStack) -> Result<(), String> {
     let _ = tc_pop(st, "sort.perm")?;
     st.push(Shape::Prim(PrimWidth::W64));
@@ -120,7 +141,7 @@ pub fn sort_perm_tc(st: &mut TypeStack) -> Result<(), String> {
 /// `sort` — polymorphic. Returns the sorted value. The data-returning
 /// face of the engine (`sort_seq`); the order/group labels it also
 /// produces are discarded here.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SortPoly;
 
 impl PrimOp for SortPoly {
@@ -129,11 +150,22 @@ impl PrimOp for SortPoly {
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { sort_poly_run(st) }
 }
 impl Typed for SortPoly {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { sort_poly_tc(st) }
 }
 /// `sort` kernel (back-end `SystemOp::Sort` calls this directly).
 pub fn sort_poly_run(st: &mut Stack) -> Result<(), String> {
-    let v = pop(st)?;
+    let v = crate::ir::stack::pop_raw(st)?;
+    // RLE: sort the runs and stay encoded (`rle_keep` merges equal neighbours).
+    if let Value::Encoded(e @ Encoded::Rle { values, .. }) = &v {
+        let order = rle_run_order(values);
+        let lens: Vec<u64> = e.runs().map(|(lo, hi)| (hi - lo) as u64).collect();
+        let sorted_vals = crate::ops::helpers::gather_prim(values, &order);
+        let sorted_lens: Vec<u64> = order.iter().map(|&r| lens[r]).collect();
+        st.push(Value::Encoded(crate::ir::encoding::rle_keep(&sorted_vals, &sorted_lens)));
+        return Ok(());
+    }
+    let v = crate::ir::stack::materialize_top(v)?;
     let order = vec![0u64; v.len()];
     let (sorted, _labels) = sort_seq(&order, &v, false)?;
     st.push(sorted);
@@ -153,7 +185,7 @@ pub fn sort_poly_tc(st: &mut TypeStack) -> Result<(), String> {
 /// labels as the `order`. `sort_seq` orders by id first (rows already
 /// contiguous, so they stay put) then refines *within* each equal-id group
 /// by value — i.e. sorts inside each row. Bounds are reattached unchanged.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SortSegmented;
 
 impl PrimOp for SortSegmented {
@@ -162,6 +194,7 @@ impl PrimOp for SortSegmented {
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { sort_seg_run(st) }
 }
 impl Typed for SortSegmented {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { sort_seg_tc(st) }
 }
 /// `sort.segmented` kernel (back-end `SystemOp::SortSegmented` calls this directly).
@@ -170,9 +203,8 @@ pub fn sort_seg_run(st: &mut Stack) -> Result<(), String> {
     match v {
         Value::List { bounds, values } => {
             let mut labels: Vec<u64> = Vec::with_capacity(values.len());
-            for (i, (lo, hi)) in bounds.iter_pairs().enumerate() {
-                for _ in lo..hi { labels.push(i as u64); }
-            }
+            let mut i = 0u64;
+            bounds.for_each_row(|lo, hi| { labels.extend(std::iter::repeat_n(i, hi - lo)); i += 1; });
             let (sorted, _) = sort_seq(&labels, values.as_ref(), false)?;
             st.push(Value::List { bounds, values: Arc::new(sorted) });
             Ok(())
@@ -283,6 +315,11 @@ mod perm {
     /// data-returning entry point; this is the mechanism it (and `sort.perm`)
     /// delegate to where a permutation is still needed. See module docs.
     pub fn sort_blocks(labels: &[u64], value: &Value) -> Result<(Vec<u64>, Vec<u64>), String> {
+        // A field-less Prod (e.g. `opt`'s unit lane) has no length of its
+        // own: its rows are all equal, whatever their number.
+        if let Value::Prod(fs) = value {
+            if fs.is_empty() { return sort_prod_blocks(labels, &[]); }
+        }
         let n = value.len();
         if labels.len() != n {
             return Err(format!("sort_blocks: labels.len() {} != value.len() {}", labels.len(), n));
@@ -310,7 +347,7 @@ mod perm {
             Value::Prod(fs) => sort_prod_blocks(labels, fs.as_ref()),
             Value::List { bounds, values } => sort_list_blocks(labels, bounds, values.as_ref()),
             Value::Sum { disc, lanes } => sort_sum_blocks(labels, disc, lanes.as_ref()),
-            Value::View { .. } => unreachable!("materialize_ref dropped View"),
+            Value::View { .. } | Value::Encoded(_) => unreachable!("materialize_ref dropped View / Encoded"),
         }
     }
     /// Average block size at or above which the packed-label radix is
@@ -455,6 +492,15 @@ mod perm {
         values: &Value,
     ) -> Result<(Vec<u64>, Vec<u64>), String> {
         let n = labels.len();
+        // Uniform rows: no length refinement needed. View the list as the
+        // dense `Prod[T; stride]` it is and sort column-by-column.
+        if let BoundsRepr::Stride { stride, .. } = bounds {
+            let s = *stride as usize;
+            let fields = (0..s)
+                .map(|k| gather(values, &(0..n).map(|i| i * s + k).collect::<Vec<_>>()))
+                .collect::<Result<Vec<_>, _>>()?;
+            return sort_prod_blocks(labels, &fields);
+        }
         // Materialize bounds as N+1 start-offsets for the random-access lookups
         // below (sample_row / row → row_start).
         let bnds: Vec<u64> = bounds.iter_starts().collect();
@@ -1068,6 +1114,23 @@ mod tests {
         assert_eq!(sort_bytes_radix(Prim::P32(Arc::new(vec![0, 0, 0]))), Prim::P32(Arc::new(vec![0, 0, 0])));
     }
 
+    #[test]
+    fn strided_list_sorts_as_dense_prod() {
+        // A `Stride` list takes the dense `Prod[T; stride]` path; it must
+        // agree with the length-refining `Var` path on perm and labels.
+        use crate::ir::value::bounds_stride;
+        let vals = from_vec::<u64>(vec![2, 9, 1, 5, 2, 3, 1, 5, 2, 3]);
+        let strided = list(bounds_stride(2, 5), vals.clone());
+        let var = list(bounds_var_from_ends(vec![2, 4, 6, 8, 10]), vals);
+        let labels = vec![0, 0, 0, 1, 1];
+        let got = sort_blocks(&labels, &strided).unwrap();
+        assert_eq!(got, sort_blocks(&labels, &var).unwrap());
+        assert_eq!(got.0, vec![1, 2, 0, 3, 4]);
+        // Stride 0: every row is empty, so all rows tie.
+        let empty = list(bounds_stride(0, 3), from_vec::<u64>(vec![]));
+        assert_eq!(sort_blocks(&[0, 0, 0], &empty).unwrap(), (vec![0, 1, 2], vec![0, 0, 0]));
+    }
+
     #[test]
     fn sort_seq_returns_sorted_data_and_labels() {
         let v = from_vec::<u64>(vec![3, 1, 4, 1, 5]);
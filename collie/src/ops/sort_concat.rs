@@ -11,7 +11,7 @@ use crate::ir::op::PrimOp;
 use crate::ir::stack::{Stack, pop};
 use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
 use std::sync::Arc;
-use crate::ir::value::{Value, Prim, PrimWidth, bounds_var_from_ends};
+use crate::ir::value::{Value, Prim, PrimWidth, bounds_from_ends, bounds_stride};
 use crate::ir::shape::Shape;
 use crate::ops::helpers::{concat_values, gather};
 
@@ -83,13 +83,14 @@ mod segmented_tests {
 
 /// Concat: List<T> -> T. Drops outer list structure, returns the flat values.
 /// (Equivalent to `flatten drop`.)
-#[derive(Debug)] pub struct Concat;
+#[derive(Debug, Clone)] pub struct Concat;
 impl PrimOp for Concat {
     fn name(&self) -> &str { "concat" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { concat_run(st) }
 }
 impl Typed for Concat {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { concat_tc(st) }
 }
 pub fn concat_run(st: &mut Stack) -> Result<(), String> {
@@ -108,13 +109,14 @@ pub fn concat_tc(st: &mut TypeStack) -> Result<(), String> {
 }
 
 /// Take the first n rows of a list (or a flat column).
-#[derive(Debug)] pub struct Take;
+#[derive(Debug, Clone)] pub struct Take;
 impl PrimOp for Take {
     fn name(&self) -> &str { "take" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }  // (v, n) → first-n
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { take_run(st) }
 }
 impl Typed for Take {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { take_tc(st) }
 }
 pub fn take_run(st: &mut Stack) -> Result<(), String> {
@@ -138,13 +140,14 @@ pub fn take_tc(st: &mut TypeStack) -> Result<(), String> {
 }
 
 /// Skip the first n rows.
-#[derive(Debug)] pub struct Skip;
+#[derive(Debug, Clone)] pub struct Skip;
 impl PrimOp for Skip {
     fn name(&self) -> &str { "skip" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }  // (v, n) → skip-first-n
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { skip_run(st) }
 }
 impl Typed for Skip {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { skip_tc(st) }
 }
 pub fn skip_run(st: &mut Stack) -> Result<(), String> {
@@ -180,13 +183,14 @@ pub fn skip_tc(st: &mut TypeStack) -> Result<(), String> {
 ///
 /// Stack effect: `v_0 v_1 … v_{N-1} -- concat`. Order is preserved (v_0 is
 /// the first segment of the result).
-#[derive(Debug)] pub struct CatN { pub n: usize }
+#[derive(Debug, Clone)] pub struct CatN { pub n: usize }
 impl PrimOp for CatN {
     fn name(&self) -> &str { "cat" }
     fn arity(&self) -> Option<(usize, usize)> { Some((self.n, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { cat_run(self.n, st) }
 }
 impl Typed for CatN {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { cat_tc(self.n, st) }
 }
 pub fn cat_run(n: usize, st: &mut Stack) -> Result<(), String> {
@@ -229,13 +233,14 @@ pub fn cat_tc(n: usize, st: &mut TypeStack) -> Result<(), String> {
 
 /// Reverse a Prim column (or each field of a Prod). Outer row order is
 /// reversed. For Lists, reverse the row order (bounds recomputed).
-#[derive(Debug)] pub struct Reverse;
+#[derive(Debug, Clone)] pub struct Reverse;
 impl PrimOp for Reverse {
     fn name(&self) -> &str { "reverse" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { reverse_run(st) }
 }
 impl Typed for Reverse {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { reverse_tc(st) }
 }
 pub fn reverse_run(st: &mut Stack) -> Result<(), String> {
@@ -254,13 +259,14 @@ pub fn reverse_tc(st: &mut TypeStack) -> Result<(), String> {
 /// `reverse.segmented` — reverse the elements *within* each row of a List
 /// (outer row order and per-row counts unchanged). The per-row sibling of
 /// flat `reverse`; with `sort.segmented` it makes per-group descending order.
-#[derive(Debug)] pub struct ReverseSegmented;
+#[derive(Debug, Clone)] pub struct ReverseSegmented;
 impl PrimOp for ReverseSegmented {
     fn name(&self) -> &str { "reverse.segmented" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { reverse_seg_run(st) }
 }
 impl Typed for ReverseSegmented {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { reverse_seg_tc(st) }
 }
 pub fn reverse_seg_run(st: &mut Stack) -> Result<(), String> {
@@ -268,9 +274,7 @@ pub fn reverse_seg_run(st: &mut Stack) -> Result<(), String> {
     match v {
         Value::List { bounds, values } => {
             let mut idxs: Vec<usize> = Vec::with_capacity(values.len());
-            for (lo, hi) in bounds.iter_pairs() {
-                for j in (lo..hi).rev() { idxs.push(j as usize); }
-            }
+            bounds.for_each_row(|lo, hi| idxs.extend((lo..hi).rev()));
             let new_vals = gather(values.as_ref(), &idxs)?;
             st.push(Value::List { bounds, values: Arc::new(new_vals) });
             Ok(())
@@ -289,13 +293,14 @@ pub fn reverse_seg_tc(st: &mut TypeStack) -> Result<(), String> {
 /// `take.segmented` — keep the first `n` elements of *each* row of a List
 /// (per-row head). Pops `n` (length-1 P64) and the List; rows shorter than
 /// `n` are kept whole. The per-row sibling of flat `take`.
-#[derive(Debug)] pub struct TakeSegmented;
+#[derive(Debug, Clone)] pub struct TakeSegmented;
 impl PrimOp for TakeSegmented {
     fn name(&self) -> &str { "take.segmented" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { take_seg_run(st) }
 }
 impl Typed for TakeSegmented {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { take_seg_tc(st) }
 }
 pub fn take_seg_run(st: &mut Stack) -> Result<(), String> {
@@ -306,15 +311,21 @@ pub fn take_seg_run(st: &mut Stack) -> Result<(), String> {
     let v = pop(st)?;
     match v {
         Value::List { bounds, values } => {
+            // Every row of a `Stride` list keeps the same count, so only
+            // ragged rows need their ends recorded.
+            let stride = bounds.stride();
             let mut idxs: Vec<usize> = Vec::new();
-            let mut ends: Vec<u64> = Vec::with_capacity(bounds.len());
-            for (lo, hi) in bounds.iter_pairs() {
-                let keep = ((hi - lo) as usize).min(n);
-                for j in 0..keep { idxs.push(lo as usize + j); }
-                ends.push(idxs.len() as u64);
-            }
+            let mut ends: Vec<u64> = Vec::with_capacity(if stride.is_some() { 0 } else { bounds.len() });
+            bounds.for_each_row(|lo, hi| {
+                idxs.extend(lo..lo + (hi - lo).min(n));
+                if stride.is_none() { ends.push(idxs.len() as u64); }
+            });
+            let new_bounds = match stride {
+                Some(stride) => bounds_stride(stride.min(n as u64), bounds.len() as u64),
+                None => bounds_from_ends(ends),
+            };
             let new_vals = gather(values.as_ref(), &idxs)?;
-            st.push(Value::List { bounds: bounds_var_from_ends(ends), values: Arc::new(new_vals) });
+            st.push(Value::List { bounds: new_bounds, values: Arc::new(new_vals) });
             Ok(())
         }
         other => Err(format!("take.segmented: expected List, got {:?}", other)),
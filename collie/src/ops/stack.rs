@@ -14,7 +14,7 @@ use crate::ir::stack::{Stack, pop};
 use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
 use crate::ir::value::Value;
 
-#[derive(Debug)] pub struct Dup;
+#[derive(Debug, Clone)] pub struct Dup;
 impl PrimOp for Dup {
     fn name(&self) -> &str { "dup" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 2)) }
@@ -26,13 +26,14 @@ impl PrimOp for Dup {
     }
 }
 impl Typed for Dup {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         let v = st.last().ok_or("dup: empty")?.clone();
         st.push(v); Ok(())
     }
 }
 
-#[derive(Debug)] pub struct Drop_;
+#[derive(Debug, Clone)] pub struct Drop_;
 impl PrimOp for Drop_ {
     fn name(&self) -> &str { "drop" }
     fn arity(&self) -> Option<(usize, usize)> { Some((1, 0)) }
@@ -42,12 +43,13 @@ impl PrimOp for Drop_ {
     }
 }
 impl Typed for Drop_ {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         tc_pop(st, "drop")?; Ok(())
     }
 }
 
-#[derive(Debug)] pub struct Swap;
+#[derive(Debug, Clone)] pub struct Swap;
 impl PrimOp for Swap {
     fn name(&self) -> &str { "swap" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 2)) }
@@ -58,13 +60,14 @@ impl PrimOp for Swap {
     }
 }
 impl Typed for Swap {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         if st.len() < 2 { return Err("swap: <2".into()); }
         let n = st.len(); st.swap(n-1, n-2); Ok(())
     }
 }
 
-#[derive(Debug)] pub struct Over;
+#[derive(Debug, Clone)] pub struct Over;
 impl PrimOp for Over {
     fn name(&self) -> &str { "over" }
     fn arity(&self) -> Option<(usize, usize)> { Some((2, 3)) }
@@ -76,13 +79,14 @@ impl PrimOp for Over {
     }
 }
 impl Typed for Over {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         if st.len() < 2 { return Err("over: <2".into()); }
         let v = st[st.len()-2].clone(); st.push(v); Ok(())
     }
 }
 
-#[derive(Debug)] pub struct Rot;
+#[derive(Debug, Clone)] pub struct Rot;
 impl PrimOp for Rot {
     fn name(&self) -> &str { "rot" }
     fn arity(&self) -> Option<(usize, usize)> { Some((3, 3)) }
@@ -94,13 +98,14 @@ impl PrimOp for Rot {
     }
 }
 impl Typed for Rot {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         if st.len() < 3 { return Err("rot: <3".into()); }
         let n = st.len(); let a = st.remove(n-3); st.push(a); Ok(())
     }
 }
 
-#[derive(Debug)] pub struct Pick { pub n: usize }
+#[derive(Debug, Clone)] pub struct Pick { pub n: usize }
 impl PrimOp for Pick {
     fn name(&self) -> &str { "pick" }
     // Reads from depth n (so stack needs n+1 elements) and pushes a
@@ -120,13 +125,14 @@ impl PrimOp for Pick {
     }
 }
 impl Typed for Pick {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         if st.len() <= self.n { return Err(format!("pick {}: stack {}", self.n, st.len())); }
         let v = st[st.len()-1-self.n].clone(); st.push(v); Ok(())
     }
 }
 
-#[derive(Debug)] pub struct Roll { pub n: usize }
+#[derive(Debug, Clone)] pub struct Roll { pub n: usize }
 impl PrimOp for Roll {
     fn name(&self) -> &str { "roll" }
     // Moves the n-deep element to the top — touches n+1 stack slots.
@@ -145,6 +151,7 @@ impl PrimOp for Roll {
     }
 }
 impl Typed for Roll {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         if st.len() <= self.n { return Err(format!("roll {}: stack {}", self.n, st.len())); }
         let pos = st.len()-1-self.n; let v = st.remove(pos); st.push(v); Ok(())
@@ -159,9 +166,10 @@ impl Typed for Roll {
 ///
 /// Use to bracket regions of a program (e.g., data load vs query
 /// execution) the way datatoad's `.time` directive does.
-#[derive(Debug)] pub struct TimeOp;
+#[derive(Debug, Clone)] pub struct TimeOp;
 impl PrimOp for TimeOp {
     fn name(&self) -> &str { "time" }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
     fn arity(&self) -> Option<(usize, usize)> { Some((0, 0)) }
     fn run(&self, _st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
         use std::cell::RefCell;
@@ -187,7 +195,7 @@ impl PrimOp for TimeOp {
         Ok(())
     }
 }
-impl Typed for TimeOp {}
+impl Typed for TimeOp { crate::cloneable!(); }
 
 /// `profile.start` — enable per-op profiling (clears any previous stats).
 /// `profile.print` — print sorted summary (longest first) and disable.
@@ -196,27 +204,29 @@ impl Typed for TimeOp {}
 /// disabled (cheap), and one `Instant::now()` + map insert per op when
 /// enabled. Use to attribute wall-clock time across collie ops in a
 /// program region.
-#[derive(Debug)] pub struct ProfileStart;
+#[derive(Debug, Clone)] pub struct ProfileStart;
 impl PrimOp for ProfileStart {
     fn name(&self) -> &str { "profile.start" }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
     fn arity(&self) -> Option<(usize, usize)> { Some((0, 0)) }
     fn run(&self, _st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
         crate::ir::profile::start();
         Ok(())
     }
 }
-impl Typed for ProfileStart {}
+impl Typed for ProfileStart { crate::cloneable!(); }
 
-#[derive(Debug)] pub struct ProfilePrint;
+#[derive(Debug, Clone)] pub struct ProfilePrint;
 impl PrimOp for ProfilePrint {
     fn name(&self) -> &str { "profile.print" }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
     fn arity(&self) -> Option<(usize, usize)> { Some((0, 0)) }
     fn run(&self, _st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
         crate::ir::profile::print_and_stop();
         Ok(())
     }
 }
-impl Typed for ProfilePrint {}
+impl Typed for ProfilePrint { crate::cloneable!(); }
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,561 @@
+//! Statistical float aggregates — mean, variance, standard deviation,
+//! covariance and correlation — accurate on large columns.
+//!
+//! Each group (the whole column, or one row of a `List`) is reduced to
+//! its *moments*: `n`, the mean and `m2 = Σ(x − mean)²` (plus the other
+//! column's and the co-moment `Σ(x − mx)(y − my)` for two columns). Blocks
+//! of `BLOCK` elements are reduced two-pass with compensated (Neumaier)
+//! sums; blocks combine pairwise with the Chan et al. update — Welford's
+//! one-pass recurrence generalized to merging two partial states. Error
+//! grows with `log(n / BLOCK)`, not `n`, and the mean never passes
+//! through a large raw sum, so cancellation doesn't bite.
+//!
+//! - `stats.mean/var/stddev.<f>` — `vals → result`; `stats.cov/corr.<f>`
+//!   — `xs ys → result`. Flat input gives one value, per-row (`List<T>`)
+//!   one per row, in the input's float width. `var`/`stddev`/`cov` are the
+//!   sample (`n − 1`) forms; too few rows give NaN.
+//! - `stats.moments.<f>` / `stats.comoments.<f>` — the partial states, one
+//!   row per group: `Prod[n, mean, m2]` and `Prod[n, mx, my, m2x, m2y,
+//!   cxy]` (`n` a u64, the rest f64 bits).
+//! - `stats.merge` — `state state → state`, row by row; merging the states
+//!   of two batches equals the state of their concatenation (up to
+//!   rounding), so batches can be reduced independently.
+//! - `stats.<fn>.finish` — `state → f64` results from a state.
+//!
+//! Like `approx.*`, these are front-end ops (`SystemOp::Foreign`).
+
+use std::sync::Arc;
+use crate::ir::op::PrimOp;
+use crate::ir::stack::{Stack, pop};
+use crate::ir::typecheck::{Typed, TypeStack, TypeEnv, tc_pop};
+use crate::ir::value::{Value, Prim, PrimWidth, Storage, from_vec, prod};
+use crate::ir::shape::{Interp, Shape};
+use crate::ops::helpers::materialize_ref;
+
+/// Elements reduced directly (two-pass, compensated) before switching to
+/// pairwise merging.
+const BLOCK: usize = 1024;
+
+/// Neumaier's compensated sum.
+fn sum_compensated(xs: impl Iterator<Item = f64>) -> f64 {
+    let (mut s, mut c) = (0.0f64, 0.0f64);
+    for x in xs {
+        let t = s + x;
+        c += if s.abs() >= x.abs() { (s - t) + x } else { (x - t) + s };
+        s = t;
+    }
+    s + c
+}
+
+/// One column's moments: count, mean, `Σ(x − mean)²`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Moments { n: u64, mean: f64, m2: f64 }
+
+impl Moments {
+    fn of(xs: &[f64]) -> Moments {
+        if xs.len() > BLOCK {
+            let (a, b) = xs.split_at(xs.len() / 2);
+            return Moments::of(a).merge(Moments::of(b));
+        }
+        if xs.is_empty() { return Moments::default(); }
+        let n = xs.len() as u64;
+        let mean = sum_compensated(xs.iter().copied()) / n as f64;
+        let m2 = sum_compensated(xs.iter().map(|x| (x - mean) * (x - mean)));
+        Moments { n, mean, m2 }
+    }
+
+    /// Chan et al.: the moments of the concatenation.
+    fn merge(self, o: Moments) -> Moments {
+        if self.n == 0 { return o; }
+        if o.n == 0 { return self; }
+        let n = self.n + o.n;
+        let (na, nb, nf) = (self.n as f64, o.n as f64, n as f64);
+        let d = o.mean - self.mean;
+        Moments { n, mean: self.mean + d * (nb / nf), m2: self.m2 + o.m2 + d * d * (na * nb / nf) }
+    }
+
+    fn mean(&self) -> f64 { if self.n == 0 { f64::NAN } else { self.mean } }
+    fn var(&self) -> f64 { if self.n < 2 { f64::NAN } else { self.m2 / (self.n - 1) as f64 } }
+}
+
+/// Two columns' moments and co-moment `Σ(x − mx)(y − my)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct CoMoments { x: Moments, y: Moments, cxy: f64 }
+
+impl CoMoments {
+    fn of(xs: &[f64], ys: &[f64]) -> CoMoments {
+        if xs.len() > BLOCK {
+            let h = xs.len() / 2;
+            return CoMoments::of(&xs[..h], &ys[..h]).merge(CoMoments::of(&xs[h..], &ys[h..]));
+        }
+        let (x, y) = (Moments::of(xs), Moments::of(ys));
+        let cxy = sum_compensated(xs.iter().zip(ys).map(|(a, b)| (a - x.mean) * (b - y.mean)));
+        CoMoments { x, y, cxy }
+    }
+
+    fn merge(self, o: CoMoments) -> CoMoments {
+        if self.x.n == 0 { return o; }
+        if o.x.n == 0 { return self; }
+        let (na, nb) = (self.x.n as f64, o.x.n as f64);
+        let (dx, dy) = (o.x.mean - self.x.mean, o.y.mean - self.y.mean);
+        let cxy = self.cxy + o.cxy + dx * dy * (na * nb / (na + nb));
+        CoMoments { x: self.x.merge(o.x), y: self.y.merge(o.y), cxy }
+    }
+
+    fn cov(&self) -> f64 { if self.x.n < 2 { f64::NAN } else { self.cxy / (self.x.n - 1) as f64 } }
+    fn corr(&self) -> f64 { self.cxy / (self.x.m2 * self.y.m2).sqrt() }
+}
+
+/// The statistic a result or `.finish` op computes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stat { Mean, Var, Stddev, Cov, Corr }
+
+impl Stat {
+    fn name(self) -> &'static str {
+        match self {
+            Stat::Mean => "mean", Stat::Var => "var", Stat::Stddev => "stddev",
+            Stat::Cov => "cov", Stat::Corr => "corr",
+        }
+    }
+    fn bivariate(self) -> bool { matches!(self, Stat::Cov | Stat::Corr) }
+    fn parse(s: &str) -> Option<Stat> {
+        Some(match s {
+            "mean" => Stat::Mean, "var" => Stat::Var, "stddev" => Stat::Stddev,
+            "cov" => Stat::Cov, "corr" => Stat::Corr,
+            _ => return None,
+        })
+    }
+}
+
+/// A float column read as f64: f64 storage shared as it is, f32 widened
+/// once.
+enum F64s { Shared(Prim), Widened(Vec<f64>) }
+
+impl F64s {
+    fn as_slice(&self) -> &[f64] {
+        match self {
+            F64s::Shared(p) => <f64 as Storage>::extract(p).expect("checked in `groups`"),
+            F64s::Widened(xs) => xs,
+        }
+    }
+}
+
+/// A float column's groups: its values and each group's range of them.
+/// Flat input is one group.
+struct Groups { xs: F64s, rows: Vec<(usize, usize)> }
+
+impl Groups {
+    fn iter(&self) -> impl Iterator<Item = &[f64]> {
+        let xs = self.xs.as_slice();
+        self.rows.iter().map(move |&(lo, hi)| &xs[lo..hi])
+    }
+}
+
+fn groups(v: &Value, interp: Interp, ctx: &str) -> Result<Groups, String> {
+    let as_f64 = |p: &Prim| -> Result<F64s, String> {
+        match interp {
+            Interp::F32 => Ok(F64s::Widened(<f32 as Storage>::extract(p)?.iter().map(|&x| x as f64).collect())),
+            Interp::F64 => { <f64 as Storage>::extract(p)?; Ok(F64s::Shared(p.clone())) }
+            other => Err(format!("{}: float interpretations only, got {}", ctx, other)),
+        }
+    };
+    match v {
+        Value::Prim(p) => Ok(Groups { xs: as_f64(p)?, rows: vec![(0, p.len())] }),
+        Value::List { bounds, values } => match materialize_ref(values)?.as_ref() {
+            Value::Prim(p) => Ok(Groups {
+                xs: as_f64(p)?,
+                rows: bounds.iter_pairs().map(|(lo, hi)| (lo as usize, hi as usize)).collect(),
+            }),
+            other => Err(format!("{}: list inner must be Prim, got {:?}", ctx, other)),
+        },
+        other => Err(format!("{}: expected Prim or List<Prim>, got {:?}", ctx, other)),
+    }
+}
+
+/// Both columns' groups, which must line up (same row count and row lengths).
+fn paired(xs: &Value, ys: &Value, interp: Interp, ctx: &str) -> Result<Vec<CoMoments>, String> {
+    let (gx, gy) = (groups(xs, interp, ctx)?, groups(ys, interp, ctx)?);
+    if gx.rows.len() != gy.rows.len() || gx.iter().zip(gy.iter()).any(|(a, b)| a.len() != b.len()) {
+        return Err(format!("{}: xs and ys must have the same rows", ctx));
+    }
+    Ok(gx.iter().zip(gy.iter()).map(|(x, y)| CoMoments::of(x, y)).collect())
+}
+
+/// Results in `interp`'s float width, one per group.
+fn results(xs: Vec<f64>, interp: Interp) -> Value {
+    match interp {
+        Interp::F32 => from_vec::<f32>(xs.into_iter().map(|x| x as f32).collect()),
+        _ => from_vec::<f64>(xs),
+    }
+}
+
+fn f64s(xs: impl Iterator<Item = f64>) -> Value { from_vec::<f64>(xs.collect()) }
+
+fn moments_value(ms: &[Moments]) -> Value {
+    prod(vec![
+        from_vec::<u64>(ms.iter().map(|m| m.n).collect()),
+        f64s(ms.iter().map(|m| m.mean)),
+        f64s(ms.iter().map(|m| m.m2)),
+    ])
+}
+
+fn comoments_value(cs: &[CoMoments]) -> Value {
+    prod(vec![
+        from_vec::<u64>(cs.iter().map(|c| c.x.n).collect()),
+        f64s(cs.iter().map(|c| c.x.mean)),
+        f64s(cs.iter().map(|c| c.y.mean)),
+        f64s(cs.iter().map(|c| c.x.m2)),
+        f64s(cs.iter().map(|c| c.y.m2)),
+        f64s(cs.iter().map(|c| c.cxy)),
+    ])
+}
+
+/// A state value's columns: the `n` column and the f64 columns.
+fn state_columns(v: &Value, ctx: &str) -> Result<(Vec<u64>, Vec<Vec<f64>>), String> {
+    let Value::Prod(fs) = v else { return Err(format!("{}: expected a stats state, got {:?}", ctx, v)) };
+    if fs.len() != 3 && fs.len() != 6 {
+        return Err(format!("{}: expected a 3- or 6-field stats state, got {} fields", ctx, fs.len()));
+    }
+    let col = |i: usize| -> Result<Arc<Vec<u64>>, String> {
+        match materialize_ref(&fs[i])?.as_ref() {
+            Value::Prim(Prim::P64(x)) => Ok(x.clone()),
+            other => Err(format!("{}: state field {} must be P64, got {:?}", ctx, i, other)),
+        }
+    };
+    let n = col(0)?.to_vec();
+    let rest = (1..fs.len())
+        .map(|i| col(i).map(|c| c.iter().map(|&b| f64::from_bits(b)).collect()))
+        .collect::<Result<Vec<Vec<f64>>, String>>()?;
+    if rest.iter().any(|c| c.len() != n.len()) { return Err(format!("{}: ragged state", ctx)); }
+    Ok((n, rest))
+}
+
+fn moments_of(v: &Value, ctx: &str) -> Result<Vec<Moments>, String> {
+    let (n, c) = state_columns(v, ctx)?;
+    if c.len() != 2 { return Err(format!("{}: expected stats.moments state, got stats.comoments", ctx)); }
+    Ok((0..n.len()).map(|i| Moments { n: n[i], mean: c[0][i], m2: c[1][i] }).collect())
+}
+
+fn comoments_of(v: &Value, ctx: &str) -> Result<Vec<CoMoments>, String> {
+    let (n, c) = state_columns(v, ctx)?;
+    if c.len() != 5 { return Err(format!("{}: expected stats.comoments state, got stats.moments", ctx)); }
+    Ok((0..n.len()).map(|i| CoMoments {
+        x: Moments { n: n[i], mean: c[0][i], m2: c[2][i] },
+        y: Moments { n: n[i], mean: c[1][i], m2: c[3][i] },
+        cxy: c[4][i],
+    }).collect())
+}
+
+fn univariate(stat: Stat, m: &Moments) -> f64 {
+    match stat {
+        Stat::Mean => m.mean(),
+        Stat::Var => m.var(),
+        Stat::Stddev => m.var().sqrt(),
+        Stat::Cov | Stat::Corr => unreachable!("bivariate"),
+    }
+}
+
+fn bivariate(stat: Stat, c: &CoMoments) -> f64 {
+    match stat {
+        Stat::Cov => c.cov(),
+        Stat::Corr => c.corr(),
+        _ => unreachable!("univariate"),
+    }
+}
+
+fn moments_shape() -> Shape { Shape::Prod(vec![Shape::Prim(PrimWidth::W64); 3]) }
+fn comoments_shape() -> Shape { Shape::Prod(vec![Shape::Prim(PrimWidth::W64); 6]) }
+
+/// Input shape under a float interp: `Prim(w)` or `List<Prim(w)>`.
+fn tc_vals(tag: &str, interp: Interp, v: &Shape) -> Result<(), String> {
+    let inner = match v { Shape::List { inner, .. } => inner.as_ref(), other => other };
+    match inner {
+        Shape::Prim(w) if *w == interp.width() => Ok(()),
+        _ => Err(format!("{}.{}: expected Prim({}) or List<Prim({})>, got {}", tag, interp, interp.width(), interp.width(), v)),
+    }
+}
+
+/// `stats.<stat>.<f>` — `vals → result` (mean/var/stddev) or `xs ys →
+/// result` (cov/corr); one value per group, in the input's float width.
+#[derive(Debug, Clone)] pub struct StatsOp { pub stat: Stat, pub interp: Interp }
+impl PrimOp for StatsOp {
+    fn name(&self) -> &str {
+        match self.stat {
+            Stat::Mean => "stats.mean", Stat::Var => "stats.var", Stat::Stddev => "stats.stddev",
+            Stat::Cov => "stats.cov", Stat::Corr => "stats.corr",
+        }
+    }
+    fn arity(&self) -> Option<(usize, usize)> { Some((if self.stat.bivariate() { 2 } else { 1 }, 1)) }
+    fn token(&self) -> Option<String> { Some(format!("{}.{}", self.name(), self.interp)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let ctx = format!("{}.{}", self.name(), self.interp);
+        let out: Vec<f64> = if self.stat.bivariate() {
+            let ys = pop(st)?;
+            let xs = pop(st)?;
+            paired(&xs, &ys, self.interp, &ctx)?.iter().map(|c| bivariate(self.stat, c)).collect()
+        } else {
+            let gs = groups(&pop(st)?, self.interp, &ctx)?;
+            gs.iter().map(|g| univariate(self.stat, &Moments::of(g))).collect()
+        };
+        st.push(results(out, self.interp));
+        Ok(())
+    }
+}
+impl Typed for StatsOp {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let tag = self.name();
+        let v = tc_pop(st, tag)?;
+        tc_vals(tag, self.interp, &v)?;
+        if self.stat.bivariate() {
+            let xs = tc_pop(st, tag)?;
+            if xs != v { return Err(format!("{}.{}: xs and ys must have the same shape, got {} and {}", tag, self.interp, xs, v)); }
+        }
+        st.push(Shape::Prim(self.interp.width()));
+        Ok(())
+    }
+}
+
+/// `stats.moments.<f>` — `vals → Prod[n, mean, m2]`;
+/// `stats.comoments.<f>` — `xs ys → Prod[n, mx, my, m2x, m2y, cxy]`.
+/// One row per group.
+#[derive(Debug, Clone)] pub struct StatsState { pub bivariate: bool, pub interp: Interp }
+impl PrimOp for StatsState {
+    fn name(&self) -> &str { if self.bivariate { "stats.comoments" } else { "stats.moments" } }
+    fn arity(&self) -> Option<(usize, usize)> { Some((if self.bivariate { 2 } else { 1 }, 1)) }
+    fn token(&self) -> Option<String> { Some(format!("{}.{}", self.name(), self.interp)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let ctx = format!("{}.{}", self.name(), self.interp);
+        let out = if self.bivariate {
+            let ys = pop(st)?;
+            let xs = pop(st)?;
+            comoments_value(&paired(&xs, &ys, self.interp, &ctx)?)
+        } else {
+            let gs = groups(&pop(st)?, self.interp, &ctx)?;
+            moments_value(&gs.iter().map(Moments::of).collect::<Vec<_>>())
+        };
+        st.push(out);
+        Ok(())
+    }
+}
+impl Typed for StatsState {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let v = tc_pop(st, self.name())?;
+        tc_vals(self.name(), self.interp, &v)?;
+        if self.bivariate {
+            let xs = tc_pop(st, self.name())?;
+            if xs != v { return Err(format!("{}.{}: xs and ys must have the same shape, got {} and {}", self.name(), self.interp, xs, v)); }
+        }
+        st.push(if self.bivariate { comoments_shape() } else { moments_shape() });
+        Ok(())
+    }
+}
+
+/// `stats.merge` — `state state → state`, row by row, for either state kind.
+#[derive(Debug, Clone)] pub struct StatsMerge;
+impl PrimOp for StatsMerge {
+    fn name(&self) -> &str { "stats.merge" }
+    fn token(&self) -> Option<String> { Some(self.name().to_string()) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((2, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let b = pop(st)?;
+        let a = pop(st)?;
+        let rows = |x: usize, y: usize| if x == y { Ok(()) } else {
+            Err(format!("stats.merge: row counts differ ({} vs {})", x, y))
+        };
+        let out = if matches!(&a, Value::Prod(fs) if fs.len() == 6) {
+            let (xa, xb) = (comoments_of(&a, "stats.merge")?, comoments_of(&b, "stats.merge")?);
+            rows(xa.len(), xb.len())?;
+            comoments_value(&xa.into_iter().zip(xb).map(|(x, y)| x.merge(y)).collect::<Vec<_>>())
+        } else {
+            let (ma, mb) = (moments_of(&a, "stats.merge")?, moments_of(&b, "stats.merge")?);
+            rows(ma.len(), mb.len())?;
+            moments_value(&ma.into_iter().zip(mb).map(|(x, y)| x.merge(y)).collect::<Vec<_>>())
+        };
+        st.push(out);
+        Ok(())
+    }
+}
+impl Typed for StatsMerge {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let b = tc_pop(st, "stats.merge")?;
+        let a = tc_pop(st, "stats.merge")?;
+        if a != b || (a != moments_shape() && a != comoments_shape()) {
+            return Err(format!("stats.merge: expected two {} or two {} states, got {} and {}",
+                moments_shape(), comoments_shape(), a, b));
+        }
+        st.push(a);
+        Ok(())
+    }
+}
+
+/// `stats.<stat>.finish` — `state → f64`, one result per state row.
+#[derive(Debug, Clone)] pub struct StatsFinish { pub stat: Stat }
+impl PrimOp for StatsFinish {
+    fn name(&self) -> &str { "stats.finish" }
+    fn token(&self) -> Option<String> { Some(format!("stats.{}.finish", self.stat.name())) }
+    fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+    fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+        let ctx = format!("stats.{}.finish", self.stat.name());
+        let s = pop(st)?;
+        let out = if self.stat.bivariate() {
+            comoments_of(&s, &ctx)?.iter().map(|c| bivariate(self.stat, c)).collect()
+        } else {
+            moments_of(&s, &ctx)?.iter().map(|m| univariate(self.stat, m)).collect()
+        };
+        st.push(from_vec::<f64>(out));
+        Ok(())
+    }
+}
+impl Typed for StatsFinish {
+    crate::cloneable!();
+    fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
+        let tag = format!("stats.{}.finish", self.stat.name());
+        let s = tc_pop(st, &tag)?;
+        let want = if self.stat.bivariate() { comoments_shape() } else { moments_shape() };
+        if s != want { return Err(format!("{}: expected state {}, got {}", tag, want, s)); }
+        st.push(Shape::Prim(PrimWidth::W64));
+        Ok(())
+    }
+}
+
+pub fn register(r: &mut crate::syntax::registry::OpRegistry) {
+    use crate::ir::typecheck::Op;
+    use crate::syntax::registry::{parse_interp, split_suffix};
+    // stats.<stat>.finish, stats.merge
+    r.add(|t: &str| -> Option<Box<dyn Op>> {
+        if t == "stats.merge" { return Some(Box::new(StatsMerge)); }
+        let stat = t.strip_prefix("stats.")?.strip_suffix(".finish")?;
+        Some(Box::new(StatsFinish { stat: Stat::parse(stat)? }))
+    });
+    // stats.<stat>.<f>, stats.moments.<f>, stats.comoments.<f>
+    r.add(|t: &str| -> Option<Box<dyn Op>> {
+        let (head, sfx) = split_suffix(t)?;
+        let interp = parse_interp(sfx).filter(|i| matches!(i, Interp::F32 | Interp::F64))?;
+        match head.strip_prefix("stats.")? {
+            "moments" => Some(Box::new(StatsState { bivariate: false, interp })),
+            "comoments" => Some(Box::new(StatsState { bivariate: true, interp })),
+            stat => Some(Box::new(StatsOp { stat: Stat::parse(stat)?, interp })),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::value::{bounds_var, list};
+
+    fn run(op: &dyn PrimOp, mut st: Vec<Value>) -> Value {
+        op.run(&mut st, &mut Vec::new()).unwrap();
+        st.pop().unwrap()
+    }
+
+    fn f64_col(v: &Value) -> Vec<f64> {
+        match v { Value::Prim(p) => <f64 as Storage>::extract(p).unwrap().to_vec(), other => panic!("{:?}", other) }
+    }
+
+    /// Scaled integers `X = 1024 x`: a large common offset over a small
+    /// spread, so the naive sum-of-squares variance cancels to noise. Every
+    /// `x` is exact in f64, and `i128` sums are an exact reference.
+    fn data(n: usize, seed: u64, offset: i128) -> Vec<i128> {
+        let mut s = seed;
+        (0..n).map(|_| {
+            s ^= s << 13; s ^= s >> 7; s ^= s << 17;
+            offset * 1024 + (s % (1 << 20)) as i128
+        }).collect()
+    }
+
+    fn to_f64(xs: &[i128]) -> Vec<f64> { xs.iter().map(|&x| x as f64 / 1024.0).collect() }
+
+    fn rel(got: f64, want: f64) -> f64 { ((got - want) / want).abs() }
+
+    #[test]
+    fn matches_exact_reference_on_large_offset_columns() {
+        let n = 200_000i128;
+        let xs = data(n as usize, 7, 1_000_000_000);
+        // ys: correlated with xs (shared component) plus noise.
+        let ys: Vec<i128> = xs.iter().zip(data(n as usize, 11, 0)).map(|(x, e)| 3 * (x - 1_000_000_000 * 1024) + e - 5_000).collect();
+        let (sx, sy): (i128, i128) = (xs.iter().sum(), ys.iter().sum());
+        let sxx: i128 = xs.iter().map(|x| x * x).sum();
+        let syy: i128 = ys.iter().map(|y| y * y).sum();
+        let sxy: i128 = xs.iter().zip(&ys).map(|(x, y)| x * y).sum();
+        // Exact numerators over `n (n - 1) 1024²`.
+        let scale = (n * (n - 1)) as f64 * 1024.0 * 1024.0;
+        let var_x = (n * sxx - sx * sx) as f64 / scale;
+        let var_y = (n * syy - sy * sy) as f64 / scale;
+        let cov = (n * sxy - sx * sy) as f64 / scale;
+        let (fx, fy) = (from_vec::<f64>(to_f64(&xs)), from_vec::<f64>(to_f64(&ys)));
+
+        let mean = f64_col(&run(&StatsOp { stat: Stat::Mean, interp: Interp::F64 }, vec![fx.clone()]))[0];
+        assert!(rel(mean, sx as f64 / (n as f64 * 1024.0)) < 1e-15, "mean {}", mean);
+        let var = f64_col(&run(&StatsOp { stat: Stat::Var, interp: Interp::F64 }, vec![fx.clone()]))[0];
+        assert!(rel(var, var_x) < 1e-12, "var {} vs {}", var, var_x);
+        let sd = f64_col(&run(&StatsOp { stat: Stat::Stddev, interp: Interp::F64 }, vec![fx.clone()]))[0];
+        assert!(rel(sd, var_x.sqrt()) < 1e-12);
+        let c = f64_col(&run(&StatsOp { stat: Stat::Cov, interp: Interp::F64 }, vec![fx.clone(), fy.clone()]))[0];
+        assert!(rel(c, cov) < 1e-12, "cov {} vs {}", c, cov);
+        let r = f64_col(&run(&StatsOp { stat: Stat::Corr, interp: Interp::F64 }, vec![fx.clone(), fy]))[0];
+        assert!(rel(r, cov / (var_x * var_y).sqrt()) < 1e-12, "corr {}", r);
+
+        // The textbook one-pass formula loses most of its digits here.
+        let v = to_f64(&xs);
+        let (s, ss) = v.iter().fold((0.0, 0.0), |(s, ss), x| (s + x, ss + x * x));
+        let naive = (ss - s * s / n as f64) / (n - 1) as f64;
+        assert!(rel(naive, var_x) > 1e-6, "naive {} vs {}", naive, var_x);
+    }
+
+    #[test]
+    fn merged_batch_states_equal_the_whole() {
+        let xs = to_f64(&data(50_001, 3, 1_000_000));
+        let ys = to_f64(&data(50_001, 5, -2_000));
+        let (a, b) = (30_000, 50_001);
+        let state = |bivariate: bool, lo: usize, hi: usize| {
+            let op = StatsState { bivariate, interp: Interp::F64 };
+            let mut st = vec![from_vec::<f64>(xs[lo..hi].to_vec())];
+            if bivariate { st.push(from_vec::<f64>(ys[lo..hi].to_vec())); }
+            run(&op, st)
+        };
+        for (bivariate, stat) in [(false, Stat::Var), (true, Stat::Corr)] {
+            let merged = run(&StatsMerge, vec![state(bivariate, 0, a), state(bivariate, a, b)]);
+            let finish = |s: Value| f64_col(&run(&StatsFinish { stat }, vec![s]))[0];
+            let (m, w) = (finish(merged), finish(state(bivariate, 0, b)));
+            // Relative to the scale of the result (corr is near 0 here).
+            assert!((m - w).abs() < 1e-13 * w.abs().max(1.0), "{:?}: merged {} whole {}", stat, m, w);
+        }
+        // Kinds don't mix.
+        let mut st = vec![state(false, 0, 10)];
+        assert!(StatsFinish { stat: Stat::Cov }.run(&mut st, &mut Vec::new()).unwrap_err().contains("got stats.moments"));
+    }
+
+    #[test]
+    fn per_row_f32_and_degenerate_rows() {
+        // Rows: [1, 2, 3, 4], [5], [].
+        let l = list(bounds_var(vec![0, 4, 5, 5]), from_vec::<f32>(vec![1.0, 2.0, 3.0, 4.0, 5.0]));
+        let out = |stat| match run(&StatsOp { stat, interp: Interp::F32 }, vec![l.clone()]) {
+            Value::Prim(p) => <f32 as Storage>::extract(&p).unwrap().to_vec(),
+            other => panic!("{:?}", other),
+        };
+        let mean = out(Stat::Mean);
+        assert_eq!(&mean[..2], &[2.5, 5.0]);
+        assert!(mean[2].is_nan());
+        let var = out(Stat::Var);
+        assert!((var[0] - 5.0 / 3.0).abs() < 1e-6 && var[1].is_nan() && var[2].is_nan());
+    }
+
+    #[test]
+    fn stats_ops_parse_and_typecheck() {
+        use crate::syntax::{parse::parse, registry::OpRegistry};
+        let reg = OpRegistry::standard();
+        let run = |src: &str| crate::pipeline::build(parse(src, &reg).unwrap())
+            .and_then(|(g, _)| crate::pipeline::eval_graph(&g));
+        let out = run("f64[1 2 3 4] dup stats.moments.f64 swap stats.moments.f64 stats.merge stats.mean.finish").unwrap();
+        assert_eq!(out, vec![from_vec::<f64>(vec![2.5])]);
+        let out = run("f64[1 2 3] f64[2 4 6] stats.corr.f64").unwrap();
+        assert_eq!(out, vec![from_vec::<f64>(vec![1.0])]);
+        let e = run("u32[1 2] stats.mean.f64").unwrap_err();
+        assert!(e.contains("stats.mean.f64: expected Prim(P64)"), "{}", e);
+        assert!(parse("u64[1 2] stats.mean.u64", &reg).is_err());
+    }
+}
@@ -72,8 +72,8 @@ pub fn tc_swizzle(st: &mut TypeStack, interp: Interp, who: &str) -> Result<(), S
     }
 }
 
-#[derive(Debug)] pub struct Enswizzle { pub interp: Interp }
-#[derive(Debug)] pub struct Deswizzle { pub interp: Interp }
+#[derive(Debug, Clone)] pub struct Enswizzle { pub interp: Interp }
+#[derive(Debug, Clone)] pub struct Deswizzle { pub interp: Interp }
 
 impl PrimOp for Enswizzle {
     fn name(&self) -> &str { "enswizzle" }
@@ -83,6 +83,7 @@ impl PrimOp for Enswizzle {
     }
 }
 impl Typed for Enswizzle {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         tc_swizzle(st, self.interp, "enswizzle")
     }
@@ -96,6 +97,7 @@ impl PrimOp for Deswizzle {
     }
 }
 impl Typed for Deswizzle {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         tc_swizzle(st, self.interp, "deswizzle")
     }
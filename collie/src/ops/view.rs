@@ -33,7 +33,7 @@ use crate::ir::shape::Shape;
 ///
 /// In both cases, positions are interpreted as logical units of the input
 /// (elements for Prim, rows for List).
-#[derive(Debug)] pub struct View;
+#[derive(Debug, Clone)] pub struct View;
 
 impl PrimOp for View {
     fn name(&self) -> &str { "view" }
@@ -41,6 +41,7 @@ impl PrimOp for View {
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { view_run(st) }
 }
 impl Typed for View {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { view_tc(st) }
 }
 
@@ -62,6 +63,14 @@ pub fn view_run(st: &mut Stack) -> Result<(), String> {
             Shape::Prim(_) => {
                 // Build a flat Indices view; the smart constructor in
                 // `value::view` collapses if source is itself a View.
+                // Check positions now: the view is lazy, and an
+                // out-of-range one would otherwise only surface (as a
+                // panic) wherever it's first materialized.
+                if let Some(&k) = pos.iter().find(|&&k| k as usize >= source.len()) {
+                    return Err(format!(
+                        "view: position {} out of bounds (source has {} rows)", k, source.len()
+                    ));
+                }
                 let sel = Selector::Indices(pos);
                 st.push(view(source, sel));
                 Ok(())
@@ -158,7 +167,7 @@ pub fn view_tc(st: &mut TypeStack) -> Result<(), String> {
 /// The `Range` selector lets reductive ops skip the indirection entirely —
 /// they can read the source's underlying slice with an offset rather than
 /// dereferencing per-element through an indices vector.
-#[derive(Debug)] pub struct ViewRange;
+#[derive(Debug, Clone)] pub struct ViewRange;
 
 impl PrimOp for ViewRange {
     fn name(&self) -> &str { "view.range" }
@@ -166,6 +175,7 @@ impl PrimOp for ViewRange {
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { view_range_run(st) }
 }
 impl Typed for ViewRange {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { view_range_tc(st) }
 }
 /// `view.range` kernel (back-end `SystemOp::ViewRange` calls this directly).
@@ -184,6 +194,9 @@ pub fn view_range_run(st: &mut Stack) -> Result<(), String> {
         return Err(format!("view.range: hi {} < lo {}", hi, lo));
     }
     let source = pop_raw(st)?;
+    if hi as usize > source.len() {
+        return Err(format!("view.range: hi {} out of bounds (source has {} rows)", hi, source.len()));
+    }
     let sel = Selector::range(lo, hi);
     st.push(view(source, sel));
     Ok(())
@@ -223,7 +236,7 @@ pub fn view_range_tc(st: &mut TypeStack) -> Result<(), String> {
 /// Errors at runtime if the popped value isn't a View. The typechecker
 /// can't enforce this (View is shape-transparent), so the check is
 /// runtime-only.
-#[derive(Debug)] pub struct DecomposeView;
+#[derive(Debug, Clone)] pub struct DecomposeView;
 
 impl PrimOp for DecomposeView {
     fn name(&self) -> &str { "decompose-view" }
@@ -231,6 +244,7 @@ impl PrimOp for DecomposeView {
     fn run(&self, st: &mut Stack, _env: &mut Vec<Value>) -> Result<(), String> { decompose_view_run(st) }
 }
 impl Typed for DecomposeView {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> { decompose_view_tc(st) }
 }
 
@@ -0,0 +1,596 @@
+//! Stage B, alternative rewriter — equality saturation over the term graph.
+//!
+//! `optimize::rewrite` applies its rules in one greedy topological sweep:
+//! the first rule to match wins, and a rewrite that looks worse locally
+//! but enables a better one later never happens. This module instead
+//! builds an *e-graph* — equivalence classes of terms, hash-consed so
+//! equal subterms are stored once — applies a declarative rule set until
+//! nothing new is learned (or a budget runs out), then extracts the
+//! cheapest graph under a simple cost model.
+//!
+//! - **Ingest** (`EGraph::from_graph`): one e-node per term. Ops keyed as
+//!   `optimize::cse` keys them; side-effecting ops and large `Const`s get
+//!   a unique key so they are never merged. A multi-output term becomes a
+//!   *tuple* class plus one `#k` projection node per output — consumers and
+//!   rule patterns see single values only.
+//! - **Rules** (`Rewrite`): `lhs`/`rhs` patterns written as s-expressions
+//!   over surface tokens (`(gather (gather ?v ?i) ?j)`), with guards on the
+//!   pattern variables' class facts. The right-hand side is rebuilt from
+//!   its tokens (`OpRegistry` + `promote`), so no `SystemOp` is cloned.
+//! - **Analysis**: each class carries its output shapes, whether it is
+//!   known to be materialized, and whether it is known to be sorted (a
+//!   fixpoint of `sort`). All nodes in a class evaluate to the *same*
+//!   value, representation included, so one fact holds for all of them.
+//! - **Extraction**: per-class cheapest node by tree cost; ties go to the
+//!   newer node, i.e. to the rewritten form. Terms are re-emitted in their
+//!   original order where they survive, so evaluation order barely moves.
+//!
+//! The rolled-back `egg` spike (BAKEOFF.md) stalled on DAG-to-stack
+//! scheduling, re-creating ops that carry data, and multi-output ops. Here
+//! the graph is already a DAG, rule outputs are built from tokens, and
+//! outputs are projection nodes. Like every pass, it is never
+//! load-bearing: `eval_graph` gives the same result with or without it.
+
+use std::collections::HashMap;
+
+use crate::ir::shape::Shape;
+use crate::ir::value::PrimWidth;
+use crate::pipeline::graph::{Graph, Term, OutRef};
+use crate::pipeline::optimize::{cse, cse_key_for, elide_routing, eliminate_dead, materializes, term_shapes};
+use crate::pipeline::sysop::{promote, SystemOp};
+use crate::syntax::registry::OpRegistry;
+
+/// Equivalence-class id (canonical after `find`).
+type Id = usize;
+
+/// Saturation budget: rule rounds, and e-nodes before giving up on
+/// learning more (extraction still runs on what was learned).
+const MAX_ROUNDS: usize = 16;
+const MAX_NODES: usize = 20_000;
+
+/// The interps `swizzle-pair` is stated at.
+const INTERPS: &[&str] = &["i8", "u8", "i16", "u16", "i32", "u32", "f32", "i64", "u64", "f64"];
+
+enum NodeOp {
+    /// An operator; taken out when the node is extracted.
+    Sys(Option<SystemOp>),
+    /// Output `k` of a tuple class (a multi-output term).
+    Out(usize),
+}
+
+struct Node {
+    op: NodeOp,
+    key: String,
+    children: Vec<Id>,
+    /// The class the node was added to (`find` it for the current one).
+    class: Id,
+}
+
+struct Class {
+    nodes: Vec<usize>,
+    /// Output shapes: one for a value class, `n_outputs` for a tuple class.
+    shapes: Vec<Shape>,
+    materialized: bool,
+    sorted: bool,
+}
+
+/// A rule pattern: a variable or an op (surface token) over sub-patterns.
+/// `#k` is the projection of output `k` of a multi-output op.
+#[derive(Debug)]
+enum Pat {
+    Var(String),
+    Op { token: String, key: String, children: Vec<Pat> },
+}
+
+/// A guard on a pattern variable, read from its class's analysis.
+#[derive(Debug, Clone, Copy)]
+pub enum Cond {
+    /// The value is fully materialized (not a `View`/`Encoded`).
+    Materialized(&'static str),
+    /// The value is a flat column.
+    Flat(&'static str),
+    /// The value is a flat P8 mask.
+    Mask(&'static str),
+    /// The value is its own `sort`.
+    Sorted(&'static str),
+}
+
+/// One rewrite: wherever `lhs` matches and `conds` hold, `rhs` is added to
+/// the matched class. Rules sharing a `name` are one family.
+#[derive(Debug)]
+pub struct Rewrite {
+    pub name: &'static str,
+    lhs: Pat,
+    rhs: Pat,
+    conds: Vec<Cond>,
+}
+
+impl Rewrite {
+    /// Parse `lhs`/`rhs` against `reg`. Panics on a malformed pattern or an
+    /// unknown token — rules are fixed at build time.
+    pub fn new(reg: &OpRegistry, name: &'static str, lhs: &str, rhs: &str, conds: &[Cond]) -> Rewrite {
+        let lhs = parse_pat(reg, lhs).unwrap_or_else(|e| panic!("rule {}: {}", name, e));
+        let rhs = parse_pat(reg, rhs).unwrap_or_else(|e| panic!("rule {}: {}", name, e));
+        Rewrite { name, lhs, rhs, conds: conds.to_vec() }
+    }
+}
+
+/// The standard rule set, by family:
+///
+/// - `filter-fusion`: `(filter (filter x m1) m2)` → one filter over
+///   `mask.compose`d flat masks.
+/// - `gather-compose`: compose gather positions, gather the values once.
+/// - `reverse-pair`, `swizzle-pair`, `detuple-entuple`, `proj-entuple`:
+///   iso cancellation, each guarded so a materialized result is never
+///   replaced by a `View`.
+/// - `sort-elision`: `sort` of a value already sorted (a `sort`, an
+///   `iota`, or a prefix/suffix of a sorted column) is that value.
+pub fn rules() -> Vec<Rewrite> {
+    use Cond::*;
+    let reg = OpRegistry::standard();
+    let mut rs = vec![
+        Rewrite::new(&reg, "filter-fusion",
+            "(filter (filter ?x ?m1) ?m2)", "(filter ?x (mask.compose ?m1 ?m2))",
+            &[Mask("?m1"), Mask("?m2")]),
+        Rewrite::new(&reg, "gather-compose",
+            "(gather (gather ?v ?i) ?j)", "(gather ?v (gather ?i ?j))", &[]),
+        Rewrite::new(&reg, "reverse-pair",
+            "(reverse (reverse ?x))", "?x", &[Materialized("?x"), Flat("?x")]),
+        Rewrite::new(&reg, "sort-elision", "(sort ?x)", "?x", &[Sorted("?x")]),
+    ];
+    for i in INTERPS {
+        rs.push(Rewrite::new(&reg, "swizzle-pair",
+            &format!("(deswizzle.{i} (enswizzle.{i} ?x))"), "?x", &[Materialized("?x")]));
+        rs.push(Rewrite::new(&reg, "swizzle-pair",
+            &format!("(enswizzle.{i} (deswizzle.{i} ?x))"), "?x", &[Materialized("?x")]));
+    }
+    const VARS: &[&str] = &["?a", "?b", "?c", "?d"];
+    for n in 2..=VARS.len() {
+        let args = VARS[..n].join(" ");
+        for (k, &var) in VARS[..n].iter().enumerate() {
+            rs.push(Rewrite::new(&reg, "detuple-entuple",
+                &format!("(#{k} (detuple.{n} (entuple.{n} {args})))"), var, &[Materialized(var)]));
+            rs.push(Rewrite::new(&reg, "proj-entuple",
+                &format!("(.{k} (entuple.{n} {args}))"), var, &[Materialized(var)]));
+        }
+    }
+    rs
+}
+
+fn parse_pat(reg: &OpRegistry, src: &str) -> Result<Pat, String> {
+    let toks: Vec<String> = src.replace('(', " ( ").replace(')', " ) ")
+        .split_whitespace().map(str::to_string).collect();
+    let mut pos = 0;
+    let pat = parse_pat_at(reg, &toks, &mut pos)?;
+    if pos != toks.len() { return Err(format!("trailing tokens in {:?}", src)); }
+    Ok(pat)
+}
+
+fn parse_pat_at(reg: &OpRegistry, toks: &[String], pos: &mut usize) -> Result<Pat, String> {
+    let tok = toks.get(*pos).ok_or("unexpected end of pattern")?;
+    *pos += 1;
+    if tok.starts_with('?') { return Ok(Pat::Var(tok.clone())); }
+    if tok != "(" { return Err(format!("expected '(' or a variable, got {:?}", tok)); }
+    let token = toks.get(*pos).ok_or("unexpected end of pattern")?.clone();
+    *pos += 1;
+    let key = match token.strip_prefix('#') {
+        Some(k) => { k.parse::<usize>().map_err(|_| format!("bad projection {:?}", token))?; token.clone() }
+        None => {
+            let op = promote(reg.make(&token).ok_or_else(|| format!("unknown op {:?}", token))?);
+            cse_key_for(&op).ok_or_else(|| format!("{:?} can't appear in a rule", token))?
+        }
+    };
+    let mut children = Vec::new();
+    while toks.get(*pos).map(String::as_str) != Some(")") {
+        children.push(parse_pat_at(reg, toks, pos)?);
+    }
+    *pos += 1;
+    Ok(Pat::Op { token, key, children })
+}
+
+type Subst = Vec<(String, Id)>;
+
+fn lookup(s: &Subst, var: &str) -> Option<Id> {
+    s.iter().find(|(v, _)| v == var).map(|&(_, c)| c)
+}
+
+/// An e-graph over one `Graph`: classes of equal values, plus what is
+/// needed to emit a graph back out (roots, side effects, term order).
+pub struct EGraph {
+    nodes: Vec<Node>,
+    parent: Vec<Id>,
+    classes: Vec<Class>,
+    memo: HashMap<(String, Vec<Id>), Id>,
+    /// Per source term, its class (the tuple class for multi-output
+    /// terms): the emission order.
+    anchors: Vec<Id>,
+    /// Classes of side-effecting terms, which are kept regardless of use.
+    effects: Vec<Id>,
+    roots: Vec<Id>,
+}
+
+impl EGraph {
+    /// Ingest `g`. `Err` (with `g` handed back) if it doesn't typecheck.
+    pub fn from_graph(g: Graph) -> Result<EGraph, Graph> {
+        let shapes = match term_shapes(&g) {
+            Ok(s) => s,
+            Err(_) => return Err(g),
+        };
+        let mut eg = EGraph {
+            nodes: Vec::new(), parent: Vec::new(), classes: Vec::new(), memo: HashMap::new(),
+            anchors: Vec::with_capacity(g.terms.len()), effects: Vec::new(), roots: Vec::new(),
+        };
+        // Per term, the class of each output.
+        let mut outs: Vec<Vec<Id>> = Vec::with_capacity(g.terms.len());
+        for (id, (term, sh)) in g.terms.into_iter().zip(shapes).enumerate() {
+            let children = term.children.iter().map(|c| outs[c.term][c.idx]).collect();
+            let effect = term.op.is_side_effecting();
+            let key = cse_key_for(&term.op);
+            let mat = materializes(&term.op);
+            let tuple = term.n_outputs != 1;
+            let class = eg.add(NodeOp::Sys(Some(term.op)), key.unwrap_or_else(|| format!("opaque#{}", id)),
+                               children, sh.clone(), mat);
+            eg.anchors.push(class);
+            if effect { eg.effects.push(class); }
+            outs.push(if tuple {
+                sh.into_iter().enumerate()
+                    .map(|(k, s)| eg.add(NodeOp::Out(k), format!("#{}", k), vec![class], vec![s], false))
+                    .collect()
+            } else {
+                vec![class]
+            });
+        }
+        eg.roots = g.roots.iter().map(|r| outs[r.term][r.idx]).collect();
+        eg.rebuild();
+        Ok(eg)
+    }
+
+    fn find(&self, mut a: Id) -> Id {
+        while self.parent[a] != a { a = self.parent[a]; }
+        a
+    }
+
+    /// Add a node (hash-consed: an identical node returns its class).
+    fn add(&mut self, op: NodeOp, key: String, children: Vec<Id>, shapes: Vec<Shape>, materialized: bool) -> Id {
+        let children: Vec<Id> = children.into_iter().map(|c| self.find(c)).collect();
+        if let Some(&c) = self.memo.get(&(key.clone(), children.clone())) {
+            return self.find(c);
+        }
+        let class = self.classes.len();
+        self.parent.push(class);
+        self.classes.push(Class { nodes: vec![self.nodes.len()], shapes, materialized, sorted: false });
+        self.memo.insert((key.clone(), children.clone()), class);
+        self.nodes.push(Node { op, key, children, class });
+        class
+    }
+
+    /// Merge two classes; false if they were already one.
+    fn union(&mut self, a: Id, b: Id) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b { return false; }
+        self.parent[b] = a;
+        let nodes = std::mem::take(&mut self.classes[b].nodes);
+        let (mat, sorted) = (self.classes[b].materialized, self.classes[b].sorted);
+        let ca = &mut self.classes[a];
+        ca.nodes.extend(nodes);
+        ca.materialized |= mat;
+        ca.sorted |= sorted;
+        true
+    }
+
+    /// Restore the invariants after unions: re-canonicalize every node,
+    /// merge classes that now hold congruent nodes (same op, same child
+    /// classes), and re-derive `sorted` to a fixpoint.
+    fn rebuild(&mut self) {
+        loop {
+            self.memo.clear();
+            let mut merged = false;
+            for n in 0..self.nodes.len() {
+                let children: Vec<Id> = self.nodes[n].children.iter().map(|&c| self.find(c)).collect();
+                self.nodes[n].children = children.clone();
+                let k = (self.nodes[n].key.clone(), children);
+                let class = self.find(self.nodes[n].class);
+                match self.memo.get(&k) {
+                    Some(&other) => merged |= self.union(other, class),
+                    None => { self.memo.insert(k, class); }
+                }
+            }
+            if !merged { break; }
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for n in 0..self.nodes.len() {
+                let class = self.find(self.nodes[n].class);
+                if !self.classes[class].sorted && self.node_sorted(n, class) {
+                    self.classes[class].sorted = true;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    /// Whether node `n` (in `class`) is known to produce its own `sort`.
+    /// `sort` is idempotent; `iota` counts up; a `take`/`skip` of a sorted
+    /// flat column is sorted (and materialized, so re-sorting it returns
+    /// an equal column).
+    fn node_sorted(&self, n: usize, class: Id) -> bool {
+        match &self.nodes[n].op {
+            NodeOp::Sys(Some(SystemOp::Sort | SystemOp::Iota)) => true,
+            NodeOp::Sys(Some(SystemOp::Take | SystemOp::Skip)) => {
+                matches!(self.classes[class].shapes[..], [Shape::Prim(_)])
+                    && self.classes[self.find(self.nodes[n].children[0])].sorted
+            }
+            _ => false,
+        }
+    }
+
+    /// Canonical class ids, in creation order.
+    fn class_ids(&self) -> Vec<Id> {
+        (0..self.classes.len()).filter(|&c| self.parent[c] == c).collect()
+    }
+
+    /// Every way `pat` matches class `c`, extending `s`.
+    fn ematch(&self, pat: &Pat, c: Id, s: Subst) -> Vec<Subst> {
+        match pat {
+            Pat::Var(v) => match lookup(&s, v) {
+                Some(bound) if self.find(bound) != c => vec![],
+                Some(_) => vec![s],
+                None => { let mut s = s; s.push((v.clone(), c)); vec![s] }
+            },
+            Pat::Op { key, children, .. } => {
+                let mut out = Vec::new();
+                for &n in &self.classes[c].nodes {
+                    let node = &self.nodes[n];
+                    if &node.key != key || node.children.len() != children.len() { continue; }
+                    let mut partial = vec![s.clone()];
+                    for (p, &ch) in children.iter().zip(&node.children) {
+                        let ch = self.find(ch);
+                        partial = partial.into_iter().flat_map(|s| self.ematch(p, ch, s)).collect();
+                    }
+                    out.extend(partial);
+                }
+                out
+            }
+        }
+    }
+
+    fn holds(&self, cond: Cond, s: &Subst) -> bool {
+        let class = |v: &str| lookup(s, v).map(|c| &self.classes[self.find(c)]);
+        match cond {
+            Cond::Materialized(v) => class(v).is_some_and(|c| c.materialized),
+            Cond::Flat(v) => class(v).is_some_and(|c| matches!(c.shapes[..], [Shape::Prim(_)])),
+            Cond::Mask(v) => class(v).is_some_and(|c| matches!(c.shapes[..], [Shape::Prim(PrimWidth::W8)])),
+            Cond::Sorted(v) => class(v).is_some_and(|c| c.sorted),
+        }
+    }
+
+    /// Add `pat` under `s`; `None` if it doesn't typecheck.
+    fn instantiate(&mut self, reg: &OpRegistry, pat: &Pat, s: &Subst) -> Option<Id> {
+        match pat {
+            Pat::Var(v) => lookup(s, v).map(|c| self.find(c)),
+            Pat::Op { token, key, children } => {
+                let ids = children.iter().map(|p| self.instantiate(reg, p, s)).collect::<Option<Vec<Id>>>()?;
+                if let Some(k) = token.strip_prefix('#') {
+                    let k: usize = k.parse().ok()?;
+                    let shape = self.classes[ids[0]].shapes.get(k)?.clone();
+                    return Some(self.add(NodeOp::Out(k), key.clone(), ids, vec![shape], false));
+                }
+                let op = promote(reg.make(token)?);
+                let mut st = Vec::with_capacity(ids.len());
+                for &c in &ids {
+                    match &self.classes[c].shapes[..] {
+                        [sh] => st.push(sh.clone()),
+                        _ => return None,
+                    }
+                }
+                op.tc(&mut st, &mut Vec::new()).ok()?;
+                let mat = materializes(&op);
+                Some(self.add(NodeOp::Sys(Some(op)), key.clone(), ids, st, mat))
+            }
+        }
+    }
+
+    /// Apply `rules` until no rule learns anything new, or the budget
+    /// (`MAX_ROUNDS` rounds, `MAX_NODES` nodes) runs out. Each round
+    /// matches every rule against the same e-graph, then applies all the
+    /// matches. Returns the number of merges rules caused.
+    pub fn saturate(&mut self, rules: &[Rewrite]) -> usize {
+        let reg = OpRegistry::standard();
+        let mut total = 0usize;
+        for _ in 0..MAX_ROUNDS {
+            let mut matches: Vec<(&Rewrite, Id, Subst)> = Vec::new();
+            for rule in rules {
+                for c in self.class_ids() {
+                    for s in self.ematch(&rule.lhs, c, Vec::new()) {
+                        if rule.conds.iter().all(|&cond| self.holds(cond, &s)) {
+                            matches.push((rule, c, s));
+                        }
+                    }
+                }
+            }
+            let mut hits = 0usize;
+            for (rule, c, s) in matches {
+                let Some(new) = self.instantiate(&reg, &rule.rhs, &s) else { continue };
+                let c = self.find(c);
+                if self.classes[c].shapes != self.classes[new].shapes { continue; }
+                if self.union(c, new) { hits += 1; }
+            }
+            self.rebuild();
+            total += hits;
+            if hits == 0 || self.nodes.len() > MAX_NODES { break; }
+        }
+        total
+    }
+
+    /// Cost of node `n` alone: an op weight times the number of leaf
+    /// columns it writes. Sources and projections are free.
+    fn node_cost(&self, n: usize) -> u64 {
+        let op = match &self.nodes[n].op {
+            NodeOp::Sys(Some(op)) => op,
+            _ => return 0,
+        };
+        let weight = match op {
+            SystemOp::Const(_) | SystemOp::Input { .. } => return 0,
+            SystemOp::Sort | SystemOp::SortPerm | SystemOp::SortSegmented
+            | SystemOp::Group | SystemOp::Unique => 8,
+            SystemOp::Foreign(_) => 4,
+            SystemOp::Gather | SystemOp::Spread => 2,
+            _ => 1,
+        };
+        let class = self.find(self.nodes[n].class);
+        weight * self.classes[class].shapes.iter().map(leaves).sum::<u64>().max(1)
+    }
+
+    /// Per class, the node of least tree cost (ties to the newer node).
+    fn choose(&self) -> Vec<Option<usize>> {
+        let mut cost: Vec<Option<u64>> = vec![None; self.classes.len()];
+        let mut best: Vec<Option<usize>> = vec![None; self.classes.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for n in 0..self.nodes.len() {
+                let kids: Option<u64> = self.nodes[n].children.iter()
+                    .map(|&c| cost[self.find(c)]).sum();
+                let Some(kids) = kids else { continue };
+                let c = self.node_cost(n).saturating_add(kids);
+                let class = self.find(self.nodes[n].class);
+                let better = match (cost[class], best[class]) {
+                    (Some(cur), Some(b)) => c < cur || (c == cur && n > b),
+                    _ => true,
+                };
+                if better {
+                    cost[class] = Some(c);
+                    best[class] = Some(n);
+                    changed = true;
+                }
+            }
+        }
+        best
+    }
+
+    /// Extract the cheapest graph. Kept: what the roots and the side
+    /// effects need. Emitted in source-term order where terms survive;
+    /// nodes rules introduced go just before their first consumer.
+    pub fn extract(mut self) -> Graph {
+        let best = self.choose();
+        let chosen = |eg: &EGraph, c: Id| best[eg.find(c)].expect("every reachable class has a finite cost");
+
+        let mut needed = vec![false; self.classes.len()];
+        let mut stack: Vec<Id> = self.roots.iter().chain(&self.effects).map(|&c| self.find(c)).collect();
+        while let Some(c) = stack.pop() {
+            if std::mem::replace(&mut needed[c], true) { continue; }
+            stack.extend(self.nodes[chosen(&self, c)].children.iter().map(|&ch| self.find(ch)));
+        }
+
+        // Per emitted class: the output it stands for (a tuple class's
+        // ref names its term; `idx` is meaningless there).
+        let mut done: Vec<Option<OutRef>> = vec![None; self.classes.len()];
+        let mut terms: Vec<Term> = Vec::new();
+        // Source terms first, then roots (a root that is one output of a
+        // multi-output term has no term of its own).
+        let order: Vec<Id> = self.anchors.iter().chain(&self.roots).map(|&c| self.find(c)).collect();
+        for start in order {
+            if !needed[start] || done[start].is_some() { continue; }
+            let mut stack: Vec<(Id, bool)> = vec![(start, false)];
+            while let Some((c, ready)) = stack.pop() {
+                if done[c].is_some() { continue; }
+                let n = chosen(&self, c);
+                let children: Vec<Id> = self.nodes[n].children.iter().map(|&ch| self.find(ch)).collect();
+                if !ready {
+                    stack.push((c, true));
+                    stack.extend(children.iter().filter(|&&ch| done[ch].is_none()).map(|&ch| (ch, false)));
+                    continue;
+                }
+                let refs: Vec<OutRef> = children.iter().map(|&ch| done[ch].expect("child emitted first")).collect();
+                done[c] = Some(match &mut self.nodes[n].op {
+                    NodeOp::Out(k) => OutRef { term: refs[0].term, idx: *k },
+                    NodeOp::Sys(op) => {
+                        let op = op.take().expect("each node is emitted once");
+                        let n_outputs = self.classes[c].shapes.len();
+                        terms.push(Term { op, children: refs, n_outputs });
+                        OutRef { term: terms.len() - 1, idx: 0 }
+                    }
+                });
+            }
+        }
+        let roots = self.roots.iter().map(|&c| done[self.find(c)].expect("roots are needed")).collect();
+        Graph { terms, roots }
+    }
+}
+
+/// Leaf columns in a shape (a `List`'s bounds and a `Sum`'s tags count).
+fn leaves(s: &Shape) -> u64 {
+    match s {
+        Shape::Prim(_) => 1,
+        Shape::Prod(fs) => fs.iter().map(leaves).sum(),
+        Shape::Sum { lanes, .. } => 1 + lanes.iter().map(leaves).sum::<u64>(),
+        Shape::List { inner, .. } => 1 + leaves(inner),
+    }
+}
+
+/// Saturate `g` under `rules` and extract the cheapest equivalent graph.
+/// A graph that doesn't typecheck comes back unchanged. Returns the new
+/// graph and the number of merges rules caused.
+pub fn saturate(g: Graph, rules: &[Rewrite]) -> (Graph, usize) {
+    let mut eg = match EGraph::from_graph(g) {
+        Ok(eg) => eg,
+        Err(g) => return (g, 0),
+    };
+    let hits = eg.saturate(rules);
+    (eg.extract(), hits)
+}
+
+/// `optimize::optimize` with equality saturation in place of the greedy
+/// `rewrite_fixpoint` (`collie run --egraph`).
+pub fn optimize_saturated(g: Graph) -> Graph {
+    let (g, _hits) = crate::pipeline::optimize::fold_constants(saturate(elide_routing(g), &rules()).0);
+    let (g, _hits) = cse(g);
+    eliminate_dead(g)
+}
+
+/// `optimize_saturated` without constant folding (see
+/// `optimize::optimize_unfolded`).
+pub fn optimize_saturated_unfolded(g: Graph) -> Graph {
+    let (g, _hits) = cse(saturate(elide_routing(g), &rules()).0);
+    eliminate_dead(g)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{build, eval_graph};
+    use crate::syntax::parse::parse;
+
+    fn graph(src: &str) -> Graph {
+        let reg = OpRegistry::standard();
+        elide_routing(build(parse(src, &reg).unwrap()).unwrap().0)
+    }
+
+    #[test]
+    fn multi_output_terms_round_trip_through_projections() {
+        let src = "u64[1 2 3 5 7] u64[2 3 4 5 6] intersect swap";
+        let want = eval_graph(&graph(src)).unwrap();
+        let (g, hits) = saturate(graph(src), &rules());
+        assert_eq!(hits, 0);
+        assert_eq!(eval_graph(&g).unwrap(), want);
+    }
+
+    #[test]
+    fn side_effects_are_kept_apart() {
+        let (g, _) = saturate(graph("time u64[1] reduce.+.u64 time"), &rules());
+        assert_eq!(g.terms.iter().filter(|t| t.op.name() == "time").count(), 2);
+    }
+
+    #[test]
+    fn extraction_picks_the_cheaper_form() {
+        // Both cancellations fire; the extracted graph is just the sum.
+        let src = "u64[3 1 2] 1u64 +.u64 reverse reverse sort sort";
+        let (g, hits) = saturate(graph(src), &rules());
+        assert!(hits >= 2, "{} merges", hits);
+        let sorts = g.terms.iter().filter(|t| matches!(t.op, SystemOp::Sort)).count();
+        assert_eq!(sorts, 1);
+        assert!(!g.terms.iter().any(|t| matches!(t.op, SystemOp::Reverse)));
+        assert_eq!(eval_graph(&g).unwrap(), eval_graph(&graph(src)).unwrap());
+    }
+}
@@ -4,8 +4,15 @@
 //! built from its children's outputs, and gathers the roots. Uses
 //! take-on-last-use (the final reader of an output moves it; earlier
 //! readers clone), preserving the Arc-1 reuse the legacy stack eval gets.
+//! `eval_graph_profiled` is the same walk, timing and measuring each term;
+//! `eval_graph_memo` the same walk with some terms' outputs supplied from
+//! elsewhere (the run cache, `tools::cache`).
+
+use std::time::{Duration, Instant};
 
 use crate::pipeline::graph::Graph;
+use crate::pipeline::profile::{GraphProfile, TermProfile};
+use crate::ir::shape::shape_of;
 use crate::ir::stack::Stack;
 use crate::ir::value::Value;
 
@@ -32,10 +39,76 @@ pub fn use_counts(g: &Graph) -> Vec<Vec<usize>> {
 /// lowering, and no body-bearing op survives), so a throwaway env is passed
 /// to satisfy the `PrimOp::run` signature and never populated.
 pub fn eval_graph(g: &Graph) -> Result<Vec<Value>, String> {
-    let mut env: Vec<Value> = Vec::new();
+    eval_graph_inner(g, Vec::new(), None, None)
+}
+
+/// `eval_graph` for a graph with `SystemOp::Input` sources: the env is
+/// `inputs`, and input `slot` reads `inputs[slot]`. The one case where the
+/// env is populated — a compiled plan run over fresh tables.
+pub fn eval_graph_inputs(g: &Graph, inputs: Vec<Value>) -> Result<Vec<Value>, String> {
+    eval_graph_inner(g, inputs, None, None)
+}
+
+/// `eval_graph`, recording a [`TermProfile`] for every term (see
+/// `pipeline::profile`). Inputs' and outputs' row counts and the output
+/// shapes are read outside the timed region, so they don't inflate it.
+pub fn eval_graph_profiled(g: &Graph) -> Result<(Vec<Value>, GraphProfile), String> {
+    let started = Instant::now();
+    let mut prof = GraphProfile { terms: Vec::with_capacity(g.terms.len()), total: Duration::ZERO };
+    let result = eval_graph_inner(g, Vec::new(), Some((&mut prof.terms, started)), None)?;
+    prof.total = started.elapsed();
+    Ok((result, prof))
+}
+
+/// What [`eval_graph_memo`] does with one term.
+#[derive(Debug)]
+pub enum Memo {
+    /// Run the op, as `eval_graph` would.
+    Run,
+    /// Don't run it: nothing that runs reads its outputs.
+    Skip,
+    /// Don't run it: these are its outputs.
+    Reuse(Vec<Value>),
+}
+
+/// `eval_graph` with `memo[i]` deciding term `i`'s fate. `ran` sees each
+/// term that did run, with its outputs, before any consumer takes them.
+/// The caller guarantees every output a `Run` term or a root reads comes
+/// from a term that isn't `Skip`.
+pub fn eval_graph_memo(g: &Graph, memo: Vec<Memo>, ran: &mut RanHook) -> Result<Vec<Value>, String> {
+    eval_graph_inner(g, Vec::new(), None, Some((memo, ran)))
+}
+
+/// Called by [`eval_graph_memo`] with each term that ran and its outputs.
+pub type RanHook<'a> = dyn FnMut(usize, &[Value]) -> Result<(), String> + 'a;
+
+type MemoHook<'a, 'b> = (Vec<Memo>, &'a mut RanHook<'b>);
+
+fn eval_graph_inner(
+    g: &Graph,
+    mut env: Vec<Value>,
+    mut prof: Option<(&mut Vec<TermProfile>, Instant)>,
+    memo: Option<MemoHook>,
+) -> Result<Vec<Value>, String> {
+    let (mut memo, mut ran) = match memo {
+        Some((m, ran)) => (m.into_iter().map(Some).collect(), Some(ran)),
+        None => (Vec::new(), None),
+    };
     let mut counts = use_counts(g);
+    if !memo.is_empty() {
+        // Only terms that run read their children.
+        for (term, m) in g.terms.iter().zip(&memo) {
+            if matches!(m, Some(Memo::Run)) { continue; }
+            for ch in &term.children { counts[ch.term][ch.idx] -= 1; }
+        }
+    }
     let mut outs: Vec<Vec<Value>> = Vec::with_capacity(g.terms.len());
-    for term in &g.terms {
+    for (i, term) in g.terms.iter().enumerate() {
+        match memo.get_mut(i).and_then(Option::take) {
+            Some(Memo::Skip) => { outs.push(Vec::new()); continue; }
+            Some(Memo::Reuse(vs)) => { outs.push(vs); continue; }
+            Some(Memo::Run) | None => {}
+        }
         let mut sub: Stack = Vec::with_capacity(term.children.len());
         for ch in &term.children {
             let remaining = &mut counts[ch.term][ch.idx];
@@ -47,13 +120,42 @@ pub fn eval_graph(g: &Graph) -> Result<Vec<Value>, String> {
                 sub.push(slot.clone());
             }
         }
-        term.op.run(&mut sub, &mut env)?;
+        match prof.as_mut() {
+            None if crate::ir::profile::is_enabled() => {
+                // `profile.start` … `profile.print` in the program: bill
+                // per op name, as the old interpreter did.
+                crate::ir::profile::time_op(&term.op.name(), || term.op.run(&mut sub, &mut env))?;
+            }
+            None => term.op.run(&mut sub, &mut env)?,
+            Some((terms, started)) => {
+                let in_rows: Vec<usize> = sub.iter().map(|v| v.len()).collect();
+                let bytes_before = crate::ir::profile::allocated_bytes();
+                let t0 = Instant::now();
+                term.op.run(&mut sub, &mut env)?;
+                let elapsed = t0.elapsed();
+                let bytes = match (bytes_before, crate::ir::profile::allocated_bytes()) {
+                    (Some(a), Some(b)) => Some(b - a),
+                    _ => None,
+                };
+                terms.push(TermProfile {
+                    term: i,
+                    op: term.op.name(),
+                    start: t0.duration_since(*started),
+                    elapsed,
+                    in_rows,
+                    out_rows: sub.iter().map(|v| v.len()).collect(),
+                    bytes,
+                    shapes: sub.iter().map(shape_of).collect(),
+                });
+            }
+        }
         if sub.len() != term.n_outputs {
             return Err(format!(
                 "graph eval: op {} produced {} outputs, declared {}",
                 term.op.name(), sub.len(), term.n_outputs
             ));
         }
+        if let Some(ran) = ran.as_mut() { ran(i, &sub)?; }
         outs.push(sub);
     }
     let mut result: Vec<Value> = Vec::with_capacity(g.roots.len());
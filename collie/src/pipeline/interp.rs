@@ -0,0 +1,463 @@
+//! Interpretation inference — an optional analysis over a freshly lowered
+//! graph.
+//!
+//! A `Shape` is width-only and a `Value` carries bytes; what those bytes
+//! mean lives on op tokens (`+.u32`, `reduce.min.f64`). Nothing in the
+//! core stops `+.f64` from reading a column `+.u32` produced, and an
+//! ordered op without an interp (`sort`, `<`, `reduce.min`) reads words
+//! unsigned, so a signed or float column has to be bracketed by hand with
+//! `enswizzle.<i>` / `deswizzle.<i>`.
+//!
+//! `infer` walks the graph once in topological order and gives every
+//! output a [`Kind`]: the interp of each Prim leaf, from the ops and
+//! literals that produce it (and, for the program's inputs, from the
+//! declared types). An op that reads a leaf under a different interp is
+//! a conflict, reported at the op's span with the producer as a note.
+//! A literal is lenient about sign only: `3 +.u64` reads the bare `i64`
+//! literal's bits as `u64`, which is what was meant.
+//!
+//! `elaborate` then uses the kinds to resolve the interp-less ops:
+//!
+//! - `<`, `=`, … over a signed or float Prim: both operands enswizzled;
+//! - `sort` / `unique` over a signed or float Prim, or a tuple with such a
+//!   field: enswizzle, the op, deswizzle (field-wise for a tuple, via
+//!   `detuple`/`zip`); `sort.perm` needs only the first half;
+//! - bare `reduce.min` / `reduce.max` → the typed `Reduce`.
+//!
+//! Lists, Sums and leaves of unknown kind are left as written. Neither
+//! pass changes a `Shape` or a `Value`: the rewrite only adds ops the
+//! user could have written, so the elaborated graph typechecks exactly
+//! like the original. Run both before `optimize` (the per-term `origins`
+//! are stale after any reindexing pass); `collie --infer` does.
+
+use std::fmt;
+
+use crate::ir::shape::{Interp, Shape};
+use crate::ir::span::Diagnostic;
+use crate::pipeline::graph::{Graph, OutRef, Term};
+use crate::pipeline::lower::{Built, Origin};
+use crate::pipeline::sysop::{ReduceKind, SystemOp};
+
+/// What a value's Prim leaves mean — `Shape`'s structure with an interp
+/// (or `Any`, not known) at each leaf.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    Any,
+    Prim(Interp),
+    Prod(Vec<Kind>),
+    List(Box<Kind>),
+    Sum(Vec<Kind>),
+}
+
+impl Kind {
+    /// The interp an element-wise op reads: a Prim's, or a List's leaf.
+    pub fn elem(&self) -> Option<Interp> {
+        match self {
+            Kind::Prim(i) => Some(*i),
+            Kind::List(k) => k.elem(),
+            _ => None,
+        }
+    }
+
+    /// `interp` at the Prim leaf of `shape`, through Lists. `Any` where
+    /// the shape has no single leaf.
+    fn at_leaf(shape: &Shape, interp: Interp) -> Kind {
+        match shape {
+            Shape::Prim(_) => Kind::Prim(interp),
+            Shape::List { inner, .. } => Kind::List(Box::new(Kind::at_leaf(inner, interp))),
+            _ => Kind::Any,
+        }
+    }
+
+    /// Whether the kind has `shape`'s structure and widths.
+    fn fits(&self, shape: &Shape) -> bool {
+        match (self, shape) {
+            (Kind::Any, _) => true,
+            (Kind::Prim(i), Shape::Prim(w)) => i.width() == *w,
+            (Kind::List(k), Shape::List { inner, .. }) => k.fits(inner),
+            (Kind::Prod(ks), Shape::Prod(fs)) | (Kind::Sum(ks), Shape::Sum { lanes: fs, .. }) =>
+                ks.len() == fs.len() && ks.iter().zip(fs).all(|(k, f)| k.fits(f)),
+            _ => false,
+        }
+    }
+
+    /// Whether ordering the value by unsigned word differs from ordering
+    /// it by this kind — i.e. it has a signed or float leaf a swizzle can
+    /// reach (a Prim, or a field of a tuple of them).
+    fn needs_swizzle(&self, shape: &Shape) -> bool {
+        match (self, shape) {
+            (Kind::Prim(i), Shape::Prim(_)) => !i.is_unsigned(),
+            (Kind::Prod(ks), Shape::Prod(fs)) => ks.iter().zip(fs).any(|(k, f)| k.needs_swizzle(f)),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |ks: &[Kind], sep: &str| ks.iter().map(|k| k.to_string()).collect::<Vec<_>>().join(sep);
+        match self {
+            Kind::Any => write!(f, "?"),
+            Kind::Prim(i) => write!(f, "{}", i),
+            Kind::Prod(ks) => write!(f, "({})", join(ks, ", ")),
+            Kind::List(k) => write!(f, "[{}]", k),
+            Kind::Sum(ks) => write!(f, "<{}>", join(ks, " | ")),
+        }
+    }
+}
+
+/// Per-term output kinds, plus the interp each `Cmp` term's operands
+/// agreed on (`elaborate` swizzles both when it isn't unsigned).
+#[derive(Debug)]
+pub struct Inference {
+    pub kinds: Vec<Vec<Kind>>,
+    cmp: Vec<Option<Interp>>,
+}
+
+impl Inference {
+    pub fn of(&self, r: OutRef) -> &Kind { &self.kinds[r.term][r.idx] }
+}
+
+/// The op as written, interp suffix included.
+fn token(op: &SystemOp) -> String {
+    match op {
+        SystemOp::Arith { interp, .. } | SystemOp::UnaryArith { interp, .. } | SystemOp::As { interp }
+        | SystemOp::Reduce { interp, .. } | SystemOp::Cumsum { interp } | SystemOp::Shift { interp }
+        | SystemOp::Enswizzle { interp } | SystemOp::Deswizzle { interp }
+        | SystemOp::AsofJoin { interp } | SystemOp::IntervalJoin { interp } => format!("{}.{}", op.name(), interp),
+        SystemOp::Const(_) => "literal".to_string(),
+        other => other.name(),
+    }
+}
+
+/// Ops whose first output is their first input's values, reordered or
+/// subset: a leaf's producer is found by looking through them.
+fn passes_through(op: &SystemOp) -> bool {
+    matches!(op, SystemOp::Filter | SystemOp::Gather | SystemOp::Take | SystemOp::Skip
+        | SystemOp::Reverse | SystemOp::TakeSegmented | SystemOp::ReverseSegmented
+        | SystemOp::Sort | SystemOp::SortSegmented | SystemOp::Unique
+        | SystemOp::View | SystemOp::ViewRange | SystemOp::Encode { .. } | SystemOp::Decode)
+}
+
+/// Forward analysis: the kind of every term output. `sources` are the
+/// kinds of the graph's leading source terms (the declared inputs, or
+/// seeds), bottom first; a source without one is `Any`.
+pub fn infer(b: &Built, sources: &[Kind]) -> Result<Inference, Diagnostic> {
+    let g = &b.graph;
+    let mut inf = Inference { kinds: Vec::with_capacity(g.terms.len()), cmp: vec![None; g.terms.len()] };
+    for (t, term) in g.terms.iter().enumerate() {
+        let outs = &b.shapes[t];
+        let ks: Vec<Kind> = term.children.iter().map(|c| inf.of(*c).clone()).collect();
+        let child = |i: usize| ks.get(i).cloned().unwrap_or(Kind::Any);
+        let leaf = |i: Interp| vec![Kind::at_leaf(&outs[0], i)];
+        let cx = Cx { b, t };
+        let mut kinds: Vec<Kind> = match &term.op {
+            _ if t < sources.len() && term.children.is_empty() => vec![sources[t].clone()],
+            SystemOp::Const(_) => vec![b.origins[t].literal.map_or(Kind::Any, Kind::Prim)],
+            SystemOp::Arith { interp, .. } => {
+                for (i, k) in ks.iter().enumerate() { cx.expect(i, k, *interp)?; }
+                leaf(*interp)
+            }
+            SystemOp::UnaryArith { interp, .. } | SystemOp::Reduce { interp, .. }
+            | SystemOp::Cumsum { interp } | SystemOp::Shift { interp } => {
+                cx.expect(0, &child(0), *interp)?;
+                leaf(*interp)
+            }
+            SystemOp::Enswizzle { interp } => {
+                cx.expect(0, &child(0), *interp)?;
+                leaf(Interp::unsigned(interp.width()))
+            }
+            SystemOp::AsofJoin { interp } | SystemOp::IntervalJoin { interp } => {
+                cx.expect(0, &child(0), *interp)?;
+                cx.expect(1, &child(1), *interp)?;
+                vec![Kind::Prim(Interp::U64); 2]
+            }
+            SystemOp::As { interp } | SystemOp::Deswizzle { interp } => leaf(*interp),
+            SystemOp::ReduceOrd { .. } => child(0).elem().map_or(vec![Kind::Any], leaf),
+            SystemOp::Cmp { .. } => {
+                inf.cmp[t] = cx.agree(&ks, "compares")?;
+                leaf(Interp::U8)
+            }
+            SystemOp::Cat { .. } => match cx.agree(&ks, "joins")? {
+                Some(i) => leaf(i),
+                None => vec![child(0)],
+            },
+            SystemOp::Not | SystemOp::And | SystemOp::Or | SystemOp::Any | SystemOp::All
+            | SystemOp::MaskCompose => leaf(Interp::U8),
+            SystemOp::Count | SystemOp::Where | SystemOp::SortPerm | SystemOp::Iota | SystemOp::Search
+            | SystemOp::Bounds | SystemOp::BoundsKeys | SystemOp::Intersect =>
+                vec![Kind::Prim(Interp::U64); term.n_outputs],
+            op if passes_through(op) => vec![child(0)],
+            SystemOp::Group => vec![child(1), Kind::List(Box::new(child(0)))],
+            SystemOp::Zip { .. } => vec![Kind::Prod(ks.clone())],
+            SystemOp::Detuple { .. } => match child(0) {
+                Kind::Prod(fs) => fs,
+                _ => Vec::new(),
+            },
+            SystemOp::Proj { i } => match child(0) {
+                Kind::Prod(mut fs) if *i < fs.len() => vec![fs.swap_remove(*i)],
+                _ => Vec::new(),
+            },
+            SystemOp::Pivot { n } => match child(0) {
+                Kind::Prod(fs) if fs.len() == 3 => {
+                    let v = fs[2].clone();
+                    vec![Kind::Prod(std::iter::once(fs[0].clone()).chain(std::iter::repeat_n(v, *n)).collect())]
+                }
+                _ => Vec::new(),
+            },
+            SystemOp::Unpivot => match child(0) {
+                Kind::Prod(fs) if fs.len() >= 2 => vec![Kind::Prod(vec![fs[0].clone(), Kind::Prim(Interp::U64), fs[1].clone()])],
+                _ => Vec::new(),
+            },
+            SystemOp::Inject { .. } => vec![Kind::Sum(ks[1..].to_vec())],
+            SystemOp::Split => match child(0) {
+                Kind::Sum(ls) => std::iter::once(Kind::Prim(Interp::U8)).chain(ls).collect(),
+                _ => Vec::new(),
+            },
+            SystemOp::Nest | SystemOp::NestStride | SystemOp::Enlist => vec![Kind::List(Box::new(child(0)))],
+            SystemOp::Flatten => match child(0) {
+                Kind::List(k) => vec![*k, Kind::Prim(Interp::U64)],
+                _ => Vec::new(),
+            },
+            SystemOp::Unlist | SystemOp::Head | SystemOp::Concat => match child(0) {
+                Kind::List(k) => vec![*k],
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+        // Anything unmodeled, or guessed at a structure the shapes don't
+        // bear out, is `Any`.
+        kinds.resize(term.n_outputs, Kind::Any);
+        for (k, s) in kinds.iter_mut().zip(outs) {
+            if !k.fits(s) { *k = Kind::Any; }
+        }
+        inf.kinds.push(kinds);
+    }
+    Ok(inf)
+}
+
+/// The term being inferred, for its checks and diagnostics.
+struct Cx<'a> {
+    b: &'a Built,
+    t: usize,
+}
+
+impl Cx<'_> {
+    fn term(&self) -> &Term { &self.b.graph.terms[self.t] }
+
+    /// The term that first produced input `i`'s values.
+    fn producer(&self, i: usize) -> usize {
+        let mut r = self.term().children[i];
+        while passes_through(&self.b.graph.terms[r.term].op) && r.idx == 0 {
+            r = self.b.graph.terms[r.term].children[0];
+        }
+        r.term
+    }
+
+    /// A literal of the same width reads as either sign.
+    fn lenient(&self, i: usize, have: Interp, want: Interp) -> bool {
+        self.b.origins[self.producer(i)].literal.is_some()
+            && have.width() == want.width() && !have.is_float() && !want.is_float()
+    }
+
+    /// Input `i` (of kind `k`) is read as `want`.
+    fn expect(&self, i: usize, k: &Kind, want: Interp) -> Result<(), Diagnostic> {
+        match k.elem() {
+            Some(have) if have != want && !self.lenient(i, have, want) => Err(self.conflict(
+                format!("`{}` reads its input as {}, but it holds {}", token(&self.term().op), want, have),
+                &[(i, have)])),
+            _ => Ok(()),
+        }
+    }
+
+    /// The interp all inputs agree on, if any is known. A literal defers
+    /// to a non-literal input of the same width and class.
+    fn agree(&self, ks: &[Kind], verb: &str) -> Result<Option<Interp>, Diagnostic> {
+        let mut seen: Option<(usize, Interp)> = None;
+        for (i, k) in ks.iter().enumerate() {
+            let Some(have) = k.elem() else { continue };
+            match seen {
+                None => seen = Some((i, have)),
+                Some((_, s)) if s == have => {}
+                Some((j, s)) if self.lenient(i, have, s) => seen = Some((j, s)),
+                Some((j, s)) if self.lenient(j, s, have) => seen = Some((i, have)),
+                Some((j, s)) => return Err(self.conflict(
+                    format!("`{}` {} {} with {}", token(&self.term().op), verb, s, have),
+                    &[(j, s), (i, have)])),
+            }
+        }
+        Ok(seen.map(|(_, i)| i))
+    }
+
+    fn conflict(&self, msg: String, inputs: &[(usize, Interp)]) -> Diagnostic {
+        let mut d = match self.b.origins[self.t].span {
+            Some(sp) => Diagnostic::at(sp, msg),
+            None => Diagnostic::new(msg),
+        };
+        for &(i, have) in inputs {
+            let p = self.producer(i);
+            // A declared input by name, whether it's still an `Input`
+            // (`check`) or already bound to a `Const` (`run`).
+            let from = match self.b.origins[p].input {
+                Some(slot) => match self.b.inputs.get(slot) {
+                    Some(name) => format!("input `{}`", name),
+                    None => format!("input {}", slot),
+                },
+                None => format!("`{}`", token(&self.b.graph.terms[p].op)),
+            };
+            d = d.note(match self.b.origins[p].span {
+                Some(sp) => format!("the {} comes from {} at {}", have, from, sp),
+                None => format!("the {} comes from {}", have, from),
+            });
+        }
+        d
+    }
+}
+
+/// Rewrite the interp-less ordered ops over the kinds `inf` found (see
+/// the module docs). Returns the new graph with its shapes and origins;
+/// an inserted op takes the span of the op it serves.
+pub fn elaborate(b: Built, inf: &Inference) -> Built {
+    let Built { graph, shapes, effect, def_effects, inputs, origins } = b;
+    let mut e = Emit { g: Graph::default(), shapes: Vec::new(), origins: Vec::new() };
+    let mut map: Vec<Vec<OutRef>> = Vec::with_capacity(graph.terms.len());
+    for (t, term) in graph.terms.into_iter().enumerate() {
+        let at = origins[t];
+        let old = term.children.clone();
+        let mut children: Vec<OutRef> = old.iter().map(|c| map[c.term][c.idx]).collect();
+        let in_shape = |i: usize| &shapes[old[i].term][old[i].idx];
+        let ordered = |i: usize| inf.of(old[i]).needs_swizzle(in_shape(i));
+        let outs = match term.op {
+            SystemOp::Cmp { op } => {
+                if let Some(l) = inf.cmp[t].filter(|l| !l.is_unsigned()) {
+                    if (0..2).all(|i| matches!(in_shape(i), Shape::Prim(_))) {
+                        for (i, c) in children.iter_mut().enumerate() {
+                            *c = e.push(SystemOp::Enswizzle { interp: l }, vec![*c], vec![in_shape(i).clone()], at)[0];
+                        }
+                    }
+                }
+                e.push(SystemOp::Cmp { op }, children, shapes[t].clone(), at)
+            }
+            op @ (SystemOp::Sort | SystemOp::Unique | SystemOp::SortPerm) if ordered(0) => {
+                let (k, s) = (inf.of(old[0]), in_shape(0));
+                let c = e.swizzle(children[0], k, s, true, at);
+                let back = !matches!(op, SystemOp::SortPerm);
+                let r = e.push(op, vec![c], shapes[t].clone(), at)[0];
+                vec![if back { e.swizzle(r, k, s, false, at) } else { r }]
+            }
+            SystemOp::ReduceOrd { max } => {
+                let op = match inf.of(old[0]).elem() {
+                    Some(interp) => SystemOp::Reduce { kind: if max { ReduceKind::Max } else { ReduceKind::Min }, interp },
+                    None => SystemOp::ReduceOrd { max },
+                };
+                e.push(op, children, shapes[t].clone(), at)
+            }
+            op => e.push(op, children, shapes[t].clone(), at),
+        };
+        map.push(outs);
+    }
+    e.g.roots = graph.roots.iter().map(|r| map[r.term][r.idx]).collect();
+    Built { graph: e.g, shapes: e.shapes, effect, def_effects, inputs, origins: e.origins }
+}
+
+/// The graph `elaborate` is building, with its side tables.
+struct Emit {
+    g: Graph,
+    shapes: Vec<Vec<Shape>>,
+    origins: Vec<Origin>,
+}
+
+impl Emit {
+    fn push(&mut self, op: SystemOp, children: Vec<OutRef>, shapes: Vec<Shape>, at: Origin) -> Vec<OutRef> {
+        let term = self.g.terms.len();
+        let n_outputs = shapes.len();
+        self.g.terms.push(Term { op, children, n_outputs });
+        self.shapes.push(shapes);
+        self.origins.push(Origin { literal: None, ..at });
+        (0..n_outputs).map(|idx| OutRef { term, idx }).collect()
+    }
+
+    /// En- (or de-) swizzle every signed or float leaf `needs_swizzle`
+    /// reaches; a tuple is taken apart and rebuilt around its fields.
+    fn swizzle(&mut self, r: OutRef, k: &Kind, s: &Shape, encode: bool, at: Origin) -> OutRef {
+        if !k.needs_swizzle(s) { return r; }
+        match (k, s) {
+            (Kind::Prim(interp), _) => {
+                let op = if encode { SystemOp::Enswizzle { interp: *interp } } else { SystemOp::Deswizzle { interp: *interp } };
+                self.push(op, vec![r], vec![s.clone()], at)[0]
+            }
+            (Kind::Prod(ks), Shape::Prod(fs)) => {
+                let n = fs.len();
+                let parts = self.push(SystemOp::Detuple { n }, vec![r], fs.clone(), at);
+                let parts = parts.iter().zip(ks.iter().zip(fs))
+                    .map(|(p, (k, f))| self.swizzle(*p, k, f, encode, at)).collect();
+                self.push(SystemOp::Zip { n }, parts, vec![s.clone()], at)[0]
+            }
+            _ => r,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::value::{from_vec, Value};
+    use crate::pipeline::{build_parsed, build_parsed_inputs, build_parsed_seeded, eval_graph};
+    use crate::syntax::parse::{parse_program, parse_program_bound};
+    use crate::syntax::registry::OpRegistry;
+
+    fn built(src: &str) -> Built {
+        build_parsed(parse_program(src, &OpRegistry::standard()).unwrap()).unwrap()
+    }
+
+    /// Infer, elaborate and run `src`.
+    fn run(src: &str) -> Vec<Value> {
+        let b = built(src);
+        let inf = infer(&b, &[]).unwrap();
+        let out = eval_graph(&elaborate(b, &inf).graph).unwrap();
+        out.into_iter().map(|v| crate::ir::stack::materialize_top(v).unwrap()).collect()
+    }
+
+    #[test]
+    fn conflicting_reads_point_at_the_op_and_the_producer() {
+        let src = "f32[1.5 2] f32[1 1] +.f32\n  f32[3 4] +.u32";
+        let d = infer(&built(src), &[]).unwrap_err();
+        assert_eq!(d.message, "`+.u32` reads its input as u32, but it holds f32");
+        let sp = d.span.unwrap();
+        assert_eq!((sp.line, sp.col), (2, 12));
+        assert_eq!(d.notes, vec!["the f32 comes from `+.f32` at 1:21"]);
+        // Through a filter, and across a comparison.
+        let d = infer(&built("i32[1 2] :x x x 0i32 > filter f32[1 2] <"), &[]).unwrap_err();
+        assert_eq!(d.message, "`<` compares i32 with f32");
+        assert_eq!(d.notes.len(), 2);
+        // Literals are lenient about sign, and inputs seed the analysis.
+        let inf = infer(&built("u64[1 2] 3 +.u64"), &[]).unwrap();
+        assert_eq!(inf.kinds.last().unwrap(), &vec![Kind::Prim(Interp::U64)]);
+        let p = parse_program_bound("xs 1 +.u64", &OpRegistry::standard(), &["xs".to_string()]).unwrap();
+        let b = build_parsed_inputs(p, &[Shape::Prim(crate::ir::value::PrimWidth::W64)]).unwrap();
+        let d = infer(&b, &[Kind::Prim(Interp::I64)]).unwrap_err();
+        assert_eq!(d.message, "`+.u64` reads its input as u64, but it holds i64");
+        assert_eq!(d.notes, vec!["the i64 comes from input `xs`"]);
+        // The same once the input is bound to a value, as under `run`.
+        let p = parse_program_bound("xs 1 +.u64", &OpRegistry::standard(), &["xs".to_string()]).unwrap();
+        let b = build_parsed_seeded(p, vec![from_vec::<i64>(vec![-1])]).unwrap();
+        let d = infer(&b, &[Kind::Prim(Interp::I64)]).unwrap_err();
+        assert_eq!(d.notes, vec!["the i64 comes from input `xs`"]);
+    }
+
+    #[test]
+    fn interp_less_ordered_ops_resolve_through_inference() {
+        assert_eq!(run("i32[3 -1 2] i32[0 0 0] +.i32 sort"), vec![from_vec::<i32>(vec![-1, 2, 3])]);
+        assert_eq!(run("f64[2.5 -1 0] 1.0f64 *.f64 sort.perm"), vec![from_vec::<u64>(vec![1, 2, 0])]);
+        assert_eq!(run("i32[-4 5 -4] neg.i32 unique"), vec![from_vec::<i32>(vec![-5, 4])]);
+        assert_eq!(run("i64[-2 7 1] :x x x 0 < filter"), vec![from_vec::<i64>(vec![-2])]);
+        assert_eq!(run("f64[-2 7 1] neg.f64 reduce.max"), vec![from_vec::<f64>(vec![2.0])]);
+        assert_eq!(run("i32[-2 7 1] neg.i32 reduce.min"), vec![from_vec::<i32>(vec![-7])]);
+        // Without inference the bare ops read unsigned words.
+        let b = built("i32[-2 7 1] neg.i32 reduce.min");
+        assert_eq!(eval_graph(&b.graph).unwrap(), vec![from_vec::<i32>(vec![2])]);
+        // A tuple sorts field-wise: i32 first, then u64.
+        let tup = run("i32[1 -3 1] neg.i32 u64[9 8 7] entuple.2 sort detuple.2");
+        assert_eq!(tup, vec![from_vec::<i32>(vec![-1, -1, 3]), from_vec::<u64>(vec![7, 9, 8])]);
+    }
+}
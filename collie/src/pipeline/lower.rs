@@ -23,29 +23,38 @@
 //! legacy-eval blob. Boiling is what turns the concatenative front end's
 //! scoped binding into a dataflow graph at all; it is the concatenative
 //! lowering, not a removable `Graph → Graph` pass. See dev/LAYERING.md.
+//!
+//! `build_parsed` lowers a `parse::Parsed` program: errors come back as a
+//! `Diagnostic` at the site of the op that failed, and the net stack
+//! effect of the program and of every `def` expansion is recorded along
+//! the way (what `collie check` prints).
+//!
+//! **Quotations** (`[ … ]`, see `ops::quote`) are compile-time values: a
+//! `Slot::Quote` on the build stack with a stand-in shape on the type
+//! stack. Routing and binding move them like any slot; `apply`,
+//! `match.K` and `under.<i>` lower a fresh copy of the body where they
+//! stand (against the bindings captured where the quotation was written).
+//! Any other op that reaches one is an error, as is a quotation left on
+//! the stack at the end — no closure survives into the graph.
 
 use crate::pipeline::graph::{Graph, Term, OutRef};
 use crate::pipeline::sysop::{promote, SystemOp};
 use crate::ir::typecheck::{Op, TypeStack, TypeEnv};
-use crate::ir::shape::{Shape, shape_of};
+use crate::ir::shape::{Interp, Shape, shape_of};
+use crate::ir::span::{Diagnostic, Site, SourceMap, Span, StackEffect};
 use crate::ir::value::Value;
+use crate::ops::combinators::{DetupleN, MergeN, Split, ZipN};
+use crate::ops::convert::{LitArr, LitNum};
 use crate::ops::letbind::{Let, Ref};
+use crate::ops::quote::{Consume, Quote, Use};
+use crate::syntax::parse::Parsed;
+use crate::syntax::registry::parse_interp;
 
 /// Lower a parsed op stream into a term graph. Returns the graph and
 /// per-term output shapes (side-table; valid for the freshly-built graph,
 /// stale after any reindexing pass — recompute if a later stage needs them).
 pub fn build(prog: Vec<Box<dyn Op>>) -> Result<(Graph, Vec<Vec<Shape>>), String> {
-    let mut g = Graph::default();
-    let mut bstack: Vec<OutRef> = Vec::new();
-    let mut tstack: TypeStack = Vec::new();
-    let mut tenv: TypeEnv = Vec::new();
-    let mut shapes: Vec<Vec<Shape>> = Vec::new();
-    // Build-time env of bound producers, mirroring the runtime `Let` env.
-    // Empty at top level — `Ref`s only ever appear inside a `Let` body.
-    let mut env: Vec<OutRef> = Vec::new();
-    build_in(prog, &mut g, &mut bstack, &mut tstack, &mut tenv, &mut shapes, &mut env)?;
-    g.roots = bstack;
-    Ok((g, shapes))
+    build_seeded(prog, Vec::new())
 }
 
 /// Lower an *open* program — one that expects `seeds` already on the stack —
@@ -54,55 +63,272 @@ pub fn build(prog: Vec<Box<dyn Op>>) -> Result<(Graph, Vec<Vec<Shape>>), String>
 /// eval). Used where the op-stream interpreter's "push inputs, then run"
 /// shape is wanted on the graph engine (e.g. the bench harness).
 pub fn build_seeded(prog: Vec<Box<dyn Op>>, seeds: Vec<Value>) -> Result<(Graph, Vec<Vec<Shape>>), String> {
-    let mut g = Graph::default();
-    let mut bstack: Vec<OutRef> = Vec::new();
-    let mut tstack: TypeStack = Vec::new();
-    let mut tenv: TypeEnv = Vec::new();
-    let mut shapes: Vec<Vec<Shape>> = Vec::new();
-    let mut env: Vec<OutRef> = Vec::new();
-    for v in seeds {
-        let sh = shape_of(&v);
-        let id = g.terms.len();
-        g.terms.push(Term { op: SystemOp::Const(v), children: vec![], n_outputs: 1 });
-        shapes.push(vec![sh.clone()]);
-        bstack.push(OutRef { term: id, idx: 0 });
-        tstack.push(sh);
-    }
-    build_in(prog, &mut g, &mut bstack, &mut tstack, &mut tenv, &mut shapes, &mut env)?;
-    g.roots = bstack;
-    Ok((g, shapes))
-}
-
-fn build_in(
-    prog: Vec<Box<dyn Op>>,
-    g: &mut Graph,
-    bstack: &mut Vec<OutRef>,
-    tstack: &mut TypeStack,
-    tenv: &mut TypeEnv,
-    shapes: &mut Vec<Vec<Shape>>,
-    env: &mut Vec<OutRef>,
-) -> Result<(), String> {
-    for op in prog {
+    let map = SourceMap::default();
+    let b = lower(prog, &[], &map, consts(seeds)).map_err(|d| d.to_string())?;
+    Ok((b.graph, b.shapes))
+}
+
+/// What `build_parsed` produces: the graph and its shapes, plus the stack
+/// effects seen while lowering.
+pub struct Built {
+    pub graph: Graph,
+    pub shapes: Vec<Vec<Shape>>,
+    /// Net effect of the whole program.
+    pub effect: StackEffect,
+    /// Per def (indexed like `SourceMap::defs`): the distinct effects of
+    /// its expansions, in first-seen order. Empty for a def never used.
+    pub def_effects: Vec<Vec<StackEffect>>,
+    /// Names of the inputs the source terms stand for, by slot
+    /// (`SourceMap::inputs`); empty when the program names none.
+    pub inputs: Vec<String>,
+    /// Per term: where it came from. Indexed like `shapes`, and stale the
+    /// same way after a reindexing pass.
+    pub origins: Vec<Origin>,
+}
+
+/// Provenance of one term: the span of the op it was lowered from (none
+/// for source terms and unsited programs), the interp a literal was
+/// written with — both lost once the literal is promoted to a `Const` —
+/// and, for a source term, the input slot it stands for, whether it is
+/// an `Input` or a bound `Const`. Read by `interp::infer`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Origin {
+    pub span: Option<Span>,
+    pub literal: Option<Interp>,
+    pub input: Option<usize>,
+}
+
+/// Lower a parsed program, keeping its sites: a failing op's error is a
+/// `Diagnostic` at its span (with notes for the def expansions it came
+/// from). Render it against the source to show the excerpt.
+pub fn build_parsed(p: Parsed) -> Result<Built, Diagnostic> {
+    build_parsed_seeded(p, Vec::new())
+}
+
+/// `build_parsed` for an open program: `build_seeded`'s `Const` source per
+/// seed, with the parsed program's sites kept for errors.
+pub fn build_parsed_seeded(p: Parsed, seeds: Vec<Value>) -> Result<Built, Diagnostic> {
+    lower(p.ops, &p.sites, &p.map, consts(seeds))
+}
+
+/// Lower a program over run-time inputs of the given shapes: one
+/// `SystemOp::Input` source per shape, typechecked as that shape. The
+/// graph runs under `execute::eval_graph_inputs` (see `crate::session`).
+pub fn build_parsed_inputs(p: Parsed, inputs: &[Shape]) -> Result<Built, Diagnostic> {
+    let sources = inputs.iter().enumerate()
+        .map(|(slot, sh)| (SystemOp::Input { slot, shape: sh.clone() }, sh.clone()))
+        .collect();
+    lower(p.ops, &p.sites, &p.map, sources)
+}
+
+/// Seed values as `Const` sources.
+fn consts(seeds: Vec<Value>) -> Vec<(SystemOp, Shape)> {
+    seeds.into_iter().map(|v| { let sh = shape_of(&v); (SystemOp::Const(v), sh) }).collect()
+}
+
+/// Stack-effect bookkeeping for def expansions. An expansion opens at the
+/// first op tagged with it and closes at the first op (or end) that isn't;
+/// its inputs are the entry shapes from the lowest height any of its ops
+/// consumed down to, its outputs whatever sits above that height at close.
+#[derive(Default)]
+struct Effects {
+    open: Vec<Open>,
+    /// (expansion id, effect), in close order.
+    done: Vec<(usize, StackEffect)>,
+}
+
+struct Open {
+    expansion: usize,
+    entry: Vec<Shape>,
+    low: usize,
+}
+
+impl Effects {
+    /// Move to the expansion stack `within` before an op that consumes
+    /// `n_in` of the `tstack` it sees.
+    fn step(&mut self, within: &[usize], tstack: &TypeStack, n_in: usize) {
+        let keep = self.open.iter().zip(within).take_while(|(o, &e)| o.expansion == e).count();
+        self.close_to(keep, tstack);
+        for &e in &within[keep..] {
+            self.open.push(Open { expansion: e, entry: tstack.clone(), low: tstack.len() });
+        }
+        let floor = tstack.len().saturating_sub(n_in);
+        for o in &mut self.open { o.low = o.low.min(floor); }
+    }
+
+    fn close_to(&mut self, keep: usize, tstack: &TypeStack) {
+        while self.open.len() > keep {
+            let o = self.open.pop().expect("nonempty");
+            let low = o.low.min(tstack.len());
+            let effect = StackEffect {
+                inputs: o.entry[low.min(o.entry.len())..].to_vec(),
+                outputs: tstack[low..].to_vec(),
+            };
+            self.done.push((o.expansion, effect));
+        }
+    }
+}
+
+/// A build-stack entry: a term output, or a quotation (index into
+/// `Lowering::quotes`), which never becomes a term.
+#[derive(Clone, Copy, Debug)]
+enum Slot {
+    Out(OutRef),
+    Quote(usize),
+}
+
+/// Type-stack stand-in for a quotation slot (no value has a zero-field
+/// Prod). Ops never typecheck against it: `emit_term` stops any op whose
+/// inputs include a quotation before its `tc` runs.
+const QUOTE_SHAPE: Shape = Shape::Prod(Vec::new());
+
+/// Splices deeper than this are taken to be a quotation that applies
+/// itself through fresh copies.
+const MAX_SPLICE_DEPTH: usize = 256;
+
+/// A quotation pushed during lowering: its body (copied per use) and the
+/// bindings in scope where it was written. A body op that can't be copied
+/// (`Typed::clone_op` is `None`) moves out on the first use, leaving
+/// `Err(its name)` behind.
+struct Quoted {
+    body: Vec<Result<Box<dyn Op>, String>>,
+    sites: Vec<Site>,
+    env: Vec<Slot>,
+    tenv: TypeEnv,
+    span: Option<Span>,
+}
+
+/// Lowering state shared across `Let` bodies and quotation splices.
+struct Lowering<'a> {
+    g: Graph,
+    bstack: Vec<Slot>,
+    tstack: TypeStack,
+    tenv: TypeEnv,
+    shapes: Vec<Vec<Shape>>,
+    origins: Vec<Origin>,
+    /// Span of the op being lowered, for the terms it emits.
+    cur: Option<Span>,
+    // Build-time env of bound producers, mirroring the runtime `Let` env.
+    // Empty at top level — `Ref`s only ever appear inside a `Let` body.
+    env: Vec<Slot>,
+    map: &'a SourceMap,
+    effects: Effects,
+    quotes: Vec<Quoted>,
+    /// Quotations being spliced, innermost last.
+    splicing: Vec<usize>,
+    /// While splicing, body ops count toward the def expansions of the
+    /// consuming op, not of where the body was written. `None` while
+    /// an isolated (`match.K`/`under.<i>`) body runs on its own stack.
+    within: Option<Option<Vec<usize>>>,
+}
+
+impl Lowering<'_> {
+    /// Record the step before an op at `site` consuming `n_in` values.
+    fn step(&mut self, site: Option<&Site>, n_in: usize) {
+        let within = match (&self.within, site) {
+            (Some(None), _) => return,
+            (Some(Some(w)), _) => w.clone(),
+            (None, Some(s)) => s.within.clone(),
+            (None, None) => return,
+        };
+        self.effects.step(&within, &self.tstack, n_in);
+    }
+
+    /// Effective expansion stack at `site` (see `within`).
+    fn within_at(&self, site: Option<&Site>) -> Option<Vec<usize>> {
+        match &self.within {
+            Some(w) => w.clone(),
+            None => site.map(|s| s.within.clone()),
+        }
+    }
+}
+
+/// `sources` are source terms (no inputs) standing for the stack the
+/// program starts from, bottom first.
+fn lower(prog: Vec<Box<dyn Op>>, sites: &[Site], map: &SourceMap, sources: Vec<(SystemOp, Shape)>) -> Result<Built, Diagnostic> {
+    let mut lw = Lowering {
+        g: Graph::default(), bstack: Vec::new(), tstack: Vec::new(), tenv: Vec::new(),
+        shapes: Vec::new(), origins: Vec::new(), cur: None, env: Vec::new(), map, effects: Effects::default(),
+        quotes: Vec::new(), splicing: Vec::new(), within: None,
+    };
+    for (slot, (op, sh)) in sources.into_iter().enumerate() {
+        let id = lw.g.terms.len();
+        lw.g.terms.push(Term { op, children: vec![], n_outputs: 1 });
+        lw.shapes.push(vec![sh.clone()]);
+        lw.origins.push(Origin { input: Some(slot), ..Origin::default() });
+        lw.bstack.push(Slot::Out(OutRef { term: id, idx: 0 }));
+        lw.tstack.push(sh);
+    }
+    let entry = lw.tstack.clone();
+    build_in(prog, sites, &mut lw)?;
+    lw.effects.close_to(0, &lw.tstack);
+    let mut def_effects: Vec<Vec<StackEffect>> = vec![Vec::new(); map.defs.len()];
+    let mut done = std::mem::take(&mut lw.effects.done);
+    done.sort_by_key(|(e, _)| *e);
+    for (e, effect) in done {
+        let Some(x) = map.expansions.get(e) else { continue };
+        let seen = &mut def_effects[x.def];
+        if !seen.contains(&effect) { seen.push(effect); }
+    }
+    let mut roots = Vec::with_capacity(lw.bstack.len());
+    for slot in &lw.bstack {
+        match *slot {
+            Slot::Out(r) => roots.push(r),
+            Slot::Quote(q) => {
+                let d = Diagnostic::new("quotation left on the stack (never applied)");
+                return Err(match lw.quotes[q].span { Some(sp) => d.or_at(sp), None => d });
+            }
+        }
+    }
+    let mut g = lw.g;
+    g.roots = roots;
+    Ok(Built {
+        graph: g,
+        shapes: lw.shapes,
+        effect: StackEffect { inputs: entry, outputs: lw.tstack },
+        def_effects,
+        inputs: map.inputs.clone(),
+        origins: lw.origins,
+    })
+}
+
+fn build_in(prog: Vec<Box<dyn Op>>, sites: &[Site], lw: &mut Lowering) -> Result<(), Diagnostic> {
+    // A spliced body's terms take its own ops' spans; whatever the
+    // consuming op emits after it takes the consumer's again.
+    let outer = lw.cur;
+    for (k, op) in prog.into_iter().enumerate() {
+        let site = sites.get(k);
+        lw.cur = site.map(|s| s.span);
+        // Errors from this op point at its site.
+        let at = |e: String| match site {
+            Some(s) => lw_notes(Diagnostic::at(s.span, e), lw.map, s),
+            None => Diagnostic::new(e),
+        };
         // Stack-routing ops (dup/drop/swap/over/rot/pick/roll) carry no
         // semantic content — they only rearrange the stack. Resolve them
         // here into direct edges so the system graph is routing-free by
         // construction. (Routing ops *inside* opaque bodies stay there;
         // legacy eval handles them via op.run.)
         if let Some(map) = op.routing_map() {
-            resolve_routing(&*op, &map, bstack, tstack, tenv)?;
+            lw.step(site, op.arity().map_or(0, |a| a.0));
+            resolve_routing(&*op, &map, &mut lw.bstack, &mut lw.tstack, &mut lw.tenv).map_err(at)?;
             continue;
         }
         // Binding: always boiled into edges. (There are no longer any
         // opaque body-bearing ops that would force a `Let` to stay Foreign
         // — `each`/`match`/`cleave`/`repeat` are all gone — so every `Let`
         // boils and no `Let`/`Ref` term ever reaches the graph.)
-        enum Kind { BoilLet, RefIdx(usize), Other }
+        enum Kind { BoilLet, RefIdx(usize), Quote, Consume(Use), Other }
         let kind = {
             let any: &dyn std::any::Any = op.as_ref();
             if any.is::<Let>() {
                 Kind::BoilLet
             } else if let Some(r) = any.downcast_ref::<Ref>() {
                 Kind::RefIdx(r.idx)
+            } else if any.is::<Quote>() {
+                Kind::Quote
+            } else if let Some(c) = any.downcast_ref::<Consume>() {
+                Kind::Consume(c.how)
             } else {
                 Kind::Other
             }
@@ -111,60 +337,213 @@ fn build_in(
             Kind::BoilLet => {
                 let any_box: Box<dyn std::any::Any> = op;
                 let l = *any_box.downcast::<Let>().expect("classified as Let");
-                boil_let(l, g, bstack, tstack, tenv, shapes, env)?;
+                lw.step(site, l.names.len());
+                boil_let(l, lw).map_err(|d| match site {
+                    Some(s) if d.span.is_none() => lw_notes(d.or_at(s.span), lw.map, s),
+                    _ => d,
+                })?;
             }
             Kind::RefIdx(idx) => {
+                lw.step(site, 0);
                 // Resolve to the bound producer's OutRef. The graph's
                 // take-on-last-use makes `Ref`'s `take` flag irrelevant.
-                let slot = *env.get(idx).ok_or_else(|| {
-                    format!("graph build: ref {} out of env (len {})", idx, env.len())
+                let slot = *lw.env.get(idx).ok_or_else(|| {
+                    at(format!("graph build: ref {} out of env (len {})", idx, lw.env.len()))
                 })?;
-                op.tc(tstack, tenv).map_err(|e| format!("graph build: ref: {}", e))?;
-                bstack.push(slot);
+                op.tc(&mut lw.tstack, &mut lw.tenv).map_err(|e| at(format!("graph build: ref: {}", e)))?;
+                lw.bstack.push(slot);
+            }
+            Kind::Quote => {
+                lw.step(site, 0);
+                let any_box: Box<dyn std::any::Any> = op;
+                let q = *any_box.downcast::<Quote>().expect("classified as Quote");
+                lw.quotes.push(Quoted {
+                    body: q.body.into_iter().map(Ok).collect(), sites: q.sites,
+                    env: lw.env.clone(), tenv: lw.tenv.clone(),
+                    span: site.map(|s| s.span),
+                });
+                lw.bstack.push(Slot::Quote(lw.quotes.len() - 1));
+                lw.tstack.push(QUOTE_SHAPE);
+            }
+            Kind::Consume(how) => {
+                let n_in = Consume { how }.n_quotes() + usize::from(how != Use::Apply);
+                lw.step(site, n_in);
+                consume(how, site, lw).map_err(|d| match site {
+                    Some(s) if d.span.is_none() => lw_notes(d.or_at(s.span), lw.map, s),
+                    _ => d,
+                })?;
+            }
+            Kind::Other => {
+                // Dynamic-arity ops (`split`) consume one input.
+                lw.step(site, op.arity().map_or(1, |a| a.0));
+                emit_term(op, lw).map_err(at)?
+            }
+        }
+    }
+    lw.cur = outer;
+    Ok(())
+}
+
+/// Specialize a quotation consumer: pop its quotation(s) and lower their
+/// bodies in place.
+fn consume(how: Use, site: Option<&Site>, lw: &mut Lowering) -> Result<(), Diagnostic> {
+    let name = match how {
+        Use::Apply => "apply".to_string(),
+        Use::Match(k) => format!("match.{}", k),
+        Use::Under(i) => format!("under.{}", i),
+    };
+    let call = site.map(|s| s.span);
+    let k = Consume { how }.n_quotes();
+    if lw.bstack.len() < k {
+        return Err(format!("{}: needs {} quotation(s) on top of the stack, has {} values", name, k, lw.bstack.len()).into());
+    }
+    let mut qs = Vec::with_capacity(k);
+    for slot in lw.bstack.split_off(lw.bstack.len() - k) {
+        match slot {
+            Slot::Quote(q) => qs.push(q),
+            Slot::Out(_) => return Err(format!("{}: expected {} quotation(s) on top of the stack, got a value", name, k).into()),
+        }
+    }
+    lw.tstack.truncate(lw.tstack.len() - k);
+    let within = lw.within_at(site);
+    match how {
+        Use::Apply => splice(qs[0], call, within, lw),
+        Use::Under(i) => {
+            // Prod → fields, the body on field i alone, fields → Prod.
+            let n = match lw.tstack.last() {
+                Some(Shape::Prod(fs)) if !fs.is_empty() => fs.len(),
+                Some(other) => return Err(format!("under.{}: expects a Prod, got {}", i, other).into()),
+                None => return Err(format!("under.{}: needs a Prod below the quotation", i).into()),
+            };
+            if i >= n { return Err(format!("under.{}: Prod has {} fields", i, n).into()); }
+            emit_term(Box::new(DetupleN { n }), lw)?;
+            let at = lw.bstack.len() - n + i;
+            isolated(qs[0], at, &name, call, lw)?;
+            emit_term(Box::new(ZipN { n }), lw)?;
+            Ok(())
+        }
+        Use::Match(k) => {
+            // Sum → disc + lanes, arm j on lane j alone, merge.
+            match lw.tstack.last() {
+                Some(Shape::Sum { lanes, .. }) if lanes.len() == k => {}
+                Some(Shape::Sum { lanes, .. }) => {
+                    return Err(format!("match.{}: {} arms for a Sum of {} lanes", k, k, lanes.len()).into());
+                }
+                Some(other) => return Err(format!("match.{}: expects a Sum, got {}", k, other).into()),
+                None => return Err(format!("match.{}: needs a Sum below the arms", k).into()),
+            }
+            emit_term(Box::new(Split), lw)?;
+            for (j, &q) in qs.iter().enumerate() {
+                let at = lw.bstack.len() - k + j;
+                isolated(q, at, &name, call, lw)?;
             }
-            Kind::Other => emit_term(op, g, bstack, tstack, tenv, shapes)?,
+            emit_term(Box::new(MergeN { n: k }), lw)?;
+            Ok(())
         }
     }
+}
+
+/// Lower a copy of quotation `q`'s body on the current stack, with the
+/// bindings it captured in scope.
+fn splice(q: usize, call: Option<Span>, within: Option<Vec<usize>>, lw: &mut Lowering) -> Result<(), Diagnostic> {
+    if lw.splicing.contains(&q) || lw.splicing.len() >= MAX_SPLICE_DEPTH {
+        return Err("quotation applied recursively".into());
+    }
+    let quoted = &mut lw.quotes[q];
+    let mut body = Vec::with_capacity(quoted.body.len());
+    for slot in quoted.body.iter_mut() {
+        let copy = match slot {
+            Ok(op) => op.clone_op(),
+            Err(name) => return Err(format!(
+                "quotation applied more than once, but its `{}` can't be copied (no `Typed::clone_op`)", name).into()),
+        };
+        body.push(match copy {
+            Some(op) => op,
+            None => {
+                let name = slot.as_ref().map_or_else(|n| n.clone(), |op| op.name().to_string());
+                let Ok(op) = std::mem::replace(slot, Err(name)) else { unreachable!("checked above") };
+                op
+            }
+        });
+    }
+    let sites = quoted.sites.clone();
+    let written = quoted.span;
+    let env = std::mem::replace(&mut lw.env, quoted.env.clone());
+    let tenv = std::mem::replace(&mut lw.tenv, quoted.tenv.clone());
+    let saved_within = lw.within.replace(within);
+    lw.splicing.push(q);
+    let r = build_in(body, &sites, lw);
+    lw.splicing.pop();
+    lw.within = saved_within;
+    lw.env = env;
+    lw.tenv = tenv;
+    r.map_err(|d| match (written, call) {
+        (Some(w), Some(c)) => d.note(format!("in the quotation written at {} and applied at {}", w, c)),
+        _ => d,
+    })
+}
+
+/// Run quotation `q` on the single value at build-stack position `at`,
+/// in isolation, and put its one result back there. The body must be
+/// exactly one value in, one out; anything else is reported at `call`.
+fn isolated(q: usize, at: usize, name: &str, call: Option<Span>, lw: &mut Lowering) -> Result<(), Diagnostic> {
+    let input = (lw.bstack[at], lw.tstack[at].clone());
+    let bstack = std::mem::replace(&mut lw.bstack, vec![input.0]);
+    let tstack = std::mem::replace(&mut lw.tstack, vec![input.1.clone()]);
+    let r = splice(q, call, None, lw);
+    let (out_b, out_t) = (std::mem::replace(&mut lw.bstack, bstack), std::mem::replace(&mut lw.tstack, tstack));
+    let fit = |d: Diagnostic| match call {
+        Some(c) => d.or_at(c),
+        None => d,
+    };
+    if let Err(d) = r {
+        let note = format!("`{}` runs each quotation on one value: ( {} -- ? )", name, input.1);
+        return Err(d.note(note));
+    }
+    if out_b.len() != 1 {
+        let effect = StackEffect { inputs: vec![input.1], outputs: out_t };
+        return Err(fit(Diagnostic::new(format!(
+            "{}: quotation must leave exactly one value, its effect here is {}", name, effect))));
+    }
+    lw.bstack[at] = out_b[0];
+    lw.tstack[at] = out_t[0].clone();
     Ok(())
 }
 
+/// Add the def-expansion notes for `site`.
+fn lw_notes(d: Diagnostic, map: &SourceMap, site: &Site) -> Diagnostic {
+    map.notes(site).into_iter().fold(d, |d, n| d.note(n))
+}
+
 /// Boil a `Let` into the graph: bind its inputs' OutRefs into `env`
 /// (mirroring `Let::run`/`tc`'s env push), lower its body, then unwind.
 /// Produces no term.
-fn boil_let(
-    l: Let,
-    g: &mut Graph,
-    bstack: &mut Vec<OutRef>,
-    tstack: &mut TypeStack,
-    tenv: &mut TypeEnv,
-    shapes: &mut Vec<Vec<Shape>>,
-    env: &mut Vec<OutRef>,
-) -> Result<(), String> {
+fn boil_let(l: Let, lw: &mut Lowering) -> Result<(), Diagnostic> {
     let n = l.names.len();
-    if bstack.len() < n || tstack.len() < n {
-        return Err(format!("graph build: let needs {} inputs, has {}", n, bstack.len()));
+    if lw.bstack.len() < n || lw.tstack.len() < n {
+        return Err(format!("graph build: let needs {} inputs, has {}", n, lw.bstack.len()).into());
     }
     // Move the bound values off both stacks into the envs, in stack order
     // (deepest..top) — exactly what `Let::run`/`tc` do.
-    let bound_refs = bstack.split_off(bstack.len() - n);
-    let bound_shapes = tstack.split_off(tstack.len() - n);
-    let env0 = env.len();
-    let tenv0 = tenv.len();
-    env.extend(bound_refs);
-    tenv.extend(bound_shapes);
-    build_in(l.body, g, bstack, tstack, tenv, shapes, env)?;
-    env.truncate(env0);
-    tenv.truncate(tenv0);
+    let bound_refs = lw.bstack.split_off(lw.bstack.len() - n);
+    let bound_shapes = lw.tstack.split_off(lw.tstack.len() - n);
+    let env0 = lw.env.len();
+    let tenv0 = lw.tenv.len();
+    lw.env.extend(bound_refs);
+    lw.tenv.extend(bound_shapes);
+    build_in(l.body, &l.sites, lw)?;
+    lw.env.truncate(env0);
+    lw.tenv.truncate(tenv0);
     Ok(())
 }
 
 /// Apply a routing op's `map` to the build stack: pop its inputs, push the
-/// aliased OutRefs. The op produces no term. `tc` is run to keep the
+/// aliased slots. The op produces no term. `tc` is run to keep the
 /// type-stack in lockstep (routing ops still shift shapes around).
 fn resolve_routing(
     op: &dyn Op,
     map: &[usize],
-    bstack: &mut Vec<OutRef>,
+    bstack: &mut Vec<Slot>,
     tstack: &mut TypeStack,
     tenv: &mut TypeEnv,
 ) -> Result<(), String> {
@@ -178,7 +557,7 @@ fn resolve_routing(
         ));
     }
     op.tc(tstack, tenv).map_err(|e| format!("graph build: {}: {}", op.name(), e))?;
-    let inputs: Vec<OutRef> = bstack.split_off(bstack.len() - n_in);
+    let inputs: Vec<Slot> = bstack.split_off(bstack.len() - n_in);
     for &in_i in map {
         bstack.push(inputs[in_i]);
     }
@@ -186,39 +565,56 @@ fn resolve_routing(
 }
 
 /// Emit one term for one op. Always allocates — lowering is 1:1.
-fn emit_term(
-    op: Box<dyn Op>,
-    g: &mut Graph,
-    bstack: &mut Vec<OutRef>,
-    tstack: &mut TypeStack,
-    tenv: &mut TypeEnv,
-    shapes: &mut Vec<Vec<Shape>>,
-) -> Result<(), String> {
+fn emit_term(op: Box<dyn Op>, lw: &mut Lowering) -> Result<(), String> {
+    // A quotation is not a value: stop before `tc` sees its stand-in shape.
+    // (Dynamic-arity ops take one input.)
+    let want = op.arity().map_or(1, |a| a.0).min(lw.bstack.len());
+    if lw.bstack[lw.bstack.len() - want..].iter().any(|s| matches!(s, Slot::Quote(_))) {
+        return Err(format!(
+            "graph build: {}: got a quotation where a value was expected (use apply, match.K or under.<i>)",
+            op.name()
+        ));
+    }
     // Run tc to discover the output shapes and (for dynamic-arity ops) the
     // input/output counts. Snapshot type-stack height before tc.
-    let pre = tstack.len();
-    op.tc(tstack, tenv).map_err(|e| format!("graph build: {}: {}", op.name(), e))?;
+    let pre = lw.tstack.len();
+    op.tc(&mut lw.tstack, &mut lw.tenv).map_err(|e| format!("graph build: {}: {}", op.name(), e))?;
     let (n_in, n_out): (usize, usize) = match op.arity() {
         Some((i, o)) => (i, o),
-        None => derive_dynamic_arity(op.as_ref(), pre, tstack)?,
+        None => derive_dynamic_arity(op.as_ref(), pre, &lw.tstack)?,
     };
-    if bstack.len() < n_in {
+    if lw.bstack.len() < n_in {
         return Err(format!(
             "graph build: op {} needs {} stack inputs, has {}",
-            op.name(), n_in, bstack.len()
+            op.name(), n_in, lw.bstack.len()
         ));
     }
 
-    let children: Vec<OutRef> = bstack.split_off(bstack.len() - n_in);
-    let out_shapes: Vec<Shape> = tstack[tstack.len() - n_out..].to_vec();
+    let mut children: Vec<OutRef> = Vec::with_capacity(n_in);
+    for slot in lw.bstack.split_off(lw.bstack.len() - n_in) {
+        match slot {
+            Slot::Out(r) => children.push(r),
+            Slot::Quote(_) => return Err(format!("graph build: {}: got a quotation where a value was expected", op.name())),
+        }
+    }
+    let out_shapes: Vec<Shape> = lw.tstack[lw.tstack.len() - n_out..].to_vec();
 
-    let id = g.terms.len();
+    let id = lw.g.terms.len();
+    let literal = {
+        let any: &dyn std::any::Any = op.as_ref();
+        match (any.downcast_ref::<LitNum>(), any.downcast_ref::<LitArr>()) {
+            (Some(l), _) => Some(l.interp),
+            (_, Some(l)) => parse_interp(l.tag.trim_end_matches("[]")),
+            _ => None,
+        }
+    };
     // Promote into the system operator vocabulary: a first-class variant
     // where the system models the op, else `Foreign`.
-    g.terms.push(Term { op: promote(op), children, n_outputs: n_out });
-    shapes.push(out_shapes);
+    lw.g.terms.push(Term { op: promote(op), children, n_outputs: n_out });
+    lw.shapes.push(out_shapes);
+    lw.origins.push(Origin { span: lw.cur, literal, input: None });
     for i in 0..n_out {
-        bstack.push(OutRef { term: id, idx: i });
+        lw.bstack.push(Slot::Out(OutRef { term: id, idx: i }));
     }
     Ok(())
 }
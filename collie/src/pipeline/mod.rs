@@ -9,10 +9,22 @@
 //! returning the graph (or a result):
 //!
 //! ```text
-//! parsed ops ──lower::build──▶ Graph ──optimize::{elide_routing,cse,
-//!              eliminate_dead}──▶ Graph ──execute::eval_graph──▶ Vec<Value>
+//! parsed ops ──lower::build──▶ Graph ──optimize::{elide_routing,rewrite,
+//!              fold_constants,cse,eliminate_dead}──▶ Graph
+//!              ──execute::eval_graph──▶ Vec<Value>
 //! ```
 //!
+//! `egraph::optimize_saturated` is the same pipeline with equality
+//! saturation (`egraph::saturate`) in place of the greedy `rewrite` sweep
+//! (`collie run --egraph`).
+//!
+//! `interp::{infer, elaborate}` is an optional analysis between lowering
+//! and optimizing: it checks numeric interpretations across ops and
+//! resolves interp-less `sort`/`<`/`reduce.min` (`collie --infer`).
+//!
+//! `execute::eval_graph_profiled` runs the last stage with a per-term
+//! `profile::GraphProfile` alongside (`collie run --profile`).
+//!
 //! `ir/` holds the *vocabulary* (Value, Shape, Graph, Op); this module
 //! holds the *stages* that transform a program through it. No stage
 //! reaches backward — each consumes what it needs and hands the graph on.
@@ -20,12 +32,18 @@
 pub mod graph;
 pub mod sysop;
 pub mod lower;
+pub mod interp;
 pub mod optimize;
+pub mod egraph;
 pub mod execute;
+pub mod profile;
 
-pub use lower::{build, build_seeded};
-pub use optimize::{cse, elide_routing, eliminate_dead, optimize};
-pub use execute::{eval_graph, use_counts};
+pub use lower::{build, build_parsed, build_parsed_inputs, build_parsed_seeded, build_seeded, Built, Origin};
+pub use interp::{elaborate, infer, Inference, Kind};
+pub use optimize::{cse, elide_routing, eliminate_dead, fold_constants, rewrite, rewrite_fixpoint, term_shapes, optimize, optimize_unfolded, Rule};
+pub use egraph::{optimize_saturated, optimize_saturated_unfolded, EGraph, Rewrite};
+pub use execute::{eval_graph, eval_graph_inputs, eval_graph_memo, eval_graph_profiled, use_counts, Memo};
+pub use profile::{GraphProfile, TermProfile};
 
 #[cfg(test)]
 mod tests {
@@ -235,10 +253,397 @@ mod tests {
         }
     }
 
+    /// Run `pass` on every example and check the result against the
+    /// unoptimized graph; returns the pass's total hit count. Examples the
+    /// pass leaves untouched are not re-evaluated (the corpus is slow).
+    fn corpus_agrees_under(pass: impl Fn(crate::pipeline::graph::Graph) -> (crate::pipeline::graph::Graph, usize)) -> usize {
+        let reg = OpRegistry::standard();
+        let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir("examples")
+            .unwrap().filter_map(|e| e.ok()).map(|e| e.path())
+            .filter(|p| p.extension().map_or(false, |x| x == "col")).collect();
+        paths.sort();
+        let mut total = 0;
+        for path in &paths {
+            let src = std::fs::read_to_string(path).unwrap();
+            let (g, _) = build(parse(&src, &reg).unwrap()).unwrap();
+            let (g, hits) = pass(g);
+            if hits == 0 { continue; }
+            total += hits;
+            assert_eq!(eval_graph(&g).unwrap(), via_graph(&src).unwrap(),
+                       "pass diverged from unoptimized on {}", path.display());
+        }
+        total
+    }
+
+    #[test]
+    fn fold_constants_corpus_preserves_results() {
+        assert!(corpus_agrees_under(fold_constants) > 0, "no term folded on the corpus");
+    }
+
+    #[test]
+    fn fold_constants_leaves_only_consts_for_literal_programs() {
+        let reg = OpRegistry::standard();
+        let (g, _) = build(parse("u64[1 2 3] dup 2u64 *.u64 +.u64 reduce.+.u64", &reg).unwrap()).unwrap();
+        let g = eliminate_dead(fold_constants(g).0);
+        assert_eq!(g.terms.len(), 1);
+        assert!(matches!(g.terms[0].op, crate::pipeline::sysop::SystemOp::Const(_)));
+        assert_eq!(eval_graph(&g).unwrap(), vec![crate::ir::value::from_vec::<u64>(vec![18])]);
+    }
+
+    #[test]
+    fn fold_constants_keeps_side_effects() {
+        let reg = OpRegistry::standard();
+        let (g, _) = build(parse("time u64[1 2 3] reduce.+.u64", &reg).unwrap()).unwrap();
+        let g = eliminate_dead(fold_constants(g).0);
+        assert!(g.terms.iter().any(|t| t.op.name() == "time"), "time wrongly folded away");
+    }
+
+    #[test]
+    fn fold_constants_skips_large_or_expanding_terms() {
+        // Nothing that could outgrow its inputs runs at optimize time:
+        // `iota` of a literal stays a term, and so does an op over a
+        // `Const` above the cap. A small non-expanding term still folds.
+        let reg = OpRegistry::standard();
+        let big = (0..70_000).map(|i| i.to_string()).collect::<Vec<_>>().join(" ");
+        let src = format!("u64[1000000] iota reduce.+.u64 u64[{big}] 2u64 *.u64 u64[1 2] 3u64 *.u64");
+        let (g, _) = build(parse(&src, &reg).unwrap()).unwrap();
+        let (g, hits) = fold_constants(g);
+        assert_eq!(hits, 1);
+        let g = eliminate_dead(g);
+        assert!(g.terms.iter().any(|t| t.op.name() == "iota"), "iota folded");
+        assert_eq!(g.terms.iter().filter(|t| t.op.name() == "*").count(), 1);
+        assert_eq!(eval_graph(&g).unwrap(), via_graph(&src).unwrap());
+    }
+
+    /// One rule at a time over the corpus: it must fire somewhere (the
+    /// `20_rewrites.col` example carries each pattern) and must not change
+    /// any example's result.
+    fn rule_agrees_on_corpus(rule: Rule) {
+        let hits = corpus_agrees_under(|g| rewrite_fixpoint(g, &[rule]));
+        assert!(hits > 0, "{:?} never fired on the corpus", rule);
+    }
+
+    #[test]
+    fn rewrite_gather_gather_corpus() { rule_agrees_on_corpus(Rule::GatherGather); }
+
+    #[test]
+    fn rewrite_filter_filter_corpus() { rule_agrees_on_corpus(Rule::FilterFilter); }
+
+    #[test]
+    fn rewrite_take_below_map_corpus() { rule_agrees_on_corpus(Rule::TakeBelowMap); }
+
+    #[test]
+    fn rewrite_reverse_reverse_corpus() { rule_agrees_on_corpus(Rule::ReverseReverse); }
+
+    #[test]
+    fn rewrite_swizzle_pair_corpus() { rule_agrees_on_corpus(Rule::SwizzlePair); }
+
+    #[test]
+    fn rewrite_reverse_reverse_keeps_views() {
+        // `filter` yields a View; cancelling `reverse reverse` over it would
+        // hand back the View where the unoptimized graph materializes.
+        let src = "u64[1 2 3 4] bool[t f t t] filter reverse reverse";
+        let reg = OpRegistry::standard();
+        let (g, _) = build(parse(src, &reg).unwrap()).unwrap();
+        let (g, hits) = rewrite(g, Rule::ALL);
+        assert_eq!(hits, 0);
+        assert_eq!(eval_graph(&g).unwrap(), via_graph(src).unwrap());
+    }
+
+    /// One e-graph rule family at a time over the corpus, saturated and
+    /// extracted: it must fire somewhere and must not change any result.
+    fn egraph_family_agrees_on_corpus(name: &str) {
+        let family: Vec<Rewrite> = egraph::rules().into_iter().filter(|r| r.name == name).collect();
+        assert!(!family.is_empty(), "no rule family {}", name);
+        let hits = corpus_agrees_under(|g| egraph::saturate(elide_routing(g), &family));
+        assert!(hits > 0, "{} never fired on the corpus", name);
+    }
+
+    #[test]
+    fn egraph_filter_fusion_corpus() { egraph_family_agrees_on_corpus("filter-fusion"); }
+
+    #[test]
+    fn egraph_gather_compose_corpus() { egraph_family_agrees_on_corpus("gather-compose"); }
+
+    #[test]
+    fn egraph_reverse_pair_corpus() { egraph_family_agrees_on_corpus("reverse-pair"); }
+
+    #[test]
+    fn egraph_swizzle_pair_corpus() { egraph_family_agrees_on_corpus("swizzle-pair"); }
+
+    #[test]
+    fn egraph_detuple_entuple_corpus() { egraph_family_agrees_on_corpus("detuple-entuple"); }
+
+    #[test]
+    fn egraph_proj_entuple_corpus() { egraph_family_agrees_on_corpus("proj-entuple"); }
+
+    #[test]
+    fn egraph_sort_elision_corpus() { egraph_family_agrees_on_corpus("sort-elision"); }
+
+    #[test]
+    fn optimize_saturated_corpus_preserves_results() {
+        // `optimize` with the e-graph in place of the greedy rewriter.
+        let reg = OpRegistry::standard();
+        let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir("examples")
+            .unwrap().filter_map(|e| e.ok()).map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|x| x == "col")).collect();
+        paths.sort();
+        for path in &paths {
+            let src = std::fs::read_to_string(path).unwrap();
+            let (g, _) = build(parse(&src, &reg).unwrap()).unwrap();
+            assert_eq!(eval_graph(&optimize_saturated(g)).unwrap(), via_graph(&src).unwrap(),
+                       "optimize_saturated diverged on {}", path.display());
+        }
+    }
+
+    #[test]
+    fn egraph_keeps_views() {
+        // As `rewrite_reverse_reverse_keeps_views`; and `sort` over a
+        // filtered sorted column stays (the filter's View isn't sorted).
+        for src in ["u64[1 2 3 4] bool[t f t t] filter reverse reverse",
+                    "u64[1 2 3 4] bool[t f t t] filter sort"] {
+            let reg = OpRegistry::standard();
+            let (g, _) = build(parse(src, &reg).unwrap()).unwrap();
+            let (g, hits) = egraph::saturate(elide_routing(g), &egraph::rules());
+            assert_eq!(hits, 0, "{}", src);
+            assert_eq!(eval_graph(&g).unwrap(), via_graph(src).unwrap());
+        }
+    }
+
+    #[test]
+    fn rewrite_gather_gather_skips_shared_inner() {
+        // The inner gather feeds two consumers; composing positions would
+        // gather the values twice.
+        let src = "u64[5 6 7] u64[2 1 0] gather dup u64[0] gather swap u64[1] gather";
+        let reg = OpRegistry::standard();
+        let (g, _) = build(parse(src, &reg).unwrap()).unwrap();
+        let (g, hits) = rewrite(g, &[Rule::GatherGather]);
+        assert_eq!(hits, 0);
+        agree(src);
+        assert_eq!(eval_graph(&g).unwrap(), via_graph(src).unwrap());
+    }
+
+    #[test]
+    fn rewrite_filter_filter_skips_shared_inner() {
+        // The inner filter feeds two consumers: it runs anyway, so the
+        // rewrite would only add a compose and a second pass over `src`.
+        let src = "u64[5 6 7 8] bool[t f t t] filter dup bool[t f t] filter swap bool[f t t] filter";
+        let reg = OpRegistry::standard();
+        let (g, _) = build(parse(src, &reg).unwrap()).unwrap();
+        let (g, hits) = rewrite(g, &[Rule::FilterFilter]);
+        assert_eq!(hits, 0);
+        assert_eq!(eval_graph(&g).unwrap(), via_graph(src).unwrap());
+        // A lone consumer still fuses.
+        let src = "u64[5 6 7 8] bool[t f t t] filter bool[t f t] filter";
+        let (g, _) = build(parse(src, &reg).unwrap()).unwrap();
+        assert_eq!(rewrite(g, &[Rule::FilterFilter]).1, 1);
+    }
+
     #[test]
     fn wco_small_smoke() {
         let src = std::fs::read_to_string("examples/18_wco_lftj_def.col").unwrap();
         let src = src.replace("1000000u64", "10u64");
         agree(&src);
     }
+
+    #[test]
+    fn profiled_eval_records_every_term() {
+        let src = "u64[5 6 7 8] bool[t f t t] filter reduce.+.u64";
+        let reg = OpRegistry::standard();
+        let (g, _) = build(parse(src, &reg).unwrap()).unwrap();
+        let (out, prof) = eval_graph_profiled(&g).unwrap();
+        assert_eq!(out, via_graph(src).unwrap());
+        assert_eq!(prof.terms.len(), g.terms.len());
+        let filter = prof.terms.iter().find(|t| t.op == "filter").expect("filter term");
+        assert_eq!(filter.in_rows, vec![4, 4]);
+        assert_eq!(filter.out_rows, vec![3]);
+        assert_eq!(filter.shapes, vec![crate::ir::shape::Shape::Prim(crate::ir::value::PrimWidth::W64)]);
+        // Test binaries don't install the counting allocator.
+        assert!(prof.terms.iter().all(|t| t.bytes.is_none()));
+
+        let trace = prof.chrome_trace();
+        assert_eq!(trace.matches("\"ph\":\"X\"").count(), g.terms.len());
+        let dot = prof.dot(&g);
+        let n_edges: usize = g.terms.iter().map(|t| t.children.len()).sum::<usize>() + g.roots.len();
+        assert_eq!(dot.matches(" -> ").count(), n_edges);
+        assert_eq!(prof.table().lines().count(), g.terms.len() + 2);
+    }
+
+    #[test]
+    fn build_errors_point_at_the_failing_op() {
+        use crate::syntax::parse::parse_program;
+        let reg = OpRegistry::standard();
+        let src = "def bad { u32[1 2] +.u64 }\nu64[1 2 3]\n  bad\n";
+        let parsed = parse_program(src, &reg).expect("parses");
+        let d = build_parsed(parsed).err().expect("typecheck fails");
+        let sp = d.span.expect("has a span");
+        assert_eq!((sp.line, sp.col, sp.len), (1, 20, 5));
+        assert_eq!(d.notes.len(), 1);
+        assert!(d.notes[0].contains("`bad` at 3:3"), "{:?}", d.notes);
+        let shown = d.render(src, "t.col");
+        assert!(shown.contains("--> t.col:1:20"), "{}", shown);
+        assert!(shown.lines().any(|l| l.ends_with("  ^^^^^")), "{}", shown);
+
+        let d = parse_program("u64[1 2]\n  frob 3", &reg).err().expect("unknown token");
+        let sp = d.span.expect("has a span");
+        assert_eq!((sp.line, sp.col, sp.len), (2, 3, 4));
+    }
+
+    #[test]
+    fn build_parsed_reports_stack_effects() {
+        use crate::ir::shape::Shape;
+        use crate::ir::value::PrimWidth;
+        use crate::syntax::parse::parse_program;
+        let reg = OpRegistry::standard();
+        let src = "def sq { :x x x *.u64 }\n\
+                   def total { sq reduce.+.u64 }\n\
+                   def pair { :[a b] b a }\n\
+                   def unused { dup }\n\
+                   u64[1 2 3] total u64[4 5] sq u32[1] pair";
+        let parsed = parse_program(src, &reg).expect("parses");
+        let names: Vec<String> = parsed.map.defs.iter().map(|d| d.name.clone()).collect();
+        let built = build_parsed(parsed).expect("builds");
+        let w = |w| Shape::Prim(w);
+        let (w64, w32) = (w(PrimWidth::W64), w(PrimWidth::W32));
+        let effect = |name: &str| {
+            let k = names.iter().position(|n| n == name).unwrap();
+            built.def_effects[k].iter().map(|e| e.to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(effect("sq"), vec![format!("( {} -- {} )", w64, w64)]);
+        assert_eq!(effect("total"), vec![format!("( {} -- {} )", w64, w64)]);
+        assert_eq!(effect("pair"), vec![format!("( {} {} -- {} {} )", w64, w32, w32, w64)]);
+        assert!(effect("unused").is_empty());
+        assert!(built.effect.inputs.is_empty());
+        assert_eq!(built.effect.outputs, vec![w64.clone(), w32, w64]);
+    }
+
+    #[test]
+    fn quotations_specialize_at_lowering() {
+        use crate::ir::value::from_vec;
+        let src = "def twice { :[x f] x f apply f apply }\n\
+                   u64[1 2 3] [ dup +.u64 ] twice\n\
+                   u64[5 6] u64[7 8] entuple.2 [ dup *.u64 ] under.0 .0\n\
+                   i64[1 -2 3] u8[0 1 0] branch.2 [ neg.i64 ] [ 10 +.i64 ] match.2\n\
+                   u64[1 2] :k u64[10 20] [ k +.u64 ] apply";
+        let reg = OpRegistry::standard();
+        let (g, _) = build(parse(src, &reg).unwrap()).unwrap();
+        assert!(g.terms.iter().all(|t| !matches!(t.op.name().as_str(), "quote" | "apply" | "match" | "under")));
+        let out = eval_graph(&g).unwrap();
+        assert_eq!(out, vec![
+            from_vec::<u64>(vec![4, 8, 12]),
+            from_vec::<u64>(vec![25, 36]),
+            from_vec::<i64>(vec![-1, 8, -3]),
+            from_vec::<u64>(vec![11, 22]),
+        ]);
+        agree(src);
+    }
+
+    #[test]
+    fn quotation_misuse_is_reported_at_the_call_site() {
+        use crate::syntax::parse::parse_program;
+        let reg = OpRegistry::standard();
+        let err = |src: &str| build_parsed(parse_program(src, &reg).unwrap()).err().expect("build fails");
+        // Effect doesn't fit `under.1` (two values out): caret on `under.1`.
+        let d = err("u64[1 2] u64[3 4] entuple.2\n  [ dup ] under.1");
+        let sp = d.span.unwrap();
+        assert_eq!((sp.line, sp.col, sp.len), (2, 11, 7));
+        assert!(d.message.contains("exactly one value"), "{}", d.message);
+        // Fed to an ordinary op, or never applied.
+        assert!(err("u64[1 2] [ dup ] +.u64").message.contains("got a quotation"));
+        assert!(err("u64[1 2] [ dup ]").message.contains("never applied"));
+        assert!(err("u64[1] [ dup apply ] dup apply").message.contains("recursively"));
+    }
+
+    #[test]
+    fn ops_without_clone_still_lower() {
+        // An out-of-tree op needn't be `Clone`: it lowers as usual, and in
+        // a quotation applied once. Only a second use of that quotation
+        // needs a copy, and says so.
+        use crate::ir::op::PrimOp;
+        use crate::ir::typecheck::{Op, Typed};
+        use crate::syntax::parse::parse_program;
+        #[derive(Debug)]
+        struct Bump;
+        impl PrimOp for Bump {
+            fn name(&self) -> &str { "bump" }
+            fn arity(&self) -> Option<(usize, usize)> { Some((1, 1)) }
+            fn run(&self, st: &mut crate::ir::stack::Stack, _env: &mut Vec<Value>) -> Result<(), String> {
+                let Value::Prim(crate::ir::value::Prim::P64(xs)) = crate::ir::stack::pop(st)? else {
+                    return Err("bump: expected P64".into());
+                };
+                st.push(crate::ir::value::from_vec::<u64>(xs.iter().map(|x| x + 1).collect()));
+                Ok(())
+            }
+        }
+        impl Typed for Bump {}
+        let mut reg = OpRegistry::standard();
+        reg.add(|t: &str| -> Option<Box<dyn Op>> { (t == "bump").then(|| Box::new(Bump) as Box<dyn Op>) });
+        let run = |src: &str| build_parsed(parse_program(src, &reg).unwrap()).map(|b| eval_graph(&b.graph).unwrap());
+        let one = vec![crate::ir::value::from_vec::<u64>(vec![2, 3])];
+        assert_eq!(run("u64[1 2] bump").unwrap(), one);
+        assert_eq!(run("u64[1 2] [ bump ] apply").unwrap(), one);
+        let d = run("u64[1 2] [ bump ] :[f] f apply f apply").expect_err("second use fails");
+        assert!(d.message.contains("`bump` can't be copied"), "{}", d.message);
+    }
+
+    #[test]
+    fn encoded_columns_agree_with_decoded() {
+        use crate::ops::helpers::normalize;
+        let norm = |vs: Vec<Value>| vs.iter().map(normalize).collect::<Result<Vec<_>, _>>().unwrap();
+        // `E` marks where the column is encoded; each program must give the
+        // same (decoded) stack with `E` removed, through either pipeline.
+        let progs = [
+            "u64[3 3 3 7 7 1 1 1 1] E reduce.+.u64",
+            "i32[-2 -2 5 5 5 -9] E :x x reduce.+.i32 x reduce.min.i32 x reduce.max.i32 x reduce.*.i32",
+            "f64[0.1 0.1 0.1 0.2 0.2] E reduce.+.f64",
+            "u8[20 20 100] E reduce.+.u8",
+            "u64[1 1 2 2 2 3] :k u64[10 20 30 40 50 60] k E 2u64 = filter",
+            "u64[1 1 2 2 2 3] E :k k k 2u64 >= filter dup 3u64 != filter reduce.+.u64",
+            "u64[1 1 2 2 2 3] E u8[1 0 1 1 0 1] filter",
+            "u64[10 20 30 40 50 60] u64[5 5 2 2 9 9] E group sort.segmented :[k g] k g count g reduce.+.u64",
+            "u64[10 20 30 40 50 60] u64[1 1 2 3 3 3] E group count",
+            "u16[4 4 1 1 1 9 4] E sort",
+            "u64[1000 1003 1001 1007 1000] E reduce.+.u64",
+            "i64[-5 3 -1 -5] E :x x reduce.+.i64 x x i64[-5] != filter",
+            "u32[0 4294967295 7 9 11 13 15 17 19 21 23 25 27 29 31 33] E decode",
+            "u64[0 18446744073709551615 5] E decode",
+            "u16[0 99 13 57 42 7 88 3 61 25 99 0 14 76 33 50 9 68 21 95] E decode",
+        ];
+        for p in progs {
+            let plain = norm(via_graph(&p.replace(" E", "")).unwrap());
+            for enc in ["encode.rle", "encode.for"] {
+                let src = p.replace('E', enc);
+                assert_eq!(norm(via_graph(&src).unwrap()), plain, "{}", src);
+                assert_eq!(norm(via_graph_opt(&src).unwrap()), plain, "optimized: {}", src);
+            }
+        }
+    }
+
+    #[test]
+    fn encoded_kernels_stay_encoded() {
+        use crate::ir::value::{Encoded, from_vec};
+        let top = |src: &str| via_graph(src).unwrap().pop().unwrap();
+        let rle = |v: &Value| matches!(v, Value::Encoded(Encoded::Rle { .. }));
+        // Cmp against a scalar, filter by (or of) RLE, and sort keep the runs.
+        assert!(rle(&top("u64[1 1 2 2 2 3] encode.rle 2u64 =")));
+        assert!(rle(&top("u64[1 1 2 2 2 3] encode.rle u8[1 0 1 1 0 1] filter")));
+        assert!(rle(&top("u16[4 4 1 1 1 9 4] encode.rle sort")));
+        match top("u64[5 6 7 8 9] u64[1 1 2 2 2] encode.rle 2u64 = filter") {
+            Value::View { selector: crate::ir::value::Selector::Runs(r), .. } => assert_eq!(*r, vec![(2, 5)]),
+            other => panic!("expected a Runs view, got {:?}", other),
+        }
+        // sort.perm of RLE is the stable run order.
+        assert_eq!(top("u16[4 4 1 1 1 9 4] encode.rle sort.perm"), from_vec::<u64>(vec![2, 3, 4, 0, 1, 6, 5]));
+        // Sorted keys group without moving the values.
+        let out = via_graph("u64[10 20 30 40] u64[1 1 1 2] encode.rle group count").unwrap();
+        assert_eq!(out, vec![from_vec::<u64>(vec![1, 2]), from_vec::<u64>(vec![3, 1])]);
+        // Compression: 1000 rows of 4 sorted keys; ids within a 0..100 window.
+        let keys: Vec<String> = (0..1000).map(|i| (i / 250).to_string()).collect();
+        let e = top(&format!("u64[{}] encode.rle", keys.join(" ")));
+        let Value::Encoded(e) = e else { panic!("expected Encoded") };
+        assert!(e.payload_bytes() * 50 < 1000 * 8, "{} bytes", e.payload_bytes());
+        let ids: Vec<String> = (0..1000).map(|i| (1_000_000 + i % 100).to_string()).collect();
+        let Value::Encoded(e) = top(&format!("u64[{}] encode.for", ids.join(" "))) else { panic!("expected Encoded") };
+        assert!(e.payload_bytes() * 9 < 1000 * 8, "{} bytes", e.payload_bytes());
+    }
 }
@@ -8,15 +8,66 @@
 
 use crate::pipeline::graph::{Graph, Term, OutRef};
 use crate::pipeline::sysop::SystemOp;
+use crate::ir::shape::Shape;
+use crate::ir::value::Value;
+
+/// Row count above which `Const` terms are left out of CSE.
+const CSE_CONST_MAX_ROWS: usize = 1024;
+
+/// Leaf-element count above which `fold_constants` leaves a term unfolded:
+/// a folded output is baked into the graph (and any saved plan) whole.
+/// Checked on the inputs before the kernel runs (so optimizing never does
+/// eval's work over a big column) and on the outputs after.
+const FOLD_CONST_MAX_ELEMS: usize = 1 << 16;
+
+/// Ops whose output is no larger than their inputs (for flat data), so
+/// folding them over capped `Const`s can't run away. Generators (`iota`,
+/// and `rand.*` among the `Foreign` ops), products and joins, and
+/// expanders (`like`, `spread`, `bounds>keys`, `decode`) are left for eval.
+fn non_expanding(op: &SystemOp) -> bool {
+    matches!(op,
+        SystemOp::Arith { .. } | SystemOp::UnaryArith { .. } | SystemOp::Cmp { .. } | SystemOp::As { .. }
+        | SystemOp::Not | SystemOp::And | SystemOp::Or | SystemOp::Any | SystemOp::All
+        | SystemOp::Reduce { .. } | SystemOp::ReduceOrd { .. } | SystemOp::Cumsum { .. }
+        | SystemOp::Shift { .. } | SystemOp::Count
+        | SystemOp::Where | SystemOp::Filter | SystemOp::MaskCompose | SystemOp::Gather
+        | SystemOp::Intersect | SystemOp::Search
+        | SystemOp::SortPerm | SystemOp::Sort | SystemOp::SortSegmented | SystemOp::Group | SystemOp::Unique
+        | SystemOp::Enswizzle { .. } | SystemOp::Deswizzle { .. }
+        | SystemOp::Zip { .. } | SystemOp::Detuple { .. } | SystemOp::Proj { .. }
+        | SystemOp::Inject { .. } | SystemOp::Split | SystemOp::Merge { .. } | SystemOp::Partition { .. }
+        | SystemOp::Branch { .. }
+        | SystemOp::Nest | SystemOp::NestStride | SystemOp::Flatten | SystemOp::Bounds | SystemOp::Head
+        | SystemOp::Enlist | SystemOp::Unlist
+        | SystemOp::Concat | SystemOp::Cat { .. } | SystemOp::Take | SystemOp::Skip | SystemOp::Reverse
+        | SystemOp::TakeSegmented | SystemOp::ReverseSegmented)
+}
+
+/// Leaf elements a value holds — its size as a baked-in `Const`.
+fn const_elems(v: &Value) -> usize {
+    match v {
+        Value::Prim(p) => p.len(),
+        Value::Prod(fs) => fs.iter().map(const_elems).sum(),
+        Value::Sum { disc, lanes } => disc.len() + lanes.iter().map(const_elems).sum::<usize>(),
+        Value::List { bounds, values } => bounds.len() + const_elems(values),
+        Value::View { .. } | Value::Encoded(_) => v.len(),
+    }
+}
 
 /// Cheap key for hash-cons: op-debug-repr captures op identity +
 /// parameters baked into the variant (`Cat { n }`'s n, `Proj { i }`'s i).
 /// `None` for side-effecting ops, which must not be merged — re-running
 /// them changes observable behavior.
-fn cse_key_for(op: &SystemOp) -> Option<String> {
+pub(crate) fn cse_key_for(op: &SystemOp) -> Option<String> {
     if op.is_side_effecting() {
         return None;
     }
+    // The key is the op's Debug form; for a large `Const` (typically the
+    // output of `fold_constants`) formatting it costs more than a merge
+    // would save.
+    if let SystemOp::Const(v) = op {
+        if v.len() > CSE_CONST_MAX_ROWS { return None; }
+    }
     Some(format!("{:?}", op))
 }
 
@@ -104,18 +155,319 @@ pub fn elide_routing(g: Graph) -> Graph {
 }
 
 /// The default optimize pipeline (`Graph → Graph`): routing elision →
-/// CSE → dead-term elimination. Routing elision is a no-op on graphs from
-/// `build` (routing-free by construction) but kept for graphs other
-/// front-ends might produce. Because the whole thing is `Graph → Graph`,
-/// it is *never load-bearing for execution* — `eval_graph` runs an
-/// un-optimized graph to the same result (the `--no-opt` escape and the
+/// algebraic rewrites → constant folding → CSE → dead-term elimination.
+/// Routing elision is a no-op on graphs from `build` (routing-free by
+/// construction) but kept for graphs other front-ends might produce.
+/// Rewrites run before folding so they see the literal-fed patterns the
+/// examples are made of. Because the whole thing is `Graph → Graph`, it is
+/// *never load-bearing for execution* — `eval_graph` runs an un-optimized
+/// graph to the same result (the `--no-opt` escape and the
 /// `optimize_corpus_preserves_results` test rely on this). See
 /// `dev/LAYERING.md`.
 pub fn optimize(g: Graph) -> Graph {
-    let (g, _hits) = cse(elide_routing(g));
+    let (g, _hits) = fold_constants(optimize_unfolded_rewrites(g));
+    let (g, _hits) = cse(g);
     eliminate_dead(g)
 }
 
+/// `optimize` without constant folding. For graphs whose `Const` sources
+/// stand in for run-time inputs (`build_seeded`, the bench harness):
+/// folding would evaluate the whole program at optimize time and leave
+/// nothing to measure.
+pub fn optimize_unfolded(g: Graph) -> Graph {
+    let (g, _hits) = cse(optimize_unfolded_rewrites(g));
+    eliminate_dead(g)
+}
+
+fn optimize_unfolded_rewrites(g: Graph) -> Graph {
+    let (g, _hits) = rewrite_fixpoint(elide_routing(g), Rule::ALL);
+    g
+}
+
+/// Per-term output shapes, recomputed by running each term's `tc` over its
+/// children's shapes. `build` returns the same table, but it goes stale
+/// once a pass reindexes; passes that need shapes call this.
+pub fn term_shapes(g: &Graph) -> Result<Vec<Vec<Shape>>, String> {
+    let mut shapes: Vec<Vec<Shape>> = Vec::with_capacity(g.terms.len());
+    let mut env = Vec::new();
+    for term in &g.terms {
+        let mut st: Vec<Shape> = term.children.iter()
+            .map(|c| shapes[c.term][c.idx].clone())
+            .collect();
+        term.op.tc(&mut st, &mut env).map_err(|e| format!("term shapes: {}: {}", term.op.name(), e))?;
+        shapes.push(st);
+    }
+    Ok(shapes)
+}
+
+/// Constant folding. A term whose inputs are all `Const` terms is run at
+/// optimize time and replaced by one `Const` per output. Only
+/// `non_expanding` ops over inputs within `FOLD_CONST_MAX_ELEMS` are run;
+/// terms whose kernel errors are left alone (the error is then raised at
+/// eval, where it belongs), as are terms whose outputs exceed the cap. Folding cascades within the
+/// single topological sweep. The `Const`s a folded term consumed are left
+/// for `eliminate_dead`. Returns the new graph and the number of terms
+/// folded.
+pub fn fold_constants(g: Graph) -> (Graph, usize) {
+    let mut map: Vec<Vec<OutRef>> = Vec::with_capacity(g.terms.len());
+    let mut new_terms: Vec<Term> = Vec::with_capacity(g.terms.len());
+    let mut env: Vec<Value> = Vec::new();
+    let mut hits = 0usize;
+
+    for mut term in g.terms.into_iter() {
+        for ch in term.children.iter_mut() {
+            *ch = map[ch.term][ch.idx];
+        }
+        let foldable = !term.children.is_empty()
+            && non_expanding(&term.op)
+            && term.children.iter().all(|c| matches!(new_terms[c.term].op, SystemOp::Const(_)))
+            && term.children.iter().map(|c| match &new_terms[c.term].op {
+                SystemOp::Const(v) => const_elems(v),
+                _ => 0,
+            }).sum::<usize>() <= FOLD_CONST_MAX_ELEMS;
+        if foldable {
+            let mut sub: Vec<Value> = term.children.iter().map(|c| match &new_terms[c.term].op {
+                SystemOp::Const(v) => v.clone(),
+                _ => unreachable!("checked foldable"),
+            }).collect();
+            if term.op.run(&mut sub, &mut env).is_ok() && sub.len() == term.n_outputs
+                && sub.iter().all(|v| const_elems(v) <= FOLD_CONST_MAX_ELEMS)
+            {
+                hits += 1;
+                let outs = sub.into_iter().map(|v| {
+                    new_terms.push(Term { op: SystemOp::Const(v), children: vec![], n_outputs: 1 });
+                    OutRef { term: new_terms.len() - 1, idx: 0 }
+                }).collect();
+                map.push(outs);
+                continue;
+            }
+        }
+        let id = new_terms.len();
+        map.push((0..term.n_outputs).map(|idx| OutRef { term: id, idx }).collect());
+        new_terms.push(term);
+    }
+    let new_roots = g.roots.iter().map(|r| map[r.term][r.idx]).collect();
+    (Graph { terms: new_terms, roots: new_roots }, hits)
+}
+
+/// The algebraic rewrites `rewrite` knows. Each is result-preserving
+/// under `eval_graph`'s value equality, not just up to materialization —
+/// rules that would swap a materialized output for a `View` (or back) are
+/// guarded so they don't fire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// `v i gather j gather` → `v i j gather gather` (compose the
+    /// positions, then gather the values once). Fires only when the inner
+    /// gather has no other consumer, so no work is duplicated.
+    GatherGather,
+    /// `src m1 filter m2 filter` → `src m1 m2 mask.compose filter`, for
+    /// flat (P8) masks. Fires only when the inner filter has no other
+    /// consumer, so `src` isn't filtered twice.
+    FilterFilter,
+    /// `x f n take` → `x n take f` (likewise `skip`) for unary,
+    /// length-preserving, order-preserving maps `f` (`neg`/`abs`, `as`,
+    /// `not`, `enswizzle`/`deswizzle`) over a flat column: map only the
+    /// rows that survive. Fires only when `f`'s output has no other
+    /// consumer.
+    TakeBelowMap,
+    /// `x reverse reverse` → `x`.
+    ReverseReverse,
+    /// `x enswizzle.<i> deswizzle.<i>` → `x` (and the converse): the
+    /// swizzles are bijections, inverse to each other at the same interp.
+    SwizzlePair,
+}
+
+impl Rule {
+    pub const ALL: &'static [Rule] = &[
+        Rule::GatherGather, Rule::FilterFilter, Rule::TakeBelowMap,
+        Rule::ReverseReverse, Rule::SwizzlePair,
+    ];
+}
+
+/// Whether `op` is known to push a fresh, fully materialized value (never a
+/// `View` or an `Encoded` column). `Cmp` is out: an RLE operand gives an
+/// RLE mask. Cancellation rules replace `f(f⁻¹(x))` — which materializes —
+/// with `x` itself, so they require this of `x`'s producer.
+pub(crate) fn materializes(op: &SystemOp) -> bool {
+    match op {
+        SystemOp::Const(v) => !matches!(v, Value::View { .. } | Value::Encoded(_)),
+        SystemOp::Arith { .. } | SystemOp::UnaryArith { .. } | SystemOp::Decode
+        | SystemOp::As { .. } | SystemOp::Not | SystemOp::And | SystemOp::Or
+        | SystemOp::Any | SystemOp::All | SystemOp::Reduce { .. } | SystemOp::ReduceOrd { .. } | SystemOp::Cumsum { .. }
+        | SystemOp::Shift { .. } | SystemOp::Count | SystemOp::Where | SystemOp::MaskCompose
+        | SystemOp::Gather | SystemOp::Spread | SystemOp::Iota | SystemOp::SortPerm
+        | SystemOp::Enswizzle { .. } | SystemOp::Deswizzle { .. }
+        | SystemOp::Take | SystemOp::Skip | SystemOp::Reverse => true,
+        _ => false,
+    }
+}
+
+fn is_unary_map(op: &SystemOp) -> bool {
+    matches!(op, SystemOp::UnaryArith { .. } | SystemOp::As { .. } | SystemOp::Not
+        | SystemOp::Enswizzle { .. } | SystemOp::Deswizzle { .. })
+}
+
+/// A copy of a map op's variant (the ops `is_unary_map` accepts are all
+/// plain data), so a rewrite can re-emit it above a new child.
+fn clone_unary_map(op: &SystemOp) -> Option<SystemOp> {
+    Some(match op {
+        SystemOp::UnaryArith { op, interp } => SystemOp::UnaryArith { op: *op, interp: *interp },
+        SystemOp::As { interp } => SystemOp::As { interp: *interp },
+        SystemOp::Not => SystemOp::Not,
+        SystemOp::Enswizzle { interp } => SystemOp::Enswizzle { interp: *interp },
+        SystemOp::Deswizzle { interp } => SystemOp::Deswizzle { interp: *interp },
+        _ => return None,
+    })
+}
+
+/// Apply `rules` to `g` in one topological sweep. Each term is rewritten
+/// against the already-rewritten producers of its inputs, so a rule's
+/// output can feed a later rule in the same sweep. Inserted terms are
+/// placed just before the term they serve (keeping topological order);
+/// producers a rewrite bypasses are left for `eliminate_dead`. Rules that
+/// need shapes read them from `term_shapes`; if the graph doesn't
+/// typecheck, it is returned unchanged. Returns the new graph and the
+/// number of rewrites applied.
+pub fn rewrite(g: Graph, rules: &[Rule]) -> (Graph, usize) {
+    let shapes = match term_shapes(&g) {
+        Ok(s) => s,
+        Err(_) => return (g, 0),
+    };
+    let uses = crate::pipeline::execute::use_counts(&g);
+    let on = |r: Rule| rules.contains(&r);
+
+    // Per new term: its output shapes and, for copied terms, the old
+    // term's per-output consumer counts (inserted terms serve exactly one
+    // consumer).
+    let mut new_shapes: Vec<Vec<Shape>> = Vec::with_capacity(g.terms.len());
+    let mut new_uses: Vec<Vec<usize>> = Vec::with_capacity(g.terms.len());
+    let mut new_terms: Vec<Term> = Vec::with_capacity(g.terms.len());
+    let mut map: Vec<Vec<OutRef>> = Vec::with_capacity(g.terms.len());
+    let mut hits = 0usize;
+
+    let push = |terms: &mut Vec<Term>, sh: &mut Vec<Vec<Shape>>, us: &mut Vec<Vec<usize>>,
+                op: SystemOp, children: Vec<OutRef>, shape: Shape| -> OutRef {
+        terms.push(Term { op, children, n_outputs: 1 });
+        sh.push(vec![shape]);
+        us.push(vec![1]);
+        OutRef { term: terms.len() - 1, idx: 0 }
+    };
+
+    for (old_id, mut term) in g.terms.into_iter().enumerate() {
+        for ch in term.children.iter_mut() {
+            *ch = map[ch.term][ch.idx];
+        }
+        let out_shape = shapes[old_id].first().cloned();
+        let producer = |r: OutRef| &new_terms[r.term];
+        let single_use = |r: OutRef, us: &Vec<Vec<usize>>| us[r.term][r.idx] == 1;
+
+        // Rewrites that forward this term's (single) output to an existing
+        // value: no new term.
+        let forward: Option<OutRef> = match &term.op {
+            SystemOp::Reverse if on(Rule::ReverseReverse) => {
+                let inner = producer(term.children[0]);
+                match inner.op {
+                    SystemOp::Reverse => {
+                        let x = inner.children[0];
+                        let x_ok = materializes(&new_terms[x.term].op)
+                            && matches!(new_shapes[x.term][x.idx], Shape::Prim(_));
+                        if x_ok { Some(x) } else { None }
+                    }
+                    _ => None,
+                }
+            }
+            SystemOp::Enswizzle { interp } | SystemOp::Deswizzle { interp } if on(Rule::SwizzlePair) => {
+                let outer_enc = matches!(term.op, SystemOp::Enswizzle { .. });
+                let inner = producer(term.children[0]);
+                let pair = match inner.op {
+                    SystemOp::Enswizzle { interp: i } => !outer_enc && i == *interp,
+                    SystemOp::Deswizzle { interp: i } => outer_enc && i == *interp,
+                    _ => false,
+                };
+                let x = inner.children.first().copied();
+                match x {
+                    Some(x) if pair && materializes(&new_terms[x.term].op) => Some(x),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some(x) = forward {
+            hits += 1;
+            new_uses[x.term][x.idx] += uses[old_id][0];
+            map.push(vec![x]);
+            continue;
+        }
+
+        // Rewrites that re-emit this term over new inputs.
+        match &term.op {
+            SystemOp::Gather if on(Rule::GatherGather) => {
+                let (a, j) = (term.children[0], term.children[1]);
+                let inner = producer(a);
+                if matches!(inner.op, SystemOp::Gather) && single_use(a, &new_uses) {
+                    let (v, i) = (inner.children[0], inner.children[1]);
+                    let ij = push(&mut new_terms, &mut new_shapes, &mut new_uses,
+                                  SystemOp::Gather, vec![i, j], Shape::Prim(crate::ir::value::PrimWidth::W64));
+                    term.children = vec![v, ij];
+                    hits += 1;
+                }
+            }
+            SystemOp::Filter if on(Rule::FilterFilter) => {
+                let (a, m2) = (term.children[0], term.children[1]);
+                let inner = producer(a);
+                let p8 = Shape::Prim(crate::ir::value::PrimWidth::W8);
+                if matches!(inner.op, SystemOp::Filter) && single_use(a, &new_uses) {
+                    let (src, m1) = (inner.children[0], inner.children[1]);
+                    if new_shapes[m1.term][m1.idx] == p8 && new_shapes[m2.term][m2.idx] == p8 {
+                        let m = push(&mut new_terms, &mut new_shapes, &mut new_uses,
+                                     SystemOp::MaskCompose, vec![m1, m2], p8);
+                        term.children = vec![src, m];
+                        hits += 1;
+                    }
+                }
+            }
+            SystemOp::Take | SystemOp::Skip if on(Rule::TakeBelowMap) => {
+                let (a, n) = (term.children[0], term.children[1]);
+                let inner = producer(a);
+                if is_unary_map(&inner.op) && single_use(a, &new_uses) {
+                    let x = inner.children[0];
+                    if let (Some(f), Shape::Prim(_)) = (clone_unary_map(&inner.op), &new_shapes[x.term][x.idx]) {
+                        let x_shape = new_shapes[x.term][x.idx].clone();
+                        let slice_op = if matches!(term.op, SystemOp::Take) { SystemOp::Take } else { SystemOp::Skip };
+                        let sliced = push(&mut new_terms, &mut new_shapes, &mut new_uses,
+                                          slice_op, vec![x, n], x_shape);
+                        term.op = f;
+                        term.children = vec![sliced];
+                        hits += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let id = new_terms.len();
+        map.push((0..term.n_outputs).map(|idx| OutRef { term: id, idx }).collect());
+        new_shapes.push(if term.n_outputs == 1 { out_shape.into_iter().collect() } else { shapes[old_id].clone() });
+        new_uses.push(uses[old_id].clone());
+        new_terms.push(term);
+    }
+    let new_roots = g.roots.iter().map(|r| map[r.term][r.idx]).collect();
+    (Graph { terms: new_terms, roots: new_roots }, hits)
+}
+
+/// Run `rewrite` to a fixpoint (bounded), collecting bypassed producers
+/// between sweeps so consumer counts stay exact for the single-use guards.
+pub fn rewrite_fixpoint(mut g: Graph, rules: &[Rule]) -> (Graph, usize) {
+    let mut total = 0usize;
+    for _ in 0..8 {
+        let (g2, hits) = rewrite(g, rules);
+        g = eliminate_dead(g2);
+        total += hits;
+        if hits == 0 { break; }
+    }
+    (g, total)
+}
+
 /// Dead-term elimination. Keeps only terms reachable from `roots`, plus
 /// side-effecting ops (whose execution is observable beyond their data
 /// outputs — `time`/`show`/`profile.*`). Reindexes survivors. (No
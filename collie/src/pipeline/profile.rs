@@ -0,0 +1,178 @@
+//! Per-term profile of one graph evaluation.
+//!
+//! `execute::eval_graph_profiled` records a [`TermProfile`] for every term
+//! it runs: wall time, input and output row counts, bytes allocated while
+//! the kernel ran, and the output shapes. [`GraphProfile`] then renders
+//! that three ways:
+//!
+//! - [`GraphProfile::table`] — terms sorted by time, for the terminal;
+//! - [`GraphProfile::chrome_trace`] — Chrome trace-event JSON (load in
+//!   `chrome://tracing` or Perfetto), one complete event per term;
+//! - [`GraphProfile::dot`] — the graph as Graphviz DOT, nodes shaded by
+//!   their share of the run and edges labelled with row counts.
+//!
+//! Unlike `ir::profile` (keyed by op name, opted into with
+//! `profile.start`), this is keyed by term, so two `gather`s in different
+//! parts of a query show up separately.
+//!
+//! Bytes come from `ir::profile::CountingAlloc` and are `None` unless the
+//! running binary installed it as the global allocator and started it
+//! (`collie` does both under `--profile`).
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use crate::ir::shape::Shape;
+use crate::pipeline::graph::Graph;
+
+/// What one term cost.
+#[derive(Clone, Debug)]
+pub struct TermProfile {
+    /// Index into `Graph::terms`.
+    pub term: usize,
+    pub op: String,
+    /// Offset from the start of the evaluation.
+    pub start: Duration,
+    pub elapsed: Duration,
+    /// Row count of each input, in stack order.
+    pub in_rows: Vec<usize>,
+    /// Row count of each output, in stack order.
+    pub out_rows: Vec<usize>,
+    /// Bytes allocated while the kernel ran (`None` without the counting
+    /// allocator).
+    pub bytes: Option<u64>,
+    pub shapes: Vec<Shape>,
+}
+
+/// The per-term profile of a whole evaluation, in term order.
+#[derive(Clone, Debug, Default)]
+pub struct GraphProfile {
+    pub terms: Vec<TermProfile>,
+    /// Wall time of the whole evaluation (terms plus the engine's own
+    /// bookkeeping).
+    pub total: Duration,
+}
+
+fn ms(d: Duration) -> f64 { d.as_secs_f64() * 1000.0 }
+
+fn join_rows(rows: &[usize]) -> String {
+    if rows.is_empty() { return "-".to_string(); }
+    rows.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn join_shapes(shapes: &[Shape]) -> String {
+    shapes.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// JSON string literal (quotes included).
+fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Escape one line of a double-quoted DOT label.
+fn dot_str(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl GraphProfile {
+    /// Sum of the terms' own times — what the share column divides by.
+    pub fn term_time(&self) -> Duration {
+        self.terms.iter().map(|t| t.elapsed).sum()
+    }
+
+    fn share(&self, t: &TermProfile) -> f64 {
+        let total = self.term_time().as_secs_f64();
+        if total == 0.0 { 0.0 } else { t.elapsed.as_secs_f64() / total }
+    }
+
+    /// Human-readable table, slowest term first.
+    pub fn table(&self) -> String {
+        let mut order: Vec<&TermProfile> = self.terms.iter().collect();
+        order.sort_by(|a, b| b.elapsed.cmp(&a.elapsed).then(a.term.cmp(&b.term)));
+        let mut out = String::new();
+        let _ = writeln!(out, "profile: {} terms, {:.3} ms in terms, {:.3} ms total",
+                         self.terms.len(), ms(self.term_time()), ms(self.total));
+        let _ = writeln!(out, "  {:>6}  {:<16} {:>10} {:>6}  {:>14} {:>14} {:>12}  shape",
+                         "term", "op", "ms", "share", "rows in", "rows out", "bytes");
+        for t in order {
+            let bytes = t.bytes.map_or("-".to_string(), |b| b.to_string());
+            let _ = writeln!(out, "  {:>6}  {:<16} {:>10.3} {:>5.1}%  {:>14} {:>14} {:>12}  {}",
+                             format!("t{}", t.term), t.op, ms(t.elapsed), self.share(t) * 100.0,
+                             join_rows(&t.in_rows), join_rows(&t.out_rows), bytes,
+                             join_shapes(&t.shapes));
+        }
+        out
+    }
+
+    /// Chrome trace-event JSON: one complete (`"ph":"X"`) event per term,
+    /// timestamps in microseconds, per-term details under `args`.
+    pub fn chrome_trace(&self) -> String {
+        let mut out = String::from("{\"traceEvents\":[\n");
+        let _ = write!(out, "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":1,\"args\":{{\"name\":\"collie eval_graph\"}}}}");
+        for t in &self.terms {
+            let bytes = t.bytes.map_or("null".to_string(), |b| b.to_string());
+            let _ = write!(out,
+                ",\n{{\"name\":{},\"cat\":\"term\",\"ph\":\"X\",\"pid\":1,\"tid\":1,\"ts\":{:.3},\"dur\":{:.3},\
+                 \"args\":{{\"term\":{},\"rows_in\":[{}],\"rows_out\":[{}],\"bytes\":{},\"shape\":{}}}}}",
+                json_str(&t.op),
+                t.start.as_secs_f64() * 1e6, t.elapsed.as_secs_f64() * 1e6,
+                t.term,
+                t.in_rows.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(","),
+                t.out_rows.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(","),
+                bytes, json_str(&join_shapes(&t.shapes)));
+        }
+        out.push_str("\n],\"displayTimeUnit\":\"ms\"}\n");
+        out
+    }
+
+    /// Graphviz rendering of `g` annotated with this profile. Node fill
+    /// deepens with the term's share of the run; each edge carries the row
+    /// count of the output it reads. `g` must be the graph that was
+    /// profiled.
+    pub fn dot(&self, g: &Graph) -> String {
+        let mut out = String::from("digraph collie {\n");
+        out.push_str("  node [shape=box, style=filled, fontname=\"monospace\"];\n");
+        for (i, term) in g.terms.iter().enumerate() {
+            let lines = match self.terms.get(i) {
+                Some(t) => vec![
+                    format!("t{} {}", i, t.op),
+                    format!("{:.3} ms ({:.1}%)", ms(t.elapsed), self.share(t) * 100.0),
+                    format!("rows {}", join_rows(&t.out_rows)),
+                    join_shapes(&t.shapes),
+                ],
+                None => vec![format!("t{} {}", i, term.op.name())],
+            };
+            let label = lines.iter().filter(|l| !l.is_empty()).map(|l| dot_str(l)).collect::<Vec<_>>().join("\\n");
+            let heat = self.terms.get(i).map_or(0.0, |t| self.share(t));
+            let _ = writeln!(out, "  t{} [label=\"{}\", fillcolor=\"0.000 {:.3} 1.000\"];",
+                             i, label, heat);
+        }
+        let rows_of = |term: usize, idx: usize| self.terms.get(term).and_then(|t| t.out_rows.get(idx)).copied();
+        for (i, term) in g.terms.iter().enumerate() {
+            for ch in &term.children {
+                match rows_of(ch.term, ch.idx) {
+                    Some(r) => { let _ = writeln!(out, "  t{} -> t{} [label=\"{}\"];", ch.term, i, r); }
+                    None => { let _ = writeln!(out, "  t{} -> t{};", ch.term, i); }
+                }
+            }
+        }
+        for (k, r) in g.roots.iter().enumerate() {
+            let _ = writeln!(out, "  root{} [label=\"[{}]\", shape=plaintext, style=\"\"];", k, g.roots.len() - 1 - k);
+            let _ = writeln!(out, "  t{} -> root{};", r.term, k);
+        }
+        out.push_str("}\n");
+        out
+    }
+}
@@ -23,9 +23,10 @@
 use crate::ir::stack::Stack;
 use crate::ir::typecheck::{Op, TypeStack, TypeEnv};
 use crate::ir::value::Value;
-use crate::ir::shape::Interp;
+use crate::ir::shape::{Interp, Shape};
 use crate::ops::arith::{ArithOp, UnaryArithOp};
 use crate::ops::cmp::CmpOp;
+use crate::ops::encode::Encoding;
 
 /// Which associative reduction (`reduce.+/min/max/*`).
 #[derive(Debug, Clone, Copy)]
@@ -42,24 +43,31 @@ pub enum SystemOp {
     Not, And, Or, Any, All,
     // Aggregations / scans
     Reduce { kind: ReduceKind, interp: Interp },
+    /// Interp-less `reduce.min`/`reduce.max`, by unsigned word until
+    /// `interp::elaborate` resolves it to a `Reduce`.
+    ReduceOrd { max: bool },
     Cumsum { interp: Interp },
     Shift { interp: Interp },
     Count,
     // Surveys / joins
-    Where, Filter, Gather, Spread,
+    Where, Filter, MaskCompose, Gather, Spread,
     Intersect,
     Search,
+    AsofJoin { interp: Interp }, IntervalJoin { interp: Interp },
     XProd,
     // Sort family
     SortPerm, Sort, SortSegmented, Group, Unique,
     Enswizzle { interp: Interp }, Deswizzle { interp: Interp },
     // Structural — Prod
     Zip { n: usize }, Detuple { n: usize }, Proj { i: usize },
+    Pivot { n: usize }, Unpivot,
     // Structural — Sum
     Inject { n: usize }, Split, Merge { n: usize }, Partition { n: usize }, Branch { k: usize },
     // Structural — List / View
     Nest, NestStride, Flatten, Bounds, ListRanges, BoundsKeys, Head, Like, Enlist, Unlist, Iota,
     View, ViewRange, DecomposeView,
+    // Storage encodings
+    Encode { kind: Encoding }, Decode,
     // Slicing / concat
     Concat, Cat { n: usize }, Take, Skip, Reverse,
     TakeSegmented, ReverseSegmented,
@@ -71,6 +79,11 @@ pub enum SystemOp {
     /// Also the natural home for promoted literals.
     Const(Value),
 
+    /// A run-time input source: pushes input `slot`, which
+    /// `execute::eval_graph_inputs` supplies through the env. `shape` is
+    /// what the graph was typechecked against (see `crate::session`).
+    Input { slot: usize, shape: Shape },
+
     /// An operator the system doesn't model as a first-class variant
     /// (body-bearing, binding, diagnostics, literals, FFI). Opaque to
     /// optimization; runnable/typecheckable via the wrapped kernel.
@@ -82,10 +95,12 @@ impl SystemOp {
         match self {
             SystemOp::Foreign(o) => o.name().to_string(),
             SystemOp::Const(_) => "const".to_string(),
+            SystemOp::Input { slot, .. } => format!("input.{}", slot),
             SystemOp::Reduce { kind, .. } => match kind {
                 ReduceKind::Add => "reduce.+", ReduceKind::Min => "reduce.min",
                 ReduceKind::Max => "reduce.max", ReduceKind::Mul => "reduce.*",
             }.to_string(),
+            SystemOp::ReduceOrd { max } => if *max { "reduce.max" } else { "reduce.min" }.to_string(),
             SystemOp::Cmp { op } => crate::ops::cmp::op_name(*op).to_string(),
             SystemOp::Arith { op, .. } => crate::ops::arith::op_name(*op).to_string(),
             SystemOp::UnaryArith { op, .. } => crate::ops::arith::unary_name(*op).to_string(),
@@ -110,15 +125,22 @@ impl SystemOp {
             SystemOp::View => "view".to_string(),
             SystemOp::ViewRange => "view.range".to_string(),
             SystemOp::DecomposeView => "decompose-view".to_string(),
+            SystemOp::Encode { kind } => match kind {
+                Encoding::Rle => "encode.rle", Encoding::For => "encode.for",
+            }.to_string(),
+            SystemOp::Decode => "decode".to_string(),
             SystemOp::Gather => "gather".to_string(),
             SystemOp::Intersect => "intersect".to_string(),
             SystemOp::Search => "search".to_string(),
+            SystemOp::AsofJoin { .. } => "join.asof".to_string(),
+            SystemOp::IntervalJoin { .. } => "join.interval".to_string(),
             SystemOp::XProd => "xprod".to_string(),
             SystemOp::Cumsum { .. } => "cumsum".to_string(),
             SystemOp::Shift { .. } => "shift".to_string(),
             SystemOp::Count => "count".to_string(),
             SystemOp::Where => "where".to_string(),
             SystemOp::Filter => "filter".to_string(),
+            SystemOp::MaskCompose => "mask.compose".to_string(),
             SystemOp::Spread => "spread".to_string(),
             SystemOp::Group => "group".to_string(),
             SystemOp::Unique => "unique".to_string(),
@@ -131,6 +153,8 @@ impl SystemOp {
             SystemOp::Zip { .. } => "zip".to_string(),
             SystemOp::Detuple { .. } => "detuple".to_string(),
             SystemOp::Proj { .. } => ".".to_string(),
+            SystemOp::Pivot { n } => format!("pivot.{}", n),
+            SystemOp::Unpivot => "unpivot".to_string(),
             SystemOp::Inject { .. } => "inject".to_string(),
             SystemOp::Split => "split".to_string(),
             SystemOp::Merge { .. } => "merge".to_string(),
@@ -148,12 +172,18 @@ impl SystemOp {
         match self {
             SystemOp::Foreign(o) => o.run(st, env),
             SystemOp::Const(v) => { st.push(v.clone()); Ok(()) }
+            SystemOp::Input { slot, .. } => {
+                let v = env.get(*slot).ok_or_else(|| format!("input {}: no value supplied ({} inputs)", slot, env.len()))?;
+                st.push(v.clone());
+                Ok(())
+            }
             SystemOp::Reduce { kind, interp } => match kind {
                 ReduceKind::Add => crate::ops::list::reduce_add_run(*interp, st),
                 ReduceKind::Min => crate::ops::reduce_ops::reduce_min_run(*interp, st),
                 ReduceKind::Max => crate::ops::reduce_ops::reduce_max_run(*interp, st),
                 ReduceKind::Mul => crate::ops::reduce_ops::reduce_mul_run(*interp, st),
             },
+            SystemOp::ReduceOrd { max } => crate::ops::reduce_ops::reduce_ord_run(*max, st),
             SystemOp::Cmp { op } => crate::ops::cmp::run(*op, st),
             SystemOp::Arith { op, interp } => crate::ops::arith::run(*op, *interp, st),
             SystemOp::UnaryArith { op, interp } => crate::ops::arith::unary_run(*op, *interp, st),
@@ -178,15 +208,20 @@ impl SystemOp {
             SystemOp::View => crate::ops::view::view_run(st),
             SystemOp::ViewRange => crate::ops::view::view_range_run(st),
             SystemOp::DecomposeView => crate::ops::view::decompose_view_run(st),
+            SystemOp::Encode { kind } => crate::ops::encode::encode_run(*kind, st),
+            SystemOp::Decode => crate::ops::encode::decode_run(st),
             SystemOp::Gather => crate::ops::join::gather_run(st),
             SystemOp::Intersect => crate::ops::join::intersect_run(st),
             SystemOp::Search => crate::ops::join::search_run(st),
+            SystemOp::AsofJoin { interp } => crate::ops::join::asof_run(*interp, st),
+            SystemOp::IntervalJoin { interp } => crate::ops::join::interval_run(*interp, st),
             SystemOp::XProd => crate::ops::join::xprod_run(st),
             SystemOp::Cumsum { interp } => crate::ops::list::cumsum_run(*interp, st),
             SystemOp::Shift { interp } => crate::ops::list::shift_run(*interp, st),
             SystemOp::Count => crate::ops::list::count_run(st),
             SystemOp::Where => crate::ops::list::where_run(st),
             SystemOp::Filter => crate::ops::list::filter_run(st),
+            SystemOp::MaskCompose => crate::ops::list::mask_compose_run(st),
             SystemOp::Spread => crate::ops::list::spread_run(st),
             SystemOp::Group => crate::ops::list::group_run(st),
             SystemOp::Unique => crate::ops::list::unique_run(st),
@@ -199,6 +234,8 @@ impl SystemOp {
             SystemOp::Zip { n } => crate::ops::combinators::zip_run(*n, st),
             SystemOp::Detuple { n } => crate::ops::combinators::detuple_run(*n, st),
             SystemOp::Proj { i } => crate::ops::combinators::proj_run(*i, st),
+            SystemOp::Pivot { n } => crate::ops::pivot::pivot_run(*n, st),
+            SystemOp::Unpivot => crate::ops::pivot::unpivot_run(st),
             SystemOp::Inject { n } => crate::ops::combinators::inject_run(*n, st),
             SystemOp::Split => crate::ops::combinators::split_run(st),
             SystemOp::Merge { n } => crate::ops::combinators::merge_run(*n, st),
@@ -216,12 +253,14 @@ impl SystemOp {
         match self {
             SystemOp::Foreign(o) => o.tc(st, env),
             SystemOp::Const(v) => { st.push(crate::ir::shape::shape_of(v)); Ok(()) }
+            SystemOp::Input { shape, .. } => { st.push(shape.clone()); Ok(()) }
             SystemOp::Reduce { kind, interp } => match kind {
                 ReduceKind::Add => crate::ops::list::reduce_add_tc(*interp, st),
                 ReduceKind::Min => crate::ops::reduce_ops::reduce_min_tc(*interp, st),
                 ReduceKind::Max => crate::ops::reduce_ops::reduce_max_tc(*interp, st),
                 ReduceKind::Mul => crate::ops::reduce_ops::reduce_mul_tc(*interp, st),
             },
+            SystemOp::ReduceOrd { max } => crate::ops::reduce_ops::reduce_ord_tc(*max, st),
             SystemOp::Cmp { .. } => crate::ops::cmp::tc(st),
             SystemOp::Arith { interp, .. } => crate::ops::arith::tc(*interp, st),
             SystemOp::UnaryArith { op, interp } => crate::ops::arith::unary_tc(*op, *interp, st),
@@ -246,15 +285,22 @@ impl SystemOp {
             SystemOp::View => crate::ops::view::view_tc(st),
             SystemOp::ViewRange => crate::ops::view::view_range_tc(st),
             SystemOp::DecomposeView => crate::ops::view::decompose_view_tc(st),
+            SystemOp::Encode { kind } => crate::ops::encode::encode_tc(st, match kind {
+                Encoding::Rle => "encode.rle", Encoding::For => "encode.for",
+            }),
+            SystemOp::Decode => crate::ops::encode::decode_tc(st),
             SystemOp::Gather => crate::ops::join::gather_tc(st),
             SystemOp::Intersect => crate::ops::join::intersect_tc(st),
             SystemOp::Search => crate::ops::join::search_tc(st),
+            SystemOp::AsofJoin { interp } => crate::ops::join::asof_tc(*interp, st),
+            SystemOp::IntervalJoin { interp } => crate::ops::join::interval_tc(*interp, st),
             SystemOp::XProd => crate::ops::join::xprod_tc(st),
             SystemOp::Cumsum { interp } => crate::ops::list::cumsum_tc(*interp, st),
             SystemOp::Shift { interp } => crate::ops::list::shift_tc(*interp, st),
             SystemOp::Count => crate::ops::list::count_tc(st),
             SystemOp::Where => crate::ops::list::where_tc(st),
             SystemOp::Filter => crate::ops::list::filter_tc(st),
+            SystemOp::MaskCompose => crate::ops::list::mask_compose_tc(st),
             SystemOp::Spread => crate::ops::list::spread_tc(st),
             SystemOp::Group => crate::ops::list::group_tc(st),
             SystemOp::Unique => crate::ops::list::unique_tc(st),
@@ -267,6 +313,8 @@ impl SystemOp {
             SystemOp::Zip { n } => crate::ops::combinators::zip_tc(*n, st),
             SystemOp::Detuple { n } => crate::ops::combinators::detuple_tc(*n, st),
             SystemOp::Proj { i } => crate::ops::combinators::proj_tc(*i, st),
+            SystemOp::Pivot { n } => crate::ops::pivot::pivot_tc(*n, st),
+            SystemOp::Unpivot => crate::ops::pivot::unpivot_tc(st),
             SystemOp::Inject { n } => crate::ops::combinators::inject_tc(*n, st),
             SystemOp::Split => crate::ops::combinators::split_tc(st),
             SystemOp::Merge { n } => crate::ops::combinators::merge_tc(*n, st),
@@ -283,14 +331,14 @@ impl SystemOp {
     pub fn arity(&self) -> Option<(usize, usize)> {
         match self {
             SystemOp::Foreign(o) => o.arity(),
-            SystemOp::Const(_) => Some((0, 1)),
+            SystemOp::Const(_) | SystemOp::Input { .. } => Some((0, 1)),
             SystemOp::Cmp { .. } => Some((2, 1)),
             SystemOp::Arith { .. } => Some((2, 1)),
             SystemOp::UnaryArith { .. } => Some((1, 1)),
             SystemOp::As { .. } => Some((1, 1)),
             SystemOp::Not | SystemOp::Any | SystemOp::All => Some((1, 1)),
             SystemOp::And | SystemOp::Or => Some((2, 1)),
-            SystemOp::Reduce { .. } => Some((1, 1)),
+            SystemOp::Reduce { .. } | SystemOp::ReduceOrd { .. } => Some((1, 1)),
             SystemOp::Concat | SystemOp::Reverse | SystemOp::ReverseSegmented => Some((1, 1)),
             SystemOp::Take | SystemOp::Skip | SystemOp::TakeSegmented => Some((2, 1)),
             SystemOp::Cat { n } => Some((*n, 1)),
@@ -299,15 +347,18 @@ impl SystemOp {
             SystemOp::View => Some((2, 1)),
             SystemOp::ViewRange => Some((3, 1)),
             SystemOp::DecomposeView => Some((1, 2)),
+            SystemOp::Encode { .. } | SystemOp::Decode => Some((1, 1)),
             SystemOp::Gather | SystemOp::Search => Some((2, 1)),
-            SystemOp::Intersect => Some((2, 2)),
+            SystemOp::Intersect | SystemOp::AsofJoin { .. } | SystemOp::IntervalJoin { .. } => Some((2, 2)),
             SystemOp::XProd => Some((1, 1)),
             SystemOp::Cumsum { .. } | SystemOp::Count | SystemOp::Where | SystemOp::Unique
             | SystemOp::Bounds | SystemOp::ListRanges | SystemOp::BoundsKeys
             | SystemOp::Head | SystemOp::Iota => Some((1, 1)),
-            SystemOp::Shift { .. } | SystemOp::Filter | SystemOp::Spread | SystemOp::Like => Some((2, 1)),
+            SystemOp::Shift { .. } | SystemOp::Filter | SystemOp::MaskCompose
+            | SystemOp::Spread | SystemOp::Like => Some((2, 1)),
             SystemOp::Group => Some((2, 2)),
-            SystemOp::Proj { .. } | SystemOp::Enlist | SystemOp::Unlist => Some((1, 1)),
+            SystemOp::Proj { .. } | SystemOp::Enlist | SystemOp::Unlist | SystemOp::Unpivot => Some((1, 1)),
+            SystemOp::Pivot { .. } => Some((2, 1)),
             SystemOp::Zip { n } => Some((*n, 1)),
             SystemOp::Detuple { n } => Some((1, *n)),
             SystemOp::Inject { n } | SystemOp::Merge { n } => Some((*n + 1, 1)),
@@ -373,14 +424,18 @@ pub fn promote(op: Box<dyn Op>) -> SystemOp {
     one!(red::ReduceMin, interp, SystemOp::Reduce { kind: ReduceKind::Min, interp });
     one!(red::ReduceMax, interp, SystemOp::Reduce { kind: ReduceKind::Max, interp });
     one!(red::ReduceMul, interp, SystemOp::Reduce { kind: ReduceKind::Mul, interp });
+    one!(red::ReduceOrd, max, SystemOp::ReduceOrd { max });
     one!(list::Cumsum, interp, SystemOp::Cumsum { interp });
     one!(list::Shift, interp, SystemOp::Shift { interp });
     zst!(list::Count, SystemOp::Count);
     // Surveys / joins
     zst!(list::Where_, SystemOp::Where); zst!(list::Filter, SystemOp::Filter);
+    zst!(list::MaskCompose, SystemOp::MaskCompose);
     zst!(join::Gather, SystemOp::Gather); zst!(list::Spread, SystemOp::Spread);
     zst!(join::Intersect, SystemOp::Intersect);
     zst!(join::Search, SystemOp::Search);
+    one!(join::AsofJoin, interp, SystemOp::AsofJoin { interp });
+    one!(join::IntervalJoin, interp, SystemOp::IntervalJoin { interp });
     zst!(join::XProd, SystemOp::XProd);
     // Sort family
     zst!(sort::SortPerm, SystemOp::SortPerm); zst!(sort::SortPoly, SystemOp::Sort);
@@ -393,6 +448,8 @@ pub fn promote(op: Box<dyn Op>) -> SystemOp {
     one!(cmb::ZipN, n, SystemOp::Zip { n });
     one!(cmb::DetupleN, n, SystemOp::Detuple { n });
     one!(cmb::Proj, i, SystemOp::Proj { i });
+    one!(crate::ops::pivot::Pivot, n, SystemOp::Pivot { n });
+    zst!(crate::ops::pivot::Unpivot, SystemOp::Unpivot);
     one!(cmb::InjectN, n, SystemOp::Inject { n });
     zst!(cmb::Split, SystemOp::Split);
     one!(cmb::MergeN, n, SystemOp::Merge { n });
@@ -408,6 +465,8 @@ pub fn promote(op: Box<dyn Op>) -> SystemOp {
     zst!(crate::ops::view::View, SystemOp::View);
     zst!(crate::ops::view::ViewRange, SystemOp::ViewRange);
     zst!(crate::ops::view::DecomposeView, SystemOp::DecomposeView);
+    one!(crate::ops::encode::Encode, kind, SystemOp::Encode { kind });
+    zst!(crate::ops::encode::Decode, SystemOp::Decode);
     // Slicing / concat
     zst!(sc::Concat, SystemOp::Concat);
     one!(sc::CatN, n, SystemOp::Cat { n });
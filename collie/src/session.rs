@@ -0,0 +1,228 @@
+//! Host embedding: named input tables, programs compiled once into plans,
+//! plans executed many times.
+//!
+//! A [`Session`] holds named tables, each a `Value` with a declared
+//! `Shape`. [`Session::compile`] turns a `.col` program that refers to the
+//! tables by name into a [`Plan`]: parsed with the names bound around it
+//! (`parse::parse_program_bound`), lowered against the declared shapes —
+//! each table is a `SystemOp::Input` source, so none of its contents is
+//! baked into the graph — and optimized. [`Session::execute`] runs a plan
+//! over the tables' current contents (`execute::eval_graph_inputs`) and
+//! hands the roots back under the names given at compile time.
+//!
+//! ```ignore
+//! let mut s = Session::new();
+//! s.declare("sales", Shape::Prod(vec![Shape::Prim(PrimWidth::W64); 2]))?;
+//! let plan = s.compile("sales dup .1 swap .0 group reduce.+.u64", &["region", "total"])?;
+//! loop {
+//!     s.set("sales", load_sales())?;
+//!     let out = s.execute(&plan)?;
+//!     report(out.get("region"), out.get("total"));
+//! }
+//! ```
+//!
+//! A plan stays valid while the tables it reads keep their declared
+//! shapes; redeclaring one with another shape makes `execute` refuse the
+//! plan until it is recompiled.
+
+use crate::ir::shape::{shape_of, Shape};
+use crate::ir::value::Value;
+use crate::pipeline::graph::Graph;
+use crate::pipeline::sysop::SystemOp;
+use crate::pipeline::{build_parsed_inputs, eval_graph_inputs, optimize};
+use crate::syntax::{parse::parse_program_bound, registry::OpRegistry};
+
+/// Named tables, and the registry programs are parsed with.
+pub struct Session {
+    reg: OpRegistry,
+    tables: Vec<Table>,
+}
+
+struct Table {
+    name: String,
+    shape: Shape,
+    value: Option<Value>,
+}
+
+/// A program compiled against a session's table shapes.
+#[derive(Debug)]
+pub struct Plan {
+    graph: Graph,
+    /// Per input slot: the table it reads and the shape it was compiled
+    /// against (every table declared at compile time, in order).
+    inputs: Vec<(String, Shape)>,
+    /// Whether the optimized graph still reads the slot.
+    used: Vec<bool>,
+    /// Names of the roots, bottom-to-top.
+    outputs: Vec<String>,
+}
+
+/// The roots of one execution, by name.
+#[derive(Debug)]
+pub struct Outputs {
+    names: Vec<String>,
+    values: Vec<Value>,
+}
+
+impl Default for Session {
+    fn default() -> Self { Self::new() }
+}
+
+impl Session {
+    /// A session over the standard operator registry.
+    pub fn new() -> Self { Self::with_registry(OpRegistry::standard()) }
+
+    /// A session over a custom registry (e.g. with out-of-tree ops added).
+    pub fn with_registry(reg: OpRegistry) -> Self { Session { reg, tables: Vec::new() } }
+
+    /// Declare table `name` with `shape`, without contents yet. Declaring
+    /// an existing table again replaces its shape and drops its contents.
+    pub fn declare(&mut self, name: &str, shape: Shape) -> Result<(), String> {
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(format!("table {:?}: not a valid name", name));
+        }
+        match self.tables.iter_mut().find(|t| t.name == name) {
+            Some(t) => { t.shape = shape; t.value = None; }
+            None => self.tables.push(Table { name: name.to_string(), shape, value: None }),
+        }
+        Ok(())
+    }
+
+    /// Set the contents of declared table `name`; `v` must have its shape.
+    pub fn set(&mut self, name: &str, v: Value) -> Result<(), String> {
+        let t = self.tables.iter_mut().find(|t| t.name == name)
+            .ok_or_else(|| format!("table {}: not declared", name))?;
+        let got = shape_of(&v);
+        if got != t.shape {
+            return Err(format!("table {}: declared {}, got {}", name, t.shape, got));
+        }
+        t.value = Some(v);
+        Ok(())
+    }
+
+    /// Declare `name` with `v`'s shape and set it to `v`.
+    pub fn register(&mut self, name: &str, v: Value) -> Result<(), String> {
+        self.declare(name, shape_of(&v))?;
+        self.set(name, v)
+    }
+
+    /// The current contents of table `name`, if set.
+    pub fn table(&self, name: &str) -> Option<&Value> {
+        self.tables.iter().find(|t| t.name == name).and_then(|t| t.value.as_ref())
+    }
+
+    /// Compile `src` against the declared table shapes. The program must
+    /// leave exactly one value per name in `outputs` (bottom first).
+    /// Errors are rendered against `src`.
+    pub fn compile(&self, src: &str, outputs: &[&str]) -> Result<Plan, String> {
+        let names: Vec<String> = self.tables.iter().map(|t| t.name.clone()).collect();
+        let shapes: Vec<Shape> = self.tables.iter().map(|t| t.shape.clone()).collect();
+        let parsed = parse_program_bound(src, &self.reg, &names).map_err(|d| d.render(src, "<program>"))?;
+        let built = build_parsed_inputs(parsed, &shapes).map_err(|d| d.render(src, "<program>"))?;
+        let n_roots = built.graph.roots.len();
+        if n_roots != outputs.len() {
+            return Err(format!("program leaves {} values, {} output names given", n_roots, outputs.len()));
+        }
+        if let Some((i, dup)) = outputs.iter().enumerate().find(|(i, o)| outputs[..*i].contains(o)) {
+            return Err(format!("output {} ({}): name given twice", i, dup));
+        }
+        let graph = optimize(built.graph);
+        let mut used = vec![false; shapes.len()];
+        for t in &graph.terms {
+            if let SystemOp::Input { slot, .. } = t.op { used[slot] = true; }
+        }
+        Ok(Plan {
+            graph,
+            inputs: names.into_iter().zip(shapes).collect(),
+            used,
+            outputs: outputs.iter().map(|o| o.to_string()).collect(),
+        })
+    }
+
+    /// Run `plan` over the tables' current contents. Every table the plan
+    /// reads must be set, with the shape the plan was compiled against.
+    pub fn execute(&self, plan: &Plan) -> Result<Outputs, String> {
+        let mut inputs = Vec::with_capacity(plan.inputs.len());
+        for ((name, shape), &used) in plan.inputs.iter().zip(&plan.used) {
+            if !used { inputs.push(Value::default()); continue; }
+            let t = self.tables.iter().find(|t| &t.name == name)
+                .ok_or_else(|| format!("table {}: no longer declared", name))?;
+            if &t.shape != shape {
+                return Err(format!("table {}: plan compiled for {}, now declared {}; recompile", name, shape, t.shape));
+            }
+            inputs.push(t.value.clone().ok_or_else(|| format!("table {}: no contents set", name))?);
+        }
+        let values = eval_graph_inputs(&plan.graph, inputs)?;
+        Ok(Outputs { names: plan.outputs.clone(), values })
+    }
+}
+
+impl Plan {
+    /// Output names, bottom-to-top.
+    pub fn outputs(&self) -> &[String] { &self.outputs }
+
+    /// The optimized graph (for `pretty`, profiling, inspection).
+    pub fn graph(&self) -> &Graph { &self.graph }
+
+    /// Names of the tables the plan reads.
+    pub fn reads(&self) -> impl Iterator<Item = &str> {
+        self.inputs.iter().zip(&self.used).filter(|(_, &u)| u).map(|((n, _), _)| n.as_str())
+    }
+}
+
+impl Outputs {
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.names.iter().position(|n| n == name).map(|i| &self.values[i])
+    }
+
+    /// `(name, value)` pairs, bottom-to-top.
+    pub fn into_pairs(self) -> Vec<(String, Value)> {
+        self.names.into_iter().zip(self.values).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::value::{from_vec, prod, PrimWidth};
+
+    fn u64s(xs: &[u64]) -> Value { from_vec::<u64>(xs.to_vec()) }
+
+    #[test]
+    fn plan_reused_across_table_contents() {
+        let mut s = Session::new();
+        s.declare("sales", Shape::Prod(vec![Shape::Prim(PrimWidth::W64); 2])).unwrap();
+        s.register("scale", u64s(&[10])).unwrap();
+        s.register("unused", u64s(&[1, 2, 3])).unwrap();
+        let plan = s.compile("sales dup .1 swap .0 group reduce.+.u64 scale *.u64", &["region", "total"]).unwrap();
+        assert_eq!(plan.reads().collect::<Vec<_>>(), vec!["sales", "scale"]);
+        assert!(s.execute(&plan).unwrap_err().contains("sales: no contents"));
+
+        s.set("sales", prod(vec![u64s(&[1, 2, 1]), u64s(&[5, 6, 7])])).unwrap();
+        let out = s.execute(&plan).unwrap();
+        assert_eq!(out.get("region"), Some(&u64s(&[1, 2])));
+        assert_eq!(out.get("total"), Some(&u64s(&[120, 60])));
+
+        // Same plan, new contents (different row count, same shape).
+        s.set("sales", prod(vec![u64s(&[3, 3, 3, 4]), u64s(&[1, 1, 1, 2])])).unwrap();
+        let out = s.execute(&plan).unwrap().into_pairs();
+        assert_eq!(out, vec![("region".to_string(), u64s(&[3, 4])), ("total".to_string(), u64s(&[30, 20]))]);
+    }
+
+    #[test]
+    fn session_rejects_mismatches() {
+        let mut s = Session::new();
+        s.register("xs", u64s(&[1, 2])).unwrap();
+        assert!(s.set("xs", from_vec::<u32>(vec![1])).unwrap_err().contains("declared P64, got P32"));
+        assert!(s.compile("xs xs", &["a"]).unwrap_err().contains("leaves 2 values"));
+        let e = s.compile("xs reduce.+.u32", &["a"]).unwrap_err();
+        assert!(e.contains("<program>:1:4"), "{}", e);
+
+        let plan = s.compile("xs reduce.+.u64", &["sum"]).unwrap();
+        s.declare("xs", Shape::Prim(PrimWidth::W32)).unwrap();
+        s.set("xs", from_vec::<u32>(vec![1])).unwrap();
+        assert!(s.execute(&plan).unwrap_err().contains("recompile"));
+    }
+}
@@ -0,0 +1,337 @@
+//! Program headers: typed inputs a `.col` program expects from its host,
+//! and how to read the values it leaves.
+//!
+//! ```text
+//! input sales : (u64, i32, f64)     # a table: any number of rows
+//! param k : u64                     # a scalar: exactly one row
+//! output by_region : (region: u64, total: f64)
+//! ```
+//!
+//! `output` lines name and type the program's results, bottom of the stack
+//! first; the runner's writers (`tools::report`) use them for column
+//! labels and per-column interpretations. They bind nothing.
+//!
+//! Declarations are the leading lines of the file (blank and comment-only
+//! lines may sit between them). `split_header` returns them and the
+//! program with those lines blanked, so spans in the body still point at
+//! the right line. The body refers to each declared name as if it were
+//! bound around the whole program (`parse::parse_program_bound`), in
+//! declaration order.
+//!
+//! Types name interpretations, not just widths — the host needs them to
+//! read CSV and literal text — and lower to `Shape`s:
+//!
+//! ```text
+//! T ::= u8 | i8 | u16 | i16 | u32 | i32 | f32 | u64 | i64 | f64 | bool
+//!     | str                 [u8] holding UTF-8 text
+//!     | date | timestamp    i32 days / i64 micros since 1970 (ISO-8601 text)
+//!     | d64.S | d128.S      fixed-point decimals, S digits after the point
+//!     | ( F, …, F )         Prod
+//!     | [ T ]               List
+//!     | < F | … | F >       Sum (u8 disc)
+//!     | T ?                 opt: a value or missing (`ops::opt`)
+//! F ::= T | name : T        a labeled field or lane
+//! ```
+
+use std::fmt;
+
+use crate::ir::shape::{Interp, Shape};
+use crate::ir::span::{Diagnostic, Span};
+use crate::ir::value::PrimWidth;
+use crate::ops::calendar::Temporal;
+use crate::ops::decimal::Dec;
+use crate::ops::opt::{opt_inner, opt_shape};
+use crate::pipeline::interp::Kind;
+use crate::syntax::registry::parse_interp;
+
+/// `input` (a table), `param` (a one-row scalar) or `output` (a result).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeclKind { Input, Param, Output }
+
+impl DeclKind {
+    pub fn keyword(self) -> &'static str {
+        match self { DeclKind::Input => "input", DeclKind::Param => "param", DeclKind::Output => "output" }
+    }
+}
+
+/// One header line.
+#[derive(Clone, Debug)]
+pub struct Decl {
+    pub kind: DeclKind,
+    pub name: String,
+    pub ty: Ty,
+    /// The declaration's name, for errors about its binding.
+    pub span: Span,
+}
+
+impl Decl {
+    /// Whether the program reads it (an `input` or `param`).
+    pub fn is_bound(&self) -> bool { self.kind != DeclKind::Output }
+}
+
+/// A declared type: a `Shape` whose leaves keep their interpretation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Ty {
+    Prim(Interp),
+    /// `bool` — a u8 column of 0/1.
+    Bool,
+    /// `str` — a `[u8]` whose rows are UTF-8 text.
+    Str,
+    /// `date` / `timestamp` — read and written as ISO-8601 text.
+    Temporal(Temporal),
+    /// `d64.S` / `d128.S` — read and written as decimal text.
+    Decimal(Dec),
+    Prod(Vec<Ty>),
+    List(Box<Ty>),
+    Sum(Vec<Ty>),
+    /// `T?` — `T` or missing: the `opt` Sum of `ops::opt`.
+    Opt(Box<Ty>),
+    /// `name : T` as a Prod field or Sum lane; only the label differs from `T`.
+    Labeled(String, Box<Ty>),
+}
+
+impl Ty {
+    pub fn shape(&self) -> Shape {
+        match self {
+            Ty::Prim(i) => Shape::Prim(i.width()),
+            Ty::Bool => Shape::Prim(PrimWidth::W8),
+            Ty::Str => Ty::List(Box::new(Ty::Prim(Interp::U8))).shape(),
+            Ty::Temporal(t) => Shape::Prim(t.width()),
+            Ty::Decimal(d) => d.shape(),
+            Ty::Prod(fs) => Shape::Prod(fs.iter().map(Ty::shape).collect()),
+            Ty::List(t) => Shape::List { bounds: PrimWidth::W64, inner: Box::new(t.shape()) },
+            Ty::Sum(ls) => Shape::Sum { disc: PrimWidth::W8, lanes: ls.iter().map(Ty::shape).collect() },
+            Ty::Opt(t) => opt_shape(t.shape()),
+            Ty::Labeled(_, t) => t.shape(),
+        }
+    }
+
+    /// What interp inference (`pipeline::interp`) starts from for a
+    /// declared value: dates and timestamps are signed day / microsecond
+    /// counts, decimals signed unit counts (a `d128`'s low word unsigned).
+    pub fn kind(&self) -> Kind {
+        match self {
+            Ty::Prim(i) => Kind::Prim(*i),
+            Ty::Bool => Kind::Prim(Interp::U8),
+            Ty::Str => Kind::List(Box::new(Kind::Prim(Interp::U8))),
+            Ty::Temporal(Temporal::Date) => Kind::Prim(Interp::I32),
+            Ty::Temporal(Temporal::Timestamp) => Kind::Prim(Interp::I64),
+            Ty::Decimal(Dec { wide: false, .. }) => Kind::Prim(Interp::I64),
+            Ty::Decimal(Dec { wide: true, .. }) => Kind::Prod(vec![Kind::Prim(Interp::I64), Kind::Prim(Interp::U64)]),
+            Ty::Prod(fs) => Kind::Prod(fs.iter().map(Ty::kind).collect()),
+            Ty::List(t) => Kind::List(Box::new(t.kind())),
+            Ty::Sum(ls) => Kind::Sum(ls.iter().map(Ty::kind).collect()),
+            Ty::Opt(t) => Kind::Sum(vec![Kind::Prod(vec![]), t.kind()]),
+            Ty::Labeled(_, t) => t.kind(),
+        }
+    }
+
+    /// The type without a field label, and the label if any.
+    pub fn unlabeled(&self) -> (Option<&str>, &Ty) {
+        match self {
+            Ty::Labeled(l, t) => (Some(l), t),
+            t => (None, t),
+        }
+    }
+
+    /// The default reading of `shape`: unsigned integers, and `[u8]` as text.
+    pub fn of_shape(shape: &Shape) -> Ty {
+        match shape {
+            Shape::Prim(w) => Ty::Prim(match w {
+                PrimWidth::W8 => Interp::U8,
+                PrimWidth::W16 => Interp::U16,
+                PrimWidth::W32 => Interp::U32,
+                PrimWidth::W64 => Interp::U64,
+            }),
+            Shape::Prod(fs) => Ty::Prod(fs.iter().map(Ty::of_shape).collect()),
+            Shape::List { inner, .. } if **inner == Shape::Prim(PrimWidth::W8) => Ty::Str,
+            Shape::List { inner, .. } => Ty::List(Box::new(Ty::of_shape(inner))),
+            s if opt_inner(s).is_some() => Ty::Opt(Box::new(Ty::of_shape(opt_inner(s).unwrap()))),
+            Shape::Sum { lanes, .. } => Ty::Sum(lanes.iter().map(Ty::of_shape).collect()),
+        }
+    }
+
+    /// The Prim leaves, left to right, when the type is a Prim or a
+    /// (nested) Prod of Prims — the types a row of text can hold. A
+    /// `d128` is one leaf (one field of text) over two words, and so is a
+    /// `T?` over such a leaf (a field that may be empty).
+    pub fn flat_leaves(&self) -> Option<Vec<&Ty>> {
+        match self {
+            Ty::Prim(_) | Ty::Bool | Ty::Temporal(_) | Ty::Decimal(_) => Some(vec![self]),
+            Ty::Opt(t) => match t.flat_leaves()?.as_slice() {
+                [leaf] if !matches!(leaf, Ty::Opt(_)) => Some(vec![self]),
+                _ => None,
+            },
+            Ty::Prod(fs) => {
+                let mut out = Vec::new();
+                for f in fs { out.extend(f.flat_leaves()?); }
+                Some(out)
+            }
+            Ty::Labeled(_, t) => t.flat_leaves(),
+            Ty::Str | Ty::List(_) | Ty::Sum(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, ts: &[Ty], sep: &str| -> fmt::Result {
+            for (i, t) in ts.iter().enumerate() {
+                if i > 0 { write!(f, "{}", sep)?; }
+                write!(f, "{}", t)?;
+            }
+            Ok(())
+        };
+        match self {
+            Ty::Prim(i) => write!(f, "{}", i),
+            Ty::Bool => write!(f, "bool"),
+            Ty::Str => write!(f, "str"),
+            Ty::Temporal(t) => write!(f, "{}", t),
+            Ty::Decimal(d) => write!(f, "{}", d),
+            Ty::Prod(fs) => { write!(f, "(")?; join(f, fs, ", ")?; write!(f, ")") }
+            Ty::List(t) => write!(f, "[{}]", t),
+            Ty::Sum(ls) => { write!(f, "<")?; join(f, ls, " | ")?; write!(f, ">") }
+            Ty::Opt(t) => write!(f, "{}?", t),
+            Ty::Labeled(l, t) => write!(f, "{}: {}", l, t),
+        }
+    }
+}
+
+impl fmt::Display for Decl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} : {}", self.kind.keyword(), self.name, self.ty)
+    }
+}
+
+/// Split the leading declarations off `src`. Returns them, in order, and
+/// `src` with their lines blanked.
+pub fn split_header(src: &str) -> Result<(Vec<Decl>, String), Diagnostic> {
+    let mut decls: Vec<Decl> = Vec::new();
+    let mut body = String::with_capacity(src.len());
+    let mut in_header = true;
+    for (n, line) in src.lines().enumerate() {
+        if n > 0 { body.push('\n'); }
+        let code = line.split('#').next().unwrap_or("");
+        let kind = match code.split_whitespace().next() {
+            Some("input") => Some(DeclKind::Input),
+            Some("param") => Some(DeclKind::Param),
+            Some("output") => Some(DeclKind::Output),
+            _ => None,
+        };
+        match kind {
+            Some(kind) if in_header => {
+                let d = parse_decl(kind, code, n + 1)?;
+                if let Some(prev) = decls.iter().find(|p| p.name == d.name) {
+                    return Err(Diagnostic::at(d.span, format!("{} declared twice (first at {})", d.name, prev.span)));
+                }
+                decls.push(d);
+            }
+            _ => {
+                if !code.trim().is_empty() { in_header = false; }
+                body.push_str(line);
+            }
+        }
+    }
+    Ok((decls, body))
+}
+
+/// `<kw> name : T`, on line `line`.
+fn parse_decl(kind: DeclKind, code: &str, line: usize) -> Result<Decl, Diagnostic> {
+    let kw_at = code.find(|c: char| !c.is_whitespace()).unwrap_or(0);
+    let kw_len = kind.keyword().len();
+    let rest = &code[kw_at + kw_len..];
+    let (name_part, ty_part) = rest.split_once(':')
+        .ok_or_else(|| Diagnostic::at(Span { line, col: kw_at + 1, len: kw_len }, "expected `name : type`"))?;
+    let name = name_part.trim();
+    let name_col = kw_at + kw_len + name_part.find(name).unwrap_or(0) + 1;
+    let span = Span { line, col: name_col, len: name.chars().count().max(1) };
+    let ident = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !ident { return Err(Diagnostic::at(span, format!("not a valid {} name: {:?}", kind.keyword(), name))); }
+    let ty_col = kw_at + kw_len + name_part.len() + 2;
+    let ty_span = Span { line, col: ty_col, len: ty_part.trim_end().chars().count().max(1) };
+    let ty = parse_ty(ty_part).map_err(|e| Diagnostic::at(ty_span, e))?;
+    Ok(Decl { kind, name: name.to_string(), ty, span })
+}
+
+/// Parse a type (the whole of `s`).
+pub fn parse_ty(s: &str) -> Result<Ty, String> {
+    let toks = ty_tokens(s);
+    let mut i = 0;
+    let ty = ty_at(&toks, &mut i)?;
+    if i < toks.len() { return Err(format!("type: unexpected {:?}", toks[i])); }
+    Ok(ty)
+}
+
+fn ty_tokens(s: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    for ch in s.chars() {
+        if ch.is_whitespace() || "(),[]<>|:?".contains(ch) {
+            if !cur.is_empty() { out.push(std::mem::take(&mut cur)); }
+            if !ch.is_whitespace() { out.push(ch.to_string()); }
+        } else {
+            cur.push(ch);
+        }
+    }
+    if !cur.is_empty() { out.push(cur); }
+    out
+}
+
+/// A type and any `?` suffixes.
+fn ty_at(toks: &[String], i: &mut usize) -> Result<Ty, String> {
+    let mut ty = base_ty_at(toks, i)?;
+    while toks.get(*i).map(String::as_str) == Some("?") {
+        *i += 1;
+        ty = Ty::Opt(Box::new(ty));
+    }
+    Ok(ty)
+}
+
+fn base_ty_at(toks: &[String], i: &mut usize) -> Result<Ty, String> {
+    let t = toks.get(*i).ok_or("type: unexpected end")?.as_str();
+    *i += 1;
+    match t {
+        "(" => Ok(Ty::Prod(ty_list(toks, i, ",", ")")?)),
+        "<" => Ok(Ty::Sum(ty_list(toks, i, "|", ">")?)),
+        "[" => {
+            let inner = ty_at(toks, i)?;
+            match toks.get(*i).map(String::as_str) {
+                Some("]") => { *i += 1; Ok(Ty::List(Box::new(inner))) }
+                other => Err(format!("type: expected ], got {:?}", other)),
+            }
+        }
+        "bool" => Ok(Ty::Bool),
+        "str" => Ok(Ty::Str),
+        "date" => Ok(Ty::Temporal(Temporal::Date)),
+        "timestamp" => Ok(Ty::Temporal(Temporal::Timestamp)),
+        other => parse_interp(other).map(Ty::Prim).or_else(|| Dec::parse(other).map(Ty::Decimal)).ok_or_else(|| format!("type: unknown {:?}", other)),
+    }
+}
+
+/// One or more (optionally labeled) types separated by `sep`, up to `close`.
+fn ty_list(toks: &[String], i: &mut usize, sep: &str, close: &str) -> Result<Vec<Ty>, String> {
+    let mut out = vec![field_at(toks, i)?];
+    loop {
+        match toks.get(*i).map(String::as_str) {
+            Some(t) if t == sep => { *i += 1; out.push(field_at(toks, i)?); }
+            Some(t) if t == close => { *i += 1; return Ok(out); }
+            other => return Err(format!("type: expected {} or {}, got {:?}", sep, close, other)),
+        }
+    }
+}
+
+/// `T` or `name : T`.
+fn field_at(toks: &[String], i: &mut usize) -> Result<Ty, String> {
+    if toks.get(*i + 1).map(String::as_str) == Some(":") {
+        let label = toks[*i].clone();
+        if !label.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(format!("type: not a valid label: {:?}", label));
+        }
+        *i += 2;
+        return Ok(Ty::Labeled(label, Box::new(ty_at(toks, i)?)));
+    }
+    ty_at(toks, i)
+}
@@ -1,5 +1,6 @@
 //! Layer 4: concrete syntax. Parser + name→op registry.
 
+pub mod header;
 pub mod inference;
 pub mod parse;
 pub mod registry;
@@ -1,50 +1,151 @@
 //! Layer 4: parser. Tokenizes whitespace-separated text. Op-name lookups are
 //! delegated to an `OpRegistry` (one of several possible front-ends). The
 //! parser only knows about structural forms — blocks, refs, array literals,
-//! `pick`/`roll` and N-arity ops, `:`-binding, and the `match`/`cleave`
-//! desugarings. Everything else is a token the registry resolves.
+//! quotations, `pick`/`roll` and N-arity ops, `:`-binding, and the
+//! `match`/`cleave` desugarings. Everything else is a token the registry resolves.
 
 use std::collections::HashMap;
 
+use crate::ir::span::{DefSite, Diagnostic, Expansion, Site, SourceMap, Span};
 use crate::syntax::inference::mark_last_use_in_body;
 use crate::syntax::registry::OpRegistry;
 use crate::ir::typecheck::Op;
 use crate::ops::convert as cv;
 use crate::ops::letbind as lb;
+use crate::ops::quote as qt;
 use crate::ops::stack as sk;
 
 /// Parse-time inline definitions: `def name { body }` saves the body
-/// tokens, and later occurrences of `name` re-parse the body in place.
-/// Macro semantics, not first-class procedures — name lookups inside the
-/// body resolve against the calling environment.
-type Defs = HashMap<String, Vec<String>>;
+/// tokens (and the def's index in `SourceMap::defs`), and later
+/// occurrences of `name` re-parse the body in place. Macro semantics, not
+/// first-class procedures — name lookups inside the body resolve against
+/// the calling environment.
+type Defs = HashMap<String, (usize, Vec<Tok>)>;
 
-fn tokenize(src: &str) -> Vec<String> {
+/// A token and where it came from. Tokens a desugaring synthesizes carry
+/// the span of the construct that produced them.
+#[derive(Clone, Debug)]
+struct Tok {
+    text: String,
+    span: Span,
+}
+
+impl Tok {
+    fn synth(text: impl Into<String>, span: Span) -> Tok { Tok { text: text.into(), span } }
+    fn as_str(&self) -> &str { &self.text }
+}
+
+impl PartialEq<&str> for Tok {
+    fn eq(&self, other: &&str) -> bool { self.text == *other }
+}
+
+fn tokenize(src: &str) -> Vec<Tok> {
     let mut out = Vec::new();
     let mut cur = String::new();
+    let mut start = Span::default();
+    let (mut line, mut col) = (1, 0);
+    let flush = |cur: &mut String, start: Span, out: &mut Vec<Tok>| {
+        if !cur.is_empty() {
+            let len = cur.chars().count();
+            out.push(Tok { text: std::mem::take(cur), span: Span { len, ..start } });
+        }
+    };
     for ch in src.chars() {
+        col += 1;
         if ch.is_whitespace() || ch == ',' {
             // `,` is a separator (whitespace-equivalent): `:[a, b]` ≡ `:[a b]`.
-            if !cur.is_empty() { out.push(std::mem::take(&mut cur)); }
+            flush(&mut cur, start, &mut out);
         } else if ch == '[' || ch == ']' {
-            if !cur.is_empty() { out.push(std::mem::take(&mut cur)); }
-            out.push(ch.to_string());
+            flush(&mut cur, start, &mut out);
+            out.push(Tok { text: ch.to_string(), span: Span { line, col, len: 1 } });
         } else {
+            if cur.is_empty() { start = Span { line, col, len: 0 }; }
             cur.push(ch);
         }
+        if ch == '\n' { line += 1; col = 0; }
     }
-    if !cur.is_empty() { out.push(cur); }
+    flush(&mut cur, start, &mut out);
     out
 }
 
-pub fn parse(src: &str, reg: &OpRegistry) -> Result<Vec<Box<dyn Op>>, String> {
+/// A parsed program plus where each op came from: `sites[i]` is the site
+/// of `ops[i]` (ops inside a `Let` body are covered by the `Let`'s own
+/// `sites`), and `map` records the defs and their expansions.
+pub struct Parsed {
+    pub ops: Vec<Box<dyn Op>>,
+    pub sites: Vec<Site>,
+    pub map: SourceMap,
+}
+
+/// Parser state threaded through `parse_block`.
+struct Cx<'a> {
+    scopes: Vec<Vec<String>>,
+    defs: Defs,
+    /// Def expansions in progress (ids into `map.expansions`), outermost
+    /// first. Guards against recursive defs and tags op sites.
+    active: Vec<usize>,
+    map: SourceMap,
+    reg: &'a OpRegistry,
+}
+
+/// Ops and their sites, kept in step.
+#[derive(Default)]
+struct Block {
+    ops: Vec<Box<dyn Op>>,
+    sites: Vec<Site>,
+}
+
+impl Block {
+    fn push(&mut self, op: Box<dyn Op>, site: Site) {
+        self.ops.push(op);
+        self.sites.push(site);
+    }
+    fn extend(&mut self, other: Block) {
+        self.ops.extend(other.ops);
+        self.sites.extend(other.sites);
+    }
+}
+
+/// What `parse_token` leaves its block loop to do next.
+enum Flow {
+    Next,
+    /// The block ends here (a terminator, or a binding that took the rest).
+    Done,
+}
+
+/// Parse a program, keeping source sites for diagnostics (`collie check`,
+/// `lower::build_parsed`).
+pub fn parse_program(src: &str, reg: &OpRegistry) -> Result<Parsed, Diagnostic> {
+    let stripped = strip_comments(src);
+    let toks = tokenize(&stripped);
+    let mut i = 0;
+    let mut cx = Cx { scopes: Vec::new(), defs: HashMap::new(), active: Vec::new(), map: SourceMap::default(), reg };
+    let block = parse_block(&toks, &mut i, None, &mut cx)?;
+    Ok(Parsed { ops: block.ops, sites: block.sites, map: cx.map })
+}
+
+/// Parse a program that refers to `names` as if bound around it — the
+/// host's named tables (`crate::session`). The result is one `Let` of
+/// `names` over the whole program, so it expects one stack value per name,
+/// bottom first. Names shadow registry ops and defs, as `:name` does.
+pub fn parse_program_bound(src: &str, reg: &OpRegistry, names: &[String]) -> Result<Parsed, Diagnostic> {
+    if let Some(bad) = names.iter().find(|n| !is_ident_after_prefix(n)) {
+        return Err(format!("not a valid binding name: {:?}", bad).into());
+    }
     let stripped = strip_comments(src);
     let toks = tokenize(&stripped);
     let mut i = 0;
-    let mut scopes: Vec<Vec<String>> = Vec::new();
-    let mut defs: Defs = HashMap::new();
-    let mut expansions: Vec<String> = Vec::new();
-    parse_block(&toks, &mut i, None, &mut scopes, &mut defs, &mut expansions, reg)
+    let map = SourceMap { inputs: names.to_vec(), ..SourceMap::default() };
+    let mut cx = Cx { scopes: vec![names.to_vec()], defs: HashMap::new(), active: Vec::new(), map, reg };
+    let mut body = parse_block(&toks, &mut i, None, &mut cx)?;
+    mark_last_use_in_body(&mut body.ops, 0, names.len());
+    let bind: Box<dyn Op> = Box::new(lb::Let { names: names.to_vec(), body: body.ops, sites: body.sites });
+    Ok(Parsed { ops: vec![bind], sites: vec![Site::default()], map: cx.map })
+}
+
+/// Parse a program. Errors are rendered against `src` (excerpt and caret).
+pub fn parse(src: &str, reg: &OpRegistry) -> Result<Vec<Box<dyn Op>>, String> {
+    parse_program(src, reg).map(|p| p.ops).map_err(|d| d.render(src, "<input>"))
 }
 
 /// Strip `#` line comments (from `#` to end-of-line). Required for source
@@ -66,7 +167,7 @@ pub fn strip_comments(src: &str) -> String {
 pub fn parse_file(path: &std::path::Path, reg: &OpRegistry) -> Result<Vec<Box<dyn Op>>, String> {
     let src = std::fs::read_to_string(path)
         .map_err(|e| format!("read {}: {}", path.display(), e))?;
-    parse(&src, reg)
+    parse_program(&src, reg).map(|p| p.ops).map_err(|d| d.render(&src, &path.display().to_string()))
 }
 
 /// Total bindings currently in scope — the env index the next binding lands
@@ -98,321 +199,349 @@ fn lookup_binding(name: &str, scopes: &[Vec<String>]) -> Option<usize> {
     None
 }
 
-fn expect(toks: &[String], i: &mut usize, want: &str) -> Result<(), String> {
+fn expect(toks: &[Tok], i: &mut usize, want: &str) -> Result<(), Diagnostic> {
     if *i >= toks.len() {
-        return Err(format!("expected {}, got end of input", want));
+        return Err(format!("expected {}, got end of input", want).into());
     }
     if toks[*i] != want {
-        return Err(format!("expected {}, got {}", want, toks[*i]));
+        return Err(Diagnostic::at(toks[*i].span, format!("expected {}, got {}", want, toks[*i].text)));
     }
     *i += 1;
     Ok(())
 }
 
+/// Span from `a` through `b` when both are on one line, else just `a`.
+fn span_to(a: Span, b: Span) -> Span {
+    if a.line == b.line && b.col >= a.col { Span { len: b.col + b.len - a.col, ..a } } else { a }
+}
+
 fn parse_block(
-    toks: &[String],
+    toks: &[Tok],
     i: &mut usize,
     end: Option<&str>,
-    scopes: &mut Vec<Vec<String>>,
-    defs: &mut Defs,
-    expansions: &mut Vec<String>,
-    reg: &OpRegistry,
-) -> Result<Vec<Box<dyn Op>>, String> {
+    cx: &mut Cx,
+) -> Result<Block, Diagnostic> {
     // Snapshot defs at block entry; restore on exit so any defs added in
     // this block (and any shadowing of outer defs) don't leak outward.
-    // Cloning a HashMap of small Vec<String>s is cheap.
-    let defs_snapshot = defs.clone();
-    let mut out: Vec<Box<dyn Op>> = Vec::new();
+    // Cloning a HashMap of small Vec<Tok>s is cheap.
+    let defs_snapshot = cx.defs.clone();
+    let mut out = Block::default();
     while *i < toks.len() {
         let t = &toks[*i];
-        if Some(t.as_str()) == end {
-            *defs = defs_snapshot;
-            return Ok(out);
-        }
+        if Some(t.as_str()) == end { break; }
         *i += 1;
-        match t.as_str() {
-            // pick/roll take a separate numeric arg, not handled by the registry
-            "pick" => {
-                if *i >= toks.len() { return Err("pick: needs index".into()); }
-                let n: usize = toks[*i].parse().map_err(|_| format!("pick: bad index {}", toks[*i]))?;
-                *i += 1;
-                out.push(Box::new(sk::Pick { n }));
+        // Errors without a span of their own point at the token being
+        // parsed.
+        match parse_token(t, toks, i, end, cx, &mut out) {
+            Ok(Flow::Next) => {}
+            Ok(Flow::Done) => break,
+            Err(d) => return Err(d.or_at(t.span)),
+        }
+    }
+    cx.defs = defs_snapshot;
+    Ok(out)
+}
+
+/// Parse one token `t` (already consumed; `toks[*i]` is the next one),
+/// appending what it produces to `out`.
+fn parse_token(
+    t: &Tok,
+    toks: &[Tok],
+    i: &mut usize,
+    end: Option<&str>,
+    cx: &mut Cx,
+    out: &mut Block,
+) -> Result<Flow, Diagnostic> {
+    let site = |span: Span, cx: &Cx| Site { span, within: cx.active.clone() };
+    match t.as_str() {
+        // pick/roll take a separate numeric arg, not handled by the registry
+        "pick" | "roll" => {
+            if *i >= toks.len() { return Err(format!("{}: needs index", t.text).into()); }
+            let arg = &toks[*i];
+            let n: usize = arg.text.parse()
+                .map_err(|_| Diagnostic::at(arg.span, format!("{}: bad index {}", t.text, arg.text)))?;
+            *i += 1;
+            let op: Box<dyn Op> = if t.text == "pick" { Box::new(sk::Pick { n }) } else { Box::new(sk::Roll { n }) };
+            out.push(op, site(span_to(t.span, arg.span), cx));
+        }
+        // `.{ p0 ; p1 ; … }` — cleave: each path runs on a fresh copy of
+        // TOS, results bundled into a Prod. Sugar, desugared here to
+        // `:[g_v]  g_v <p0>  g_v <p1> … entuple.K` (bind the input, run each
+        // path on a reference). No `Cleave` op is built — the paths become
+        // inline op-stream. Faithful because every path is net 1→1 (it
+        // consumes the one reference, produces one value); paths are
+        // captured as raw token spans and the synthesized stream re-parsed.
+        ".{" => {
+            let tag = *i; // unique per cleave site → collision-free gensym
+            let mut paths_toks: Vec<Vec<Tok>> = Vec::new();
+            let mut start = *i;
+            let mut depth: usize = 0;
+            loop {
+                if *i >= toks.len() { return Err(".{: unterminated, expected }".into()); }
+                match toks[*i].as_str() {
+                    "{" | ".{" | "[" => { depth += 1; *i += 1; }
+                    "}" | "]" if depth > 0 => { depth -= 1; *i += 1; }
+                    "}" => {
+                        if start < *i || paths_toks.is_empty() { paths_toks.push(toks[start..*i].to_vec()); }
+                        *i += 1;
+                        break;
+                    }
+                    ";" if depth == 0 => { paths_toks.push(toks[start..*i].to_vec()); *i += 1; start = *i; }
+                    _ => { *i += 1; }
+                }
             }
-            "roll" => {
-                if *i >= toks.len() { return Err("roll: needs index".into()); }
-                let n: usize = toks[*i].parse().map_err(|_| format!("roll: bad index {}", toks[*i]))?;
-                *i += 1;
-                out.push(Box::new(sk::Roll { n }));
+            let k = paths_toks.len();
+            let g_v = format!("__c{}_v", tag);
+            let sp = t.span;
+            let mut synth: Vec<Tok> = vec![Tok::synth(":", sp), Tok::synth("[", sp), Tok::synth(g_v.clone(), sp), Tok::synth("]", sp)];
+            for path in &paths_toks {
+                synth.push(Tok::synth(g_v.clone(), sp));
+                synth.extend(path.iter().cloned());
             }
-            // `.{ p0 ; p1 ; … }` — cleave: each path runs on a fresh copy of
-            // TOS, results bundled into a Prod. Sugar, desugared here to
-            // `:[g_v]  g_v <p0>  g_v <p1> … entuple.K` (bind the input, run each
-            // path on a reference). No `Cleave` op is built — the paths become
-            // inline op-stream. Faithful because every path is net 1→1 (it
-            // consumes the one reference, produces one value); paths are
-            // captured as raw token spans and the synthesized stream re-parsed.
-            ".{" => {
-                let tag = *i; // unique per cleave site → collision-free gensym
-                let mut paths_toks: Vec<Vec<String>> = Vec::new();
-                let mut start = *i;
+            synth.push(Tok::synth(format!("entuple.{}", k), sp));
+            let mut j = 0;
+            out.extend(parse_block(&synth, &mut j, None, cx)?);
+        }
+        // `match { -> arm0 -> arm1 … }` is sugar, desugared here to
+        // `split :[g_disc g_l0 …] g_disc g_l0 <arm0> g_l1 <arm1> … mergeK`
+        // (the merge half is `mergeK`; binding lets each arm apply to its
+        // lane while disc rides through). No `Match` op is built — the arms
+        // become inline op-stream visible to the optimizer. The arms are
+        // captured as raw token spans and the synthesized stream is
+        // re-parsed in place (so arms still see outer bindings/defs).
+        "match" => {
+            let tag = *i; // unique per match site → collision-free gensyms
+            expect(toks, i, "{")?;
+            let mut arms_toks: Vec<Vec<Tok>> = Vec::new();
+            while *i < toks.len() && toks[*i] != "}" {
+                expect(toks, i, "->")?;
+                let start = *i;
                 let mut depth: usize = 0;
-                loop {
-                    if *i >= toks.len() { return Err(".{: unterminated, expected }".into()); }
+                while *i < toks.len() {
                     match toks[*i].as_str() {
-                        "{" | ".{" | "[" => { depth += 1; *i += 1; }
-                        "}" | "]" if depth > 0 => { depth -= 1; *i += 1; }
-                        "}" => {
-                            if start < *i || paths_toks.is_empty() { paths_toks.push(toks[start..*i].to_vec()); }
-                            *i += 1;
-                            break;
-                        }
-                        ";" if depth == 0 => { paths_toks.push(toks[start..*i].to_vec()); *i += 1; start = *i; }
-                        _ => { *i += 1; }
+                        "{" | ".{" | "[" => depth += 1,
+                        "}" | "]" if depth == 0 => break,
+                        "}" | "]" => depth -= 1,
+                        "->" if depth == 0 => break,
+                        _ => {}
                     }
+                    *i += 1;
                 }
-                let k = paths_toks.len();
-                let g_v = format!("__c{}_v", tag);
-                let mut synth: Vec<String> = vec![":".into(), "[".into(), g_v.clone(), "]".into()];
-                for path in &paths_toks {
-                    synth.push(g_v.clone());
-                    synth.extend(path.iter().cloned());
-                }
-                synth.push(format!("entuple.{}", k));
-                let mut j = 0;
-                let body_ops = parse_block(&synth, &mut j, None, scopes, defs, expansions, reg)?;
-                out.extend(body_ops);
+                arms_toks.push(toks[start..*i].to_vec());
             }
-            // `match { -> arm0 -> arm1 … }` is sugar, desugared here to
-            // `split :[g_disc g_l0 …] g_disc g_l0 <arm0> g_l1 <arm1> … mergeK`
-            // (the merge half is `mergeK`; binding lets each arm apply to its
-            // lane while disc rides through). No `Match` op is built — the arms
-            // become inline op-stream visible to the optimizer. The arms are
-            // captured as raw token spans and the synthesized stream is
-            // re-parsed in place (so arms still see outer bindings/defs).
-            "match" => {
-                let tag = *i; // unique per match site → collision-free gensyms
-                expect(toks, i, "{")?;
-                let mut arms_toks: Vec<Vec<String>> = Vec::new();
-                while *i < toks.len() && toks[*i] != "}" {
-                    expect(toks, i, "->")?;
-                    let start = *i;
-                    let mut depth: usize = 0;
-                    while *i < toks.len() {
-                        match toks[*i].as_str() {
-                            "{" | ".{" | "[" => depth += 1,
-                            "}" | "]" if depth == 0 => break,
-                            "}" | "]" => depth -= 1,
-                            "->" if depth == 0 => break,
-                            _ => {}
-                        }
-                        *i += 1;
-                    }
-                    arms_toks.push(toks[start..*i].to_vec());
-                }
-                expect(toks, i, "}")?;
-                let k = arms_toks.len();
-                if k == 0 { return Err("match: no arms".into()); }
-                let g_disc = format!("__m{}_disc", tag);
-                let g_lanes: Vec<String> = (0..k).map(|j| format!("__m{}_l{}", tag, j)).collect();
-                let mut synth: Vec<String> = vec!["split".into(), ":".into(), "[".into(), g_disc.clone()];
-                synth.extend(g_lanes.iter().cloned());
-                synth.push("]".into());
-                synth.push(g_disc.clone());
-                for (j, arm) in arms_toks.iter().enumerate() {
-                    synth.push(g_lanes[j].clone());
-                    synth.extend(arm.iter().cloned());
-                }
-                synth.push(format!("merge{}", k));
-                let mut j = 0;
-                let body_ops = parse_block(&synth, &mut j, None, scopes, defs, expansions, reg)?;
-                out.extend(body_ops);
+            expect(toks, i, "}")?;
+            let k = arms_toks.len();
+            if k == 0 { return Err("match: no arms".into()); }
+            let sp = t.span;
+            let g_disc = format!("__m{}_disc", tag);
+            let g_lanes: Vec<String> = (0..k).map(|j| format!("__m{}_l{}", tag, j)).collect();
+            let mut synth: Vec<Tok> = vec![Tok::synth("split", sp), Tok::synth(":", sp), Tok::synth("[", sp), Tok::synth(g_disc.clone(), sp)];
+            synth.extend(g_lanes.iter().map(|l| Tok::synth(l.clone(), sp)));
+            synth.push(Tok::synth("]", sp));
+            synth.push(Tok::synth(g_disc.clone(), sp));
+            for (j, arm) in arms_toks.iter().enumerate() {
+                synth.push(Tok::synth(g_lanes[j].clone(), sp));
+                synth.extend(arm.iter().cloned());
             }
-            // Standalone `{ … }` scope block: parse the body and inline its
-            // ops. The braces only delimit binding scope (a `:[…]` inside
-            // scopes to this `}`); there is no runtime effect of their own.
-            // Lets `{| names |}` lower to `{ :[names] … }`.
-            "{" => {
-                let body = parse_block(toks, i, Some("}"), scopes, defs, expansions, reg)?;
-                expect(toks, i, "}")?;
-                out.extend(body);
+            synth.push(Tok::synth(format!("merge{}", k), sp));
+            let mut j = 0;
+            out.extend(parse_block(&synth, &mut j, None, cx)?);
+        }
+        // `[ … ]` — quotation: the body is parsed here (names resolve in
+        // this scope) but pushed as a compile-time value; `apply`,
+        // `match.K` and `under.<i>` splice copies of it during lowering.
+        "[" => {
+            let body = parse_block(toks, i, Some("]"), cx)?;
+            let close = toks.get(*i).map_or(t.span, |c| c.span);
+            expect(toks, i, "]")?;
+            out.push(Box::new(qt::Quote { body: body.ops, sites: body.sites }), site(span_to(t.span, close), cx));
+        }
+        // Standalone `{ … }` scope block: parse the body and inline its
+        // ops. The braces only delimit binding scope (a `:[…]` inside
+        // scopes to this `}`); there is no runtime effect of their own.
+        // Lets `{| names |}` lower to `{ :[names] … }`.
+        "{" => {
+            let body = parse_block(toks, i, Some("}"), cx)?;
+            expect(toks, i, "}")?;
+            out.extend(body);
+        }
+        "->" | "}" | ";" => {
+            *i -= 1;
+            return Ok(Flow::Done);
+        }
+        "def" => {
+            // `def name { body }` — capture the inner body tokens for
+            // later inline expansion. Emits no op. Block-scoped: the defs
+            // snapshot at parse_block entry is restored on exit. Parameters
+            // are declared inside the body with `:[names]` / `:name`.
+            if *i >= toks.len() { return Err("def: missing name".into()); }
+            let name = toks[*i].clone();
+            *i += 1;
+            if *i >= toks.len() { return Err(Diagnostic::at(name.span, format!("def {}: missing body", name.text))); }
+            if toks[*i] != "{" {
+                return Err(Diagnostic::at(toks[*i].span, format!("def {}: expected `{{`, got `{}`", name.text, toks[*i].text)));
+            }
+            *i += 1; // consume opener
+            let inner_start = *i;
+            let mut depth: usize = 1;
+            while *i < toks.len() && depth > 0 {
+                match toks[*i].as_str() {
+                    "{" | ".{" => depth += 1,
+                    "}" => { depth -= 1; if depth == 0 { break; } }
+                    _ => {}
+                }
+                *i += 1;
             }
-            "->" | "}" | ";" => {
-                *i -= 1;
-                *defs = defs_snapshot;
-                return Ok(out);
+            if *i >= toks.len() {
+                return Err(Diagnostic::at(name.span, format!("def {}: unterminated body", name.text)));
             }
-            "def" => {
-                // `def name { body }` — capture the inner body tokens for
-                // later inline expansion. Emits no op. Block-scoped: the defs
-                // snapshot at parse_block entry is restored on exit. Parameters
-                // are declared inside the body with `:[names]` / `:name`.
-                if *i >= toks.len() { return Err("def: missing name".into()); }
-                let name = toks[*i].clone();
+            let body_tokens: Vec<Tok> = toks[inner_start..*i].to_vec();
+            *i += 1; // consume the closing `}`
+            let id = cx.map.defs.len();
+            cx.map.defs.push(DefSite { name: name.text.clone(), span: name.span });
+            cx.defs.insert(name.text, (id, body_tokens));
+        }
+        // Flat binding: `:name` pops the top of the stack and binds it to
+        // `name` for the rest of the current block. Equivalent to
+        // `{ :[name] <rest> }` — same IR, sweeter for sequential pipelines.
+        // Body is the remainder of the block, so it boils to edges like
+        // any `Let`. (`>` is reserved for comparison only.)
+        s if s.starts_with(':') && is_ident_after_prefix(&s[1..]) => {
+            let name = s[1..].to_string();
+            let start = scope_depth(&cx.scopes);
+            cx.scopes.push(vec![name.clone()]);
+            let mut body = parse_block(toks, i, end, cx)?;
+            cx.scopes.pop();
+            mark_last_use_in_body(&mut body.ops, start, 1);
+            out.push(Box::new(lb::Let { names: vec![name], body: body.ops, sites: body.sites }), site(t.span, cx));
+            return Ok(Flow::Done);
+        }
+        // `:[a b c]` — bind the top N stack values to names a..c in stack
+        // order (a = deepest, c = top), for the rest of the scope. Commas
+        // optional (the tokenizer treats `,` as a separator). `()` is
+        // reserved for a future tuple-destructure pattern.
+        ":" => {
+            expect(toks, i, "[")?;
+            let mut names = Vec::new();
+            while *i < toks.len() && toks[*i] != "]" {
+                names.push(toks[*i].text.clone());
                 *i += 1;
-                if *i >= toks.len() { return Err(format!("def {}: missing body", name)); }
-                if toks[*i] != "{" {
-                    return Err(format!("def {}: expected `{{`, got `{}`", name, toks[*i]));
+            }
+            let close = toks.get(*i).map_or(t.span, |c| c.span);
+            expect(toks, i, "]")?;
+            let n_names = names.len();
+            let start = scope_depth(&cx.scopes);
+            cx.scopes.push(names.clone());
+            let mut body = parse_block(toks, i, end, cx)?;
+            cx.scopes.pop();
+            mark_last_use_in_body(&mut body.ops, start, n_names);
+            out.push(Box::new(lb::Let { names, body: body.ops, sites: body.sites }), site(span_to(t.span, close), cx));
+            return Ok(Flow::Done);
+        }
+        _ => {
+            // Binding reference (shadows registry and defs). Take is
+            // inferred (last-use) and the graph derives it via use-counting,
+            // so refs are emitted as clones; `mark_last_use_in_body` flips
+            // the final one to a take.
+            if let Some(idx) = lookup_binding(&t.text, &cx.scopes) {
+                out.push(Box::new(lb::Ref { idx, take: false }), site(t.span, cx));
+                return Ok(Flow::Next);
+            }
+            // Def expansion: re-parse the saved body in place. Name
+            // lookups inside resolve against the *calling* environment.
+            if let Some((def, body_toks)) = cx.defs.get(&t.text).cloned() {
+                if cx.active.iter().any(|&e| cx.map.defs[cx.map.expansions[e].def].name == t.text) {
+                    return Err(format!("def {}: recursive expansion", t.text).into());
                 }
-                *i += 1; // consume opener
-                let inner_start = *i;
-                let mut depth: usize = 1;
-                while *i < toks.len() && depth > 0 {
-                    match toks[*i].as_str() {
-                        "{" | ".{" => depth += 1,
-                        "}" => { depth -= 1; if depth == 0 { break; } }
-                        _ => {}
+                let id = cx.map.expansions.len();
+                cx.map.expansions.push(Expansion { def, call: t.span });
+                cx.active.push(id);
+                let mut j = 0;
+                let body = parse_block(&body_toks, &mut j, None, cx);
+                cx.active.pop();
+                let note = cx.map.expansion_note(id).unwrap_or_default();
+                out.extend(body.map_err(|d| d.note(note))?);
+                return Ok(Flow::Next);
+            }
+            // Registry lookup for plain ops, with a peephole fusion:
+            // `where` followed by `gather` becomes a single `filter` op.
+            // Same semantics (filter src by mask, produce values), one
+            // pass instead of three. parse_block recurses into nested
+            // blocks (Let bodies etc.), so the fusion fires uniformly.
+            if let Some(op) = cx.reg.make(&t.text) {
+                if op.name() == "gather"
+                    && out.ops.last().map(|o| o.name() == "where").unwrap_or(false)
+                {
+                    if let Some(filter_op) = cx.reg.make("filter") {
+                        out.ops.pop();
+                        let prev = out.sites.pop().map_or(t.span, |s| s.span);
+                        out.push(filter_op, site(span_to(prev, t.span), cx));
+                        return Ok(Flow::Next);
                     }
-                    *i += 1;
-                }
-                if *i >= toks.len() {
-                    return Err(format!("def {}: unterminated body", name));
                 }
-                let body_tokens: Vec<String> = toks[inner_start..*i].to_vec();
-                *i += 1; // consume the closing `}`
-                defs.insert(name, body_tokens);
-            }
-            // Flat binding: `:name` pops the top of the stack and binds it to
-            // `name` for the rest of the current block. Equivalent to
-            // `{ :[name] <rest> }` — same IR, sweeter for sequential pipelines.
-            // Body is the remainder of the block, so it boils to edges like
-            // any `Let`. (`>` is reserved for comparison only.)
-            s if s.starts_with(':') && is_ident_after_prefix(&s[1..]) => {
-                let name = s[1..].to_string();
-                let start = scope_depth(scopes);
-                scopes.push(vec![name.clone()]);
-                let mut body = parse_block(toks, i, end, scopes, defs, expansions, reg)?;
-                scopes.pop();
-                mark_last_use_in_body(&mut body, start, 1);
-                out.push(Box::new(lb::Let { names: vec![name], body }));
-                *defs = defs_snapshot;
-                return Ok(out);
+                out.push(op, site(t.span, cx));
+                return Ok(Flow::Next);
             }
-            // `:[a b c]` — bind the top N stack values to names a..c in stack
-            // order (a = deepest, c = top), for the rest of the scope. Commas
-            // optional (the tokenizer treats `,` as a separator). `()` is
-            // reserved for a future tuple-destructure pattern.
-            ":" => {
-                expect(toks, i, "[")?;
-                let mut names = Vec::new();
+            // Column literals: `<type>[ ... ]` — structural, parser-internal.
+            if matches!(t.as_str(), "u64"|"u8"|"u16"|"u32"|"i64"|"i32"|"i16"|"i8"|"f64"|"f32"|"bool")
+                && *i < toks.len() && toks[*i] == "["
+            {
+                let ty = t.as_str();
+                *i += 1;
+                let mut elems: Vec<&Tok> = Vec::new();
                 while *i < toks.len() && toks[*i] != "]" {
-                    names.push(toks[*i].clone());
+                    elems.push(&toks[*i]);
                     *i += 1;
                 }
+                let close = toks.get(*i).map_or(t.span, |c| c.span);
                 expect(toks, i, "]")?;
-                let n_names = names.len();
-                let start = scope_depth(scopes);
-                scopes.push(names.clone());
-                let mut body = parse_block(toks, i, end, scopes, defs, expansions, reg)?;
-                scopes.pop();
-                mark_last_use_in_body(&mut body, start, n_names);
-                out.push(Box::new(lb::Let { names, body }));
-                *defs = defs_snapshot;
-                return Ok(out);
-            }
-            _ => {
-                // Binding reference (shadows registry and defs). Take is
-                // inferred (last-use) and the graph derives it via use-counting,
-                // so refs are emitted as clones; `mark_last_use_in_body` flips
-                // the final one to a take.
-                if let Some(idx) = lookup_binding(t, scopes) {
-                    out.push(Box::new(lb::Ref { idx, take: false }));
-                    continue;
-                }
-                // Def expansion: re-parse the saved body in place. Name
-                // lookups inside resolve against the *calling* environment.
-                if let Some(body_toks) = defs.get(t).cloned() {
-                    if expansions.iter().any(|n| n == t) {
-                        return Err(format!("def {}: recursive expansion", t));
-                    }
-                    expansions.push(t.to_string());
-                    let mut j = 0;
-                    let body_ops = parse_block(
-                        &body_toks, &mut j, None,
-                        scopes, defs, expansions, reg,
-                    )?;
-                    expansions.pop();
-                    out.extend(body_ops);
-                    continue;
-                }
-                // Registry lookup for plain ops, with a peephole fusion:
-                // `where` followed by `gather` becomes a single `filter` op.
-                // Same semantics (filter src by mask, produce values), one
-                // pass instead of three. parse_block recurses into nested
-                // blocks (Let bodies etc.), so the fusion fires uniformly.
-                if let Some(op) = reg.make(t) {
-                    if op.name() == "gather"
-                        && out.last().map(|o| o.name() == "where").unwrap_or(false)
-                    {
-                        out.pop();
-                        if let Some(filter_op) = reg.make("filter") {
-                            out.push(filter_op);
-                            continue;
-                        }
+                use crate::ir::value::Storage;
+                macro_rules! parse_arr { ($t:ty, $tag:literal) => {{
+                    let vs: Vec<$t> = elems.iter().map(|s| s.text.parse::<$t>()
+                        .map_err(|_| Diagnostic::at(s.span, format!("{} literal: bad value '{}'", stringify!($t), s.text))))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Box::new(cv::LitArr { tag: $tag, prim: <$t as Storage>::wrap(vs) })
+                        as Box<dyn Op>
+                }};}
+                let op: Box<dyn Op> = match ty {
+                    "u64" => parse_arr!(u64, "u64[]"),
+                    "u32" => parse_arr!(u32, "u32[]"),
+                    "u16" => parse_arr!(u16, "u16[]"),
+                    "u8"  => parse_arr!(u8,  "u8[]"),
+                    "i64" => parse_arr!(i64, "i64[]"),
+                    "i32" => parse_arr!(i32, "i32[]"),
+                    "i16" => parse_arr!(i16, "i16[]"),
+                    "i8"  => parse_arr!(i8,  "i8[]"),
+                    "f64" => parse_arr!(f64, "f64[]"),
+                    "f32" => parse_arr!(f32, "f32[]"),
+                    "bool" => {
+                        let vs: Vec<u8> = elems.iter().map(|s| match s.as_str() {
+                            "t"|"true"|"1" => Ok(1u8),
+                            "f"|"false"|"0" => Ok(0u8),
+                            _ => Err(Diagnostic::at(s.span, format!("bool literal: bad value '{}'", s.text))),
+                        }).collect::<Result<Vec<_>, _>>()?;
+                        Box::new(cv::LitArr { tag: "bool[]", prim: <u8 as Storage>::wrap(vs) })
                     }
-                    out.push(op);
-                    continue;
-                }
-                // Column literals: `<type>[ ... ]` — structural, parser-internal.
-                if matches!(t.as_str(), "u64"|"u8"|"u16"|"u32"|"i64"|"i32"|"i16"|"i8"|"f64"|"f32"|"bool")
-                    && *i < toks.len() && toks[*i] == "["
-                {
-                    let ty = t.clone();
-                    *i += 1;
-                    let mut elems: Vec<String> = Vec::new();
-                    while *i < toks.len() && toks[*i] != "]" {
-                        elems.push(toks[*i].clone());
-                        *i += 1;
-                    }
-                    expect(toks, i, "]")?;
-                    use crate::ir::value::Storage;
-                    macro_rules! parse_arr { ($t:ty, $tag:literal) => {{
-                        let vs: Vec<$t> = elems.iter().map(|s| s.parse::<$t>()
-                            .map_err(|_| format!("{} literal: bad value '{}'", stringify!($t), s)))
-                            .collect::<Result<Vec<_>, _>>()?;
-                        Box::new(cv::LitArr { tag: $tag, prim: <$t as Storage>::wrap(vs) })
-                            as Box<dyn Op>
-                    }};}
-                    let op: Box<dyn Op> = match ty.as_str() {
-                        "u64" => parse_arr!(u64, "u64[]"),
-                        "u32" => parse_arr!(u32, "u32[]"),
-                        "u16" => parse_arr!(u16, "u16[]"),
-                        "u8"  => parse_arr!(u8,  "u8[]"),
-                        "i64" => parse_arr!(i64, "i64[]"),
-                        "i32" => parse_arr!(i32, "i32[]"),
-                        "i16" => parse_arr!(i16, "i16[]"),
-                        "i8"  => parse_arr!(i8,  "i8[]"),
-                        "f64" => parse_arr!(f64, "f64[]"),
-                        "f32" => parse_arr!(f32, "f32[]"),
-                        "bool" => {
-                            let vs: Vec<u8> = elems.iter().map(|s| match s.as_str() {
-                                "t"|"true"|"1" => Ok(1u8),
-                                "f"|"false"|"0" => Ok(0u8),
-                                _ => Err(format!("bool literal: bad value '{}'", s)),
-                            }).collect::<Result<Vec<_>, _>>()?;
-                            Box::new(cv::LitArr { tag: "bool[]", prim: <u8 as Storage>::wrap(vs) })
-                        }
-                        _ => unreachable!(),
-                    };
-                    out.push(op);
-                    continue;
-                }
-                // Bare numeric (defaults to i64 / f64) — last resort.
-                if let Ok(n) = t.parse::<i64>() {
-                    out.push(Box::new(cv::LitNum {
-                        n: n as i128, f: n as f64, interp: crate::ir::shape::Interp::I64,
-                    }));
-                    continue;
-                }
-                if let Ok(f) = t.parse::<f64>() {
-                    out.push(Box::new(cv::LitNum {
-                        n: f as i128, f, interp: crate::ir::shape::Interp::F64,
-                    }));
-                    continue;
-                }
-                return Err(format!("unknown token: {}", t));
+                    _ => unreachable!(),
+                };
+                out.push(op, site(span_to(t.span, close), cx));
+                return Ok(Flow::Next);
+            }
+            // Bare numeric (defaults to i64 / f64) — last resort.
+            if let Ok(n) = t.text.parse::<i64>() {
+                out.push(Box::new(cv::LitNum {
+                    n: n as i128, f: n as f64, interp: crate::ir::shape::Interp::I64,
+                }), site(t.span, cx));
+                return Ok(Flow::Next);
             }
+            if let Ok(f) = t.text.parse::<f64>() {
+                out.push(Box::new(cv::LitNum {
+                    n: f as i128, f, interp: crate::ir::shape::Interp::F64,
+                }), site(t.span, cx));
+                return Ok(Flow::Next);
+            }
+            return Err(Diagnostic::at(t.span, format!("unknown token: {}", t.text)));
         }
     }
-    *defs = defs_snapshot;
-    Ok(out)
+    Ok(Flow::Next)
 }
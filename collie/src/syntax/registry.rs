@@ -47,10 +47,19 @@ impl OpRegistry {
         crate::ops::cmp::register(&mut r);
         crate::ops::convert::register(&mut r);
         crate::ops::reduce_ops::register(&mut r);
+        crate::ops::approx::register(&mut r);
+        crate::ops::stats::register(&mut r);
+        crate::ops::calendar::register(&mut r);
+        crate::ops::decimal::register(&mut r);
+        crate::ops::pivot::register(&mut r);
+        crate::ops::rand::register(&mut r);
+        crate::ops::opt::register(&mut r);
         crate::ops::sort_concat::register(&mut r);
         crate::ops::sort::register(&mut r);
         crate::ops::swizzle::register(&mut r);
         crate::ops::view::register(&mut r);
+        crate::ops::encode::register(&mut r);
+        crate::ops::quote::register(&mut r);
         r
     }
 }
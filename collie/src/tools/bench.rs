@@ -2,7 +2,7 @@
 
 use std::sync::Arc;
 use std::time::Instant;
-use crate::pipeline::{build_seeded, optimize, eval_graph};
+use crate::pipeline::{build_seeded, optimize_unfolded, eval_graph};
 use crate::syntax::parse::parse;
 use crate::ir::value::{Value, Prim, from_vec, Storage, prod};
 use crate::ir::shape::Interp;
@@ -38,7 +38,7 @@ pub fn run_bench() -> Result<(), String> {
         println!("[bench 1] per-row sum, N = {}", n);
         let runs = 20;
         let pairs = prod(vec![from_vec::<u64>(a_src.clone()), from_vec::<u64>(b_src.clone())]);
-        let g = optimize(build_seeded(prog, vec![pairs]).unwrap().0);
+        let g = optimize_unfolded(build_seeded(prog, vec![pairs]).unwrap().0);
         let colang_time = bench_run("collie", n, runs, || {
             std::hint::black_box(eval_graph(&g).unwrap());
         });
@@ -56,7 +56,7 @@ pub fn run_bench() -> Result<(), String> {
         println!("[bench 2] filter (keep >= 50), N = {}", n);
         let runs = 20;
         let v_in = from_vec::<u64>(src.clone());
-        let g = optimize(build_seeded(prog, vec![v_in]).unwrap().0);
+        let g = optimize_unfolded(build_seeded(prog, vec![v_in]).unwrap().0);
         let colang_time = bench_run("collie", n, runs, || {
             std::hint::black_box(eval_graph(&g).unwrap());
         });
@@ -75,7 +75,7 @@ pub fn run_bench() -> Result<(), String> {
         println!("[bench 3] GROUP BY (100 regions, sum), N = {}", n);
         let runs = 10;
         let pair = prod(vec![from_vec::<u8>(regions.clone()), from_vec::<u64>(sales.clone())]);
-        let g = optimize(build_seeded(prog, vec![pair]).unwrap().0);
+        let g = optimize_unfolded(build_seeded(prog, vec![pair]).unwrap().0);
         let colang_time = bench_run("collie", n, runs, || {
             std::hint::black_box(eval_graph(&g).unwrap());
         });
@@ -116,7 +116,7 @@ pub fn run_bench() -> Result<(), String> {
         let n_total = n_left + n_right;
         let l = prod(vec![from_vec::<u64>(lk.clone()), from_vec::<u64>(la.clone())]);
         let r = prod(vec![from_vec::<u64>(rk.clone()), from_vec::<f64>(rb.clone())]);
-        let g = optimize(build_seeded(prog, vec![l, r]).unwrap().0);
+        let g = optimize_unfolded(build_seeded(prog, vec![l, r]).unwrap().0);
         let colang_time = bench_run("collie", n_total, runs, || {
             std::hint::black_box(eval_graph(&g).unwrap());
         });
@@ -151,7 +151,7 @@ pub fn run_bench() -> Result<(), String> {
         let runs = 100_000;
         let a = from_vec::<u64>(a_src.clone());
         let b = from_vec::<u64>(b_src.clone());
-        let g = optimize(build_seeded(prog, vec![a, b]).unwrap().0);
+        let g = optimize_unfolded(build_seeded(prog, vec![a, b]).unwrap().0);
         let colang_time = bench_run("collie", n, runs, || {
             std::hint::black_box(eval_graph(&g).unwrap());
         });
@@ -179,7 +179,7 @@ pub fn run_bench() -> Result<(), String> {
         let runs = 100_000;
         let a = from_vec::<i32>(a_src.clone());
         let b = from_vec::<i32>(b_src.clone());
-        let g = optimize(build_seeded(prog, vec![a, b]).unwrap().0);
+        let g = optimize_unfolded(build_seeded(prog, vec![a, b]).unwrap().0);
         let colang_time = bench_run("collie", n, runs, || {
             std::hint::black_box(eval_graph(&g).unwrap());
         });
@@ -245,7 +245,7 @@ pub fn run_bench() -> Result<(), String> {
         let runs = 5;
         let prog = parse("sort", &reg)?;
         let v = from_vec::<u64>(xs.clone());
-        let g = optimize(build_seeded(prog, vec![v]).unwrap().0);
+        let g = optimize_unfolded(build_seeded(prog, vec![v]).unwrap().0);
         let colang_time = bench_run("collie", n, runs, || {
             std::hint::black_box(eval_graph(&g).unwrap());
         });
@@ -267,7 +267,7 @@ pub fn run_bench() -> Result<(), String> {
         let runs = 5;
         let prog = parse("sort", &reg)?;
         let pair = prod(vec![from_vec::<u64>(k0.clone()), from_vec::<u64>(k1.clone())]);
-        let g = optimize(build_seeded(prog, vec![pair]).unwrap().0);
+        let g = optimize_unfolded(build_seeded(prog, vec![pair]).unwrap().0);
         let colang_time = bench_run("collie", n, runs, || {
             std::hint::black_box(eval_graph(&g).unwrap());
         });
@@ -300,7 +300,7 @@ pub fn run_bench() -> Result<(), String> {
             bounds: crate::ir::value::bounds_var_from_ends(bounds.clone()),
             values: Arc::new(from_vec::<u64>(flat.clone())),
         };
-        let g = optimize(build_seeded(prog, vec![list]).unwrap().0);
+        let g = optimize_unfolded(build_seeded(prog, vec![list]).unwrap().0);
         let colang_time = bench_run("collie", n_lists, runs, || {
             std::hint::black_box(eval_graph(&g).unwrap());
         });
@@ -357,7 +357,7 @@ pub fn run_bench() -> Result<(), String> {
             bounds: crate::ir::value::bounds_var_from_ends(ob.clone()),
             values: Arc::new(inner.clone()),
         };
-        let g = optimize(build_seeded(prog, vec![deep]).unwrap().0);
+        let g = optimize_unfolded(build_seeded(prog, vec![deep]).unwrap().0);
         let colang_time = bench_run("collie", outer, runs, || {
             std::hint::black_box(eval_graph(&g).unwrap());
         });
@@ -423,7 +423,7 @@ pub fn run_bench() -> Result<(), String> {
             disc: disc_prim,
             lanes: Arc::new(vec![lane0_v, lane1_v, lane2_v]),
         };
-        let g = optimize(build_seeded(prog, vec![sum_v]).unwrap().0);
+        let g = optimize_unfolded(build_seeded(prog, vec![sum_v]).unwrap().0);
         let colang_time = bench_run("collie", n, runs, || {
             std::hint::black_box(eval_graph(&g).unwrap());
         });
@@ -571,5 +571,56 @@ pub fn run_bench() -> Result<(), String> {
         }
     }
 
+    // [bench 16] random gather: 8M- and 64M-row u64 gathers at random
+    // indices, the DRAM-latency-bound case (see the "gather is a sort"
+    // note in dev/BACKLOG.md).
+    {
+        use crate::ops::helpers::gather_prim;
+        for n in [8usize << 20, 64 << 20] {
+            println!("[bench 16] gather {}M random u64 from {}M", n >> 20, n >> 20);
+            let p = Prim::P64(Arc::new((0..n as u64).collect()));
+            let idxs: Vec<usize> = (0..n as u64)
+                .map(|i| (i.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 17) as usize % n).collect();
+            bench_run("gather_prim", n, 3, || {
+                std::hint::black_box(gather_prim(&p, &idxs));
+            });
+            println!();
+        }
+    }
+
+    // [bench 17] filter materialization: the branchy positions-then-
+    // gather kernel `filter` used before vs the branch-free compress
+    // (`ops::compress`), per width, at 10/50/90% selectivity.
+    {
+        use crate::ops::compress::compress_prim;
+        use crate::ops::helpers::gather_prim;
+        let n = 4_000_000usize;
+        let runs = 10;
+        let mut s = 0x9E37_79B9_7F4A_7C15u64;
+        let rand: Vec<u64> = (0..n).map(|_| { s ^= s << 13; s ^= s >> 7; s ^= s << 17; s % 100 }).collect();
+        let cols = [
+            ("u8 ", Prim::P8(Arc::new((0..n).map(|i| i as u8).collect()))),
+            ("u16", Prim::P16(Arc::new((0..n).map(|i| i as u16).collect()))),
+            ("u32", Prim::P32(Arc::new((0..n as u32).collect()))),
+            ("u64", Prim::P64(Arc::new((0..n as u64).collect()))),
+        ];
+        for pct in [10u64, 50, 90] {
+            let mask: Vec<u8> = rand.iter().map(|&r| (r < pct) as u8).collect();
+            println!("[bench 17] filter N = {} at {}% selectivity", n, pct);
+            for (label, col) in &cols {
+                let branchy = bench_run(&format!("{} branchy+gather", label), n, runs, || {
+                    let mut idxs = Vec::with_capacity(n / 2);
+                    for (i, &b) in mask.iter().enumerate() { if b != 0 { idxs.push(i); } }
+                    std::hint::black_box(gather_prim(col, &idxs));
+                });
+                let compress = bench_run(&format!("{} compress", label), n, runs, || {
+                    std::hint::black_box(compress_prim(col, &mask));
+                });
+                println!("  {} speedup: {:.2}x", label.trim(), branchy.as_nanos() as f64 / compress.as_nanos() as f64);
+            }
+            println!();
+        }
+    }
+
     Ok(())
 }
@@ -0,0 +1,588 @@
+//! Binding a program's declared inputs (`syntax::header`) from the
+//! command line: `collie run prog.col sales=@today.csv k=5`.
+//!
+//! Each argument is `name=VALUE`:
+//!
+//! - `@path.csv` — CSV, one row per line, one field per Prim leaf of the
+//!   declared type (a Prod of Prims flattens left to right). Fields are
+//!   bare numbers (`true`/`false` also read as a `bool`, ISO-8601 text as a
+//!   `date` / `timestamp`); no quoting. A
+//!   first line that doesn't parse is taken as a column header. An empty
+//!   field is a missing value, for a `T?` leaf.
+//! - `@path.jsonl` / `@path.json` — JSON, one object per row (a `.json`
+//!   file may also hold one array of them), keyed as `--format=jsonl`
+//!   writes them: by field label, or `name.k` for an unlabeled field, or
+//!   `name` when the type isn't a Prod. Any declared type: a `str` is a
+//!   string, a List an array, a nested Prod an object by label (an array
+//!   when unlabeled), a Sum `{"label": v}` or `{"tag": k, "value": v}`,
+//!   and leaves are numbers, `true`/`false` or strings holding the text
+//!   CSV would. `null` or an absent key is a missing value for `T?` (and
+//!   NaN for a float, as the writer prints it); for any other type it is
+//!   an error.
+//! - `@path` (any other extension) — the `serialize` binary format; any
+//!   declared type, checked against its shape.
+//! - anything else — literal CSV text with `;` between rows: `k=5`,
+//!   `ids=1;2;3`, `pairs=1,2.5;2,0.5`.
+//!
+//! Every declaration must be bound, a `param` to exactly one row, and the
+//! values are checked against the declared shapes before the program is
+//! lowered. Errors point at the declaration.
+
+use std::sync::Arc;
+
+use crate::ir::encoding::from_words;
+use crate::ir::shape::{shape_of, Interp};
+use crate::ir::span::Diagnostic;
+use crate::ir::value::{bounds_var_from_ends, list, prod, sum, Prim, PrimWidth, Value};
+use crate::ops::calendar::{self, Temporal};
+use crate::ops::decimal::{self, Dec};
+use crate::ops::opt::opt_value;
+use crate::syntax::header::{Decl, DeclKind, Ty};
+
+/// The values of the `input` / `param` declarations in `decls`, in order,
+/// from `name=VALUE` arguments.
+pub fn bind(decls: &[Decl], args: &[String]) -> Result<Vec<Value>, Diagnostic> {
+    let decls: Vec<&Decl> = decls.iter().filter(|d| d.is_bound()).collect();
+    let mut given: Vec<(&str, &str)> = Vec::with_capacity(args.len());
+    for a in args {
+        let (name, text) = a.split_once('=')
+            .ok_or_else(|| Diagnostic::new(format!("binding {:?}: expected name=VALUE", a)))?;
+        if !decls.iter().any(|d| d.name == name) {
+            let declared: Vec<&str> = decls.iter().map(|d| d.name.as_str()).collect();
+            return Err(Diagnostic::new(format!("binding {}: no such input (declared: {})", name,
+                if declared.is_empty() { "none".to_string() } else { declared.join(", ") })));
+        }
+        if given.iter().any(|(n, _)| *n == name) {
+            return Err(Diagnostic::new(format!("binding {}: given twice", name)));
+        }
+        given.push((name, text));
+    }
+    decls.iter().map(|d| {
+        let err = |m: String| Diagnostic::at(d.span, format!("{} {}: {}", d.kind.keyword(), d.name, m));
+        let text = given.iter().find(|(n, _)| *n == d.name).map(|(_, t)| *t)
+            .ok_or_else(|| err(format!("not bound (pass {}=VALUE or {}=@file)", d.name, d.name)))?;
+        let v = read(&d.name, &d.ty, text).map_err(err)?;
+        let (want, got) = (d.ty.shape(), shape_of(&v));
+        if want != got { return Err(err(format!("declared {} ({}), got {}", d.ty, want, got))); }
+        if d.kind == DeclKind::Param && v.len() != 1 {
+            return Err(err(format!("a param takes one row, got {}", v.len())));
+        }
+        Ok(v)
+    }).collect()
+}
+
+/// One argument's value: a file (`@path`) or literal text, for the
+/// declaration `name`.
+fn read(name: &str, ty: &Ty, text: &str) -> Result<Value, String> {
+    let Some(path) = text.strip_prefix('@') else {
+        return from_csv(ty, &text.replace(';', "\n"), false);
+    };
+    if path.ends_with(".csv") {
+        let src = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        return from_csv(ty, &src, true).map_err(|e| format!("{}: {}", path, e));
+    }
+    if path.ends_with(".jsonl") || path.ends_with(".json") {
+        let src = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        return from_jsonl(name, ty, &src).map_err(|e| format!("{}: {}", path, e));
+    }
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut pos = 0;
+    let v = crate::tools::serialize::decode(&bytes, &mut pos).map_err(|e| format!("{}: {}", path, e))?;
+    if pos != bytes.len() { return Err(format!("{}: {} trailing bytes", path, bytes.len() - pos)); }
+    Ok(v)
+}
+
+/// Rows of CSV text as a value of `ty`, which must be a Prim or a Prod of
+/// Prims. Blank lines are skipped; with `header`, so is a first line that
+/// doesn't parse.
+pub fn from_csv(ty: &Ty, text: &str, header: bool) -> Result<Value, String> {
+    let leaves = ty.flat_leaves()
+        .ok_or_else(|| format!("{} can't be read from CSV or literal text; bind it from a serialized file", ty))?;
+    let words: usize = leaves.iter().map(|t| leaf_words(t)).sum();
+    let mut cols: Vec<Vec<u64>> = vec![Vec::new(); words];
+    let rows = text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+    for (k, (n, line)) in rows.enumerate() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let parsed: Result<Vec<u64>, String> = if fields.len() != leaves.len() {
+            Err(format!("{} fields, expected {}", fields.len(), leaves.len()))
+        } else {
+            let mut ws = Vec::with_capacity(words);
+            fields.iter().zip(&leaves).try_for_each(|(f, t)| cell(t, f, &mut ws)).map(|()| ws)
+        };
+        match parsed {
+            Ok(ws) => for (c, w) in cols.iter_mut().zip(ws) { c.push(w); },
+            Err(_) if header && k == 0 => continue,
+            Err(e) => return Err(format!("line {}: {}", n + 1, e)),
+        }
+    }
+    let mut cols = cols.into_iter();
+    Ok(assemble(ty, &mut cols))
+}
+
+/// How many words a leaf's field reads into: two for a `d128`, and a
+/// `T?` adds its presence word in front of `T`'s.
+fn leaf_words(ty: &Ty) -> usize {
+    match ty {
+        Ty::Decimal(Dec { wide: true, .. }) => 2,
+        Ty::Opt(t) => 1 + leaf_words(t),
+        _ => 1,
+    }
+}
+
+/// One field as the leaf's word (two for a `d128`: high, then low),
+/// pushed onto `out`. A `T?` pushes 1 and `T`'s words, or, for an empty
+/// field, 0 and zeros.
+fn cell(ty: &Ty, s: &str, out: &mut Vec<u64>) -> Result<(), String> {
+    if let Ty::Opt(t) = ty {
+        if s.is_empty() {
+            out.extend(std::iter::repeat_n(0, leaf_words(ty)));
+            return Ok(());
+        }
+        out.push(1);
+        return cell(t, s, out);
+    }
+    // In range, then two's complement truncated to the width (`lo` is 0
+    // or `-(hi + 1)`, so `hi - lo` is the width's all-ones mask).
+    let int = |lo: i128, hi: i128| -> Result<u64, String> {
+        let n: i128 = s.parse().map_err(|_| format!("{:?} is not an integer", s))?;
+        if n < lo || n > hi { return Err(format!("{} out of range for {}", n, ty)); }
+        Ok(n as u64 & (hi - lo) as u64)
+    };
+    let w = match ty {
+        Ty::Bool => match s {
+            "1" | "true" => Ok(1),
+            "0" | "false" => Ok(0),
+            _ => Err(format!("{:?} is not a bool", s)),
+        },
+        Ty::Prim(i) => match i {
+            Interp::U8 => int(0, u8::MAX as i128),
+            Interp::I8 => int(i8::MIN as i128, i8::MAX as i128),
+            Interp::U16 => int(0, u16::MAX as i128),
+            Interp::I16 => int(i16::MIN as i128, i16::MAX as i128),
+            Interp::U32 => int(0, u32::MAX as i128),
+            Interp::I32 => int(i32::MIN as i128, i32::MAX as i128),
+            Interp::U64 => int(0, u64::MAX as i128),
+            Interp::I64 => int(i64::MIN as i128, i64::MAX as i128),
+            Interp::F32 => s.parse::<f32>().map(|f| f.to_bits() as u64).map_err(|_| format!("{:?} is not a number", s)),
+            Interp::F64 => s.parse::<f64>().map(f64::to_bits).map_err(|_| format!("{:?} is not a number", s)),
+        },
+        Ty::Temporal(t) => {
+            let parsed = match t {
+                Temporal::Date => calendar::parse_date(s.as_bytes()).and_then(|d| i32::try_from(d).ok()).map(|d| d as u32 as u64),
+                Temporal::Timestamp => calendar::parse_timestamp(s.as_bytes()).map(|t| t as u64),
+            };
+            parsed.ok_or_else(|| format!("{:?} is not an ISO-8601 {}", s, t))
+        }
+        Ty::Decimal(d) => {
+            let x = decimal::parse_text(s.as_bytes(), *d)
+                .ok_or_else(|| format!("{:?} is not a {} decimal (or out of range)", s, d))?;
+            if d.wide { out.push((x >> 64) as u64); }
+            Ok(x as u64)
+        }
+        _ => unreachable!("flat_leaves yields Prim leaves"),
+    }?;
+    out.push(w);
+    Ok(())
+}
+
+/// Rebuild `ty`'s structure over its leaf columns, in order.
+fn assemble(ty: &Ty, cols: &mut impl Iterator<Item = Vec<u64>>) -> Value {
+    match ty {
+        Ty::Prod(fs) => prod(fs.iter().map(|f| assemble(f, cols)).collect()),
+        Ty::Labeled(_, t) => assemble(t, cols),
+        Ty::Opt(t) => {
+            // Lanes are compact: keep the inner words of present rows only.
+            let present: Vec<u8> = cols.next().unwrap_or_default().into_iter().map(|w| w as u8).collect();
+            let inner: Vec<Vec<u64>> = (0..leaf_words(t)).map(|_| {
+                let c = cols.next().unwrap_or_default();
+                c.into_iter().zip(&present).filter(|(_, &p)| p == 1).map(|(w, _)| w).collect()
+            }).collect();
+            opt_value(present, assemble(t, &mut inner.into_iter()))
+        }
+        Ty::Decimal(Dec { wide: true, .. }) => {
+            let (hi, lo) = (cols.next().unwrap_or_default(), cols.next().unwrap_or_default());
+            prod(vec![Value::Prim(from_words(PrimWidth::W64, hi)), Value::Prim(from_words(PrimWidth::W64, lo))])
+        }
+        _ => {
+            let w = ty.shape();
+            let crate::ir::shape::Shape::Prim(w) = w else { unreachable!("flat leaf") };
+            Value::Prim(from_words(w, cols.next().unwrap_or_default()))
+        }
+    }
+}
+
+/// JSON rows (one object per row, or one array of them) as a value of
+/// `ty`, for the declaration `name`. See the module docs for the keys.
+pub fn from_jsonl(name: &str, ty: &Ty, text: &str) -> Result<Value, String> {
+    let mut p = JsonParser { s: text.as_bytes(), pos: 0 };
+    let mut rows = Vec::new();
+    while p.skip_ws() {
+        rows.push(p.value().map_err(|e| format!("line {}: {}", p.line(), e))?);
+    }
+    if let [Json::Arr(xs)] = rows.as_mut_slice() { rows = std::mem::take(xs); }
+    let mut col = Col::new(ty);
+    for (n, row) in rows.iter().enumerate() {
+        let Json::Obj(kvs) = row else { return Err(format!("row {}: expected an object", n + 1)) };
+        let get = |k: &str| kvs.iter().find(|(key, _)| key == k).map(|(_, v)| v);
+        let pushed = match (ty.unlabeled().1, &mut col) {
+            (Ty::Prod(fs), Col::Prod(cs)) => fs.iter().zip(cs).enumerate().try_for_each(|(k, (f, c))| {
+                let key = f.unlabeled().0.map_or_else(|| format!("{}.{}", name, k), str::to_string);
+                c.push(f, get(&key)).map_err(|e| format!("{}: {}", key, e))
+            }),
+            _ => col.push(ty, get(name)).map_err(|e| format!("{}: {}", name, e)),
+        };
+        pushed.map_err(|e| format!("row {}: {}", n + 1, e))?;
+    }
+    Ok(col.finish(ty))
+}
+
+/// A column under construction from JSON, mirroring its `Ty` (a `T?` is
+/// the two-lane `Sum` it lowers to).
+enum Col {
+    /// A text leaf's words, as `cell` reads them: one column per word.
+    Leaf(Vec<Vec<u64>>),
+    /// Row ends and UTF-8 bytes.
+    Str(Vec<u64>, Vec<u8>),
+    /// Row ends and the elements.
+    List(Vec<u64>, Box<Col>),
+    Prod(Vec<Col>),
+    Sum(Vec<u8>, Vec<Col>),
+}
+
+impl Col {
+    fn new(ty: &Ty) -> Col {
+        match ty {
+            Ty::Labeled(_, t) => Col::new(t),
+            Ty::Str => Col::Str(Vec::new(), Vec::new()),
+            Ty::List(t) => Col::List(Vec::new(), Box::new(Col::new(t))),
+            Ty::Prod(fs) => Col::Prod(fs.iter().map(Col::new).collect()),
+            Ty::Sum(ls) => Col::Sum(Vec::new(), ls.iter().map(Col::new).collect()),
+            Ty::Opt(t) => Col::Sum(Vec::new(), vec![Col::Prod(Vec::new()), Col::new(t)]),
+            leaf => Col::Leaf(vec![Vec::new(); leaf_words(leaf)]),
+        }
+    }
+
+    /// Append one row; `j` is `None` for an absent key.
+    fn push(&mut self, ty: &Ty, j: Option<&Json>) -> Result<(), String> {
+        let ty = ty.unlabeled().1;
+        match (ty, self, j) {
+            (Ty::Opt(_), Col::Sum(disc, _), None | Some(Json::Null)) => { disc.push(0); Ok(()) }
+            (Ty::Opt(t), Col::Sum(disc, lanes), j) => { disc.push(1); lanes[1].push(t, j) }
+            (_, _, None) => Err(format!("missing (declare it {}? to allow that)", ty)),
+            (Ty::Str, Col::Str(ends, bytes), Some(Json::Str(s))) => {
+                bytes.extend_from_slice(s.as_bytes());
+                ends.push(bytes.len() as u64);
+                Ok(())
+            }
+            (Ty::List(t), Col::List(ends, inner), Some(Json::Arr(xs))) => {
+                xs.iter().try_for_each(|x| inner.push(t, Some(x)))?;
+                ends.push(ends.last().copied().unwrap_or(0) + xs.len() as u64);
+                Ok(())
+            }
+            (Ty::Prod(fs), Col::Prod(cs), Some(Json::Arr(xs))) if xs.len() == fs.len() => {
+                fs.iter().zip(cs).zip(xs).try_for_each(|((f, c), x)| c.push(f, Some(x)))
+            }
+            (Ty::Prod(fs), Col::Prod(cs), Some(Json::Obj(kvs))) if fs.iter().all(|f| f.unlabeled().0.is_some()) => {
+                fs.iter().zip(cs).try_for_each(|(f, c)| {
+                    let l = f.unlabeled().0.unwrap_or_default();
+                    c.push(f, kvs.iter().find(|(k, _)| k == l).map(|(_, v)| v)).map_err(|e| format!("{}: {}", l, e))
+                })
+            }
+            (Ty::Sum(ls), Col::Sum(disc, lanes), Some(Json::Obj(kvs))) => {
+                let by_label = match kvs.as_slice() {
+                    [(k, v)] => ls.iter().position(|l| l.unlabeled().0 == Some(k.as_str())).map(|at| (at, v)),
+                    _ => None,
+                };
+                let tagged = || {
+                    let get = |key: &str| kvs.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+                    match (get("tag"), get("value")) {
+                        (Some(Json::Num(t)), Some(v)) => t.parse::<usize>().ok().filter(|&at| at < ls.len()).map(|at| (at, v)),
+                        _ => None,
+                    }
+                };
+                let (at, v) = by_label.or_else(tagged)
+                    .ok_or_else(|| format!("expected one of {}'s lanes as {{\"label\": v}} or {{\"tag\": k, \"value\": v}}", ty))?;
+                disc.push(at as u8);
+                lanes[at].push(&ls[at], Some(v))
+            }
+            (leaf, Col::Leaf(words), Some(j)) => {
+                let text = match j {
+                    Json::Num(s) | Json::Str(s) => s.as_str(),
+                    Json::Bool(b) => if *b { "true" } else { "false" },
+                    Json::Null if matches!(leaf, Ty::Prim(Interp::F32 | Interp::F64)) => "NaN",
+                    other => return Err(format!("expected {}, got {}", leaf, other.kind())),
+                };
+                let mut ws = Vec::with_capacity(words.len());
+                cell(leaf, text, &mut ws)?;
+                for (c, w) in words.iter_mut().zip(ws) { c.push(w); }
+                Ok(())
+            }
+            (_, _, Some(j)) => Err(format!("expected {}, got {}", ty, j.kind())),
+        }
+    }
+
+    fn finish(self, ty: &Ty) -> Value {
+        match (ty.unlabeled().1, self) {
+            (leaf, Col::Leaf(words)) => assemble(leaf, &mut words.into_iter()),
+            (_, Col::Str(ends, bytes)) => list(bounds_var_from_ends(ends), Value::Prim(Prim::P8(Arc::new(bytes)))),
+            (Ty::List(t), Col::List(ends, inner)) => list(bounds_var_from_ends(ends), inner.finish(t)),
+            (Ty::Prod(fs), Col::Prod(cs)) => prod(fs.iter().zip(cs).map(|(f, c)| c.finish(f)).collect()),
+            (Ty::Sum(ls), Col::Sum(disc, lanes)) => {
+                sum(Prim::P8(Arc::new(disc)), ls.iter().zip(lanes).map(|(l, c)| c.finish(l)).collect())
+            }
+            (Ty::Opt(t), Col::Sum(disc, mut lanes)) => {
+                let inner = lanes.pop().expect("opt has two lanes");
+                opt_value(disc, inner.finish(t))
+            }
+            _ => unreachable!("Col::new mirrors the type"),
+        }
+    }
+}
+
+/// A parsed JSON value; numbers keep their text, for `cell`.
+#[derive(Debug)]
+enum Json {
+    Null,
+    Bool(bool),
+    Num(String),
+    Str(String),
+    Arr(Vec<Json>),
+    Obj(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn kind(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Bool(_) => "a bool",
+            Json::Num(_) => "a number",
+            Json::Str(_) => "a string",
+            Json::Arr(_) => "an array",
+            Json::Obj(_) => "an object",
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    /// Skip whitespace; false at the end of the text.
+    fn skip_ws(&mut self) -> bool {
+        while self.s.get(self.pos).is_some_and(u8::is_ascii_whitespace) { self.pos += 1; }
+        self.pos < self.s.len()
+    }
+
+    fn line(&self) -> usize {
+        1 + self.s[..self.pos.min(self.s.len())].iter().filter(|&&b| b == b'\n').count()
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), String> {
+        self.skip_ws();
+        if self.s.get(self.pos) != Some(&b) {
+            return Err(format!("expected {:?}", b as char));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        if !self.skip_ws() { return Err("unexpected end".into()); }
+        let rest = &self.s[self.pos..];
+        for (word, v) in [(&b"null"[..], Json::Null), (b"true", Json::Bool(true)), (b"false", Json::Bool(false))] {
+            if rest.starts_with(word) {
+                self.pos += word.len();
+                return Ok(v);
+            }
+        }
+        match rest[0] {
+            b'"' => self.string().map(Json::Str),
+            b'[' => {
+                self.pos += 1;
+                let mut xs = Vec::new();
+                if !self.close(b']')? {
+                    loop {
+                        xs.push(self.value()?);
+                        if self.close(b']')? { break; }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Json::Arr(xs))
+            }
+            b'{' => {
+                self.pos += 1;
+                let mut kvs = Vec::new();
+                if !self.close(b'}')? {
+                    loop {
+                        self.skip_ws();
+                        let k = self.string()?;
+                        self.expect(b':')?;
+                        kvs.push((k, self.value()?));
+                        if self.close(b'}')? { break; }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Json::Obj(kvs))
+            }
+            b'-' | b'0'..=b'9' => {
+                let len = rest.iter().take_while(|b| b.is_ascii_digit() || b"+-.eE".contains(b)).count();
+                self.pos += len;
+                Ok(Json::Num(String::from_utf8_lossy(&rest[..len]).into_owned()))
+            }
+            b => Err(format!("unexpected {:?}", b as char)),
+        }
+    }
+
+    /// Consume `b` if it's next.
+    fn close(&mut self, b: u8) -> Result<bool, String> {
+        if !self.skip_ws() { return Err("unexpected end".into()); }
+        let hit = self.s[self.pos] == b;
+        if hit { self.pos += 1; }
+        Ok(hit)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.s.get(self.pos) != Some(&b'"') { return Err("expected a string".into()); }
+        self.pos += 1;
+        let mut out: Vec<u8> = Vec::new();
+        loop {
+            let b = *self.s.get(self.pos).ok_or("unterminated string")?;
+            self.pos += 1;
+            match b {
+                b'"' => return String::from_utf8(out).map_err(|_| "string is not UTF-8".into()),
+                b'\\' => {
+                    let e = *self.s.get(self.pos).ok_or("unterminated string")?;
+                    self.pos += 1;
+                    let ch = match e {
+                        b'"' => '"', b'\\' => '\\', b'/' => '/',
+                        b'b' => '\u{8}', b'f' => '\u{c}', b'n' => '\n', b'r' => '\r', b't' => '\t',
+                        b'u' => {
+                            let hi = self.hex4()?;
+                            let code = if (0xD800..0xDC00).contains(&hi) && self.s[self.pos..].starts_with(b"\\u") {
+                                self.pos += 2;
+                                let lo = self.hex4()?;
+                                0x10000 + ((hi - 0xD800) << 10) + (lo.wrapping_sub(0xDC00) & 0x3FF)
+                            } else {
+                                hi
+                            };
+                            char::from_u32(code).unwrap_or('\u{FFFD}')
+                        }
+                        other => return Err(format!("bad escape \\{}", other as char)),
+                    };
+                    out.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                b => out.push(b),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self.s.get(self.pos..self.pos + 4).ok_or("short \\u escape")?;
+        self.pos += 4;
+        u32::from_str_radix(std::str::from_utf8(digits).unwrap_or(""), 16).map_err(|_| "bad \\u escape".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::value::from_vec;
+    use crate::pipeline::{build_parsed_seeded, eval_graph};
+    use crate::syntax::header::split_header;
+    use crate::syntax::{parse::parse_program_bound, registry::OpRegistry};
+
+    const PROG: &str = "# per-region totals\ninput sales : (u64, i32)\n\nparam k : i32\noutput by : (region: u64, total: i32)\nsales .0 sales .1 k *.i32 swap group reduce.+.i32\n";
+
+    fn run(args: &[&str]) -> Result<Vec<Value>, String> {
+        let (decls, body) = split_header(PROG).map_err(|d| d.render(PROG, "t.col"))?;
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let seeds = bind(&decls, &args).map_err(|d| d.render(PROG, "t.col"))?;
+        let names: Vec<String> = decls.iter().filter(|d| d.is_bound()).map(|d| d.name.clone()).collect();
+        let parsed = parse_program_bound(&body, &OpRegistry::standard(), &names).map_err(|d| d.render(&body, "t.col"))?;
+        eval_graph(&build_parsed_seeded(parsed, seeds).map_err(|d| d.render(&body, "t.col"))?.graph)
+    }
+
+    #[test]
+    fn header_declares_typed_inputs() {
+        let (decls, body) = split_header(PROG).unwrap();
+        let shown: Vec<String> = decls.iter().map(|d| d.to_string()).collect();
+        assert_eq!(shown, vec!["input sales : (u64, i32)", "param k : i32", "output by : (region: u64, total: i32)"]);
+        assert_eq!(body.lines().count(), PROG.lines().count());
+        assert!(body.lines().nth(1).unwrap().is_empty());
+        assert_eq!(crate::syntax::header::parse_ty("<u8 | [(f64, bool)]>").unwrap().to_string(), "<u8 | [(f64, bool)]>");
+        assert_eq!(crate::syntax::header::parse_ty("<a:str|b : [u8]>").unwrap().to_string(), "<a: str | b: [u8]>");
+        let e = split_header("input x : (u64,\n").unwrap_err().render("input x : (u64,\n", "t.col");
+        assert!(e.contains("t.col:1:"), "{}", e);
+    }
+
+    #[test]
+    fn binds_literals_csv_and_serialized() {
+        let out = run(&["sales=1,5;2,-1;1,2", "k=3"]).unwrap();
+        assert_eq!(out, vec![from_vec::<u64>(vec![1, 2]), from_vec::<i32>(vec![21, -3])]);
+
+        let dir = std::env::temp_dir().join(format!("collie_bind_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("sales.csv");
+        std::fs::write(&csv, "region,qty\n2,4\n2,6\n").unwrap();
+        let bin = dir.join("k.bin");
+        let mut bytes = Vec::new();
+        crate::tools::serialize::encode(&from_vec::<i32>(vec![10]), &mut bytes);
+        std::fs::write(&bin, bytes).unwrap();
+        let out = run(&[&format!("sales=@{}", csv.display()), &format!("k=@{}", bin.display())]).unwrap();
+        assert_eq!(out, vec![from_vec::<u64>(vec![2]), from_vec::<i32>(vec![100])]);
+        std::fs::remove_dir_all(&dir).ok();
+
+        let ty = crate::syntax::header::parse_ty("(date, timestamp)").unwrap();
+        let out = from_csv(&ty, "2024-02-29, 2024-02-29T01:00:00+01:00", false).unwrap();
+        assert_eq!(out, prod(vec![from_vec::<i32>(vec![19_782]), from_vec::<i64>(vec![19_782 * 86_400_000_000])]));
+        assert!(from_csv(&ty, "2023-02-29, 2024-01-01", false).unwrap_err().contains("not an ISO-8601 date"));
+
+        let ty = crate::syntax::header::parse_ty("(d64.2, d128.4)").unwrap();
+        let out = from_csv(&ty, "19.99, -0.5", false).unwrap();
+        assert_eq!(out, prod(vec![from_vec::<i64>(vec![1999]), prod(vec![from_vec::<u64>(vec![u64::MAX]), from_vec::<i64>(vec![-5000])])]));
+        assert!(from_csv(&ty, "1.999, 1", false).unwrap_err().contains("not a d64.2 decimal"));
+    }
+
+    #[test]
+    fn missing_values_read_as_opt() {
+        use crate::ops::opt::opt_value;
+        let ty = crate::syntax::header::parse_ty("(a: u64?, b: d128.1?, c: i32)").unwrap();
+        assert_eq!(ty.to_string(), "(a: u64?, b: d128.1?, c: i32)");
+        let want = prod(vec![
+            opt_value(vec![1, 0], from_vec::<u64>(vec![7])),
+            opt_value(vec![0, 1], prod(vec![from_vec::<u64>(vec![0]), from_vec::<u64>(vec![25])])),
+            from_vec::<i32>(vec![-1, 2]),
+        ]);
+        assert_eq!(from_csv(&ty, "a,b,c\n7,,-1\n,2.5,2\n", true).unwrap(), want);
+        let rows = "{\"a\":7,\"b\":null,\"c\":-1}\n{\"b\":2.5,\"c\":2}\n";
+        assert_eq!(from_jsonl("t", &ty, rows).unwrap(), want);
+        assert_eq!(from_jsonl("t", &ty, &format!("[{}]", rows.trim_end().replace('\n', ","))).unwrap(), want);
+        assert!(from_jsonl("t", &ty, "{\"a\":7,\"b\":1}").unwrap_err().contains("row 1: c: missing"));
+        assert!(from_csv(&ty, "7,1,", false).unwrap_err().contains("is not an integer"));
+
+        // JSON reads any type, and what `--format=jsonl` writes.
+        let ty = crate::syntax::header::parse_ty("(name: str, xs: [f64?], s: <n: u64 | bool>)").unwrap();
+        let rows = "{\"name\":\"h\\u00e9\",\"xs\":[1.5,null],\"s\":{\"tag\":1,\"value\":true}}\n{\"name\":\"\",\"xs\":[],\"s\":{\"n\":4}}\n";
+        let v = from_jsonl("v", &ty, rows).unwrap();
+        assert_eq!(shape_of(&v), ty.shape());
+        let written = crate::tools::report::write(crate::tools::report::Format::Jsonl, &[("v".to_string(), ty.clone(), v)]).unwrap();
+        assert_eq!(written, rows.replace("\\u00e9", "\u{e9}"));
+        let ty = crate::syntax::header::parse_ty("u64").unwrap();
+        assert!(from_jsonl("n", &ty, "{\"n\":\"x\"}").unwrap_err().contains("\"x\" is not an integer"));
+        assert!(from_jsonl("n", &ty, "{\"n\":1,}").unwrap_err().contains("line 1: expected a string"));
+    }
+
+    #[test]
+    fn rejects_bad_bindings() {
+        let err = |args: &[&str]| run(args).unwrap_err();
+        assert!(err(&["sales=1,2"]).contains("param k: not bound"));
+        assert!(err(&["sales=1,2", "k=1;2"]).contains("one row, got 2"));
+        assert!(err(&["sales=1,2", "k=1", "j=3"]).contains("no such input"));
+        assert!(err(&["sales=1,x", "k=1"]).contains("\"x\" is not an integer"));
+        assert!(err(&["sales=1,2", "k=3000000000"]).contains("out of range for i32"));
+        // Errors about a binding point at its declaration.
+        assert!(err(&["sales=1", "k=1"]).contains("t.col:2:7"));
+    }
+}
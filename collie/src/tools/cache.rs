@@ -0,0 +1,342 @@
+//! Content-addressed memoization of term outputs across runs (`collie run
+//! --cache DIR`).
+//!
+//! A term's identity is a 128-bit hash of its op — the variant and its
+//! parameters, as a plan stores them (`tools::plan::op_bytes`) — and its
+//! inputs' identities. A `Const` (literals, and inputs bound from files)
+//! is hashed by content, streamed straight from its columns rather than
+//! serialized first. Equal identity means the same computation
+//! over the same data, whatever script it came from. After a term runs,
+//! outputs whose serialized size reaches `min_bytes` are written to
+//! `DIR/<identity>.val` in the `tools::serialize` format.
+//!
+//! Before a run, every term whose entry exists is a hit. Walking back
+//! from the roots, a hit's inputs aren't needed on its account, so the
+//! expensive prefix a cached sort or group sits on never runs. Terms
+//! with no identity — an `Input` (bound by value only at run time) or a
+//! `Foreign` op without a `token`, and everything downstream of one —
+//! always run, as do side-effecting ones (`show`, `time`), so their
+//! output still appears. A `Const` is never stored: it already is its
+//! value; nor is an output holding a View or an encoded column, which
+//! the wire format would materialize.
+//!
+//! Entries are written to a temporary name and renamed into place, so a
+//! concurrent or interrupted run never leaves a torn entry behind. The
+//! identity is salted with the crate version and plan format, so
+//! entries from another build are never read. Nothing evicts: delete the
+//! directory to reset.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::ir::value::{Prim, Value};
+use crate::ops::helpers::{materialize_ref, splitmix64};
+use crate::pipeline::graph::Graph;
+use crate::pipeline::sysop::SystemOp;
+use crate::pipeline::{eval_graph_memo, Memo};
+use crate::tools::{plan, serialize};
+
+/// Outputs smaller than this (serialized) are cheaper to recompute than
+/// to read back.
+pub const DEFAULT_MIN_BYTES: usize = 64 * 1024;
+
+/// A term identity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Id(u128);
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{:032x}", self.0) }
+}
+
+/// What one cached run did.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CacheStats {
+    /// Terms read from the cache, and the bytes read.
+    pub hits: usize,
+    pub hit_bytes: usize,
+    /// Terms whose outputs were written, and the bytes written.
+    pub stored: usize,
+    pub stored_bytes: usize,
+    /// Terms that ran, and terms that didn't need to (only a hit read them).
+    pub ran: usize,
+    pub skipped: usize,
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cache: {} hit ({} bytes read), {} stored ({} bytes written), {} ran, {} skipped",
+            self.hits, self.hit_bytes, self.stored, self.stored_bytes, self.ran, self.skipped)
+    }
+}
+
+pub struct Cache {
+    dir: PathBuf,
+    /// Only outputs at least this large (serialized) are stored.
+    pub min_bytes: usize,
+}
+
+impl Cache {
+    /// A cache under `dir`, created if missing.
+    pub fn open(dir: &Path) -> Result<Cache, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("cache {}: {}", dir.display(), e))?;
+        Ok(Cache { dir: dir.to_path_buf(), min_bytes: DEFAULT_MIN_BYTES })
+    }
+
+    fn entry(&self, id: Id) -> PathBuf { self.dir.join(format!("{}.val", id)) }
+
+    /// `eval_graph(g)`, reusing and filling the cache.
+    pub fn run(&self, g: &Graph) -> Result<(Vec<Value>, CacheStats), String> {
+        let ids = identities(g);
+        let n = g.terms.len();
+        let hit: Vec<bool> = (0..n).map(|i| {
+            ids[i].is_some_and(|id| worth_storing(&g.terms[i].op) && self.entry(id).is_file())
+        }).collect();
+        let mut needed = vec![false; n];
+        for r in &g.roots { needed[r.term] = true; }
+        for (i, t) in g.terms.iter().enumerate() {
+            if t.op.is_side_effecting() { needed[i] = true; }
+        }
+        for i in (0..n).rev() {
+            if needed[i] && !hit[i] {
+                for ch in &g.terms[i].children { needed[ch.term] = true; }
+            }
+        }
+        let mut stats = CacheStats::default();
+        let mut memo = Vec::with_capacity(n);
+        for i in 0..n {
+            memo.push(match (needed[i], hit[i]) {
+                (false, _) => { stats.skipped += 1; Memo::Skip }
+                (true, true) => {
+                    let id = ids[i].expect("a hit has an identity");
+                    let (vs, bytes) = self.load(id, g.terms[i].n_outputs)?;
+                    stats.hits += 1;
+                    stats.hit_bytes += bytes;
+                    Memo::Reuse(vs)
+                }
+                (true, false) => { stats.ran += 1; Memo::Run }
+            });
+        }
+        let mut store = |i: usize, outs: &[Value]| -> Result<(), String> {
+            let Some(id) = ids[i] else { return Ok(()) };
+            if !worth_storing(&g.terms[i].op) { return Ok(()); }
+            if outs.iter().any(deferred) { return Ok(()); }
+            let mut bytes = Vec::new();
+            put_count(&mut bytes, outs.len());
+            for v in outs { serialize::encode(v, &mut bytes); }
+            if bytes.len() < self.min_bytes { return Ok(()); }
+            self.save(id, &bytes)?;
+            stats.stored += 1;
+            stats.stored_bytes += bytes.len();
+            Ok(())
+        };
+        let result = eval_graph_memo(g, memo, &mut store)?;
+        Ok((result, stats))
+    }
+
+    fn load(&self, id: Id, n_outputs: usize) -> Result<(Vec<Value>, usize), String> {
+        let path = self.entry(id);
+        let bad = |e: String| format!("cache entry {}: {} (delete it, or the cache directory, to recompute)", path.display(), e);
+        let bytes = std::fs::read(&path).map_err(|e| bad(e.to_string()))?;
+        if bytes.len() < 4 { return Err(bad("truncated".into())); }
+        let n = u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize;
+        if n != n_outputs { return Err(bad(format!("{} outputs, expected {}", n, n_outputs))); }
+        let mut pos = 4;
+        let vs = (0..n).map(|_| serialize::decode(&bytes, &mut pos)).collect::<Result<Vec<_>, _>>().map_err(bad)?;
+        if pos != bytes.len() { return Err(bad("trailing bytes".into())); }
+        Ok((vs, bytes.len()))
+    }
+
+    fn save(&self, id: Id, bytes: &[u8]) -> Result<(), String> {
+        let path = self.entry(id);
+        let tmp = self.dir.join(format!("{}.{}.tmp", id, std::process::id()));
+        std::fs::write(&tmp, bytes)
+            .and_then(|()| std::fs::rename(&tmp, &path))
+            .map_err(|e| format!("cache {}: {}", path.display(), e))
+    }
+}
+
+/// A side-effecting op must run; a `Const` already is its value.
+fn worth_storing(op: &SystemOp) -> bool {
+    !op.is_side_effecting() && !matches!(op, SystemOp::Const(_))
+}
+
+/// A View or an encoded column anywhere in `v`. The wire format
+/// materializes both, which can be far larger than the value itself (a
+/// per-row View of overlapping ranges, a run-length column), and either
+/// is cheap to rebuild from its source, so outputs holding one aren't
+/// stored.
+fn deferred(v: &Value) -> bool {
+    match v {
+        Value::View { .. } | Value::Encoded(_) => true,
+        Value::Prim(_) => false,
+        Value::Prod(fs) => fs.iter().any(deferred),
+        Value::Sum { lanes, .. } => lanes.iter().any(deferred),
+        Value::List { values, .. } => deferred(values),
+    }
+}
+
+fn put_count(out: &mut Vec<u8>, n: usize) { out.extend_from_slice(&(n as u32).to_le_bytes()); }
+
+/// Every term's identity, or `None` where it has none (see the module
+/// docs).
+pub fn identities(g: &Graph) -> Vec<Option<Id>> {
+    let mut ids: Vec<Option<Id>> = Vec::with_capacity(g.terms.len());
+    for term in &g.terms {
+        let id = (|| {
+            if matches!(term.op, SystemOp::Input { .. }) { return None; }
+            let mut h = Hasher::new();
+            h.write(env!("CARGO_PKG_VERSION").as_bytes());
+            h.write(&plan::FORMAT_VERSION.to_le_bytes());
+            match &term.op {
+                SystemOp::Const(v) => { h.write(b"Const"); h.value(v).ok()?; }
+                op => h.write(&plan::op_bytes(op).ok()?),
+            }
+            for ch in &term.children {
+                h.write(&ids[ch.term]?.0.to_le_bytes());
+                h.write(&(ch.idx as u64).to_le_bytes());
+            }
+            h.write(&(term.n_outputs as u64).to_le_bytes());
+            Some(Id(h.finish()))
+        })();
+        ids.push(id);
+    }
+    ids
+}
+
+/// Two independent 64-bit lanes of SplitMix64-finalized word mixing. Not
+/// cryptographic; 128 bits keep accidental collisions out of reach.
+struct Hasher { a: u64, b: u64, len: u64 }
+
+fn mix(mut z: u64) -> u64 { splitmix64(&mut z) }
+
+impl Hasher {
+    fn new() -> Hasher { Hasher { a: 0x636f_6c6c_6965_0001, b: 0x636f_6c6c_6965_0002, len: 0 } }
+
+    /// Each call is framed by its length, so `ab`+`c` and `a`+`bc` differ.
+    fn write(&mut self, bytes: &[u8]) {
+        self.word(bytes.len() as u64);
+        let mut chunks = bytes.chunks_exact(8);
+        for c in &mut chunks { self.word(u64::from_le_bytes(c.try_into().unwrap())); }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            let mut w = [0u8; 8];
+            w[..rest.len()].copy_from_slice(rest);
+            self.word(u64::from_le_bytes(w));
+        }
+    }
+
+    /// A value by content: one tag per node, row counts, and the raw
+    /// elements. A View or encoded column hashes as what it materializes
+    /// to, and list bounds as their row ends, whatever their encoding.
+    fn value(&mut self, v: &Value) -> Result<(), String> {
+        let v = materialize_ref(v)?;
+        match v.as_ref() {
+            // Tagged by width; `write` frames the narrow ones by length.
+            Value::Prim(Prim::P64(xs)) => {
+                self.word(8);
+                self.word(xs.len() as u64);
+                for &x in xs.iter() { self.word(x); }
+            }
+            Value::Prim(Prim::P32(xs)) => { self.word(4); self.write(bytemuck::cast_slice(xs)) }
+            Value::Prim(Prim::P16(xs)) => { self.word(2); self.write(bytemuck::cast_slice(xs)) }
+            Value::Prim(Prim::P8(xs)) => { self.word(1); self.write(xs) }
+            Value::Prod(fs) => {
+                self.word(0x5052_4f44 ^ ((fs.len() as u64) << 32));
+                for f in fs.iter() { self.value(f)?; }
+            }
+            Value::Sum { disc, lanes } => {
+                self.word(0x0053_554d ^ ((lanes.len() as u64) << 32));
+                self.value(&Value::Prim(disc.clone()))?;
+                for l in lanes.iter() { self.value(l)?; }
+            }
+            Value::List { bounds, values } => {
+                self.word(0x4c49_5354 ^ ((bounds.len() as u64) << 32));
+                for (_, hi) in bounds.iter_pairs() { self.word(hi); }
+                self.value(values)?;
+            }
+            Value::View { .. } | Value::Encoded(_) => unreachable!("materialized above"),
+        }
+        Ok(())
+    }
+
+    fn word(&mut self, w: u64) {
+        self.a = mix(self.a.rotate_left(23) ^ w);
+        self.b = mix(self.b.rotate_left(41) ^ w.wrapping_mul(0xff51_afd7_ed55_8ccd));
+        self.len += 1;
+    }
+
+    fn finish(&self) -> u128 {
+        let a = mix(self.a ^ self.len);
+        let b = mix(self.b ^ a);
+        ((a as u128) << 64) | b as u128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{build, eval_graph, optimize_unfolded};
+    use crate::syntax::parse::parse;
+    use crate::syntax::registry::OpRegistry;
+
+    fn graph(src: &str) -> Graph {
+        optimize_unfolded(build(parse(src, &OpRegistry::standard()).unwrap()).unwrap().0)
+    }
+
+    #[test]
+    fn second_run_reuses_the_prefix_and_matches() {
+        let dir = std::env::temp_dir().join(format!("collie-cache-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut cache = Cache::open(&dir).unwrap();
+        cache.min_bytes = 0;
+        let src = "u64[5 3 9 1 3] sort 2u64 *.u64 reduce.+.u64";
+        let g = graph(src);
+        let want = eval_graph(&g).unwrap();
+
+        let (got, first) = cache.run(&g).unwrap();
+        assert_eq!(got, want);
+        assert_eq!(first.hits, 0);
+        assert_eq!(first.ran, g.terms.len());
+        let consts = g.terms.iter().filter(|t| matches!(t.op, SystemOp::Const(_))).count();
+        assert_eq!(first.stored, g.terms.len() - consts);
+
+        // Everything is cached now: only the root is read, the rest skipped.
+        let (got, second) = cache.run(&g).unwrap();
+        assert_eq!(got, want);
+        assert_eq!((second.hits, second.ran, second.stored), (1, 0, 0));
+        assert_eq!(second.skipped, g.terms.len() - 1);
+
+        // A different literal changes every identity downstream of it, but
+        // the shared `sort` prefix of another script is still a hit.
+        let g2 = graph("u64[5 3 9 1 3] sort 3u64 *.u64 reduce.+.u64");
+        let (got, third) = cache.run(&g2).unwrap();
+        assert_eq!(got, eval_graph(&g2).unwrap());
+        assert!(third.hits >= 1 && third.ran >= 2, "{:?}", third);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn identities_follow_content_not_position() {
+        let a = identities(&graph("u64[1 2 3] sort"));
+        let b = identities(&graph("u64[1 2 3] sort"));
+        let c = identities(&graph("u64[1 2 4] sort"));
+        assert_eq!(a, b);
+        assert!(a.iter().all(Option::is_some));
+        assert_ne!(a.last(), c.last());
+    }
+
+    #[test]
+    fn const_identities_hash_content_not_representation() {
+        use crate::ir::value::{bounds_stride, bounds_var, from_vec, list};
+        let id = |v: Value| {
+            let mut h = Hasher::new();
+            h.value(&v).unwrap();
+            h.finish()
+        };
+        let xs = || from_vec::<u32>(vec![1, 2, 3, 4]);
+        assert_eq!(id(list(bounds_stride(2, 2), xs())), id(list(bounds_var(vec![0, 2, 4]), xs())));
+        assert_ne!(id(list(bounds_var(vec![0, 1, 4]), xs())), id(list(bounds_var(vec![0, 2, 4]), xs())));
+        assert_ne!(id(from_vec::<u32>(vec![1, 2])), id(from_vec::<u64>(vec![1 | 2 << 32])));
+        assert_ne!(id(from_vec::<u8>(vec![])), id(from_vec::<u16>(vec![])));
+    }
+}
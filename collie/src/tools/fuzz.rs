@@ -0,0 +1,677 @@
+//! Differential fuzzer: `collie fuzz [--seed N] [--iters M]`.
+//!
+//! Each case is a few random input `Value`s (every Prim width, nested
+//! Prod/Sum/List over `Var`/`Stride`/`Runs` bounds, Views with each
+//! `Selector` variant, RLE / frame-of-reference encoded columns) plus a
+//! random program over them. Programs are
+//! well-typed by construction: the generator proposes a token, runs it on
+//! the concrete stack it has so far, and keeps it only if it succeeds —
+//! so the vocabulary below can be generous and the kernels themselves
+//! decide what's valid.
+//!
+//! Every case is then built with `build_seeded` and checked four ways:
+//!
+//! - `optimize(g)` vs the raw graph — results must be *equal* (the
+//!   optimizer's contract, as in `optimize_corpus_preserves_results`);
+//! - `optimize_unfolded(g)` vs the raw graph, likewise (folding would
+//!   otherwise hide the rewrites behind a single `Const`);
+//! - `optimize_saturated_unfolded(g)` (the e-graph rewriter) vs the raw
+//!   graph, likewise;
+//! - View / encoded inputs vs the same inputs materialized — results must
+//!   agree up to representation (Views, encodings and bounds reprs
+//!   normalized away).
+//!
+//! "Agree" includes failing: both sides erroring is a pass, and so is an
+//! optimized graph succeeding where the raw one errored (the error may sit
+//! in a dead term the optimizer removed). A kernel that
+//! panics is always a failure. Failing cases are minimized (drop tokens,
+//! drop or materialize inputs, truncate inputs) while the same check keeps
+//! failing, and printed as a program plus its inputs.
+//!
+//! Deterministic: case `i` of `--seed s` is generated from its own PRNG
+//! stream, so a reported `(seed, case)` always reproduces.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+
+use crate::ir::encoding;
+use crate::ir::shape::{shape_of, Interp, Shape};
+use crate::ir::stack::Stack;
+use crate::ir::value::{
+    bounds_runs, bounds_stride, bounds_var_from_ends, list, prod, sum, view, Prim,
+    PrimWidth, Selector, Value,
+};
+use crate::ops::helpers::{gather, normalize, splitmix64};
+use crate::pipeline::graph::Graph;
+use crate::pipeline::{build_seeded, eval_graph, optimize, optimize_saturated_unfolded, optimize_unfolded};
+use crate::syntax::parse::parse;
+use crate::syntax::registry::OpRegistry;
+
+/// SplitMix64. Small, fast, and good enough to drive a fuzzer; the point
+/// is that it's ours, so seeds reproduce across platforms and releases.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self { Rng(seed) }
+
+    pub fn next_u64(&mut self) -> u64 { splitmix64(&mut self.0) }
+
+    /// Uniform in `0..n` (`n > 0`).
+    pub fn below(&mut self, n: usize) -> usize { (self.next_u64() % n as u64) as usize }
+
+    /// True with probability `num / den`.
+    pub fn chance(&mut self, num: usize, den: usize) -> bool { self.below(den) < num }
+
+    pub fn pick<'a, T>(&mut self, xs: &'a [T]) -> &'a T { &xs[self.below(xs.len())] }
+}
+
+/// One fuzz case: inputs (bottom of stack first) and program tokens.
+/// Tokens may be multi-word (`u64[0 2] gather`); joined with spaces they
+/// form the program text.
+#[derive(Clone, Debug)]
+pub struct Case {
+    pub seeds: Vec<Value>,
+    pub tokens: Vec<String>,
+}
+
+impl Case {
+    pub fn source(&self) -> String { self.tokens.join(" ") }
+}
+
+/// Which check a case failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Check {
+    Optimize,
+    OptimizeUnfolded,
+    Saturate,
+    ViewVsMaterialized,
+    Panic,
+}
+
+/// What running a case found.
+#[derive(Debug)]
+pub enum Outcome {
+    Pass,
+    /// Didn't build (typecheck rejected what the kernels accepted); not
+    /// counted against the optimizer.
+    Rejected(String),
+    Fail(Check, String),
+}
+
+// ── Value generation ────────────────────────────────────────────────────
+
+const WIDTHS: [PrimWidth; 4] = [PrimWidth::W8, PrimWidth::W16, PrimWidth::W32, PrimWidth::W64];
+
+fn gen_word(rng: &mut Rng, mode: usize) -> u64 {
+    match mode {
+        0 => rng.below(6) as u64,
+        1 => rng.below(1000) as u64,
+        _ => rng.next_u64(),
+    }
+}
+
+/// A flat column of `n` rows. Values are mostly small (to make equal keys
+/// and in-range positions likely), sometimes arbitrary bit patterns.
+pub fn gen_prim(rng: &mut Rng, w: PrimWidth, n: usize) -> Value {
+    let mode = *rng.pick(&[0, 0, 1, 2]);
+    let mut ws = Vec::with_capacity(n);
+    for _ in 0..n { ws.push(gen_word(rng, mode)); }
+    Value::Prim(match w {
+        PrimWidth::W8 => Prim::P8(Arc::new(ws.iter().map(|&x| x as u8).collect())),
+        PrimWidth::W16 => Prim::P16(Arc::new(ws.iter().map(|&x| x as u16).collect())),
+        PrimWidth::W32 => Prim::P32(Arc::new(ws.iter().map(|&x| x as u32).collect())),
+        PrimWidth::W64 => Prim::P64(Arc::new(ws)),
+    })
+}
+
+/// A content value (no View) with `n` rows, nested at most `depth` deep.
+pub fn gen_content(rng: &mut Rng, n: usize, depth: usize) -> Value {
+    let kind = if depth == 0 { 0 } else { rng.below(8) };
+    match kind {
+        0..=3 => { let w = *rng.pick(&WIDTHS); gen_prim(rng, w, n) }
+        4 => {
+            let k = 1 + rng.below(3);
+            prod((0..k).map(|_| gen_content(rng, n, depth - 1)).collect())
+        }
+        5 => {
+            let k = 1 + rng.below(3);
+            let disc: Vec<u8> = (0..n).map(|_| rng.below(k) as u8).collect();
+            let lanes = (0..k)
+                .map(|j| {
+                    let m = disc.iter().filter(|&&d| d as usize == j).count();
+                    gen_content(rng, m, depth - 1)
+                })
+                .collect();
+            sum(Prim::P8(Arc::new(disc)), lanes)
+        }
+        _ => {
+            let (bounds, m) = match rng.below(3) {
+                0 => {
+                    let mut ends = Vec::with_capacity(n);
+                    let mut acc = 0u64;
+                    for _ in 0..n { acc += rng.below(4) as u64; ends.push(acc); }
+                    (bounds_var_from_ends(ends), acc as usize)
+                }
+                1 => {
+                    let stride = rng.below(4) as u64;
+                    (bounds_stride(stride, n as u64), (stride * n as u64) as usize)
+                }
+                _ => {
+                    // The row-shaped form `join` builds: `Runs` bounds
+                    // sharing their runs with a `View<Prim, Runs>` inner
+                    // (row i is source[lo_i..hi_i], laid end to end).
+                    let m = rng.below(10);
+                    let runs: Vec<(u64, u64)> = (0..n)
+                        .map(|_| {
+                            let lo = rng.below(m + 1) as u64;
+                            let hi = lo + rng.below(m + 1 - lo as usize) as u64;
+                            (lo, hi)
+                        })
+                        .collect();
+                    let runs = Arc::new(runs);
+                    let w = *rng.pick(&WIDTHS);
+                    let inner = view(gen_prim(rng, w, m), Selector::Runs(runs.clone()));
+                    return list(bounds_runs(runs), inner);
+                }
+            };
+            list(bounds, gen_content(rng, m, depth - 1))
+        }
+    }
+}
+
+/// An input: a content value of `n` rows, or (a quarter of the time) a
+/// top-level View over a random source, with a random selector variant.
+/// A View's length is whatever its selector gives, not necessarily `n`.
+pub fn gen_seed(rng: &mut Rng, n: usize) -> Value {
+    if !rng.chance(1, 4) { return gen_content(rng, n, 2); }
+    let m = rng.below(12);
+    match rng.below(6) {
+        0 => {
+            let idxs = if m == 0 { vec![] } else { (0..n).map(|_| rng.below(m) as u64).collect() };
+            view(gen_content(rng, m, 2), Selector::Indices(Arc::new(idxs)))
+        }
+        1 => {
+            let mut runs = Vec::new();
+            let mut at = 0;
+            while at < m {
+                let lo = at + rng.below(m - at);
+                let hi = lo + rng.below(m - lo + 1);
+                runs.push((lo as u64, hi as u64));
+                at = hi.max(lo + 1);
+            }
+            view(gen_content(rng, m, 2), Selector::Runs(Arc::new(runs)))
+        }
+        2 => {
+            let mask: Vec<u8> = (0..m).map(|_| rng.below(2) as u8).collect();
+            view(gen_content(rng, m, 2), Selector::Mask(Arc::new(mask)))
+        }
+        3 => {
+            let (mut los, mut his) = (Vec::with_capacity(n), Vec::with_capacity(n));
+            for _ in 0..n {
+                let lo = rng.below(m + 1);
+                los.push(lo as u64);
+                his.push((lo + rng.below(m + 1 - lo)) as u64);
+            }
+            let w = *rng.pick(&WIDTHS);
+            view(gen_prim(rng, w, m), Selector::SequenceRange { los: Arc::new(los), his: Arc::new(his) })
+        }
+        4 => {
+            // Sorted, so the runs are long enough to matter.
+            let w = *rng.pick(&WIDTHS);
+            let Value::Prim(p) = gen_prim(rng, w, n) else { unreachable!("gen_prim") };
+            let mut ws: Vec<u64> = (0..n).map(|i| encoding::word(&p, i)).collect();
+            ws.sort_unstable();
+            Value::Encoded(encoding::rle(&encoding::from_words(w, ws)))
+        }
+        _ => {
+            let w = *rng.pick(&WIDTHS);
+            let Value::Prim(p) = gen_prim(rng, w, n) else { unreachable!("gen_prim") };
+            Value::Encoded(encoding::pack(&p))
+        }
+    }
+}
+
+// ── Program generation ──────────────────────────────────────────────────
+
+fn interps_for(w: &PrimWidth) -> &'static [Interp] {
+    match w {
+        PrimWidth::W8 => &[Interp::U8, Interp::I8],
+        PrimWidth::W16 => &[Interp::U16, Interp::I16],
+        PrimWidth::W32 => &[Interp::U32, Interp::I32, Interp::F32],
+        PrimWidth::W64 => &[Interp::U64, Interp::I64, Interp::F64],
+    }
+}
+
+const ALL_INTERPS: [Interp; 10] = [
+    Interp::I8, Interp::U8, Interp::I16, Interp::U16, Interp::I32,
+    Interp::U32, Interp::F32, Interp::I64, Interp::U64, Interp::F64,
+];
+
+/// The interp a token should use for a value of shape `s`: one matching
+/// its leaf width where there is one (Prim, or a List's flat inner).
+fn interp_for(rng: &mut Rng, s: &Shape) -> Interp {
+    match s {
+        Shape::Prim(w) => *rng.pick(interps_for(w)),
+        Shape::List { inner, .. } => interp_for(rng, inner),
+        _ => *rng.pick(&ALL_INTERPS),
+    }
+}
+
+/// A column literal of `n` rows, `<interp>[ … ]`.
+fn literal(rng: &mut Rng, i: Interp, n: usize) -> String {
+    let mode = rng.below(2);
+    let elems: Vec<String> = (0..n)
+        .map(|_| {
+            let x = gen_word(rng, mode);
+            match i {
+                Interp::F32 | Interp::F64 => format!("{}", x as f64 / 2.0),
+                Interp::I8 | Interp::I16 | Interp::I32 | Interp::I64 => {
+                    if rng.chance(1, 3) { format!("-{}", x) } else { x.to_string() }
+                }
+                _ => x.to_string(),
+            }
+        })
+        .collect();
+    format!("{}[{}]", i, elems.join(" "))
+}
+
+/// A `u64` position literal: up to `max_out - 1` positions in `0..below`.
+fn positions(rng: &mut Rng, max_out: usize, below: usize) -> String {
+    let n_out = rng.below(max_out);
+    let ps: Vec<String> = (0..n_out).map(|_| rng.below(below.max(1)).to_string()).collect();
+    format!("u64[{}]", ps.join(" "))
+}
+
+/// Propose one (possibly multi-word) token for the current stack. May be
+/// invalid — the caller runs it and discards failures.
+fn propose(rng: &mut Rng, st: &Stack) -> String {
+    let top = st.last().map(shape_of);
+    let n = st.last().map_or(0, |v| v.len());
+    let i = match &top { Some(s) => interp_for(rng, s), None => *rng.pick(&ALL_INTERPS) };
+    let second_len = if st.len() >= 2 { st[st.len() - 2].len() } else { n };
+    match rng.below(9) {
+        0 => rng.pick(&["dup", "drop", "swap", "over", "rot"]).to_string(),
+        1 => {
+            let len = if rng.chance(3, 4) { n } else { rng.below(8) };
+            literal(rng, i, len)
+        }
+        2 => format!("{}.{}", rng.pick(&["neg", "abs", "cumsum", "sort", "unique", "enswizzle", "deswizzle"]), i),
+        3 => match rng.below(4) {
+            0 => format!("reduce.{}.{}", rng.pick(&["+", "*", "min", "max"]), i),
+            1 => format!("as.{}", rng.pick(&ALL_INTERPS)),
+            2 => format!("{}u64 shift.{}", rng.below(4), i),
+            _ => format!("{}.{}", rng.pick(&["+", "-", "*", "/", "%", "<", "<=", "=", "!=", ">=", ">"]), i),
+        },
+        4 => rng.pick(&[
+            "sort", "sort.perm", "reverse", "where", "not", "and", "or", "mask.compose",
+            "enlist", "unlist", "zip2", "detuple2", "detuple3", "cat.2", ".0", ".1",
+            "flatten", "count", "head", "concat", "list>bounds", "split", "approx.distinct",
+            "encode.rle", "encode.for", "decode",
+        ]).to_string(),
+        5 => match rng.below(3) {
+            0 => format!("{} gather", positions(rng, n + 3, n)),
+            1 => format!("{} view", positions(rng, n + 3, n)),
+            _ => {
+                let lo = rng.below(n + 1);
+                format!("{}u64 {}u64 view.range", lo, lo + rng.below(n + 1 - lo))
+            }
+        },
+        6 => {
+            let mask: Vec<&str> = (0..n).map(|_| *rng.pick(&["t", "f"])).collect();
+            match rng.below(3) {
+                0 => format!("bool[{}] where gather", mask.join(" ")),
+                _ => format!("bool[{}] filter", mask.join(" ")),
+            }
+        }
+        7 => format!("{}u64 {}", rng.below(n + 2), rng.pick(&["take", "skip"])),
+        _ => match rng.below(6) {
+            0 => format!("{}.{}", rng.pick(&["intersect", "search", "group"]), i),
+            1 => {
+                let stride = 1 + rng.below(3);
+                format!("{}u64 nest.stride", stride)
+            }
+            2 => {
+                let mut ends: Vec<String> = Vec::new();
+                let mut acc = 0;
+                while acc < n { acc += rng.below(n - acc + 1); ends.push(acc.to_string()); }
+                format!("u64[{}] nest", ends.join(" "))
+            }
+            3 => {
+                let disc: Vec<String> = (0..n).map(|_| rng.below(2).to_string()).collect();
+                format!("u8[{}] branch.2", disc.join(" "))
+            }
+            4 => format!("{} gather", positions(rng, second_len + 3, second_len)),
+            _ => "dup sort.perm gather".to_string(),
+        },
+    }
+}
+
+/// Run `src` directly on `st` (the op-stream way, no graph). `Err` for a
+/// kernel error or a panic.
+fn run_tokens(src: &str, st: &mut Stack, reg: &OpRegistry) -> Result<(), String> {
+    let ops = parse(src, reg)?;
+    let mut env: Vec<Value> = Vec::new();
+    catch_unwind(AssertUnwindSafe(|| -> Result<(), String> {
+        for op in &ops { op.run(st, &mut env)?; }
+        Ok(())
+    })).unwrap_or_else(|_| Err("panic".into()))
+}
+
+/// Generate case `case` of run `seed`.
+pub fn gen_case(seed: u64, case: u64, reg: &OpRegistry) -> Case {
+    let mut rng = Rng::new(seed ^ case.wrapping_mul(0xA24B_AED4_963E_E407));
+    let n = rng.below(9);
+    let seeds: Vec<Value> = (0..1 + rng.below(3)).map(|_| gen_seed(&mut rng, n)).collect();
+    let mut st: Stack = seeds.clone();
+    let mut tokens = Vec::new();
+    let steps = 1 + rng.below(10);
+    let mut attempts = 0;
+    while tokens.len() < steps && attempts < steps * 12 {
+        attempts += 1;
+        let tok = propose(&mut rng, &st);
+        let mut next = st.clone();
+        if run_tokens(&tok, &mut next, reg).is_ok() {
+            st = next;
+            tokens.push(tok);
+        }
+    }
+    Case { seeds, tokens }
+}
+
+// ── Checking ────────────────────────────────────────────────────────────
+
+type Eval = Result<Vec<Value>, String>;
+
+/// A graph pass under test (`|g| g` for the raw graph).
+type Pass = fn(Graph) -> Graph;
+
+/// Build `case` (through `pass`) and evaluate; a panic anywhere is an `Err`
+/// tagged `"panic: …"`.
+fn eval_case(case: &Case, seeds: Vec<Value>, reg: &OpRegistry, pass: Pass) -> Result<Eval, String> {
+    let prog = parse(&case.source(), reg)?;
+    let (g, _) = build_seeded(prog, seeds)?;
+    let r = catch_unwind(AssertUnwindSafe(|| eval_graph(&pass(g))));
+    Ok(r.unwrap_or_else(|p| Err(format!("panic: {}", panic_message(p)))))
+}
+
+fn is_panic(r: &Eval) -> bool { matches!(r, Err(e) if e.starts_with("panic")) }
+
+fn agree(a: &Eval, b: &Eval, exact: bool) -> bool {
+    match (a, b) {
+        (Err(_), Err(_)) => true,
+        (Ok(x), Ok(y)) if exact => x == y,
+        (Ok(x), Ok(y)) => {
+            let nx: Result<Vec<_>, _> = x.iter().map(normalize).collect();
+            let ny: Result<Vec<_>, _> = y.iter().map(normalize).collect();
+            matches!((nx, ny), (Ok(nx), Ok(ny)) if nx == ny)
+        }
+        _ => false,
+    }
+}
+
+fn show(r: &Eval) -> String {
+    match r {
+        Ok(vs) => vs.iter().map(|v| format!("{:?}", v)).collect::<Vec<_>>().join("\n      "),
+        Err(e) => format!("error: {}", e),
+    }
+}
+
+fn panic_message(p: Box<dyn std::any::Any + Send>) -> String {
+    p.downcast_ref::<String>().cloned()
+        .or_else(|| p.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or_default()
+}
+
+/// Run every check on `case`. A panic anywhere — building, evaluating,
+/// or normalizing a result for comparison — is a `Check::Panic` failure.
+pub fn check_case(case: &Case, reg: &OpRegistry) -> Outcome {
+    catch_unwind(AssertUnwindSafe(|| check_case_inner(case, reg)))
+        .unwrap_or_else(|p| Outcome::Fail(Check::Panic, format!("panic: {}", panic_message(p))))
+}
+
+fn check_case_inner(case: &Case, reg: &OpRegistry) -> Outcome {
+    let raw = match eval_case(case, case.seeds.clone(), reg, |g| g) {
+        Ok(r) => r,
+        Err(e) => return Outcome::Rejected(e),
+    };
+    if is_panic(&raw) { return Outcome::Fail(Check::Panic, show(&raw)); }
+    let passes: [(Check, Pass); 3] = [
+        (Check::Optimize, optimize), (Check::OptimizeUnfolded, optimize_unfolded),
+        (Check::Saturate, optimize_saturated_unfolded),
+    ];
+    for (check, pass) in passes {
+        let r = match eval_case(case, case.seeds.clone(), reg, pass) {
+            Ok(r) => r,
+            Err(e) => return Outcome::Fail(check, format!("rebuild failed: {}", e)),
+        };
+        if is_panic(&r) { return Outcome::Fail(Check::Panic, show(&r)); }
+        // A raw error may live in a term whose output is dropped; dead-code
+        // elimination is allowed to remove it, so only raw successes must
+        // be reproduced.
+        if raw.is_ok() && !agree(&raw, &r, true) {
+            return Outcome::Fail(check, format!("raw:\n      {}\n    optimized:\n      {}", show(&raw), show(&r)));
+        }
+    }
+    if case.seeds.iter().any(|v| matches!(v, Value::View { .. } | Value::Encoded(_))) {
+        let mat: Result<Vec<Value>, String> = case.seeds.iter().map(normalize).collect();
+        let mat = match mat {
+            Ok(m) => m,
+            Err(e) => return Outcome::Fail(Check::ViewVsMaterialized, format!("materialize failed: {}", e)),
+        };
+        match eval_case(case, mat, reg, |g| g) {
+            Ok(r) => {
+                if is_panic(&r) { return Outcome::Fail(Check::Panic, show(&r)); }
+                if !agree(&raw, &r, false) {
+                    return Outcome::Fail(Check::ViewVsMaterialized,
+                        format!("views:\n      {}\n    materialized:\n      {}", show(&raw), show(&r)));
+                }
+            }
+            // The materialized inputs have the same shapes, so they build
+            // whenever the View inputs do.
+            Err(e) => return Outcome::Fail(Check::ViewVsMaterialized, format!("materialized build failed: {}", e)),
+        }
+    }
+    Outcome::Pass
+}
+
+// ── Minimization ────────────────────────────────────────────────────────
+
+/// A failure's message with digits removed: "index out of bounds: the len
+/// is 3 but the index is 7" and "… len is 0 but the index is 0" are the
+/// same bug.
+fn failure_key(detail: &str) -> String {
+    let line = detail.lines().find(|l| l.contains("panic")).unwrap_or(detail);
+    line.chars().filter(|c| !c.is_ascii_digit()).collect()
+}
+
+/// Smaller variants of `case`, most aggressive first.
+fn shrink_candidates(case: &Case) -> Vec<Case> {
+    let mut out = Vec::new();
+    for k in (0..case.tokens.len()).rev() {
+        let mut c = case.clone();
+        c.tokens.remove(k);
+        out.push(c);
+    }
+    for k in 0..case.seeds.len() {
+        let mut c = case.clone();
+        c.seeds.remove(k);
+        out.push(c);
+        if matches!(case.seeds[k], Value::View { .. } | Value::Encoded(_)) {
+            if let Ok(m) = normalize(&case.seeds[k]) {
+                let mut c = case.clone();
+                c.seeds[k] = m;
+                out.push(c);
+            }
+        }
+        let len = case.seeds[k].len();
+        if len > 0 && !matches!(case.seeds[k], Value::View { .. } | Value::Encoded(_)) {
+            for keep in [len / 2, len - 1] {
+                let idxs: Vec<usize> = (0..keep).collect();
+                if let Ok(v) = gather(&case.seeds[k], &idxs) {
+                    let mut c = case.clone();
+                    c.seeds[k] = v;
+                    out.push(c);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Greedily shrink `case` while `still_fails` holds. Each accepted step
+/// makes the case strictly smaller, so this terminates.
+pub fn minimize(case: Case, still_fails: impl Fn(&Case) -> bool) -> Case {
+    let mut best = case;
+    'outer: loop {
+        for c in shrink_candidates(&best) {
+            if still_fails(&c) {
+                best = c;
+                continue 'outer;
+            }
+        }
+        return best;
+    }
+}
+
+// ── Driver ──────────────────────────────────────────────────────────────
+
+/// `collie fuzz [--seed N] [--iters M]`. Errors (non-zero exit) if any
+/// case fails.
+pub fn run_fuzz(args: &[String]) -> Result<(), String> {
+    let mut seed = 0u64;
+    let mut iters = 1000u64;
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        let mut val = |name: &str| -> Result<u64, String> {
+            it.next().and_then(|v| v.parse().ok()).ok_or_else(|| format!("fuzz: {} expects a number", name))
+        };
+        match a.as_str() {
+            "--seed" => seed = val("--seed")?,
+            "--iters" => iters = val("--iters")?,
+            other => return Err(format!("fuzz: unknown argument {}", other)),
+        }
+    }
+    let reg = OpRegistry::standard();
+    // Kernels that panic are reported as failures; keep their messages
+    // from interleaving with the report.
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let (mut passed, mut rejected, mut failed, mut tokens) = (0u64, 0u64, 0u64, 0usize);
+    for case_id in 0..iters {
+        let case = gen_case(seed, case_id, &reg);
+        tokens += case.tokens.len();
+        match check_case(&case, &reg) {
+            Outcome::Pass => passed += 1,
+            Outcome::Rejected(_) => rejected += 1,
+            Outcome::Fail(check, detail) => {
+                failed += 1;
+                // Shrink while the *same* failure reproduces: same check,
+                // and for panics the same message (modulo the numbers in
+                // it), so minimization doesn't wander onto another bug.
+                let key = failure_key(&detail);
+                let small = minimize(case, |c| matches!(check_case(c, &reg),
+                    Outcome::Fail(k, d) if k == check && (k != Check::Panic || failure_key(&d) == key)));
+                let detail = match check_case(&small, &reg) { Outcome::Fail(_, d) => d, _ => String::new() };
+                println!("FAIL {:?} (seed {}, case {})", check, seed, case_id);
+                println!("  program: {}", small.source());
+                for (k, v) in small.seeds.iter().enumerate() {
+                    println!("  input {}: {:?}", k, v);
+                }
+                println!("    {}", detail);
+            }
+        }
+    }
+    std::panic::set_hook(hook);
+    println!("fuzz: seed {}, {} cases ({:.1} tokens avg): {} passed, {} rejected by typecheck, {} failed",
+             seed, iters, tokens as f64 / iters.max(1) as f64, passed, rejected, failed);
+    if failed > 0 { Err(format!("fuzz: {} failing cases", failed)) } else { Ok(()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::value::BoundsRepr;
+
+    #[test]
+    fn cases_are_deterministic() {
+        let reg = OpRegistry::standard();
+        for id in 0..20 {
+            let (a, b) = (gen_case(7, id, &reg), gen_case(7, id, &reg));
+            assert_eq!(a.tokens, b.tokens);
+            assert_eq!(a.seeds, b.seeds);
+        }
+    }
+
+    #[test]
+    fn generated_inputs_cover_every_selector_and_bounds_repr() {
+        let mut rng = Rng::new(1);
+        let mut seen = [false; 9];
+        fn walk(v: &Value, seen: &mut [bool; 9]) {
+            match v {
+                Value::View { source, selector } => {
+                    seen[match selector {
+                        Selector::Indices(_) => 0,
+                        Selector::Runs(_) => 1,
+                        Selector::Mask(_) => 2,
+                        Selector::SequenceRange { .. } => 3,
+                    }] = true;
+                    walk(source, seen);
+                }
+                Value::List { bounds, values } => {
+                    seen[match bounds {
+                        BoundsRepr::Var(_) => 4,
+                        BoundsRepr::Stride { .. } => 5,
+                        BoundsRepr::Runs(_) => 6,
+                    }] = true;
+                    walk(values, seen);
+                }
+                Value::Prod(fs) => fs.iter().for_each(|f| walk(f, seen)),
+                Value::Sum { lanes, .. } => lanes.iter().for_each(|l| walk(l, seen)),
+                Value::Encoded(e) => seen[if e.kind() == "rle" { 7 } else { 8 }] = true,
+                Value::Prim(_) => {}
+            }
+        }
+        for _ in 0..500 { walk(&gen_seed(&mut rng, 4), &mut seen); }
+        assert!(seen.iter().all(|&s| s), "not all variants generated: {:?}", seen);
+    }
+
+    #[test]
+    fn normalize_erases_representation() {
+        let src = crate::ir::value::from_vec::<u64>(vec![10, 11, 12, 13]);
+        let v = view(src, Selector::Mask(Arc::new(vec![1, 0, 1, 1])));
+        let m = crate::ir::value::from_vec::<u64>(vec![10, 12, 13]);
+        assert_eq!(normalize(&v).unwrap(), m);
+        let inner = crate::ir::value::from_vec::<u8>(vec![1, 2, 3, 4]);
+        let strided = list(bounds_stride(2, 2), inner.clone());
+        let runs = Arc::new(vec![(2, 4), (0, 2)]);
+        let src = crate::ir::value::from_vec::<u8>(vec![3, 4, 1, 2]);
+        let runs = list(bounds_runs(runs.clone()), view(src, Selector::Runs(runs)));
+        assert_eq!(normalize(&strided).unwrap(), normalize(&runs).unwrap());
+    }
+
+    #[test]
+    fn short_run_finds_no_failures() {
+        let reg = OpRegistry::standard();
+        let mut passed = 0;
+        for id in 0..300 {
+            let case = gen_case(42, id, &reg);
+            match check_case(&case, &reg) {
+                Outcome::Fail(check, detail) => panic!("{:?} on `{}` ({:?}): {}", check, case.source(), case.seeds, detail),
+                Outcome::Pass => passed += 1,
+                Outcome::Rejected(_) => {}
+            }
+        }
+        assert!(passed > 200, "only {} of 300 cases ran", passed);
+    }
+
+    #[test]
+    fn minimize_shrinks_to_the_culprit() {
+        let reg = OpRegistry::standard();
+        let case = (0..200).map(|id| gen_case(3, id, &reg))
+            .find(|c| c.tokens.len() >= 4 && c.tokens.iter().any(|t| t == "reverse"))
+            .expect("some case uses reverse");
+        let small = minimize(case, |c| c.tokens.iter().any(|t| t == "reverse"));
+        assert_eq!(small.tokens, vec!["reverse".to_string()]);
+        assert!(small.seeds.is_empty());
+    }
+}
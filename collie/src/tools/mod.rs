@@ -1,12 +1,18 @@
 //! Binary-only utilities: not part of the language, just the runner's
-//! supporting infrastructure (bench harness, pretty-printer,
-//! serialization, demo glue, external-op registration example).
+//! supporting infrastructure (bench harness, differential fuzzer,
+//! pretty-printer, result writers, serialization, compiled plans, the run
+//! cache, input binding, demo glue, external-op registration example).
 //!
 //! Library consumers of `collie` shouldn't need anything here.
 
 pub mod bench;
+pub mod bind;
+pub mod cache;
 pub mod demos;
 pub mod examples_runner;
+pub mod fuzz;
 pub mod ops_extra;
+pub mod plan;
 pub mod pretty;
+pub mod report;
 pub mod serialize;
@@ -15,7 +15,7 @@ use crate::ir::value::{Value, Storage, from_vec};
 use crate::ir::shape::{Interp, Shape};
 use crate::syntax::registry::OpRegistry;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Square { pub interp: Interp }
 
 impl PrimOp for Square {
@@ -45,6 +45,7 @@ impl PrimOp for Square {
     }
 }
 impl Typed for Square {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         let v = tc_pop(st, "square")?;
         match &v {
@@ -54,7 +55,7 @@ impl Typed for Square {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Clamp { pub interp: Interp }
 
 impl PrimOp for Clamp {
@@ -95,6 +96,7 @@ impl PrimOp for Clamp {
     }
 }
 impl Typed for Clamp {
+    crate::cloneable!();
     fn tc(&self, st: &mut TypeStack, _env: &mut TypeEnv) -> Result<(), String> {
         let hi = tc_pop(st, "clamp")?;
         let lo = tc_pop(st, "clamp")?;
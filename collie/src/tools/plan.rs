@@ -0,0 +1,439 @@
+//! Compiled plans: an optimized term graph saved to bytes, so a deployed
+//! query skips parse → lower → optimize on every run (`collie compile
+//! in.col -o out.plan`, then `collie run out.plan`).
+//!
+//! Layout (integers little-endian, strings as `u32` length + UTF-8):
+//!
+//! ```text
+//!   "COLPLAN\0"  magic
+//!   u32          FORMAT_VERSION
+//!   str          header — the script's `input`/`param`/`output` lines
+//!   u32          term count, then per term:
+//!                  op, u32 child count, (u32 term, u32 idx) per child,
+//!                  u32 n_outputs
+//!   u32          root count, then (u32 term, u32 idx) per root
+//! ```
+//!
+//! An op is its `SystemOp` variant name followed by the variant's
+//! parameters: interps and operator symbols as their surface text, counts
+//! as `u32`, a `Const` payload in the `tools::serialize` format, an
+//! `Input`'s slot and declared shape. A `Foreign` op is stored as its
+//! `PrimOp::token` and re-created through the standard registry on load,
+//! so ops outside the registry (FFI, `ops_extra`) can't be compiled.
+//!
+//! Decoding checks everything it can before anything runs: the magic and
+//! version, that every op is one this binary knows (a plan compiled by a
+//! newer collie fails here, naming the op), that edges point backwards at
+//! real outputs, and that the graph typechecks.
+
+use crate::ir::shape::{Interp, Shape};
+use crate::ir::value::PrimWidth;
+use crate::ops::arith::{ArithOp, UnaryArithOp};
+use crate::ops::cmp::CmpOp;
+use crate::ops::encode::Encoding;
+use crate::pipeline::graph::{Graph, OutRef, Term};
+use crate::pipeline::sysop::{promote, ReduceKind, SystemOp};
+use crate::syntax::registry::{parse_interp, OpRegistry};
+use crate::tools::serialize;
+
+const MAGIC: &[u8; 8] = b"COLPLAN\0";
+
+/// Bumped whenever the layout or an op's parameter encoding changes.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Whether `bytes` start like a plan (for `collie run` to tell a plan
+/// from a script).
+pub fn is_plan(bytes: &[u8]) -> bool { bytes.starts_with(MAGIC) }
+
+/// Serialize `g` with the script's declaration `header` (empty for a
+/// closed program). Fails on a `Foreign` op without a token.
+pub fn encode(g: &Graph, header: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    put_u32(&mut out, FORMAT_VERSION);
+    put_str(&mut out, header);
+    put_u32(&mut out, g.terms.len() as u32);
+    for (i, term) in g.terms.iter().enumerate() {
+        encode_op(&term.op, &mut out).map_err(|e| format!("compile: term {}: {}", i, e))?;
+        put_u32(&mut out, term.children.len() as u32);
+        for c in &term.children { put_ref(&mut out, *c); }
+        put_u32(&mut out, term.n_outputs as u32);
+    }
+    put_u32(&mut out, g.roots.len() as u32);
+    for r in &g.roots { put_ref(&mut out, *r); }
+    Ok(out)
+}
+
+/// Read a plan back: the graph and the header text it was compiled with.
+pub fn decode(bytes: &[u8]) -> Result<(Graph, String), String> {
+    if !is_plan(bytes) { return Err("plan: not a compiled plan (bad magic)".into()); }
+    let mut r = Reader { bytes, pos: MAGIC.len() };
+    let version = r.u32()?;
+    if version != FORMAT_VERSION {
+        return Err(format!(
+            "plan: format version {}, but this collie reads version {}; recompile it from the source",
+            version, FORMAT_VERSION));
+    }
+    let header = r.str()?;
+    let reg = OpRegistry::standard();
+    let n_terms = r.u32()? as usize;
+    let mut g = Graph::default();
+    for i in 0..n_terms {
+        let op = decode_op(&mut r, &reg).map_err(|e| format!("plan: term {}: {}", i, e))?;
+        let n_children = r.u32()? as usize;
+        let mut children = Vec::with_capacity(n_children.min(bytes.len()));
+        for _ in 0..n_children { children.push(r.edge(&g.terms, i)?); }
+        let n_outputs = r.u32()? as usize;
+        if let Some((ins, outs)) = op.arity() {
+            if (ins, outs) != (children.len(), n_outputs) {
+                return Err(format!("plan: term {}: `{}` takes {} and gives {}, but the plan has {} and {}",
+                    i, op.name(), ins, outs, children.len(), n_outputs));
+            }
+        }
+        g.terms.push(Term { op, children, n_outputs });
+    }
+    let n_roots = r.u32()? as usize;
+    for _ in 0..n_roots { let e = r.edge(&g.terms, n_terms)?; g.roots.push(e); }
+    if r.pos != bytes.len() { return Err(format!("plan: {} trailing bytes", bytes.len() - r.pos)); }
+    crate::pipeline::term_shapes(&g).map_err(|e| format!("plan: does not typecheck: {}", e))?;
+    Ok((g, header))
+}
+
+/// One op as a plan stores it: its variant name and parameters, a
+/// `Const`'s payload included. Equal bytes mean the same computation,
+/// which is what the run cache (`tools::cache`) keys on.
+pub fn op_bytes(op: &SystemOp) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    encode_op(op, &mut out)?;
+    Ok(out)
+}
+
+fn encode_op(op: &SystemOp, out: &mut Vec<u8>) -> Result<(), String> {
+    let interp = |out: &mut Vec<u8>, i: &Interp| put_str(out, &i.to_string());
+    let count = |out: &mut Vec<u8>, n: &usize| put_u32(out, *n as u32);
+    let key = variant_name(op);
+    put_str(out, key);
+    match op {
+        SystemOp::Arith { op, interp: i } => { put_str(out, crate::ops::arith::op_name(*op)); interp(out, i) }
+        SystemOp::UnaryArith { op, interp: i } => { put_str(out, crate::ops::arith::unary_name(*op)); interp(out, i) }
+        SystemOp::Cmp { op } => put_str(out, crate::ops::cmp::op_name(*op)),
+        SystemOp::Reduce { kind, interp: i } => {
+            put_str(out, match kind {
+                ReduceKind::Add => "+", ReduceKind::Min => "min", ReduceKind::Max => "max", ReduceKind::Mul => "*",
+            });
+            interp(out, i)
+        }
+        SystemOp::ReduceOrd { max } => out.push(*max as u8),
+        SystemOp::As { interp: i } | SystemOp::Cumsum { interp: i } | SystemOp::Shift { interp: i }
+        | SystemOp::AsofJoin { interp: i } | SystemOp::IntervalJoin { interp: i }
+        | SystemOp::Enswizzle { interp: i } | SystemOp::Deswizzle { interp: i } => interp(out, i),
+        SystemOp::Zip { n } | SystemOp::Detuple { n } | SystemOp::Pivot { n } | SystemOp::Inject { n }
+        | SystemOp::Merge { n } | SystemOp::Partition { n } | SystemOp::Cat { n } => count(out, n),
+        SystemOp::Proj { i } => count(out, i),
+        SystemOp::Branch { k } => count(out, k),
+        SystemOp::Encode { kind } => put_str(out, match kind { Encoding::Rle => "rle", Encoding::For => "for" }),
+        SystemOp::Const(v) => serialize::encode(v, out),
+        SystemOp::Input { slot, shape } => { count(out, slot); put_shape(out, shape) }
+        SystemOp::Foreign(o) => match o.token() {
+            Some(t) => put_str(out, &t),
+            None => return Err(format!("`{}` can't be stored in a plan: the standard registry can't re-create it", o.name())),
+        },
+        SystemOp::Not | SystemOp::And | SystemOp::Or | SystemOp::Any | SystemOp::All | SystemOp::Count
+        | SystemOp::Where | SystemOp::Filter | SystemOp::MaskCompose | SystemOp::Gather | SystemOp::Spread
+        | SystemOp::Intersect | SystemOp::Search | SystemOp::XProd | SystemOp::SortPerm | SystemOp::Sort
+        | SystemOp::SortSegmented | SystemOp::Group | SystemOp::Unique | SystemOp::Unpivot | SystemOp::Split
+        | SystemOp::Nest | SystemOp::NestStride | SystemOp::Flatten | SystemOp::Bounds | SystemOp::ListRanges
+        | SystemOp::BoundsKeys | SystemOp::Head | SystemOp::Like | SystemOp::Enlist | SystemOp::Unlist
+        | SystemOp::Iota | SystemOp::View | SystemOp::ViewRange | SystemOp::DecomposeView | SystemOp::Decode
+        | SystemOp::Concat | SystemOp::Take | SystemOp::Skip | SystemOp::Reverse | SystemOp::TakeSegmented
+        | SystemOp::ReverseSegmented => {}
+    }
+    Ok(())
+}
+
+/// The on-disk key of each variant. Exhaustive, so a new variant can't
+/// be added without deciding how plans name it.
+fn variant_name(op: &SystemOp) -> &'static str {
+    match op {
+        SystemOp::Arith { .. } => "Arith", SystemOp::UnaryArith { .. } => "UnaryArith",
+        SystemOp::Cmp { .. } => "Cmp", SystemOp::As { .. } => "As",
+        SystemOp::Not => "Not", SystemOp::And => "And", SystemOp::Or => "Or",
+        SystemOp::Any => "Any", SystemOp::All => "All",
+        SystemOp::Reduce { .. } => "Reduce", SystemOp::ReduceOrd { .. } => "ReduceOrd",
+        SystemOp::Cumsum { .. } => "Cumsum", SystemOp::Shift { .. } => "Shift", SystemOp::Count => "Count",
+        SystemOp::Where => "Where", SystemOp::Filter => "Filter", SystemOp::MaskCompose => "MaskCompose",
+        SystemOp::Gather => "Gather", SystemOp::Spread => "Spread", SystemOp::Intersect => "Intersect",
+        SystemOp::Search => "Search", SystemOp::AsofJoin { .. } => "AsofJoin",
+        SystemOp::IntervalJoin { .. } => "IntervalJoin", SystemOp::XProd => "XProd",
+        SystemOp::SortPerm => "SortPerm", SystemOp::Sort => "Sort", SystemOp::SortSegmented => "SortSegmented",
+        SystemOp::Group => "Group", SystemOp::Unique => "Unique",
+        SystemOp::Enswizzle { .. } => "Enswizzle", SystemOp::Deswizzle { .. } => "Deswizzle",
+        SystemOp::Zip { .. } => "Zip", SystemOp::Detuple { .. } => "Detuple", SystemOp::Proj { .. } => "Proj",
+        SystemOp::Pivot { .. } => "Pivot", SystemOp::Unpivot => "Unpivot",
+        SystemOp::Inject { .. } => "Inject", SystemOp::Split => "Split", SystemOp::Merge { .. } => "Merge",
+        SystemOp::Partition { .. } => "Partition", SystemOp::Branch { .. } => "Branch",
+        SystemOp::Nest => "Nest", SystemOp::NestStride => "NestStride", SystemOp::Flatten => "Flatten",
+        SystemOp::Bounds => "Bounds", SystemOp::ListRanges => "ListRanges", SystemOp::BoundsKeys => "BoundsKeys",
+        SystemOp::Head => "Head", SystemOp::Like => "Like", SystemOp::Enlist => "Enlist",
+        SystemOp::Unlist => "Unlist", SystemOp::Iota => "Iota",
+        SystemOp::View => "View", SystemOp::ViewRange => "ViewRange", SystemOp::DecomposeView => "DecomposeView",
+        SystemOp::Encode { .. } => "Encode", SystemOp::Decode => "Decode",
+        SystemOp::Concat => "Concat", SystemOp::Cat { .. } => "Cat", SystemOp::Take => "Take",
+        SystemOp::Skip => "Skip", SystemOp::Reverse => "Reverse",
+        SystemOp::TakeSegmented => "TakeSegmented", SystemOp::ReverseSegmented => "ReverseSegmented",
+        SystemOp::Const(_) => "Const", SystemOp::Input { .. } => "Input",
+        SystemOp::Foreign(_) => "Foreign",
+    }
+}
+
+fn decode_op(r: &mut Reader, reg: &OpRegistry) -> Result<SystemOp, String> {
+    let key = r.str()?;
+    let unknown = |what: &str| format!(
+        "uses op `{}`, which this collie doesn't know (was the plan compiled by a newer version?)", what);
+    fn pick<T: Copy>(r: &mut Reader, what: &str, all: &[T], name: impl Fn(T) -> &'static str) -> Result<T, String> {
+        let s = r.str()?;
+        all.iter().copied().find(|&x| name(x) == s).ok_or_else(|| format!("unknown {} `{}`", what, s))
+    }
+    let interp = |r: &mut Reader| -> Result<Interp, String> {
+        let s = r.str()?;
+        parse_interp(&s).ok_or_else(|| format!("unknown interp `{}`", s))
+    };
+    let count = |r: &mut Reader| r.u32().map(|n| n as usize);
+    Ok(match key.as_str() {
+        "Arith" => {
+            let op = pick(r, "arith op", &[ArithOp::Add, ArithOp::Sub, ArithOp::Mul, ArithOp::Div, ArithOp::Mod],
+                crate::ops::arith::op_name)?;
+            SystemOp::Arith { op, interp: interp(r)? }
+        }
+        "UnaryArith" => {
+            let op = pick(r, "unary op", &[UnaryArithOp::Neg, UnaryArithOp::Abs], crate::ops::arith::unary_name)?;
+            SystemOp::UnaryArith { op, interp: interp(r)? }
+        }
+        "Cmp" => SystemOp::Cmp {
+            op: pick(r, "comparison", &[CmpOp::Lt, CmpOp::Le, CmpOp::Eq, CmpOp::Ne, CmpOp::Ge, CmpOp::Gt],
+                crate::ops::cmp::op_name)?,
+        },
+        "Reduce" => {
+            let kind = match r.str()?.as_str() {
+                "+" => ReduceKind::Add, "min" => ReduceKind::Min, "max" => ReduceKind::Max, "*" => ReduceKind::Mul,
+                other => return Err(format!("unknown reduction `{}`", other)),
+            };
+            SystemOp::Reduce { kind, interp: interp(r)? }
+        }
+        "ReduceOrd" => SystemOp::ReduceOrd { max: r.u8()? != 0 },
+        "As" => SystemOp::As { interp: interp(r)? },
+        "Cumsum" => SystemOp::Cumsum { interp: interp(r)? },
+        "Shift" => SystemOp::Shift { interp: interp(r)? },
+        "AsofJoin" => SystemOp::AsofJoin { interp: interp(r)? },
+        "IntervalJoin" => SystemOp::IntervalJoin { interp: interp(r)? },
+        "Enswizzle" => SystemOp::Enswizzle { interp: interp(r)? },
+        "Deswizzle" => SystemOp::Deswizzle { interp: interp(r)? },
+        "Zip" => SystemOp::Zip { n: count(r)? },
+        "Detuple" => SystemOp::Detuple { n: count(r)? },
+        "Proj" => SystemOp::Proj { i: count(r)? },
+        "Pivot" => SystemOp::Pivot { n: count(r)? },
+        "Inject" => SystemOp::Inject { n: count(r)? },
+        "Merge" => SystemOp::Merge { n: count(r)? },
+        "Partition" => SystemOp::Partition { n: count(r)? },
+        "Branch" => SystemOp::Branch { k: count(r)? },
+        "Cat" => SystemOp::Cat { n: count(r)? },
+        "Encode" => SystemOp::Encode {
+            kind: match r.str()?.as_str() {
+                "rle" => Encoding::Rle, "for" => Encoding::For,
+                other => return Err(format!("unknown encoding `{}`", other)),
+            },
+        },
+        "Const" => SystemOp::Const(serialize::decode(r.bytes, &mut r.pos)?),
+        "Input" => SystemOp::Input { slot: count(r)?, shape: r.shape()? },
+        "Foreign" => {
+            let tok = r.str()?;
+            match reg.make(&tok).map(promote) {
+                Some(SystemOp::Foreign(o)) => SystemOp::Foreign(o),
+                Some(_) | None => return Err(unknown(&tok)),
+            }
+        }
+        "Not" => SystemOp::Not, "And" => SystemOp::And, "Or" => SystemOp::Or,
+        "Any" => SystemOp::Any, "All" => SystemOp::All, "Count" => SystemOp::Count,
+        "Where" => SystemOp::Where, "Filter" => SystemOp::Filter, "MaskCompose" => SystemOp::MaskCompose,
+        "Gather" => SystemOp::Gather, "Spread" => SystemOp::Spread, "Intersect" => SystemOp::Intersect,
+        "Search" => SystemOp::Search, "XProd" => SystemOp::XProd,
+        "SortPerm" => SystemOp::SortPerm, "Sort" => SystemOp::Sort, "SortSegmented" => SystemOp::SortSegmented,
+        "Group" => SystemOp::Group, "Unique" => SystemOp::Unique, "Unpivot" => SystemOp::Unpivot,
+        "Split" => SystemOp::Split, "Nest" => SystemOp::Nest, "NestStride" => SystemOp::NestStride,
+        "Flatten" => SystemOp::Flatten, "Bounds" => SystemOp::Bounds, "ListRanges" => SystemOp::ListRanges,
+        "BoundsKeys" => SystemOp::BoundsKeys, "Head" => SystemOp::Head, "Like" => SystemOp::Like,
+        "Enlist" => SystemOp::Enlist, "Unlist" => SystemOp::Unlist, "Iota" => SystemOp::Iota,
+        "View" => SystemOp::View, "ViewRange" => SystemOp::ViewRange, "DecomposeView" => SystemOp::DecomposeView,
+        "Decode" => SystemOp::Decode, "Concat" => SystemOp::Concat, "Take" => SystemOp::Take,
+        "Skip" => SystemOp::Skip, "Reverse" => SystemOp::Reverse,
+        "TakeSegmented" => SystemOp::TakeSegmented, "ReverseSegmented" => SystemOp::ReverseSegmented,
+        other => return Err(unknown(other)),
+    })
+}
+
+fn put_u32(out: &mut Vec<u8>, n: u32) { out.extend_from_slice(&n.to_le_bytes()); }
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    put_u32(out, s.len() as u32);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn put_ref(out: &mut Vec<u8>, r: OutRef) {
+    put_u32(out, r.term as u32);
+    put_u32(out, r.idx as u32);
+}
+
+fn width_code(w: PrimWidth) -> u8 {
+    match w { PrimWidth::W8 => 1, PrimWidth::W16 => 2, PrimWidth::W32 => 4, PrimWidth::W64 => 8 }
+}
+
+/// Tags: 0 Prim(width), 1 Prod(n, fields), 2 Sum(disc, n, lanes),
+/// 3 List(bounds, inner); widths as byte counts.
+fn put_shape(out: &mut Vec<u8>, s: &Shape) {
+    match s {
+        Shape::Prim(w) => { out.push(0); out.push(width_code(*w)); }
+        Shape::Prod(fs) => {
+            out.push(1);
+            put_u32(out, fs.len() as u32);
+            for f in fs { put_shape(out, f); }
+        }
+        Shape::Sum { disc, lanes } => {
+            out.push(2);
+            out.push(width_code(*disc));
+            put_u32(out, lanes.len() as u32);
+            for l in lanes { put_shape(out, l); }
+        }
+        Shape::List { bounds, inner } => {
+            out.push(3);
+            out.push(width_code(*bounds));
+            put_shape(out, inner);
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], String> {
+        if self.bytes.len() - self.pos < n { return Err("plan: truncated".into()); }
+        let s = &self.bytes[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(s)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> { Ok(self.take(1)?[0]) }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn str(&mut self) -> Result<String, String> {
+        let n = self.u32()? as usize;
+        String::from_utf8(self.take(n)?.to_vec()).map_err(|_| "plan: string is not UTF-8".to_string())
+    }
+
+    /// An edge into `terms`, read by term `at` (or the roots, at
+    /// `terms.len()`): it must name an output of an earlier term.
+    fn edge(&mut self, terms: &[Term], at: usize) -> Result<OutRef, String> {
+        let (term, idx) = (self.u32()? as usize, self.u32()? as usize);
+        match terms.get(term) {
+            Some(t) if idx < t.n_outputs => Ok(OutRef { term, idx }),
+            _ => Err(format!("plan: term {} reads t{}.{}, which is not an earlier term's output", at, term, idx)),
+        }
+    }
+
+    fn width(&mut self) -> Result<PrimWidth, String> {
+        match self.u8()? {
+            1 => Ok(PrimWidth::W8), 2 => Ok(PrimWidth::W16), 4 => Ok(PrimWidth::W32), 8 => Ok(PrimWidth::W64),
+            w => Err(format!("plan: bad width {}", w)),
+        }
+    }
+
+    fn shape(&mut self) -> Result<Shape, String> {
+        match self.u8()? {
+            0 => Ok(Shape::Prim(self.width()?)),
+            1 => {
+                let n = self.u32()? as usize;
+                Ok(Shape::Prod((0..n).map(|_| self.shape()).collect::<Result<_, _>>()?))
+            }
+            2 => {
+                let disc = self.width()?;
+                let n = self.u32()? as usize;
+                Ok(Shape::Sum { disc, lanes: (0..n).map(|_| self.shape()).collect::<Result<_, _>>()? })
+            }
+            3 => {
+                let bounds = self.width()?;
+                Ok(Shape::List { bounds, inner: Box::new(self.shape()?) })
+            }
+            t => Err(format!("plan: bad shape tag {}", t)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{build, build_parsed_inputs, eval_graph, eval_graph_inputs, optimize};
+    use crate::ir::value::from_vec;
+    use crate::syntax::parse::{parse_file, parse_program_bound};
+
+    #[test]
+    fn examples_round_trip_through_plans() {
+        let reg = OpRegistry::standard();
+        let mut paths: Vec<_> = std::fs::read_dir("examples").unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|x| x == "col"))
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty());
+        for path in paths {
+            let (g, _) = build(parse_file(&path, &reg).unwrap()).unwrap();
+            let g = optimize(g);
+            // Folded constants go on the wire materialized (a `View` comes
+            // back as its gathered column), so compare results on the wire.
+            let show = |vs: Vec<crate::ir::value::Value>| {
+                let mut out = Vec::new();
+                for v in &vs { serialize::encode(v, &mut out); }
+                out
+            };
+            let want = show(eval_graph(&g).unwrap());
+            let bytes = encode(&g, "").unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            let (back, header) = decode(&bytes).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            assert_eq!(header, "");
+            assert_eq!(back.terms.len(), g.terms.len());
+            assert_eq!(show(eval_graph(&back).unwrap()), want, "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn inputs_and_foreign_ops_survive_and_bad_plans_are_rejected() {
+        let reg = OpRegistry::standard();
+        let src = "xs trunc.month.date  ys stats.mean.f64";
+        let parsed = parse_program_bound(src, &reg, &["xs".to_string(), "ys".to_string()]).unwrap();
+        let shapes = [Shape::Prim(PrimWidth::W32), Shape::Prim(PrimWidth::W64)];
+        let g = optimize(build_parsed_inputs(parsed, &shapes).unwrap().graph);
+        let header = "input xs : date\ninput ys : f64";
+        let bytes = encode(&g, header).unwrap();
+        let (back, h) = decode(&bytes).unwrap();
+        assert_eq!(h, header);
+        let inputs = vec![from_vec::<i32>(vec![19_000, 19_100]), from_vec::<f64>(vec![1.0, 2.0, 6.0])];
+        assert_eq!(eval_graph_inputs(&back, inputs.clone()).unwrap(), eval_graph_inputs(&g, inputs).unwrap());
+
+        // A newer format version, an op this binary doesn't know, and a
+        // truncated file each fail with a message saying so.
+        let mut newer = bytes.clone();
+        newer[8..12].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert!(decode(&newer).unwrap_err().contains("format version 2"));
+        let tok = b"stats.mean.f64";
+        let at = bytes.windows(tok.len()).position(|w| w == tok).unwrap();
+        let mut unknown = bytes.clone();
+        unknown[at..at + tok.len()].copy_from_slice(b"stats.mode.f64");
+        let e = decode(&unknown).unwrap_err();
+        assert!(e.contains("`stats.mode.f64`") && e.contains("doesn't know"), "{}", e);
+        assert!(decode(&bytes[..bytes.len() - 3]).unwrap_err().contains("truncated"));
+        assert!(decode(b"#!collie\n").unwrap_err().contains("not a compiled plan"));
+    }
+}
@@ -58,6 +58,10 @@ fn write_value(out: &mut String, v: &Value) {
                 Err(e) => out.push_str(&format!("«{}»", e)),
             }
         }
+        Value::Encoded(e) => {
+            out.push_str(&format!("{}<n={}>", e.kind().to_uppercase(), e.len()));
+            write_prim(out, &e.decode());
+        }
     }
 }
 
@@ -184,6 +188,7 @@ fn write_row(out: &mut String, inner: &Value, lo: usize, hi: usize) {
         }
         Value::Sum { .. } => out.push_str("«sum-in-list»"),
         Value::View { .. } => out.push_str("«view-in-list»"),
+        Value::Encoded(_) => out.push_str("«encoded-in-list»"),
     }
 }
 
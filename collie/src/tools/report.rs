@@ -0,0 +1,411 @@
+//! Result writers for `collie run --format=…`: aligned tables, CSV and
+//! JSONL, reading each result through an interpretation schema (a
+//! `syntax::header::Ty`, from the program's `output` lines or
+//! `Ty::of_shape`).
+//!
+//! Each result is a column of rows; a Prod result contributes one column
+//! per field. When every result has the same number of rows they are laid
+//! side by side as one table, otherwise each is its own table (titled, in
+//! the `table` format; blank-line separated in CSV; consecutive in JSONL).
+//!
+//! Cells:
+//!   Prim:    the number under its interpretation; `bool` as `true`/`false`
+//!   str:     the row's bytes as UTF-8 (lossy)
+//!   date / timestamp: ISO-8601 text
+//!   d64.S / d128.S: exact decimal text (a bare number in JSON)
+//!   List:    an array of its elements
+//!   Prod:    an object by label, or an array when unlabeled (nested only)
+//!   Sum:     `{"label": v}` for a labeled lane, else `{"tag": k, "value": v}`
+//!   T?:      `T`'s cell, or empty (`null` in JSON) where missing
+//!
+//! In tables and CSV, array and object cells are written as JSON text.
+
+use crate::ir::encoding::word;
+use crate::ir::shape::{shape_of, Interp};
+use crate::ir::value::{Prim, Value};
+use crate::ops::calendar::{format_date, format_timestamp, Temporal};
+use crate::ops::decimal;
+use crate::ops::helpers::normalize;
+use crate::syntax::header::Ty;
+
+/// How `collie run` prints its results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// The REPL-style `tools::pretty` dump, one line per stack slot.
+    Pretty,
+    Table,
+    Csv,
+    Jsonl,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Result<Format, String> {
+        match s {
+            "pretty" => Ok(Format::Pretty),
+            "table" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            "jsonl" => Ok(Format::Jsonl),
+            other => Err(format!("--format: unknown {:?} (expected pretty, table, csv or jsonl)", other)),
+        }
+    }
+}
+
+/// One rendered element.
+#[derive(Clone, Debug, PartialEq)]
+enum Cell {
+    Int(i128),
+    /// An `f32`, kept narrow so it prints its shortest `f32` form
+    /// (`0.1`, not the widened `0.10000000149011612`).
+    Float32(f32),
+    Float(f64),
+    /// Exact decimal text, a bare number in JSON.
+    Dec(String),
+    Bool(bool),
+    Str(String),
+    Arr(Vec<Cell>),
+    Obj(Vec<(String, Cell)>),
+    /// A `T?`'s missing value.
+    Null,
+}
+
+/// A named column of cells.
+struct Column {
+    label: String,
+    cells: Vec<Cell>,
+}
+
+/// Render `results` (name, schema, value; bottom of the stack first) in
+/// `format`, which must not be `Pretty`. Each value's shape must match its
+/// schema's.
+pub fn write(format: Format, results: &[(String, Ty, Value)]) -> Result<String, String> {
+    let mut tables: Vec<(String, Vec<Column>)> = Vec::with_capacity(results.len());
+    for (name, ty, v) in results {
+        let v = normalize(v)?;
+        let (want, got) = (ty.shape(), shape_of(&v));
+        if want != got {
+            return Err(format!("output {}: declared {} ({}), got {}", name, ty, want, got));
+        }
+        tables.push((name.clone(), columns(name, ty, &v)));
+    }
+    let rows = |cols: &[Column]| cols.first().map_or(0, |c| c.cells.len());
+    if tables.len() > 1 && tables.iter().all(|(_, t)| rows(t) == rows(&tables[0].1)) {
+        let joined = tables.drain(..).flat_map(|(_, t)| t).collect();
+        tables.push((String::new(), joined));
+    }
+    let mut out = String::new();
+    let titled = tables.len() > 1;
+    for (k, (name, cols)) in tables.iter().enumerate() {
+        match format {
+            Format::Table => {
+                if k > 0 { out.push('\n'); }
+                if titled { out.push_str(name); out.push('\n'); }
+                write_table(&mut out, cols);
+            }
+            Format::Csv => {
+                if k > 0 { out.push('\n'); }
+                write_csv(&mut out, cols);
+            }
+            Format::Jsonl => write_jsonl(&mut out, cols),
+            Format::Pretty => return Err("report::write: use tools::pretty for the pretty format".into()),
+        }
+    }
+    Ok(out)
+}
+
+/// The columns of one result: its fields if it's a Prod, else itself.
+fn columns(name: &str, ty: &Ty, v: &Value) -> Vec<Column> {
+    match (ty.unlabeled().1, v) {
+        (Ty::Prod(fs), Value::Prod(vs)) => fs.iter().zip(vs.iter()).enumerate().map(|(k, (f, fv))| {
+            let label = f.unlabeled().0.map_or_else(|| format!("{}.{}", name, k), str::to_string);
+            Column { label, cells: cells(f, fv) }
+        }).collect(),
+        _ => vec![Column { label: name.to_string(), cells: cells(ty, v) }],
+    }
+}
+
+/// Every row of a normalized `v` read as `ty` (shapes already checked).
+fn cells(ty: &Ty, v: &Value) -> Vec<Cell> {
+    match (ty, v) {
+        (Ty::Labeled(_, t), _) => cells(t, v),
+        (Ty::Prim(i), Value::Prim(p)) => (0..p.len()).map(|r| number(*i, p, r)).collect(),
+        (Ty::Bool, Value::Prim(p)) => (0..p.len()).map(|r| Cell::Bool(word(p, r) != 0)).collect(),
+        (Ty::Temporal(t), Value::Prim(p)) => (0..p.len()).map(|r| Cell::Str(match t {
+            Temporal::Date => format_date(word(p, r) as u32 as i32 as i64),
+            Temporal::Timestamp => format_timestamp(word(p, r) as i64),
+        })).collect(),
+        (Ty::Decimal(d), _) => decimal::read(v, *d, "report").expect("shape checked against the schema")
+            .into_iter().map(|x| Cell::Dec(decimal::format_text(x, *d))).collect(),
+        (Ty::Str, Value::List { bounds, values }) => {
+            let Value::Prim(Prim::P8(bytes)) = &**values else { unreachable!("str is [u8]") };
+            bounds.iter_pairs()
+                .map(|(lo, hi)| Cell::Str(String::from_utf8_lossy(&bytes[lo as usize..hi as usize]).into_owned()))
+                .collect()
+        }
+        (Ty::List(t), Value::List { bounds, values }) => {
+            let mut inner = cells(t, values).into_iter();
+            bounds.iter_pairs().map(|(lo, hi)| Cell::Arr(inner.by_ref().take((hi - lo) as usize).collect())).collect()
+        }
+        (Ty::Prod(fs), Value::Prod(vs)) => {
+            let labeled = fs.iter().all(|f| f.unlabeled().0.is_some());
+            let mut cols: Vec<_> = fs.iter().zip(vs.iter()).map(|(f, fv)| cells(f, fv).into_iter()).collect();
+            (0..v.len()).map(|_| {
+                let row = fs.iter().zip(cols.iter_mut()).map(|(f, c)| (f.unlabeled().0, c.next().expect("field rows")));
+                if labeled {
+                    Cell::Obj(row.map(|(l, c)| (l.unwrap_or_default().to_string(), c)).collect())
+                } else {
+                    Cell::Arr(row.map(|(_, c)| c).collect())
+                }
+            }).collect()
+        }
+        (Ty::Opt(t), Value::Sum { disc, lanes }) => {
+            let mut somes = cells(t, &lanes[1]).into_iter();
+            (0..disc.len()).map(|r| if word(disc, r) == 0 { Cell::Null } else { somes.next().expect("lane rows match the disc") }).collect()
+        }
+        (Ty::Sum(ls), Value::Sum { disc, lanes }) => {
+            let mut lanes: Vec<_> = ls.iter().zip(lanes.iter()).map(|(l, lv)| cells(l, lv).into_iter()).collect();
+            (0..disc.len()).map(|r| {
+                let k = word(disc, r) as usize;
+                let c = lanes[k].next().expect("lane rows match the disc");
+                match ls[k].unlabeled().0 {
+                    Some(label) => Cell::Obj(vec![(label.to_string(), c)]),
+                    None => Cell::Obj(vec![("tag".to_string(), Cell::Int(k as i128)), ("value".to_string(), c)]),
+                }
+            }).collect()
+        }
+        _ => unreachable!("shape checked against the schema"),
+    }
+}
+
+/// Row `r` of `p` under `i`: integers sign-extended per their width,
+/// floats from their bits.
+fn number(i: Interp, p: &Prim, r: usize) -> Cell {
+    let w = word(p, r);
+    match i {
+        Interp::U8 | Interp::U16 | Interp::U32 | Interp::U64 => Cell::Int(w as i128),
+        Interp::I8 => Cell::Int(w as u8 as i8 as i128),
+        Interp::I16 => Cell::Int(w as u16 as i16 as i128),
+        Interp::I32 => Cell::Int(w as u32 as i32 as i128),
+        Interp::I64 => Cell::Int(w as i64 as i128),
+        Interp::F32 => Cell::Float32(f32::from_bits(w as u32)),
+        Interp::F64 => Cell::Float(f64::from_bits(w)),
+    }
+}
+
+/// A cell as table / CSV text.
+fn text(c: &Cell) -> String {
+    match c {
+        Cell::Int(n) => n.to_string(),
+        Cell::Float32(f) => f.to_string(),
+        Cell::Float(f) => f.to_string(),
+        Cell::Dec(s) => s.clone(),
+        Cell::Bool(b) => b.to_string(),
+        Cell::Str(s) => s.clone(),
+        Cell::Null => String::new(),
+        Cell::Arr(_) | Cell::Obj(_) => {
+            let mut s = String::new();
+            json(&mut s, c);
+            s
+        }
+    }
+}
+
+/// Control characters spelled as escapes, as `json_str` writes them, so a
+/// table cell stays on its row and its width counts what's printed.
+fn escape_controls(s: String) -> String {
+    if !s.contains(char::is_control) { return s; }
+    let mut out = String::with_capacity(s.len() + 8);
+    for ch in s.chars() {
+        match ch {
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn write_table(out: &mut String, cols: &[Column]) {
+    let texts: Vec<Vec<String>> = cols.iter()
+        .map(|c| c.cells.iter().map(|x| escape_controls(text(x))).collect())
+        .collect();
+    let widths: Vec<usize> = cols.iter().zip(&texts).map(|(c, ts)| {
+        ts.iter().map(|t| t.chars().count()).chain([c.label.chars().count()]).max().unwrap_or(0)
+    }).collect();
+    // Numbers right-aligned, everything else left.
+    let right: Vec<bool> = cols.iter()
+        .map(|c| c.cells.iter().find(|x| **x != Cell::Null).is_some_and(|x| matches!(x, Cell::Int(_) | Cell::Float32(_) | Cell::Float(_) | Cell::Dec(_))))
+        .collect();
+    let line = |out: &mut String, cells: &mut dyn Iterator<Item = &str>| {
+        let row: Vec<String> = cells.zip(&widths).zip(&right).map(|((s, &w), &r)| {
+            if r { format!("{:>w$}", s, w = w) } else { format!("{:<w$}", s, w = w) }
+        }).collect();
+        out.push_str(row.join("  ").trim_end());
+        out.push('\n');
+    };
+    line(out, &mut cols.iter().map(|c| c.label.as_str()));
+    let rule: Vec<String> = widths.iter().map(|&w| "-".repeat(w)).collect();
+    line(out, &mut rule.iter().map(String::as_str));
+    for r in 0..texts.first().map_or(0, Vec::len) {
+        line(out, &mut texts.iter().map(|t| t[r].as_str()));
+    }
+}
+
+fn write_csv(out: &mut String, cols: &[Column]) {
+    // Quoted only when needed (RFC 4180).
+    let field = |s: &str| if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    };
+    let labels: Vec<String> = cols.iter().map(|c| field(&c.label)).collect();
+    out.push_str(&labels.join(","));
+    out.push('\n');
+    for r in 0..cols.first().map_or(0, |c| c.cells.len()) {
+        let row: Vec<String> = cols.iter().map(|c| field(&text(&c.cells[r]))).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+}
+
+/// One JSON object per row, keyed by column label.
+fn write_jsonl(out: &mut String, cols: &[Column]) {
+    for r in 0..cols.first().map_or(0, |c| c.cells.len()) {
+        let row = Cell::Obj(cols.iter().map(|c| (c.label.clone(), c.cells[r].clone())).collect());
+        json(out, &row);
+        out.push('\n');
+    }
+}
+
+fn json(out: &mut String, c: &Cell) {
+    match c {
+        Cell::Int(n) => out.push_str(&n.to_string()),
+        // JSON has no NaN or infinities.
+        Cell::Float32(f) if !f.is_finite() => out.push_str("null"),
+        Cell::Float(f) if !f.is_finite() => out.push_str("null"),
+        Cell::Null => out.push_str("null"),
+        Cell::Float32(f) => out.push_str(&f.to_string()),
+        Cell::Float(f) => out.push_str(&f.to_string()),
+        Cell::Dec(s) => out.push_str(s),
+        Cell::Bool(b) => out.push_str(&b.to_string()),
+        Cell::Str(s) => json_str(out, s),
+        Cell::Arr(xs) => {
+            out.push('[');
+            for (i, x) in xs.iter().enumerate() {
+                if i > 0 { out.push(','); }
+                json(out, x);
+            }
+            out.push(']');
+        }
+        Cell::Obj(kvs) => {
+            out.push('{');
+            for (i, (k, v)) in kvs.iter().enumerate() {
+                if i > 0 { out.push(','); }
+                json_str(out, k);
+                out.push(':');
+                json(out, v);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::value::{bounds_var, from_vec, list, prod};
+    use crate::pipeline::{build_parsed, eval_graph};
+    use crate::syntax::header::parse_ty;
+    use crate::syntax::{parse::parse_program, registry::OpRegistry};
+
+    fn results(src: &str, schema: &[(&str, &str)]) -> Vec<(String, Ty, Value)> {
+        let parsed = parse_program(src, &OpRegistry::standard()).unwrap();
+        let out = eval_graph(&build_parsed(parsed).unwrap().graph).unwrap();
+        out.into_iter().zip(schema).map(|(v, (n, t))| (n.to_string(), parse_ty(t).unwrap(), v)).collect()
+    }
+
+    #[test]
+    fn table_csv_and_jsonl_share_columns() {
+        let rs = results("u64[1 2] i32[-5 7] entuple f64[0.5 2.0]",
+            &[("by", "(region: u64, delta: i32)"), ("rate", "f64")]);
+        assert_eq!(write(Format::Table, &rs).unwrap(),
+            "region  delta  rate\n------  -----  ----\n     1     -5   0.5\n     2      7     2\n");
+        assert_eq!(write(Format::Csv, &rs).unwrap(), "region,delta,rate\n1,-5,0.5\n2,7,2\n");
+        let dated = results("i32[19782 -1] i64[1500000 -1]", &[("d", "date"), ("t", "timestamp")]);
+        assert_eq!(write(Format::Csv, &dated).unwrap(),
+            "d,t\n2024-02-29,1970-01-01T00:00:01.500000Z\n1969-12-31,1969-12-31T23:59:59.999999Z\n");
+        let money = results("i64[1999 -5] u64[0 18446744073709551615] u64[7 18446744073709551611] entuple",
+            &[("p", "d64.2"), ("q", "d128.3")]);
+        assert_eq!(write(Format::Csv, &money).unwrap(), "p,q\n19.99,0.007\n-0.05,-0.005\n");
+        assert_eq!(write(Format::Jsonl, &money).unwrap(), "{\"p\":19.99,\"q\":0.007}\n{\"p\":-0.05,\"q\":-0.005}\n");
+        assert_eq!(write(Format::Jsonl, &rs).unwrap(),
+            "{\"region\":1,\"delta\":-5,\"rate\":0.5}\n{\"region\":2,\"delta\":7,\"rate\":2}\n");
+    }
+
+    #[test]
+    fn strings_lists_and_sums() {
+        let text = list(bounds_var(vec![0, 2, 5]), from_vec::<u8>(b"hia,b".to_vec()));
+        let nums = list(bounds_var(vec![0, 1, 1]), from_vec::<u64>(vec![9]));
+        let s = crate::ir::value::sum(crate::ir::value::Prim::P8(std::sync::Arc::new(vec![1, 0])),
+            vec![from_vec::<u64>(vec![4]), from_vec::<u8>(vec![1])]);
+        let rs = vec![
+            ("v".to_string(), parse_ty("(name: str, xs: [u64], s: <n: u64 | bool>)").unwrap(), prod(vec![text.clone(), nums, s])),
+        ];
+        assert_eq!(write(Format::Jsonl, &rs).unwrap(),
+            "{\"name\":\"hi\",\"xs\":[9],\"s\":{\"tag\":1,\"value\":true}}\n{\"name\":\"a,b\",\"xs\":[],\"s\":{\"n\":4}}\n");
+        assert_eq!(write(Format::Csv, &rs).unwrap().lines().nth(2), Some("\"a,b\",[],\"{\"\"n\"\":4}\""));
+        // A table keeps control characters on one line, escaped.
+        let ctl = list(bounds_var(vec![0, 3, 7]), from_vec::<u8>(b"a\nbc\td\x01".to_vec()));
+        let rs = vec![("s".to_string(), parse_ty("str").unwrap(), ctl)];
+        assert_eq!(write(Format::Table, &rs).unwrap(), "s\n----------\na\\nb\nc\\td\\u0001\n");
+        // Without a schema, `[u8]` still reads as text.
+        let rs = vec![("t".to_string(), Ty::of_shape(&shape_of(&text)), text)];
+        assert_eq!(write(Format::Csv, &rs).unwrap(), "t\nhi\n\"a,b\"\n");
+    }
+
+    #[test]
+    fn missing_values_are_empty_or_null() {
+        let rs = results("u64[1 5 3] opt.some  i64[4 5] opt.none.like", &[("a", "u64?"), ("b", "i64?")]);
+        assert_eq!(write(Format::Csv, &rs).unwrap(), "a\n1\n5\n3\n\nb\n\n\n");
+        let o = crate::ops::opt::opt_value(vec![0, 1], from_vec::<i32>(vec![-2]));
+        assert_eq!(Ty::of_shape(&shape_of(&o)).to_string(), "u32?");
+        let rs = vec![("o".to_string(), parse_ty("i32?").unwrap(), o)];
+        assert_eq!(write(Format::Table, &rs).unwrap(), " o\n--\n\n-2\n");
+        assert_eq!(write(Format::Jsonl, &rs).unwrap(), "{\"o\":null}\n{\"o\":-2}\n");
+    }
+
+    #[test]
+    fn f32_cells_print_as_f32() {
+        let rs = results("f32[0.1 2.5 -0.3] f64[0.1]", &[("a", "f32"), ("b", "f64")]);
+        assert_eq!(write(Format::Csv, &rs).unwrap(), "a\n0.1\n2.5\n-0.3\n\nb\n0.1\n");
+        assert_eq!(write(Format::Table, &rs).unwrap().lines().nth(3), Some(" 0.1"));
+        let rs = results("f32[0.1] f32[1.5] entuple", &[("v", "(x: f32, y: f32)")]);
+        assert_eq!(write(Format::Jsonl, &rs).unwrap(), "{\"x\":0.1,\"y\":1.5}\n");
+    }
+
+    #[test]
+    fn schema_must_match_and_ragged_results_split() {
+        let rs = results("u64[1 2] u64[3]", &[("a", "i32"), ("b", "u64")]);
+        assert!(write(Format::Csv, &rs).unwrap_err().contains("output a: declared i32 (P32), got P64"));
+        let rs = results("u64[1 2] u64[3]", &[("a", "u64"), ("b", "u64")]);
+        assert_eq!(write(Format::Table, &rs).unwrap(), "a\na\n-\n1\n2\n\nb\nb\n-\n3\n");
+        assert_eq!(write(Format::Csv, &rs).unwrap(), "a\n1\n2\n\nb\n3\n");
+    }
+}
@@ -66,6 +66,8 @@ pub fn encode(v: &Value, out: &mut Vec<u8>) {
                 }
             }
         }
+        // Likewise an encoded column goes on the wire decoded.
+        Value::Encoded(e) => encode(&Value::Prim(e.decode()), out),
     }
 }
 
@@ -147,12 +149,17 @@ pub fn decode(bytes: &[u8], pos: &mut usize) -> Result<Value, String> {
         }
         TAG_LIST => {
             let bounds_val = decode(bytes, pos)?;
+            // Bounds are P64 everywhere else; widen a narrower buffer here
+            // so the list kernels never see one.
             let bounds = match bounds_val {
-                Value::Prim(p) => p,
+                Value::Prim(Prim::P64(v)) => v,
+                Value::Prim(Prim::P32(v)) => Arc::new(v.iter().map(|&x| x as u64).collect()),
+                Value::Prim(Prim::P16(v)) => Arc::new(v.iter().map(|&x| x as u64).collect()),
+                Value::Prim(Prim::P8(v)) => Arc::new(v.iter().map(|&x| x as u64).collect()),
                 _ => return Err("decode: List bounds must be a Prim".into()),
             };
             let inner = decode(bytes, pos)?;
-            Ok(list(crate::ir::value::BoundsRepr::Var(bounds), inner))
+            Ok(list(crate::ir::value::BoundsRepr::Var(Prim::P64(bounds)), inner))
         }
         other => Err(format!("decode: unknown tag 0x{:02x}", other)),
     }
@@ -187,4 +194,28 @@ mod tests {
         assert_eq!(pos, bytes.len(), "decode did not consume all bytes");
         assert_eq!(original, decoded);
     }
+
+    #[test]
+    fn narrow_list_bounds_decode_as_p64() {
+        // A file may carry P32 bounds; the list kernels must still see every
+        // row (they walk P64 bounds), not an empty or inconsistent list.
+        let narrow = list(
+            crate::ir::value::BoundsRepr::Var(Prim::P32(Arc::new(vec![0, 2, 3, 5]))),
+            from_vec::<u64>(vec![1, 2, 3, 4, 5]),
+        );
+        let mut bytes = Vec::new();
+        encode(&narrow, &mut bytes);
+        let decoded = decode(&bytes, &mut 0).unwrap();
+        assert!(matches!(&decoded, Value::List { bounds: crate::ir::value::BoundsRepr::Var(Prim::P64(_)), .. }), "{:?}", decoded);
+        let reg = crate::syntax::registry::OpRegistry::standard();
+        let run = |tok: &str, mut st: Vec<Value>| {
+            reg.make(tok).unwrap().run(&mut st, &mut Vec::new()).unwrap();
+            st.pop().unwrap()
+        };
+        assert_eq!(run("reduce.+.u64", vec![decoded.clone()]), from_vec::<u64>(vec![3, 3, 9]));
+        assert_eq!(run("cumsum.u64", vec![decoded]),
+            list(bounds_var_from_ends(vec![2, 3, 5]), from_vec::<u64>(vec![1, 3, 3, 4, 9])));
+        // Kernels handed narrow bounds directly still walk every row.
+        assert_eq!(run("reduce.+.u64", vec![narrow]), from_vec::<u64>(vec![3, 3, 9]));
+    }
 }
@@ -0,0 +1,155 @@
+/*
+ * Round trips through the C ABI: Prim, Prod, List and Sum columns go in
+ * from host buffers, a program runs over them, and the results are read
+ * back through borrowed pointers; one compiled graph is rebound and rerun
+ * over a second input set. Exits nonzero on the first mismatch.
+ *
+ * Built and run by scripts/c_abi_test.sh.
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "collie.h"
+
+static int failures = 0;
+
+#define CHECK(cond) do { \
+    if (!(cond)) { fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); failures++; } \
+} while (0)
+
+/* Compile and run `src` over `inputs`; abort with the engine's message on error. */
+static collie_result *run(const char *src, collie_value *const *inputs, size_t n) {
+    char *err = NULL;
+    collie_graph *g = collie_compile(src, inputs, n, &err);
+    if (!g) { fprintf(stderr, "compile failed:\n%s\n", err); collie_string_free(err); exit(1); }
+    collie_result *r = collie_execute(g, &err);
+    collie_graph_free(g);
+    if (!r) { fprintf(stderr, "execute failed:\n%s\n", err); collie_string_free(err); exit(1); }
+    return r;
+}
+
+static collie_value *u64s(const uint64_t *xs, size_t n) { return collie_prim(8, xs, n, NULL); }
+
+static int eq_u64(const collie_value *v, const uint64_t *want, size_t n) {
+    return v && collie_value_kind(v) == COLLIE_PRIM && collie_prim_width(v) == 8
+        && collie_value_len(v) == n && memcmp(collie_prim_data(v), want, n * 8) == 0;
+}
+
+static void prim(void) {
+    /* i32 in, negated i32 out. */
+    int32_t xs[] = { 3, -1, 7 };
+    collie_value *in = collie_prim(4, xs, 3, NULL);
+    collie_result *r = run("0i32 swap -.i32", &in, 1);
+    CHECK(collie_result_count(r) == 1);
+    const collie_value *v = collie_result_get(r, 0);
+    CHECK(collie_prim_width(v) == 4 && collie_value_len(v) == 3);
+    const int32_t *ys = collie_prim_data(v);
+    CHECK(ys[0] == -3 && ys[1] == 1 && ys[2] == -7);
+    collie_result_free(r);
+}
+
+static void prod(void) {
+    /* (key, val) pairs; sum val per key via group. */
+    uint64_t keys[] = { 2, 1, 2, 1, 3 };
+    uint64_t vals[] = { 10, 20, 30, 40, 50 };
+    collie_value *fields[] = { u64s(keys, 5), u64s(vals, 5) };
+    collie_value *pairs = collie_prod(fields, 2, NULL);
+    collie_result *r = run("dup .1 swap .0 group reduce.+.u64 entuple", &pairs, 1);
+    const collie_value *v = collie_result_get(r, 0);
+    CHECK(collie_value_kind(v) == COLLIE_PROD && collie_arity(v) == 2);
+    uint64_t want_k[] = { 1, 2, 3 }, want_s[] = { 60, 40, 50 };
+    CHECK(eq_u64(collie_child(v, 0), want_k, 3));
+    CHECK(eq_u64(collie_child(v, 1), want_s, 3));
+    collie_result_free(r);
+}
+
+static void list(void) {
+    /* Three rows, the middle one empty; per-row sums, and the List back. */
+    uint64_t bounds[] = { 0, 2, 2, 5 };
+    uint64_t vals[] = { 1, 2, 3, 4, 5 };
+    collie_value *l = collie_list(bounds, 3, u64s(vals, 5), NULL);
+    collie_result *r = run("dup reduce.+.u64", &l, 1);
+    CHECK(collie_result_count(r) == 2);
+    const collie_value *back = collie_result_get(r, 0);
+    CHECK(collie_value_kind(back) == COLLIE_LIST && collie_value_len(back) == 3);
+    CHECK(memcmp(collie_list_bounds(back), bounds, sizeof bounds) == 0);
+    CHECK(eq_u64(collie_list_values(back), vals, 5));
+    uint64_t want[] = { 3, 0, 12 };
+    CHECK(eq_u64(collie_result_get(r, 1), want, 3));
+    /* Readers on the wrong kind answer NULL / 0 rather than crash. */
+    CHECK(collie_prim_data(back) == NULL && collie_arity(back) == 0);
+    collie_result_free(r);
+}
+
+static void sum(void) {
+    /* Lane 0: u64 passthrough; lane 1: u64 doubled. */
+    uint8_t disc[] = { 0, 1, 1, 0 };
+    uint64_t a[] = { 5, 6 }, b[] = { 7, 8 };
+    collie_value *lanes[] = { u64s(a, 2), u64s(b, 2) };
+    collie_value *s = collie_sum(disc, 4, lanes, 2, NULL);
+    collie_result *r = run("dup match { -> -> dup +.u64 }", &s, 1);
+    const collie_value *back = collie_result_get(r, 0);
+    CHECK(collie_value_kind(back) == COLLIE_SUM && collie_arity(back) == 2);
+    CHECK(memcmp(collie_sum_disc(back), disc, 4) == 0);
+    CHECK(eq_u64(collie_child(back, 1), b, 2));
+    uint64_t want[] = { 5, 14, 16, 6 };
+    CHECK(eq_u64(collie_result_get(r, 1), want, 4));
+    collie_result_free(r);
+}
+
+static void rebind(void) {
+    /* Compile once over one input set, then bind and run a second. */
+    char *err = NULL;
+    uint64_t xs[] = { 1, 2, 3 }, ys[] = { 10, 20 }, zs[] = { 4, 5, 6, 7 };
+    collie_value *in[] = { u64s(xs, 3), u64s(ys, 2) };
+    collie_graph *g = collie_compile("reduce.+.u64 swap reduce.+.u64 +.u64", in, 2, &err);
+    if (!g) { fprintf(stderr, "compile failed:\n%s\n", err); collie_string_free(err); exit(1); }
+    collie_result *r = collie_execute(g, NULL);
+    uint64_t want1[] = { 36 };
+    CHECK(r && eq_u64(collie_result_get(r, 0), want1, 1));
+    collie_result_free(r);
+
+    CHECK(collie_bind(g, 0, u64s(zs, 4), NULL) == 0);
+    CHECK(collie_bind(g, 1, u64s(xs, 3), NULL) == 0);
+    r = collie_execute(g, NULL);
+    uint64_t want2[] = { 28 };
+    CHECK(r && eq_u64(collie_result_get(r, 0), want2, 1));
+    collie_result_free(r);
+
+    /* A column of another width doesn't fit slot 0. */
+    int32_t narrow[] = { 1 };
+    CHECK(collie_bind(g, 0, collie_prim(4, narrow, 1, NULL), &err) == -1);
+    CHECK(err && strstr(err, "compiled for"));
+    collie_string_free(err);
+    collie_graph_free(g);
+}
+
+static void errors(void) {
+    char *err = NULL;
+    uint64_t bounds[] = { 0, 4 };
+    uint64_t vals[] = { 1, 2 };
+    CHECK(collie_list(bounds, 1, u64s(vals, 2), &err) == NULL);
+    CHECK(err && strstr(err, "values have 2 rows"));
+    collie_string_free(err);
+
+    err = NULL;
+    CHECK(collie_compile("reduce.+.u64", NULL, 0, &err) == NULL);
+    CHECK(err && strstr(err, "<source>:1:1"));
+    collie_string_free(err);
+
+    /* A NULL err is allowed: the failure is only in the return value. */
+    CHECK(collie_prim(3, vals, 2, NULL) == NULL);
+}
+
+int main(void) {
+    prim();
+    prod();
+    list();
+    sum();
+    rebind();
+    errors();
+    if (failures) { fprintf(stderr, "%d check(s) failed\n", failures); return 1; }
+    printf("c abi: all round trips ok\n");
+    return 0;
+}